@@ -3,10 +3,10 @@ mod value;
 use android_utilities::jni_exts::jlong::MantleJlong;
 use android_utilities::jni_exts::jobject::MantleJObject;
 use android_utilities::jni_exts::jstring::MantleJString;
-use android_utilities::{JObjectRustBridge, CallbackStruct, RuntimeAndroid, to_java_result};
+use android_utilities::{AndroidResult, JObjectRustBridge, CallbackStruct, RuntimeAndroid, to_java_result};
 use jni::JNIEnv;
 use jni::objects::{JClass, JObject, JString};
-use jni::sys::jlong;
+use jni::sys::{jlong, jobject};
 use lazy_static::lazy_static;
 use std::sync::Mutex;
 use cloudcore::CloudCore;
@@ -58,4 +58,17 @@ pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_CacheKt_getValue(
         let cb_struct = GET_CB_STRUCT.lock().unwrap();
         RuntimeAndroid::exec_sync(&cb_struct.jvm, result, &cb_struct.callback);
     });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_CacheKt_setEncryptionKey(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_key: JString,
+) -> jobject {
+    let key = MantleJString(j_key).to_char_ptr(env);
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let result = CLOUDCORE_API.cloudcore_set_cache_encryption_key(cloudcore, key);
+    *AndroidResult(result).to_jobject_result(env)
 }
\ No newline at end of file
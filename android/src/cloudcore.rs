@@ -44,6 +44,38 @@ pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_CloudCoreSDK_destroy(
     CLOUDCORE_API.cloudcore_destroy(cloudcore)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_CloudCoreSDK_registerCustomRegion(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_region: JString,
+    j_app_id: JString,
+    j_app_secret: JString,
+    j_user_url: JString,
+    j_device_url: JString,
+) {
+    let region = MantleJString(j_region).to_char_ptr(env);
+    let app_id = MantleJString(j_app_id).to_char_ptr(env);
+    let app_secret = MantleJString(j_app_secret).to_char_ptr(env);
+    let user_url = MantleJString(j_user_url).to_char_ptr(env);
+    let device_url = MantleJString(j_device_url).to_char_ptr(env);
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    CLOUDCORE_API.cloudcore_register_custom_region(cloudcore, region, app_id, app_secret, user_url, device_url)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_CloudCoreSDK_useCustomRegion(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_region: JString,
+) {
+    let region = MantleJString(j_region).to_char_ptr(env);
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    CLOUDCORE_API.cloudcore_use_custom_region(cloudcore, region)
+}
+
 fn create_ayla_region_environment_map() -> HashMap<AylaRegionEnvironment, ApplicationInfo> {
     let mut map: HashMap<AylaRegionEnvironment, ApplicationInfo> = HashMap::new();
 
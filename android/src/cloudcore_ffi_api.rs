@@ -6,13 +6,14 @@ use lazy_static::lazy_static;
 use log::error;
 use mantle_utilities::MantleError;
 
-use cloudcore::{CloudCore, WifiNetwork, WifiPairing, WifiPairingState};
+use cloudcore::{CloudCore, PairingConfig, WifiNetwork, WifiPairing, WifiPairingState};
 use cloudcore::authentication::UserSession;
 use cloudcore::cache::CacheDataValue;
 use cloudcore::cloudcore::{ApplicationInfo, AylaRegionEnvironment};
 use cloudcore::devices::IoTDevice;
 use cloudcore::notifications::notifications::Notification;
-use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointFile, IoTDatapointMessage};
+use cloudcore::polling::{PollConfig, PollEvent, PropertyEvent};
+use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointAck, IoTDatapointFile, IoTDatapointMessage, IoTPropertyWriteResult};
 use cloudcore::properties::property::IoTProperty;
 use cloudcore::properties::trigger::IoTTrigger;
 use cloudcore::properties::value::IoTPropertyValue;
@@ -31,6 +32,19 @@ pub struct CloudCoreAPI {
     cloudcore_destroy: fn(
         ptr_cloudcore: *mut CloudCore,
     ),
+    // Regions
+    cloudcore_register_custom_region: fn(
+        ptr_cloudcore: *mut CloudCore,
+        region: *const c_char,
+        app_id: *const c_char,
+        app_secret: *const c_char,
+        user_url: *const c_char,
+        device_url: *const c_char,
+    ),
+    cloudcore_use_custom_region: fn(
+        ptr_cloudcore: *mut CloudCore,
+        region: *const c_char,
+    ),
     // Account
     cloudcore_create_account: fn(
         ptr_cloudcore: *mut CloudCore,
@@ -160,6 +174,10 @@ pub struct CloudCoreAPI {
         key: *const c_char,
         callback: fn(result: Result<CacheDataValue, Box<MantleError>>),
     ),
+    cloudcore_set_cache_encryption_key: fn(
+        ptr_cloudcore: *mut CloudCore,
+        key: *const c_char,
+    ) -> Result<(), Box<MantleError>>,
     // Properties
     cloudcore_get_property: fn(
         ptr_cloudcore: *mut CloudCore,
@@ -185,6 +203,15 @@ pub struct CloudCoreAPI {
         callback_id: *const c_char,
         callback: fn(result: (Result<Vec<IoTDatapoint>, Box<MantleError>>, String)),
     ),
+    cloudcore_get_all_data_points: fn(
+        ptr_cloudcore: *mut CloudCore,
+        dsn: *const c_char,
+        prop_name: *const c_char,
+        from: *const c_char,
+        to: *const c_char,
+        callback_id: *const c_char,
+        callback: fn(result: (Result<Vec<IoTDatapoint>, Box<MantleError>>, String)),
+    ),
     cloudcore_get_file_property: fn(
         ptr_cloudcore: *mut CloudCore,
         dsn: *const c_char,
@@ -237,6 +264,22 @@ pub struct CloudCoreAPI {
         callback_id: *const c_char,
         callback: fn(result: (Result<(), Box<MantleError>>, String)),
     ),
+    cloudcore_set_property_value_and_wait_ack: fn(
+        ptr_cloudcore: *mut CloudCore,
+        dsn: *const c_char,
+        name: *const c_char,
+        value: *mut IoTPropertyValue,
+        timeout_millis: u64,
+        callback_id: *const c_char,
+        callback: fn(result: (Result<IoTDatapointAck, Box<MantleError>>, String)),
+    ),
+    cloudcore_set_property_values: fn(
+        ptr_cloudcore: *mut CloudCore,
+        dsn: *const c_char,
+        values: *mut Vec<(String, IoTPropertyValue)>,
+        callback_id: *const c_char,
+        callback: fn(result: (Result<Vec<IoTPropertyWriteResult>, Box<MantleError>>, String)),
+    ),
     cloudcore_get_file_property_as_files: fn(
         ptr_cloudcore: *mut CloudCore,
         dsn: *const c_char,
@@ -266,7 +309,48 @@ pub struct CloudCoreAPI {
         callback_id: *const c_char,
         callback: fn(result: (Result<(), Box<MantleError>>, String)),
     ),
+    // Polling
+    cloudcore_start_polling_manager: fn(
+        ptr_cloudcore: *mut CloudCore,
+    ),
+    cloudcore_create_poll_config: fn(
+        dsn: *const c_char,
+        names: *mut Vec<String>,
+        sleep_time: *const u64,
+        emit_snapshots: bool,
+        callback_id: *const c_char,
+        callback: fn(event: (PollEvent, String)),
+    ) -> *mut PollConfig,
+    cloudcore_add_poll: fn(
+        ptr_cloudcore: *mut CloudCore,
+        poll_config: *mut PollConfig,
+    ) -> u32,
+    cloudcore_remove_poll: fn(
+        ptr_cloudcore: *mut CloudCore,
+        poll_id: *mut u32,
+    ),
+    cloudcore_stop_polling_manager: fn(
+        ptr_cloudcore: *mut CloudCore,
+    ),
+    cloudcore_subscribe: fn(
+        ptr_cloudcore: *mut CloudCore,
+        dsn: *const c_char,
+        names: *mut Vec<String>,
+        callback_id: *const c_char,
+        callback: fn(event: (PropertyEvent, String)),
+    ) -> u32,
+    cloudcore_unsubscribe: fn(
+        ptr_cloudcore: *mut CloudCore,
+        subscription_id: u32,
+    ),
     // Pairing
+    cloudcore_create_pairing_config: fn(
+        device_max_attempts: u32,
+        device_pause_millis: u64,
+        ayla_max_attempts: u32,
+        ayla_pause_millis: u64,
+        deadline_millis: *const u64,
+    ) -> *mut PairingConfig,
     cloudcore_create_pairing_manager: fn(
         ptr_cloudcore: *const CloudCore,
         get_state_callback: fn(
@@ -278,6 +362,7 @@ pub struct CloudCoreAPI {
         result_callback: fn(
             result: Result<String, Box<MantleError>>
         ),
+        pairing_config: *mut PairingConfig,
     ) -> *mut WifiPairing,
     cloudcore_start_pairing: fn(
         ptr_wifi_manager: *mut WifiPairing,
@@ -286,6 +371,12 @@ pub struct CloudCoreAPI {
     cloudcore_continue_pairing: fn(
         ptr: *mut WifiPairing
     ),
+    cloudcore_cancel_pairing: fn(
+        ptr: *mut WifiPairing
+    ),
+    cloudcore_resume_pairing: fn(
+        ptr: *mut WifiPairing
+    ) -> Result<(), Box<MantleError>>,
     cloudcore_done_pairing: fn(
         ptr: *mut WifiPairing
     ),
@@ -300,6 +391,13 @@ pub struct CloudCoreAPI {
     cloudcore_get_pairing_log: fn(
         ptr_cloudcore: *const CloudCore,
     ) -> Result<String, Box<MantleError>>,
+    cloudcore_export_pairing_log: fn(
+        ptr_cloudcore: *const CloudCore,
+        redacted: bool,
+    ) -> Result<String, Box<MantleError>>,
+    cloudcore_get_pairing_diagnostics: fn(
+        ptr_cloudcore: *const CloudCore,
+    ) -> Result<String, Box<MantleError>>,
     // Schedules
     cloudcore_create_device_schedule: fn(
         ptr_cloudcore: *mut CloudCore,
@@ -7,6 +7,7 @@ pub mod cloudcore;
 pub mod devices;
 pub mod properties;
 pub mod pairing;
+pub mod polling;
 pub mod cache;
 pub mod account;
 pub mod schedules;
@@ -51,8 +51,8 @@ impl JavaClass<Notification> for JavaNotification {
         let user_uuid = AndroidString(self.0.user_uuid().to_owned()).to_jstring(jni_env);
         let id = AndroidString(self.0.id().to_owned()).to_jstring(jni_env);
         let dsn = AndroidString(self.0.dsn().to_owned()).to_jstring(jni_env);
-        let created_at = AndroidString(self.0.created_at().to_owned()).to_jstring(jni_env);
-        let datapoint_created_at = AndroidString(self.0.datapoint_created_at().to_owned()).to_jstring(jni_env);
+        let created_at = AndroidString(self.0.created_at().to_rfc3339()).to_jstring(jni_env);
+        let datapoint_created_at = AndroidString(self.0.datapoint_created_at().to_rfc3339()).to_jstring(jni_env);
         let read = JValue::Bool(self.0.read().into());
         let deleted = JValue::Bool(self.0.deleted().into());
         let notification_type = JValue::Int(self.0.notification_type() as i32);
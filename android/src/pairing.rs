@@ -9,8 +9,9 @@ use android_utilities::jni_exts::jobject::{invoke_callback, MantleJObject};
 use android_utilities::jni_exts::jstring::MantleJString;
 use jni::JNIEnv;
 use jni::objects::{GlobalRef, JClass, JObject, JString, JValue};
-use jni::sys::{jlong, jobject};
+use jni::sys::{jboolean, jint, jlong, jobject};
 use lazy_static::lazy_static;
+use std::ptr;
 use std::sync::Mutex;
 use log::{debug, error};
 use mantle_utilities::MantleError;
@@ -25,6 +26,7 @@ lazy_static! {
     static ref RESULT_CB_STRUCT: Mutex<CallbackStruct> = Mutex::new(CallbackStruct::new());
 }
 
+/// `j_deadline_millis` of 0 or less pairs without a deadline.
 #[no_mangle]
 pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_createPairingManager(
     env: JNIEnv,
@@ -33,8 +35,21 @@ pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_createPairingMa
     j_state_callback: JObject,
     j_wifi_networks_callback: JObject,
     j_result_callback: JObject,
+    j_device_max_attempts: jint,
+    j_device_pause_millis: jlong,
+    j_ayla_max_attempts: jint,
+    j_ayla_pause_millis: jlong,
+    j_deadline_millis: jlong,
 ) -> *const WifiPairing {
     let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let deadline_millis = j_deadline_millis as u64;
+    let config = CLOUDCORE_API.cloudcore_create_pairing_config(
+        j_device_max_attempts as u32,
+        j_device_pause_millis as u64,
+        j_ayla_max_attempts as u32,
+        j_ayla_pause_millis as u64,
+        if j_deadline_millis > 0 { &deadline_millis } else { ptr::null() },
+    );
 
     STATE_CB_STRUCT.lock().unwrap().update(env, j_state_callback);
     NETWORKS_CB_STRUCT.lock().unwrap().update(env, j_wifi_networks_callback);
@@ -43,7 +58,8 @@ pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_createPairingMa
         cloudcore,
         handle_state,
         handle_networks,
-        handle_result
+        handle_result,
+        config,
     )
 }
 
@@ -121,6 +137,27 @@ pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_continuePairing
     CLOUDCORE_API.cloudcore_continue_pairing(wifi_manager)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_cancelPairing(
+    _env: JNIEnv,
+    _class: JClass,
+    ptr_wifi_manager: jlong
+) {
+    let wifi_manager = MantleJlong(ptr_wifi_manager).to_pointer::<WifiPairing>();
+    CLOUDCORE_API.cloudcore_cancel_pairing(wifi_manager)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_resumePairing(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_wifi_manager: jlong
+) -> jobject {
+    let wifi_manager = MantleJlong(ptr_wifi_manager).to_pointer::<WifiPairing>();
+    let result = CLOUDCORE_API.cloudcore_resume_pairing(wifi_manager);
+    *AndroidResult(result).to_jobject_result(env)
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_donePairing(
     _env: JNIEnv,
@@ -169,4 +206,27 @@ pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_readPairingLog(
     let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
     let result: Result<String, Box<MantleError>> = CLOUDCORE_API.cloudcore_get_pairing_log(cloudcore);
     *AndroidStringResult(result).to_jobject_result(env)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_exportPairingLog(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_redacted: jboolean,
+) -> jobject {
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let result: Result<String, Box<MantleError>> = CLOUDCORE_API.cloudcore_export_pairing_log(cloudcore, j_redacted > 0);
+    *AndroidStringResult(result).to_jobject_result(env)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PairingKt_readPairingDiagnostics(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+) -> jobject {
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let result: Result<String, Box<MantleError>> = CLOUDCORE_API.cloudcore_get_pairing_diagnostics(cloudcore);
+    *AndroidStringResult(result).to_jobject_result(env)
 }
\ No newline at end of file
@@ -6,7 +6,7 @@ use jni::sys::jobject;
 use cloudcore::WifiPairingState;
 use ctor::ctor;
 use log::error;
-use cloudcore::WifiPairingState::{Done, Connected, DeviceScanningWifi, EndingAccessPointsScanning, FetchingDSN, GettingWifiNetworks, HandshakingWithAyla, Idle, PollingDeviceOnAyla, PollingUserInternetConnection, SendingWiFiCredentialsToDevice};
+use cloudcore::WifiPairingState::{Done, Cancelled, Connected, DeviceScanningWifi, EndingAccessPointsScanning, FetchingDSN, GettingWifiNetworks, HandshakingWithAyla, Idle, PollingDeviceOnAyla, PollingDeviceWifiStatus, PollingUserInternetConnection, SendingWiFiCredentialsToDevice, WifiNetworkNotFound, WrongWifiPassword};
 use crate::JAVA_PACKAGE;
 
 pub static WIFI_PAIRING_STATE_SIG: &str = "Lcom/sharkninja/cloudcore/WifiPairingState;";
@@ -56,11 +56,15 @@ fn field_name(state: &WifiPairingState) -> String {
         DeviceScanningWifi => "DeviceScanningWifi",
         GettingWifiNetworks => "GettingWifiNetworks",
         SendingWiFiCredentialsToDevice=> "SendingWiFiCredentialsToDevice",
+        PollingDeviceWifiStatus => "PollingDeviceWifiStatus",
+        WrongWifiPassword => "WrongWifiPassword",
+        WifiNetworkNotFound => "WifiNetworkNotFound",
         EndingAccessPointsScanning => "EndingAccessPointsScanning",
         PollingUserInternetConnection => "PollingUserInternetConnection",
         HandshakingWithAyla=> "HandshakingWithAyla",
         PollingDeviceOnAyla => "PollingDeviceOnAyla",
         Connected => "Connected",
         Done => "Done",
+        Cancelled => "Cancelled",
     }.to_string()
 }
\ No newline at end of file
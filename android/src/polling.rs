@@ -0,0 +1,213 @@
+mod poll_event;
+mod property_event;
+
+use std::collections::{HashMap, HashSet};
+use std::ptr;
+use android_utilities::{CallbackStruct, JavaClass};
+use android_utilities::java_class_names::CLASSREFSMAP;
+use android_utilities::java_signatures::VOID_SIG;
+use android_utilities::jni_exts::jlong::MantleJlong;
+use android_utilities::jni_exts::jobject::invoke_callback;
+use android_utilities::jni_exts::jobject_array::MantleJObjectArray;
+use android_utilities::jni_exts::jstring::MantleJString;
+use ffi_utilities::MantleString;
+use jni::JNIEnv;
+use jni::objects::{JClass, JObject, JString, JValue};
+use jni::sys::{jboolean, jint, jlong, jobjectArray};
+use lazy_static::lazy_static;
+use log::error;
+use std::sync::Mutex;
+use cloudcore::CloudCore;
+use cloudcore::polling::{PollEvent, PropertyEvent};
+use crate::cloudcore_ffi_api::CLOUDCORE_API;
+use crate::polling::poll_event::{JavaPollEvent, POLL_EVENT_SIG};
+use crate::polling::property_event::{JavaPropertyEvent, PROPERTY_EVENT_SIG};
+
+lazy_static! {
+    // Polls keep calling back until they are removed, so callbacks stay here until then
+    static ref POLL_CB_STRUCTS: Mutex<HashMap<String, CallbackStruct>> = Mutex::new(HashMap::new());
+    static ref POLL_CALLBACK_IDS: Mutex<HashMap<u32, String>> = Mutex::new(HashMap::new());
+    static ref REMOVED_CALLBACK_IDS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    static ref SUBSCRIPTION_CB_STRUCTS: Mutex<HashMap<String, CallbackStruct>> = Mutex::new(HashMap::new());
+    static ref SUBSCRIPTION_CALLBACK_IDS: Mutex<HashMap<u32, String>> = Mutex::new(HashMap::new());
+    static ref NEXT_CALLBACK_ID: Mutex<u64> = Mutex::new(0);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PollingKt_startPollingManager(
+    _env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+) {
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    CLOUDCORE_API.cloudcore_start_polling_manager(cloudcore);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PollingKt_addPoll(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_dsn: JString,
+    j_names: jobjectArray,
+    j_sleep_time: jlong,
+    j_emit_snapshots: jboolean,
+    j_callback: JObject,
+) -> jint {
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let dsn = MantleJString(j_dsn).to_char_ptr(env);
+    let names = if j_names.is_null() {
+        ptr::null_mut()
+    } else {
+        Box::into_raw(Box::new(MantleJObjectArray(j_names).to_list::<String, String>(env)))
+    };
+    let sleep_time = j_sleep_time as u64;
+    let callback_id = next_callback_id();
+    let mut callback = CallbackStruct::new();
+    callback.update(env, j_callback);
+    POLL_CB_STRUCTS.lock().unwrap().insert(callback_id.clone(), callback);
+    let config = CLOUDCORE_API.cloudcore_create_poll_config(
+        dsn,
+        names,
+        &sleep_time,
+        j_emit_snapshots != 0,
+        MantleString(callback_id.clone()).to_ptr(),
+        handle_poll_event,
+    );
+    let poll_id = CLOUDCORE_API.cloudcore_add_poll(cloudcore, config);
+    POLL_CALLBACK_IDS.lock().unwrap().insert(poll_id, callback_id);
+    poll_id as jint
+}
+
+fn handle_poll_event(event: (PollEvent, String)) {
+    let (event, callback_id) = event;
+    let stopped = matches!(event, PollEvent::Stopped { .. });
+    invoke_poll_callback(event, &callback_id);
+    if stopped && REMOVED_CALLBACK_IDS.lock().unwrap().remove(&callback_id) {
+        POLL_CB_STRUCTS.lock().unwrap().remove(&callback_id);
+    }
+}
+
+fn invoke_poll_callback(event: PollEvent, callback_id: &str) {
+    let callbacks = POLL_CB_STRUCTS.lock().unwrap();
+    let Some(cb_struct) = callbacks.get(callback_id) else {
+        return;
+    };
+    if let (Some(jvm), Some(callback)) = (&cb_struct.jvm, &cb_struct.callback) {
+        let env = jvm
+            .attach_current_thread_permanently()
+            .unwrap_or_else(|err| {
+                error!("Error getting jvm in spawned thread for poll event callback: {:?}", err);
+                panic!();
+            });
+        let sig = ["(", POLL_EVENT_SIG, ")", VOID_SIG].concat();
+        let java_event = JavaPollEvent(event);
+        let event_class = CLASSREFSMAP::get_class_from_name(JavaPollEvent::full_name(Some(&java_event)));
+        let event_value = java_event.j_object(env, event_class);
+        invoke_callback(env, callback, sig, &[
+            JValue::from(event_value)
+        ]);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PollingKt_removePoll(
+    _env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_poll_id: jint,
+) {
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let poll_id = j_poll_id as u32;
+    // The callback still gets the poll's stopped event, it is dropped after that.
+    // Marked before removing, the stopped event can be handled before remove_poll returns.
+    if let Some(callback_id) = POLL_CALLBACK_IDS.lock().unwrap().remove(&poll_id) {
+        REMOVED_CALLBACK_IDS.lock().unwrap().insert(callback_id);
+    }
+    CLOUDCORE_API.cloudcore_remove_poll(cloudcore, Box::into_raw(Box::new(poll_id)));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PollingKt_stopPollingManager(
+    _env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+) {
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    CLOUDCORE_API.cloudcore_stop_polling_manager(cloudcore);
+}
+
+/// `j_names` can be null to subscribe to every property of the device.
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PollingKt_subscribe(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_dsn: JString,
+    j_names: jobjectArray,
+    j_callback: JObject,
+) -> jint {
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let dsn = MantleJString(j_dsn).to_char_ptr(env);
+    let names = if j_names.is_null() {
+        ptr::null_mut()
+    } else {
+        Box::into_raw(Box::new(MantleJObjectArray(j_names).to_list::<String, String>(env)))
+    };
+    let callback_id = next_callback_id();
+    let mut callback = CallbackStruct::new();
+    callback.update(env, j_callback);
+    SUBSCRIPTION_CB_STRUCTS.lock().unwrap().insert(callback_id.clone(), callback);
+    let subscription_id = CLOUDCORE_API.cloudcore_subscribe(
+        cloudcore,
+        dsn,
+        names,
+        MantleString(callback_id.clone()).to_ptr(),
+        handle_property_event,
+    );
+    SUBSCRIPTION_CALLBACK_IDS.lock().unwrap().insert(subscription_id, callback_id);
+    subscription_id as jint
+}
+
+fn handle_property_event(event: (PropertyEvent, String)) {
+    let callbacks = SUBSCRIPTION_CB_STRUCTS.lock().unwrap();
+    let Some(cb_struct) = callbacks.get(&event.1) else {
+        return;
+    };
+    if let (Some(jvm), Some(callback)) = (&cb_struct.jvm, &cb_struct.callback) {
+        let env = jvm
+            .attach_current_thread_permanently()
+            .unwrap_or_else(|err| {
+                error!("Error getting jvm in spawned thread for property event callback: {:?}", err);
+                panic!();
+            });
+        let sig = ["(", PROPERTY_EVENT_SIG, ")", VOID_SIG].concat();
+        let java_event = JavaPropertyEvent(event.0);
+        let event_class = CLASSREFSMAP::get_class_from_name(JavaPropertyEvent::full_name(Some(&java_event)));
+        let event_value = java_event.j_object(env, event_class);
+        invoke_callback(env, callback, sig, &[
+            JValue::from(event_value)
+        ]);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PollingKt_unsubscribe(
+    _env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_subscription_id: jint,
+) {
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let subscription_id = j_subscription_id as u32;
+    CLOUDCORE_API.cloudcore_unsubscribe(cloudcore, subscription_id);
+    if let Some(callback_id) = SUBSCRIPTION_CALLBACK_IDS.lock().unwrap().remove(&subscription_id) {
+        SUBSCRIPTION_CB_STRUCTS.lock().unwrap().remove(&callback_id);
+    }
+}
+
+fn next_callback_id() -> String {
+    let mut next_id = NEXT_CALLBACK_ID.lock().unwrap();
+    *next_id += 1;
+    next_id.to_string()
+}
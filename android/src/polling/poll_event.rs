@@ -0,0 +1,154 @@
+use android_utilities::java_class_names::{CLASSNAMES, CLASSREFSMAP};
+use android_utilities::java_signatures::{STRING_SIG, VOID_SIG};
+use android_utilities::{AndroidList, JavaClass};
+use android_utilities::jni_exts::option_traits::AndroidOption;
+use android_utilities::jni_exts::string::AndroidString;
+use cloudcore::polling::{IoTPropertyChange, PollEvent};
+use cloudcore::properties::value::IoTPropertyValue;
+use ctor::ctor;
+use jni::objects::{JClass, JObject, JValue};
+use jni::sys::jobject;
+use jni::JNIEnv;
+use log::error;
+use crate::JAVA_PACKAGE;
+use crate::properties::property::{JavaIoTProperty, PROPERTY_SIG};
+use crate::properties::value::{JavaIoTPropertyValue, PROPERTY_VALUE_SIG};
+
+pub static POLL_EVENT_SIG: &str = "Lcom/sharkninja/cloudcore/PollEvent;";
+pub(crate) static PROPERTY_CHANGE_SIG: &str = "Lcom/sharkninja/cloudcore/IoTPropertyChange;";
+
+#[ctor]
+fn add_class_names() {
+    let mut names = CLASSNAMES.lock().unwrap();
+    names.push(JavaPollEvent::full_name(None));
+    names.push(JavaIoTPropertyChange::full_name(None));
+}
+
+pub struct JavaPollEvent(pub PollEvent);
+impl JavaClass<PollEvent> for JavaPollEvent {
+    fn full_name(_instance: Option<&Self>) -> String {
+        let mut name = JAVA_PACKAGE.to_owned();
+        name.push_str("PollEvent");
+        name
+    }
+
+    fn signature(_instance: Option<&Self>) -> String {
+        [
+            "(",
+            STRING_SIG,
+            STRING_SIG,
+            "[", PROPERTY_SIG,
+            "[", PROPERTY_CHANGE_SIG,
+            STRING_SIG,
+            ")",
+            VOID_SIG,
+        ]
+            .concat()
+    }
+
+    fn j_object(&self, jni_env: JNIEnv, j_class: JClass) -> jobject {
+        let signature = JavaPollEvent::signature(None);
+
+        let (r#type, properties, changes, error) = match &self.0 {
+            PollEvent::Data { properties, .. } => ("data", properties.clone(), vec![], None),
+            PollEvent::Changes { changes, .. } => ("changes", vec![], changes.clone(), None),
+            PollEvent::Error { error, .. } => ("error", vec![], vec![], Some(error.to_string())),
+            PollEvent::Stopped { .. } => ("stopped", vec![], vec![], None),
+        };
+        let r#type = AndroidString(r#type.to_string()).to_jstring(jni_env);
+        let dsn = AndroidString(self.0.dsn().to_string()).to_jstring(jni_env);
+        let java_properties: Vec<JavaIoTProperty> = properties.into_iter().map(JavaIoTProperty).collect();
+        let properties = AndroidList(java_properties).to_jobject(jni_env);
+        let java_changes: Vec<JavaIoTPropertyChange> = changes.into_iter().map(JavaIoTPropertyChange).collect();
+        let changes = AndroidList(java_changes).to_jobject(jni_env);
+        let error = error.to_j_value(jni_env);
+
+        // ** Order matters!!! Refer to com/sharkninja/cloudcore/Polling **
+        let args = &[
+            JValue::from(r#type.into_inner()),
+            JValue::from(dsn.into_inner()),
+            JValue::from(JObject::from(properties)),
+            JValue::from(JObject::from(changes)),
+            error,
+        ];
+
+        let event = jni_env
+            .new_object(j_class, signature, args)
+            .unwrap_or_else(|err| {
+                error!("Error creating Poll Event for JNI: {:?}", err);
+                jni_env.exception_describe().unwrap();
+                panic!();
+            });
+        *event
+    }
+
+    fn new(rust_object: PollEvent) -> Self {
+        Self(rust_object)
+    }
+}
+
+pub struct JavaIoTPropertyChange(pub IoTPropertyChange);
+impl JavaClass<IoTPropertyChange> for JavaIoTPropertyChange {
+    fn full_name(_instance: Option<&Self>) -> String {
+        let mut name = JAVA_PACKAGE.to_owned();
+        name.push_str("IoTPropertyChange");
+        name
+    }
+
+    fn signature(_instance: Option<&Self>) -> String {
+        [
+            "(",
+            STRING_SIG,
+            STRING_SIG,
+            PROPERTY_VALUE_SIG,
+            PROPERTY_VALUE_SIG,
+            ")",
+            VOID_SIG,
+        ]
+            .concat()
+    }
+
+    fn j_object(&self, jni_env: JNIEnv, j_class: JClass) -> jobject {
+        let signature = JavaIoTPropertyChange::signature(None);
+
+        let (r#type, old_value, new_value) = match &self.0 {
+            IoTPropertyChange::Added { value, .. } => ("added", None, value.as_ref()),
+            IoTPropertyChange::Changed { old_value, new_value, .. } => ("changed", old_value.as_ref(), new_value.as_ref()),
+            IoTPropertyChange::Removed { old_value, .. } => ("removed", old_value.as_ref(), None),
+        };
+        let r#type = AndroidString(r#type.to_string()).to_jstring(jni_env);
+        let name = AndroidString(self.0.name().to_string()).to_jstring(jni_env);
+
+        // ** Order matters!!! Refer to com/sharkninja/cloudcore/Polling **
+        let args = &[
+            JValue::from(r#type.into_inner()),
+            JValue::from(name.into_inner()),
+            property_value(jni_env, old_value),
+            property_value(jni_env, new_value),
+        ];
+
+        let change = jni_env
+            .new_object(j_class, signature, args)
+            .unwrap_or_else(|err| {
+                error!("Error creating IoT Property Change for JNI: {:?}", err);
+                jni_env.exception_describe().unwrap();
+                panic!();
+            });
+        *change
+    }
+
+    fn new(rust_object: IoTPropertyChange) -> Self {
+        Self(rust_object)
+    }
+}
+
+fn property_value<'a>(jni_env: JNIEnv<'a>, value: Option<&IoTPropertyValue>) -> JValue<'a> {
+    match value {
+        None => JValue::from(JObject::null()),
+        Some(value) => {
+            let java_value = JavaIoTPropertyValue(value.clone());
+            let j_class = CLASSREFSMAP::get_class(Some(&java_value));
+            JValue::from(java_value.j_object(jni_env, j_class))
+        }
+    }
+}
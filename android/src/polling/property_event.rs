@@ -0,0 +1,84 @@
+use android_utilities::java_class_names::{CLASSNAMES, CLASSREFSMAP};
+use android_utilities::java_signatures::{STRING_SIG, VOID_SIG};
+use android_utilities::JavaClass;
+use android_utilities::jni_exts::option_traits::AndroidOption;
+use android_utilities::jni_exts::string::AndroidString;
+use cloudcore::polling::PropertyEvent;
+use ctor::ctor;
+use jni::objects::{JClass, JObject, JValue};
+use jni::sys::jobject;
+use jni::JNIEnv;
+use log::error;
+use crate::JAVA_PACKAGE;
+use crate::polling::poll_event::{JavaIoTPropertyChange, PROPERTY_CHANGE_SIG};
+
+pub static PROPERTY_EVENT_SIG: &str = "Lcom/sharkninja/cloudcore/PropertyEvent;";
+
+#[ctor]
+fn add_class_names() {
+    let mut names = CLASSNAMES.lock().unwrap();
+    names.push(JavaPropertyEvent::full_name(None));
+}
+
+pub struct JavaPropertyEvent(pub PropertyEvent);
+impl JavaClass<PropertyEvent> for JavaPropertyEvent {
+    fn full_name(_instance: Option<&Self>) -> String {
+        let mut name = JAVA_PACKAGE.to_owned();
+        name.push_str("PropertyEvent");
+        name
+    }
+
+    fn signature(_instance: Option<&Self>) -> String {
+        [
+            "(",
+            STRING_SIG,
+            STRING_SIG,
+            PROPERTY_CHANGE_SIG,
+            STRING_SIG,
+            ")",
+            VOID_SIG,
+        ]
+            .concat()
+    }
+
+    fn j_object(&self, jni_env: JNIEnv, j_class: JClass) -> jobject {
+        let signature = JavaPropertyEvent::signature(None);
+
+        let (r#type, change, error) = match &self.0 {
+            PropertyEvent::Changed { change, .. } => ("changed", Some(change.clone()), None),
+            PropertyEvent::Error { error, .. } => ("error", None, Some(error.to_string())),
+        };
+        let r#type = AndroidString(r#type.to_string()).to_jstring(jni_env);
+        let dsn = AndroidString(self.0.dsn().to_string()).to_jstring(jni_env);
+        let change = match change {
+            None => JValue::from(JObject::null()),
+            Some(change) => {
+                let java_change = JavaIoTPropertyChange(change);
+                let j_class = CLASSREFSMAP::get_class(Some(&java_change));
+                JValue::from(java_change.j_object(jni_env, j_class))
+            }
+        };
+        let error = error.to_j_value(jni_env);
+
+        // ** Order matters!!! Refer to com/sharkninja/cloudcore/Polling **
+        let args = &[
+            JValue::from(r#type.into_inner()),
+            JValue::from(dsn.into_inner()),
+            change,
+            error,
+        ];
+
+        let event = jni_env
+            .new_object(j_class, signature, args)
+            .unwrap_or_else(|err| {
+                error!("Error creating Property Event for JNI: {:?}", err);
+                jni_env.exception_describe().unwrap();
+                panic!();
+            });
+        *event
+    }
+
+    fn new(rust_object: PropertyEvent) -> Self {
+        Self(rust_object)
+    }
+}
@@ -1,4 +1,4 @@
-mod property;
+pub(crate) mod property;
 pub mod value;
 mod datapoint;
 
@@ -18,9 +18,9 @@ use lazy_static::lazy_static;
 use std::sync::Mutex;
 use ffi_utilities::MantleString;
 use mantle_utilities::MantleError;
-use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointFile, IoTDatapointMessage};
+use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointAck, IoTDatapointFile, IoTDatapointMessage, IoTPropertyWriteResult};
 use cloudcore::properties::property::IoTProperty;
-use crate::properties::datapoint::{JavaIoTDatapoint, JavaIoTDatapointFile, JavaIoTDatapointMessage};
+use crate::properties::datapoint::{JavaIoTDatapoint, JavaIoTDatapointAck, JavaIoTDatapointFile, JavaIoTDatapointMessage, JavaIoTPropertyWriteResult};
 use crate::properties::property::JavaIoTProperty;
 use crate::properties::value::JavaIoTPropertyValue;
 
@@ -35,6 +35,8 @@ lazy_static! {
     static ref MULTI_MSG_CB_STRUCT: Mutex<CallbackStructMap> = Mutex::new(CallbackStructMap::new());
     static ref MSG_DATAPOINT_CB_STRUCT: Mutex<CallbackStructMap> = Mutex::new(CallbackStructMap::new());
     static ref SET_PROP_CB_STRUCT: Mutex<CallbackStructMap> = Mutex::new(CallbackStructMap::new());
+    static ref SET_PROP_ACK_CB_STRUCT: Mutex<CallbackStructMap> = Mutex::new(CallbackStructMap::new());
+    static ref SET_PROPS_CB_STRUCT: Mutex<CallbackStructMap> = Mutex::new(CallbackStructMap::new());
     static ref SAVE_FILE_CB_STRUCT: Mutex<CallbackStructMap> = Mutex::new(CallbackStructMap::new());
     static ref FILES_CB_STRUCT: Mutex<CallbackStructMap> = Mutex::new(CallbackStructMap::new());
     static ref MSG_PROPERTY_AS_FILES_CB_STRUCT: Mutex<CallbackStructMap> = Mutex::new(CallbackStructMap::new());
@@ -131,6 +133,30 @@ fn handle_data_points(result: (Result<Vec<IoTDatapoint>, Box<MantleError>>, Stri
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PropertiesKt_getAllDataPoints(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_dsn: JString,
+    j_prop_name: JString,
+    j_from: JString,
+    j_to: JString,
+    j_callback: JObject,
+) {
+    let dsn = MantleJString(j_dsn).to_char_ptr(env);
+    let prop_name = MantleJString(j_prop_name).to_char_ptr(env);
+    let from = MantleJString(j_from).to_char_ptr(env);
+    let to = MantleJString(j_to).to_char_ptr(env);
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let mut cb_struct = DATA_POINTS_CB_STRUCT.lock().unwrap();
+    let next_id = cb_struct.get_next_id_as_string();
+    let struct_callback_id = MantleString(next_id.clone()).to_ptr();
+    let mut struct_callback = CallbackStruct::new();
+    struct_callback.update(env, j_callback);
+    cb_struct.update(struct_callback, next_id.clone());
+    CLOUDCORE_API.cloudcore_get_all_data_points(cloudcore, dsn, prop_name, from, to, struct_callback_id, handle_data_points);
+}
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PropertiesKt_getFileProperty(
@@ -336,6 +362,77 @@ fn handle_set_prop(result: (Result<(), Box<MantleError>>, String)) {
     }
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PropertiesKt_setPropertyValueAndWaitAck(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_dsn: JString,
+    j_name: JString,
+    j_value: JObject,
+    j_timeout_millis: jlong,
+    j_callback: JObject,
+) {
+    let dsn = MantleJString(j_dsn).to_char_ptr(env);
+    let name = MantleJString(j_name).to_char_ptr(env);
+    let value = JavaIoTPropertyValue::rust_object(MantleJObject(j_value), env).unwrap();
+    let boxed_value = Box::into_raw(Box::new(value));
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let mut cb_struct = SET_PROP_ACK_CB_STRUCT.lock().unwrap();
+    let next_id = cb_struct.get_next_id_as_string();
+    let struct_callback_id = MantleString(next_id.clone()).to_ptr();
+    let mut struct_callback = CallbackStruct::new();
+    struct_callback.update(env, j_callback);
+    cb_struct.update(struct_callback, next_id.clone());
+    CLOUDCORE_API.cloudcore_set_property_value_and_wait_ack(cloudcore, dsn, name, boxed_value, j_timeout_millis as u64, struct_callback_id, handle_set_prop_ack);
+}
+
+fn handle_set_prop_ack(result: (Result<IoTDatapointAck, Box<MantleError>>, String)) {
+    let res = to_java_result::<_, JavaIoTDatapointAck>(result.0);
+    if let Some(cb_struct) = SET_PROP_ACK_CB_STRUCT.lock().unwrap().get(result.1) {
+        RuntimeAndroid::exec(&cb_struct.jvm, res, &cb_struct.callback);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PropertiesKt_setPropertyValues(
+    env: JNIEnv,
+    _class: JClass,
+    ptr_cloudcore: jlong,
+    j_dsn: JString,
+    j_names: jobjectArray,
+    j_values: jobjectArray,
+    j_callback: JObject,
+) {
+    let dsn = MantleJString(j_dsn).to_char_ptr(env);
+    let names = MantleJObjectArray(j_names).to_list::<String, String>(env);
+    // ** Names and values are parallel arrays, refer to com/sharkninja/cloudcore/Properties **
+    let values = names
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let j_value = env.get_object_array_element(j_values, index as i32).unwrap();
+            (name, JavaIoTPropertyValue::rust_object(MantleJObject(j_value), env).unwrap())
+        })
+        .collect::<Vec<_>>();
+    let boxed_values = Box::into_raw(Box::new(values));
+    let cloudcore = MantleJlong(ptr_cloudcore).to_pointer::<CloudCore>();
+    let mut cb_struct = SET_PROPS_CB_STRUCT.lock().unwrap();
+    let next_id = cb_struct.get_next_id_as_string();
+    let struct_callback_id = MantleString(next_id.clone()).to_ptr();
+    let mut struct_callback = CallbackStruct::new();
+    struct_callback.update(env, j_callback);
+    cb_struct.update(struct_callback, next_id.clone());
+    CLOUDCORE_API.cloudcore_set_property_values(cloudcore, dsn, boxed_values, struct_callback_id, handle_set_props);
+}
+
+fn handle_set_props(result: (Result<Vec<IoTPropertyWriteResult>, Box<MantleError>>, String)) {
+    let res = to_java_result_list::<_, JavaIoTPropertyWriteResult>(result.0);
+    if let Some(cb_struct) = SET_PROPS_CB_STRUCT.lock().unwrap().get(result.1) {
+        RuntimeAndroid::exec_list(&cb_struct.jvm, res, &cb_struct.callback);
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn Java_com_sharkninja_cloudcore_PropertiesKt_getFilePropertyAsFiles(
     env: JNIEnv,
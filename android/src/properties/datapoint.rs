@@ -8,7 +8,7 @@ use jni::JNIEnv;
 use jni::objects::{JClass, JValue};
 use jni::sys::jobject;
 use log::error;
-use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointFile, IoTDatapointMessage};
+use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointAck, IoTDatapointFile, IoTDatapointMessage, IoTPropertyWriteResult};
 use crate::JAVA_PACKAGE;
 use crate::properties::value::{JavaIoTPropertyValue, PROPERTY_VALUE_SIG};
 
@@ -18,6 +18,8 @@ fn add_class_names() {
     names.push(JavaIoTDatapointFile::full_name(None));
     names.push(JavaIoTDatapoint::full_name(None));
     names.push(JavaIoTDatapointMessage::full_name(None));
+    names.push(JavaIoTDatapointAck::full_name(None));
+    names.push(JavaIoTPropertyWriteResult::full_name(None));
 }
 
 pub struct JavaIoTDatapoint(pub IoTDatapoint);
@@ -45,8 +47,8 @@ impl JavaClass<IoTDatapoint> for JavaIoTDatapoint {
     fn j_object(&self, jni_env: JNIEnv, j_class: JClass) -> jobject {
         let signature = JavaIoTDatapoint::signature(None);
 
-        let updated_at = self.0.updated_at().map(String::from).to_j_value(jni_env);
-        let created_at = self.0.created_at().map(String::from).to_j_value(jni_env);
+        let updated_at = self.0.updated_at().map(|datetime| datetime.to_rfc3339()).to_j_value(jni_env);
+        let created_at = self.0.created_at().map(|datetime| datetime.to_rfc3339()).to_j_value(jni_env);
         let echo = self.0.echo().to_owned().to_j_value(jni_env);
         let java_value = JavaIoTPropertyValue(self.0.value().clone());
         let j_value_class = CLASSREFSMAP::get_class(Some(&java_value));
@@ -172,8 +174,8 @@ impl JavaClass<IoTDatapointMessage> for JavaIoTDatapointMessage {
         let signature = JavaIoTDatapointMessage::signature(None);
 
         let user_uuid = self.0.metadata.user_uuid().map(String::from).to_j_value(jni_env);
-        let updated_at = self.0.updated_at.to_owned().to_j_value(jni_env);
-        let created_at = self.0.created_at.to_owned().to_j_value(jni_env);
+        let updated_at = self.0.updated_at.map(|datetime| datetime.to_rfc3339()).to_j_value(jni_env);
+        let created_at = self.0.created_at.map(|datetime| datetime.to_rfc3339()).to_j_value(jni_env);
         let echo = self.0.echo.to_owned().to_j_value(jni_env);
         let local_file = AndroidString(self.0.local_file.to_owned()).to_jstring(jni_env);
 
@@ -198,4 +200,97 @@ impl JavaClass<IoTDatapointMessage> for JavaIoTDatapointMessage {
     fn new(rust_object: IoTDatapointMessage) -> Self {
         Self(rust_object)
     }
+}
+
+pub struct JavaIoTDatapointAck(pub IoTDatapointAck);
+impl JavaClass<IoTDatapointAck> for JavaIoTDatapointAck {
+    fn full_name(_instance: Option<&Self>) -> String {
+        let mut name = JAVA_PACKAGE.to_owned();
+        name.push_str("IoTDataPointAck");
+        name
+    }
+
+    fn signature(_instance: Option<&Self>) -> String {
+        [
+            "(",
+            STRING_SIG,
+            STRING_SIG,
+            STRING_SIG,
+            ")",
+            VOID_SIG,
+        ]
+            .concat()
+    }
+
+    fn j_object(&self, jni_env: JNIEnv, j_class: JClass) -> jobject {
+        let signature = JavaIoTDatapointAck::signature(None);
+
+        let ack_status = self.0.ack_status().map(String::from).to_j_value(jni_env);
+        let ack_message = self.0.ack_message().map(String::from).to_j_value(jni_env);
+        let acked_at = self.0.acked_at().map(|datetime| datetime.to_rfc3339()).to_j_value(jni_env);
+
+        // ** Order matters!!! Refer to com/sharkninja/cloudcore/DataPoint **
+        let args = &[
+            ack_status,
+            ack_message,
+            acked_at,
+        ];
+
+        let ack = jni_env
+            .new_object(j_class, signature, args)
+            .unwrap_or_else(|err| {
+                error!("Error creating IoT DataPoint Ack for JNI: {:?}", err);
+                jni_env.exception_describe().unwrap();
+                panic!();
+            });
+        *ack
+    }
+    fn new(rust_object: IoTDatapointAck) -> Self {
+        Self(rust_object)
+    }
+}
+
+pub struct JavaIoTPropertyWriteResult(pub IoTPropertyWriteResult);
+impl JavaClass<IoTPropertyWriteResult> for JavaIoTPropertyWriteResult {
+    fn full_name(_instance: Option<&Self>) -> String {
+        let mut name = JAVA_PACKAGE.to_owned();
+        name.push_str("IoTPropertyWriteResult");
+        name
+    }
+
+    fn signature(_instance: Option<&Self>) -> String {
+        [
+            "(",
+            STRING_SIG,
+            STRING_SIG,
+            ")",
+            VOID_SIG,
+        ]
+            .concat()
+    }
+
+    fn j_object(&self, jni_env: JNIEnv, j_class: JClass) -> jobject {
+        let signature = JavaIoTPropertyWriteResult::signature(None);
+
+        let name = AndroidString(self.0.name().to_owned()).to_jstring(jni_env);
+        let error = self.0.error().map(String::from).to_j_value(jni_env);
+
+        // ** Order matters!!! Refer to com/sharkninja/cloudcore/Properties **
+        let args = &[
+            JValue::from(name.into_inner()),
+            error,
+        ];
+
+        let write_result = jni_env
+            .new_object(j_class, signature, args)
+            .unwrap_or_else(|err| {
+                error!("Error creating IoT Property Write Result for JNI: {:?}", err);
+                jni_env.exception_describe().unwrap();
+                panic!();
+            });
+        *write_result
+    }
+    fn new(rust_object: IoTPropertyWriteResult) -> Self {
+        Self(rust_object)
+    }
 }
\ No newline at end of file
@@ -12,6 +12,8 @@ use jni::JNIEnv;
 use log::error;
 use crate::JAVA_PACKAGE;
 
+pub static PROPERTY_SIG: &str = "Lcom/sharkninja/cloudcore/IoTProperty;";
+
 #[ctor]
 fn add_class_names() {
     let mut names = CLASSNAMES.lock().unwrap();
@@ -66,7 +68,7 @@ impl JavaClass<IoTProperty> for JavaIoTProperty {
         let read_only = JValue::Bool(self.0.read_only().into());
         let direction = AndroidString(self.0.direction().to_owned()).to_jstring(jni_env);
         let scope = AndroidString(self.0.scope().to_owned()).to_jstring(jni_env);
-        let data_updated_at = self.0.data_updated_at().map(String::from).to_j_value(jni_env);//AndroidString(self.0.data_updated_at().to_owned()).to_jstring(jni_env);
+        let data_updated_at = self.0.data_updated_at().map(|datetime| datetime.to_rfc3339()).to_j_value(jni_env);
         let key = self.0.key().to_owned().to_j_value(jni_env);
         let device_key = self.0.device_key().to_owned().to_j_value(jni_env);
         let product_name = AndroidString(self.0.product_name().to_owned()).to_jstring(jni_env);
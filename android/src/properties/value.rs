@@ -1,10 +1,10 @@
 use android_utilities::java_class_names::CLASSNAMES;
-use android_utilities::java_signatures::{BOOL_SIG, INT_SIG, STRING_SIG, VOID_SIG};
+use android_utilities::java_signatures::{BOOL_SIG, DOUBLE_SIG, INT_SIG, LONG_SIG, STRING_SIG, VOID_SIG};
 use android_utilities::{JavaClass, JObjectRustBridge};
 use android_utilities::jni_exts::jobject::MantleJObject;
 use android_utilities::jni_exts::string::AndroidString;
 use cloudcore::properties::value::IoTPropertyValue;
-use cloudcore::properties::value::IoTPropertyValue::{Bool, Int, Str};
+use cloudcore::properties::value::IoTPropertyValue::{Bool, Decimal, Int, Int64, Json, Str};
 use ctor::ctor;
 use jni::objects::{JClass, JValue};
 use jni::sys::jobject;
@@ -20,6 +20,9 @@ fn add_class_names() {
     names.push(JavaIoTPropertyValue::full_name(Some(&default_int_value())));
     names.push(JavaIoTPropertyValue::full_name(Some(&default_str_value())));
     names.push(JavaIoTPropertyValue::full_name(Some(&default_bool_value())));
+    names.push(JavaIoTPropertyValue::full_name(Some(&default_long_value())));
+    names.push(JavaIoTPropertyValue::full_name(Some(&default_double_value())));
+    names.push(JavaIoTPropertyValue::full_name(Some(&default_json_value())));
 }
 
 fn default_int_value() -> JavaIoTPropertyValue {
@@ -31,6 +34,15 @@ fn default_str_value() -> JavaIoTPropertyValue {
 fn default_bool_value() -> JavaIoTPropertyValue {
     JavaIoTPropertyValue(IoTPropertyValue::Bool(false))
 }
+fn default_long_value() -> JavaIoTPropertyValue {
+    JavaIoTPropertyValue(IoTPropertyValue::Int64(0))
+}
+fn default_double_value() -> JavaIoTPropertyValue {
+    JavaIoTPropertyValue(IoTPropertyValue::Decimal(0.0))
+}
+fn default_json_value() -> JavaIoTPropertyValue {
+    JavaIoTPropertyValue(IoTPropertyValue::Json(serde_json::Value::Null))
+}
 
 pub struct JavaIoTPropertyValue(pub IoTPropertyValue);
 impl JavaClass<IoTPropertyValue> for JavaIoTPropertyValue {
@@ -42,6 +54,9 @@ impl JavaClass<IoTPropertyValue> for JavaIoTPropertyValue {
                 Int(_) => "Int",
                 Str(_) => "String",
                 Bool(_) => "Boolean",
+                Int64(_) => "Long",
+                Decimal(_) => "Double",
+                Json(_) => "Json",
             },
         ]
         .concat()
@@ -52,6 +67,10 @@ impl JavaClass<IoTPropertyValue> for JavaIoTPropertyValue {
             Int(_) => ["(", INT_SIG, ")", VOID_SIG],
             Str(_) => ["(", STRING_SIG, ")", VOID_SIG],
             Bool(_) => ["(", BOOL_SIG, ")", VOID_SIG],
+            Int64(_) => ["(", LONG_SIG, ")", VOID_SIG],
+            Decimal(_) => ["(", DOUBLE_SIG, ")", VOID_SIG],
+            // JSON goes over as its string form
+            Json(_) => ["(", STRING_SIG, ")", VOID_SIG],
         }
         .concat()
     }
@@ -67,6 +86,13 @@ impl JavaClass<IoTPropertyValue> for JavaIoTPropertyValue {
                     .into_inner(),
             ),
             Bool(bool) => JValue::Bool((*bool).into()),
+            Int64(long) => JValue::Long(*long),
+            Decimal(double) => JValue::Double(*double),
+            Json(json) => JValue::from(
+                AndroidString(json.to_string())
+                    .to_jstring(jni_env)
+                    .into_inner(),
+            ),
         };
 
         let args = &[value];
@@ -119,6 +145,36 @@ impl JObjectRustBridge<IoTPropertyValue> for JavaIoTPropertyValue {
             {
                 let value = j_object.to_bool_field(env, "value");
                 property_value = Some(IoTPropertyValue::Bool(value))
+            } else if env
+                .is_instance_of(
+                    j_object.0,
+                    JavaIoTPropertyValue::full_name(Some(&default_long_value())),
+                )
+                .unwrap()
+            {
+                let value = env
+                    .get_field(j_object.0, "value", LONG_SIG)
+                    .and_then(|value| value.j())
+                    .unwrap_or_default();
+                property_value = Some(IoTPropertyValue::Int64(value))
+            } else if env
+                .is_instance_of(
+                    j_object.0,
+                    JavaIoTPropertyValue::full_name(Some(&default_double_value())),
+                )
+                .unwrap()
+            {
+                let value = j_object.to_double_field(env, "value");
+                property_value = Some(IoTPropertyValue::Decimal(value))
+            } else if env
+                .is_instance_of(
+                    j_object.0,
+                    JavaIoTPropertyValue::full_name(Some(&default_json_value())),
+                )
+                .unwrap()
+            {
+                let value = j_object.to_string_field(env, "value");
+                property_value = serde_json::from_str(&value).ok().map(IoTPropertyValue::Json)
             }
         }
         property_value
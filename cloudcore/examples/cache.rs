@@ -74,7 +74,7 @@ async fn run_happy_path_real_tests() -> Result<(), Box<dyn Error>> {
             Ok(())
         },
         Err(err) => {
-            Err(err)
+            Err(err.into())
         }
     }
 }
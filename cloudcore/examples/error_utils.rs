@@ -3,6 +3,7 @@ use std::env;
 use log::LevelFilter;
 use simplelog::{Config, SimpleLogger};
 use cloudcore::CloudCore;
+use mantle_utilities::MantleError;
 use cloudcore::examples::utils::get_cloudcore;
 
 #[tokio::main]
@@ -14,6 +15,7 @@ async fn run_sad_fetch_devices() -> Result<(), Box<dyn Error>> {
             return Ok(());
         }
         Err(err) => {
+            let err = MantleError::from(err);
             let error_value: &str = "ServerError";
             println!("Returned Error: {}", err);
             if error_value.eq(&err.to_string()) {
@@ -34,6 +36,7 @@ async fn run_sad_login_tests() -> Result<(), Box<dyn Error>> {
             return Ok(());
         }
         Err(err) => {
+            let err = MantleError::from(err);
             let error_value: &str = "EmailOrPhoneNumberMissing";
             println!("Returned Error: {}", err);
             if error_value.eq(&err.to_string()) {
@@ -55,6 +58,7 @@ async fn run_sad_request_password_reset_tests() -> Result<(), Box<dyn Error>> {
             return Ok(());
         }
         Err(err) => {
+            let err = MantleError::from(err);
             let error_value: &str = "EmailOrPhoneNumberMissing";
             println!("Returned Error: {}", err);
             if error_value.eq(&err.to_string()) {
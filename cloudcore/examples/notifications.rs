@@ -4,6 +4,7 @@ use std::error::Error;
 use log::LevelFilter;
 use simplelog::{Config, SimpleLogger};
 
+use cloudcore::datetime::parse_datetime;
 use cloudcore::examples::utils::get_cloudcore;
 
 #[tokio::main]
@@ -31,14 +32,15 @@ async fn run_happy_path_real_tests() -> Result<String, Box<dyn Error>> {
     }
     let dsn = &args[3];
 
+    let from = parse_datetime("2022-05-12T00:00:00Z")?;
     let all_notifications = cloudcore.fetch_all_notifications(
-        "05/12/22".to_string()
+        Some(from)
     ).await?;
     println!("Fetched ALL {} notifications: {:?}", all_notifications.len(), all_notifications);
 
     let notifications = cloudcore.fetch_notifications(
         dsn.to_string(),
-        "05/12/22".to_string(),
+        Some(from),
     ).await?;
     println!("Fetched {} notifications: {:?}", notifications.len(), notifications);
 
@@ -47,13 +49,13 @@ async fn run_happy_path_real_tests() -> Result<String, Box<dyn Error>> {
     println!("Marked all Notifications as READ: {}", read_ok);
 
 
-    let to = "2022-05-24T03:36:55Z";
-    cloudcore.delete_all_notifications(to.to_string()).await?;
+    let to = parse_datetime("2022-05-24T03:36:55Z")?;
+    cloudcore.delete_all_notifications(to).await?;
     println!("Mark notifications up to {} as DELETED", to);
 
     let notifications = cloudcore.fetch_notifications(
         dsn.to_string(),
-        "05/12/22".to_string(),
+        Some(from),
     ).await?;
     // NOTE: These should have all hit the cache and have read=true and deleted=true
     println!("Re-fetched {} notifications: {:?}", notifications.len(), notifications);
@@ -72,7 +72,7 @@ async fn run_happy_path_real_tests() -> Result<String, Box<dyn Error>> {
             get_wifi_network(AtomicUsize::new(m), networks);
         }),
         Box::new(|result| {
-            completed(result)
+            completed(result.map_err(|err| err.into()))
         }),
         Some(token),
     );
@@ -5,8 +5,7 @@ use std::time::Duration;
 use log::LevelFilter;
 use simplelog::{Config, SimpleLogger};
 use cloudcore::examples::utils::get_cloudcore;
-use cloudcore::polling::PollConfig;
-use cloudcore::properties::property::IoTProperty;
+use cloudcore::polling::{PollConfig, PollEvent};
 
 #[tokio::main]
 async fn run_happy_path_real_tests() -> Result<(), Box<dyn Error>> {
@@ -43,7 +42,7 @@ async fn run_happy_path_real_tests() -> Result<(), Box<dyn Error>> {
         None,
         None,
         Some(dsn.to_string()),
-        Some(handle_poll),
+        Some(Box::new(handle_poll)),
     );
     let poll_2_props = vec![
         "GET_Charging_Status".to_string(),
@@ -53,7 +52,11 @@ async fn run_happy_path_real_tests() -> Result<(), Box<dyn Error>> {
         None,
         Some(8000),
         Some(dsn.to_string()),
-        Some(handle_poll),
+        Some(Box::new(|event| {
+            if let PollEvent::Changes { changes, .. } = event {
+                println!("Charging status changed: {:?}", changes);
+            }
+        })),
     );
     cloudcore.start_polling_manager();
     sleep(Duration::from_secs(3)); // Sleep for 3 seconds before adding a poll
@@ -70,8 +73,13 @@ async fn run_happy_path_real_tests() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn handle_poll(dsn: String, props: Vec<IoTProperty>) {
-    println!("Polled {} and got back: {:?}", dsn, props);
+fn handle_poll(event: PollEvent) {
+    match event {
+        PollEvent::Data { dsn, properties } => println!("Polled {} and got back: {:?}", dsn, properties),
+        PollEvent::Changes { dsn, changes } => println!("Properties changed on {}: {:?}", dsn, changes),
+        PollEvent::Error { dsn, error } => println!("Polling {} failed: {}", dsn, error),
+        PollEvent::Stopped { dsn } => println!("Stopped polling {}", dsn),
+    }
 }
 
 fn main() {
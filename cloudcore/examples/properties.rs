@@ -6,6 +6,7 @@ use cloudcore::properties::{value::IoTPropertyValue, datapoint};
 use log::LevelFilter;
 use simplelog::{Config, SimpleLogger};
 use cloudcore::examples::utils::get_cloudcore;
+use cloudcore::datetime::parse_datetime;
 
 #[tokio::main]
 async fn run_happy_path_real_tests() -> Result<(), Box<dyn Error>> {
@@ -38,8 +39,8 @@ async fn run_happy_path_real_tests() -> Result<(), Box<dyn Error>> {
     let datapoints = cloudcore.get_file_property_as_files(
         dsn.to_owned(), "GET_Visual_Floor_1".to_string(),
         Some(10),
-        Some(from.to_string()),
-        Some(to.to_string()),
+        Some(from),
+        Some(to),
     ).await?;
     println!("Got {} file datapoints between {} and {} back: {:?}", datapoints.len(), from, to, datapoints);*/
 
@@ -91,6 +92,27 @@ async fn run_happy_path_real_tests() -> Result<(), Box<dyn Error>> {
                         property_value.bool_value()
                     );
                 }
+                IoTPropertyValue::Int64(it) => {
+                    println!(
+                        "This is an i64 -> {:?} {:?}",
+                        it,
+                        property_value.int64_value()
+                    );
+                }
+                IoTPropertyValue::Decimal(it) => {
+                    println!(
+                        "This is an f64 -> {:?} {:?}",
+                        it,
+                        property_value.decimal_value()
+                    );
+                }
+                IoTPropertyValue::Json(it) => {
+                    println!(
+                        "This is JSON -> {:?} {:?}",
+                        it,
+                        property_value.json_value()
+                    );
+                }
             }
         }
     });
@@ -142,24 +164,24 @@ async fn run_happy_path_real_tests() -> Result<(), Box<dyn Error>> {
     ).await.0?;
     println!("Got {}=={} datapoints back: {:?}", limit, datapoints.len(), datapoints);
 
-    let from = "2021-12-01";
-    let to = "2022-01-01";
+    let from = parse_datetime("2021-12-01T00:00:00Z")?;
+    let to = parse_datetime("2022-01-01T00:00:00Z")?;
     let datapoints = cloudcore.get_datapoints(
         dsn.to_owned(), "SET_Operating_Mode".to_string(),
         Some(10),
-        Some(from.to_string()),
-        Some(to.to_string()),
+        Some(from),
+        Some(to),
         "".to_string()
     ).await.0?;
     println!("Got {} datapoints between {} and {} back: {:?}", datapoints.len(), from, to, datapoints);
 
-    let from = "2022-07-23";
-    let to = "2022-08-22";
+    let from = parse_datetime("2022-07-23T00:00:00Z")?;
+    let to = parse_datetime("2022-08-22T00:00:00Z")?;
     let _ = cloudcore.get_file_property_as_files_callback(
         dsn.to_owned(), "GET_Cleaning_Statistics".to_string(),
         Some(10),
-        Some(from.to_string()),
-        Some(to.to_string()),
+        Some(from),
+        Some(to),
         "".to_string(),
         |result| {
             if let Some(datapoints) = result.0.ok() {
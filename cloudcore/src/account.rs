@@ -3,7 +3,7 @@ mod user_account;
 #[cfg(feature = "library")]
 use self::user_account::{CreateAccountResponse, UserRequest};
 #[cfg(feature = "library")]
-use crate::ErrorUtil;
+use crate::cloudcore_error::InputError;
 #[cfg(feature = "library")]
 use crate::cloudcore::{ApplicationInfo, CloudCore};
 #[cfg(feature = "library")]
@@ -13,19 +13,24 @@ use serde::Serialize;
 #[cfg(feature = "library")]
 use tokio::time::sleep;
 #[cfg(feature = "library")]
-use std::error::Error;
+use crate::CloudCoreError;
 #[cfg(feature = "library")]
 use std::time::Duration;
 #[cfg(feature = "library")]
 use crate::authentication::{CACHE_USER_DIR, CACHE_USER_SESSION_KEY};
 #[cfg(feature = "library")]
 use crate::cache::CacheInteract;
+#[cfg(feature = "library")]
+use crate::secret::Secret;
 
+#[cfg(feature = "library")]
+use crate::cloudcore_client::{CloudCoreBaseURL, CloudCoreRequest};
 #[cfg(feature = "library")]
 use crate::urls::{
-    AUTHORIZATION_BEARER, AUTHORIZATION_HEADER, AYLA_CONFIRMATION_JSON, AYLA_PASSWORD_JSON,
-    AYLA_UPDATE_EMAIL_JSON, AYLA_USER_JSON,
+    AYLA_CONFIRMATION_JSON, AYLA_PASSWORD_JSON, AYLA_UPDATE_EMAIL_JSON, AYLA_USER_JSON,
 };
+#[cfg(feature = "library")]
+use reqwest::Method;
 
 #[cfg(feature = "library")]
 impl CloudCore {
@@ -38,13 +43,13 @@ impl CloudCore {
         email_template_id: Option<String>,
         email_subject: Option<String>,
         email_body_html: Option<String>,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         debug!("Sleeping for 1 second so Ayla can't blame us for sending requests within '300ms'");
         sleep(Duration::from_secs(1)).await;
         #[derive(Serialize, Debug)]
         struct User {
             email: String,
-            password: String,
+            password: Secret<String>,
             firstname: String,
             lastname: String,
             application: ApplicationInfo,
@@ -106,7 +111,7 @@ impl CloudCore {
         let user_data = UserRequest {
             user: User {
                 email,
-                password,
+                password: Secret::new(password),
                 firstname: "FirstName".to_string(),
                 lastname: "LastName".to_string(),
                 application: self.session_params().app_info.clone(),
@@ -120,41 +125,39 @@ impl CloudCore {
                 primary_contact
             },
         };
-        let mut url = String::from(&self.session_params().user_url);
-        url.push_str(AYLA_USER_JSON);
-        let response = self.client()
-            .post(url)
-            .query(&query)
-            .json(&user_data)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            let create_account_payload = response.json::<CreateAccountResponse>().await?;
-            debug!("create account payload: {:#?}", create_account_payload);
-            // If an error is thrown here it does need to propagate up because it just affects the cache
-            let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
-            Ok(())
-        } else {
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint: String::from(AYLA_USER_JSON),
+                method: Method::POST,
+                requires_auth: false,
+                query,
+                body: Some(user_data),
+            }
+        ).await?;
+        let create_account_payload = response.json::<CreateAccountResponse>().await?;
+        debug!("create account payload: {:#?}", create_account_payload);
+        // If an error is thrown here it does need to propagate up because it just affects the cache
+        let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
+        Ok(())
     }
 
-    pub async fn confirm_account(&self, token: String) -> Result<(), Box<dyn Error>> {
-        let query: Vec<(&str, String)> = vec![("confirmation_token", token.trim().to_string())];
-        let mut url = String::from(&self.session_params().user_url);
-        url.push_str(AYLA_CONFIRMATION_JSON);
-        let response = self.client().put(url).query(&query).send().await?;
-        if response.status().is_success() {
-            let create_account_payload = response.json::<CreateAccountResponse>().await?;
-            debug!("create account payload: {:#?}", create_account_payload);
-            Ok(())
-        } else {
-            // Return any errors, since this could fail because
-            // input is incorrect
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+    pub async fn confirm_account(&self, token: String) -> Result<(), CloudCoreError> {
+        let body: Option<String> = None;
+        // Errors are returned as is, since this could fail because input is incorrect
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint: String::from(AYLA_CONFIRMATION_JSON),
+                method: Method::PUT,
+                requires_auth: false,
+                query: vec![("confirmation_token", token.trim().to_string())],
+                body,
+            }
+        ).await?;
+        let create_account_payload = response.json::<CreateAccountResponse>().await?;
+        debug!("create account payload: {:#?}", create_account_payload);
+        Ok(())
     }
 
     pub async fn send_confirmation_instructions(
@@ -164,9 +167,9 @@ impl CloudCore {
         email_template_id: Option<String>,
         email_subject: Option<String>,
         email_body_html: Option<String>,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         if email.is_none() && phone_number.is_none() {
-            return Err(Box::new(ErrorUtil::email_or_phone_number_missing_error()));
+            return Err(CloudCoreError::InvalidInput(InputError::EmailOrPhoneNumberMissing));
         }
         let mut username = None;
         let mut query: Vec<(&str, String)> = vec![];
@@ -185,49 +188,35 @@ impl CloudCore {
             username = Some(phone_number.as_ref().unwrap().to_string());
         }
         let user_data = UserRequest::new(email, username, self.session_params().app_info.clone());
-        let mut url = String::from(&self.session_params().user_url);
-        url.push_str(AYLA_CONFIRMATION_JSON);
-        let response = self.client()
-            .post(url)
-            .query(&query)
-            .json(&user_data)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+        self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint: String::from(AYLA_CONFIRMATION_JSON),
+                method: Method::POST,
+                requires_auth: false,
+                query,
+                body: Some(user_data),
+            }
+        ).await?;
+        Ok(())
     }
 
-    pub async fn delete_account(&mut self) -> Result<(), Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-        let mut url = String::from(&self.session_params().user_url);
-        let endpoint = String::from(AYLA_USER_JSON);
-        url.push_str(&endpoint);
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
-
-        let client = self.client();
-
-        let response = client
-            .delete(url)
-            .header(AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            self.user_session = None;
-            // If an error is thrown here it does need to propagate up because it just affects the cache
-            let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
-            Ok(())
-        } else {
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+    pub async fn delete_account(&mut self) -> Result<(), CloudCoreError> {
+        let body: Option<String> = None;
+        self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint: String::from(AYLA_USER_JSON),
+                method: Method::DELETE,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
+        self.user_session = None;
+        // If an error is thrown here it does need to propagate up because it just affects the cache
+        let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
+        Ok(())
     }
 
     pub async fn request_password_reset(
@@ -237,9 +226,9 @@ impl CloudCore {
         email_template_id: Option<String>,
         email_subject: Option<String>,
         email_body_html: Option<String>,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         if email.is_none() && phone.is_none() {
-            return Err(Box::new(ErrorUtil::email_or_phone_number_missing_error()));
+            return Err(CloudCoreError::InvalidInput(InputError::EmailOrPhoneNumberMissing));
         }
         let mut username = None;
         let mut query: Vec<(&str, String)> = vec![];
@@ -273,22 +262,18 @@ impl CloudCore {
         self.set_ayla_region_environment(use_dev);
         let user_data = UserRequest::new(email, username, self.session_params().app_info.clone());
         debug!("User data for password request: {:#?}", user_data);
-        let mut url = String::from(&self.session_params().user_url);
-        url.push_str(AYLA_PASSWORD_JSON);
-        let response = self.client()
-            .post(url)
-            .query(&query)
-            .json(&user_data)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            // Return any errors, since this could fail because
-            // input is incorrect
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+        // Errors are returned as is, since this could fail because input is incorrect
+        self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint: String::from(AYLA_PASSWORD_JSON),
+                method: Method::POST,
+                requires_auth: false,
+                query,
+                body: Some(user_data),
+            }
+        ).await?;
+        Ok(())
     }
 
     pub async fn reset_password(
@@ -296,18 +281,15 @@ impl CloudCore {
         token: String,
         password: String,
         password_confirmation: String,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         if password != password_confirmation {
-            return Err(Box::new(ErrorUtil::passwords_mismatch_error()));
+            return Err(CloudCoreError::InvalidInput(InputError::PasswordsMismatch));
         }
-        let mut url = String::from(&self.session_params().user_url);
-        let endpoint = String::from(AYLA_PASSWORD_JSON);
-        url.push_str(&endpoint);
         #[derive(Serialize, Debug)]
         struct PasswordUser {
-            reset_password_token: String,
-            password: String,
-            password_confirmation: String,
+            reset_password_token: Secret<String>,
+            password: Secret<String>,
+            password_confirmation: Secret<String>,
         }
         #[derive(Serialize, Debug)]
         struct PasswordRequest {
@@ -315,49 +297,38 @@ impl CloudCore {
         }
         let pw_data = PasswordRequest {
             user: PasswordUser {
-                reset_password_token: token.trim().to_string(),
-                password,
-                password_confirmation,
+                reset_password_token: Secret::new(token.trim().to_string()),
+                password: Secret::new(password),
+                password_confirmation: Secret::new(password_confirmation),
             },
         };
-        let response = self.client()
-            .put(url)
-            .json(&pw_data)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            self.user_session = None;
-            // If an error is thrown here it does need to propagate up because it just affects the cache
-            // Even though the user session is not used, it could still exist
-            let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
-            Ok(())
-        } else {
-            // Return any errors, since this could fail because
-            // input is incorrect
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+        // Errors are returned as is, since this could fail because input is incorrect
+        self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint: String::from(AYLA_PASSWORD_JSON),
+                method: Method::PUT,
+                requires_auth: false,
+                query: vec![],
+                body: Some(pw_data),
+            }
+        ).await?;
+        self.user_session = None;
+        // If an error is thrown here it does need to propagate up because it just affects the cache
+        // Even though the user session is not used, it could still exist
+        let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
+        Ok(())
     }
 
     pub async fn reset_password_for_current_user(
         &mut self,
         current_password: String,
         new_password: String,
-    ) -> Result<(), Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-        let mut url = String::from(&self.session_params().user_url);
-        let endpoint = String::from(AYLA_USER_JSON);
-        url.push_str(&endpoint);
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
-
+    ) -> Result<(), CloudCoreError> {
         #[derive(Serialize, Debug)]
         struct PasswordUser {
-            password: String,
-            current_password: String,
+            password: Secret<String>,
+            current_password: Secret<String>,
         }
         #[derive(Serialize, Debug)]
         struct PasswordRequest {
@@ -365,63 +336,50 @@ impl CloudCore {
         }
         let pw_data = PasswordRequest {
             user: PasswordUser {
-                current_password,
-                password: new_password,
+                current_password: Secret::new(current_password),
+                password: Secret::new(new_password),
             },
         };
 
-        let response = self.client()
-            .put(url)
-            .header(AUTHORIZATION_HEADER, auth_bearer)
-            .json(&pw_data)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            self.user_session = None;
-            // If an error is thrown here it does need to propagate up because it just affects the cache
-            // Even though the user session is not used, it could still exist
-            let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
-            Ok(())
-        } else {
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+        self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint: String::from(AYLA_USER_JSON),
+                method: Method::PUT,
+                requires_auth: true,
+                query: vec![],
+                body: Some(pw_data),
+            }
+        ).await?;
+        self.user_session = None;
+        // If an error is thrown here it does need to propagate up because it just affects the cache
+        // Even though the user session is not used, it could still exist
+        let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
+        Ok(())
     }
 
-    pub async fn update_email(&mut self, new_email: String) -> Result<(), Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-        let mut url = String::from(&self.session_params().user_url);
-        let endpoint = String::from(AYLA_UPDATE_EMAIL_JSON);
-        url.push_str(&endpoint);
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
+    pub async fn update_email(&mut self, new_email: String) -> Result<(), CloudCoreError> {
         let calculated_new_email = match new_email.strip_prefix("dev@") {
             Some(trimmed) => {
                 trimmed.to_string()
             },
             None => new_email
         };
-        let query: Vec<(&str, String)> = vec![("email", calculated_new_email)];
-        let client = self.client();
-
-        let response = client
-            .put(url)
-            .query(&query)
-            .header(AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            // This invalidates the user session in Ayla, so remove it here as well
-            self.user_session = None;
-            // If an error is thrown here it does need to propagate up because it just affects the cache
-            let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
-            Ok(())
-        } else {
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+        let body: Option<String> = None;
+        self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint: String::from(AYLA_UPDATE_EMAIL_JSON),
+                method: Method::PUT,
+                requires_auth: true,
+                query: vec![("email", calculated_new_email)],
+                body,
+            }
+        ).await?;
+        // This invalidates the user session in Ayla, so remove it here as well
+        self.user_session = None;
+        // If an error is thrown here it does need to propagate up because it just affects the cache
+        let _ = self.cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
+        Ok(())
     }
 }
@@ -8,18 +8,21 @@ pub use self::user_profile::UserProfile;
 #[cfg(feature = "library")]
 use crate::cloudcore::{ApplicationInfo, CloudCore};
 #[cfg(feature = "library")]
-use crate::ErrorUtil;
+use crate::cloudcore_error::InputError;
 #[cfg(feature = "library")]
 use crate::urls::{
-    AUTHORIZATION_BEARER, AUTHORIZATION_HEADER, AYLA_REFRESH_TOKEN_JSON, AYLA_SIGN_IN_JSON,
-    AYLA_SIGN_OUT_JSON, AYLA_USER_PROFILE_JSON,
+    AYLA_REFRESH_TOKEN_JSON, AYLA_SIGN_IN_JSON, AYLA_SIGN_OUT_JSON, AYLA_USER_PROFILE_JSON,
 };
 #[cfg(feature = "library")]
+use crate::cloudcore_client::{CloudCoreBaseURL, CloudCoreRequest};
+#[cfg(feature = "library")]
+use reqwest::Method;
+#[cfg(feature = "library")]
 use log::{debug, error};
 #[cfg(feature = "library")]
 use serde::Serialize;
 #[cfg(feature = "library")]
-use std::error::Error;
+use crate::CloudCoreError;
 #[cfg(feature = "library")]
 use std::time::SystemTime;
 #[cfg(feature = "library")]
@@ -30,6 +33,8 @@ use crate::cache::CacheDir;
 use crate::cache::CacheInteract;
 #[cfg(feature = "library")]
 use crate::cloudcore::SELECTED_REGION_CACHE_KEY;
+#[cfg(feature = "library")]
+use crate::secret::Secret;
 
 pub static CACHE_USER_DIR: &str = "user";
 pub static CACHE_USER_SESSION_KEY: &str = "session";
@@ -41,15 +46,15 @@ impl CloudCore {
         email: Option<String>,
         phone: Option<String>,
         password: String,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         if email.is_none() && phone.is_none() {
-            return Err(Box::new(ErrorUtil::email_or_phone_number_missing_error()));
+            return Err(CloudCoreError::InvalidInput(InputError::EmailOrPhoneNumberMissing));
         }
         // local structs for login request and response
         #[derive(Serialize, Debug)]
         struct UserPayload {
             email: String,
-            password: String,
+            password: Secret<String>,
             application: ApplicationInfo,
         }
 
@@ -77,7 +82,7 @@ impl CloudCore {
         let post_data = LoginRequest {
             user: UserPayload {
                 email: calculated_email,
-                password,
+                password: Secret::new(password),
                 application: ApplicationInfo { app_id, app_secret },
             },
         };
@@ -92,8 +97,9 @@ impl CloudCore {
         if !response.status().is_success() {
             // Return any errors, since this could fail because
             // input is incorrect
+            let status = response.status().as_u16();
             let error_payload = response.text().await?;
-            return Err(Box::new(ErrorUtil::login_error(error_payload)))
+            return Err(CloudCoreError::from_login_response(status, error_payload))
         }
         let login_payload = response.json::<LoginResponse>().await?;
         debug!("login payload: {:#?}", login_payload);
@@ -129,9 +135,9 @@ impl CloudCore {
     }
 
     // TODO: Just return an Option<UserSession> instead of a result
-    pub fn get_session(&self) -> Result<UserSession, Box<dyn Error>> {
+    pub fn get_session(&self) -> Result<UserSession, CloudCoreError> {
         if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
+            return Err(CloudCoreError::SessionMissing);
         }
         Ok(self.user_session.clone().unwrap())
     }
@@ -143,9 +149,9 @@ impl CloudCore {
         };
     }
 
-    pub async fn refresh_session(&mut self) -> Result<(), Box<dyn Error>> {
+    pub async fn refresh_session(&mut self) -> Result<(), CloudCoreError> {
         if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
+            return Err(CloudCoreError::SessionMissing);
         }
         let use_dev = self.user_session.as_ref().unwrap().use_dev();
         // This sets the session parameters based on the country stored in cache and if the environment should be dev or not
@@ -155,7 +161,7 @@ impl CloudCore {
         url.push_str(&endpoint);
         #[derive(Serialize, Debug)]
         struct UserRefresh {
-            refresh_token: String,
+            refresh_token: Secret<String>,
         }
         #[derive(Serialize, Debug)]
         struct UserRefreshRequest {
@@ -163,7 +169,7 @@ impl CloudCore {
         }
         let refresh_data = UserRefreshRequest {
             user: UserRefresh {
-                refresh_token: self.user_session.as_ref().unwrap().refresh_token().to_string(),
+                refresh_token: Secret::new(self.user_session.as_ref().unwrap().refresh_token().to_string()),
             },
         };
         let response = self.client()
@@ -185,7 +191,7 @@ impl CloudCore {
                 refresh_payload.refresh_token(),
                 expire_time,
                 current_user_session.auth_username().to_string(),
-                Some(current_user_session.user_uuid().unwrap().to_string()),
+                current_user_session.user_uuid().cloned(),
                 use_dev
             );
             self.user_session = Some(user_session.clone());
@@ -194,8 +200,7 @@ impl CloudCore {
             };
             Ok(())
         } else {
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
+            Err(CloudCoreError::from_response(response).await)
         }
     }
 
@@ -216,9 +221,9 @@ impl CloudCore {
         }
     }
 
-    pub async fn logout(&mut self) -> Result<(), Box<dyn Error>> {
+    pub async fn logout(&mut self) -> Result<(), CloudCoreError> {
         if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
+            return Err(CloudCoreError::SessionMissing);
         }
         let mut url = String::from(&self.session_params().user_url);
         let endpoint = String::from(AYLA_SIGN_OUT_JSON);
@@ -265,30 +270,19 @@ impl CloudCore {
         Ok(())
     }
 
-    pub async fn get_user_profile(&self) -> Result<UserProfile, Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-        let mut url = String::from(&self.session_params().user_url);
-        let endpoint = String::from(AYLA_USER_PROFILE_JSON);
-        url.push_str(&endpoint);
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
-
-        let client = self.client();
-
-        let response = client
-            .get(url)
-            .header(AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            let user_profile_payload = response.json::<UserProfile>().await?;
-            Ok(user_profile_payload)
-        } else {
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+    pub async fn get_user_profile(&self) -> Result<UserProfile, CloudCoreError> {
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint: String::from(AYLA_USER_PROFILE_JSON),
+                method: Method::GET,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
+        let user_profile_payload = response.json::<UserProfile>().await?;
+        Ok(user_profile_payload)
     }
 }
\ No newline at end of file
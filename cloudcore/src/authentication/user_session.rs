@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
+use crate::secret::Secret;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserSession {
-    access_token: String,
-    refresh_token: String,
+    access_token: Secret<String>,
+    refresh_token: Secret<String>,
     auth_expiration_date: u64,
     auth_username: String,
     user_uuid: Option<String>,
@@ -20,8 +21,8 @@ impl UserSession {
         use_dev: bool
     ) -> Self {
         Self {
-            access_token,
-            refresh_token,
+            access_token: Secret::new(access_token),
+            refresh_token: Secret::new(refresh_token),
             auth_expiration_date,
             auth_username,
             user_uuid,
@@ -31,11 +32,11 @@ impl UserSession {
 
     /// Get a reference to the user session's access token.
     pub fn access_token(&self) -> &str {
-        self.access_token.as_ref()
+        self.access_token.expose()
     }
     /// Get a reference to the user session's refresh token.
     pub fn refresh_token(&self) -> &str {
-        self.refresh_token.as_ref()
+        self.refresh_token.expose()
     }
     /// Get a reference to the user session's auth expiration date.
     pub fn auth_expiration_date(&self) -> u64 {
@@ -62,19 +63,19 @@ impl UserSession {
 
 #[derive(Deserialize, Debug)]
 pub struct LoginResponse {
-    access_token: String,
-    refresh_token: String,
+    access_token: Secret<String>,
+    refresh_token: Secret<String>,
     expires_in: u32,
     role: String,
 }
 
 impl LoginResponse {
     pub fn access_token(&self) -> String {
-        self.access_token.clone()
+        self.access_token.expose().clone()
     }
 
     pub fn refresh_token(&self) -> String {
-        self.refresh_token.clone()
+        self.refresh_token.expose().clone()
     }
 
     pub fn expires_in(&self) -> u32 {
@@ -14,21 +14,42 @@ use log::{debug, error};
 #[cfg(feature = "library")]
 use crate::cloudcore::CACHE_APP_DIR;
 #[cfg(feature = "library")]
-use crate::io::{read_from_disk_to_string, write_to_disk};
+use crate::io::{read_from_disk_to_string, write_to_disk_atomically};
 #[cfg(feature = "library")]
 use crate::urls::CRATE_WORKSPACE;
 #[cfg(feature = "library")]
-use crate::ErrorUtil;
+use crate::cloudcore_error::CacheError;
 #[cfg(feature = "library")]
-use mantle_utilities::{ErrorType, MantleError};
+use crate::secret::Secret;
+#[cfg(feature = "library")]
+use aes_gcm::aead::{Aead, KeyInit};
+#[cfg(feature = "library")]
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+#[cfg(feature = "library")]
+use rand::RngCore;
+#[cfg(feature = "library")]
+use sha2::{Digest, Sha256};
 
-use std::error::Error;
+use crate::CloudCoreError;
 use std::sync::Mutex;
 use serde::Serialize;
 use serde_json::{Value};
 
 #[cfg(feature = "library")]
 static CACHE_HIDDEN_FILE_NAME: &str = "/.store";
+// Encrypted stores are this prefix followed by the base64 of the nonce and the AES-256-GCM ciphertext
+#[cfg(feature = "library")]
+static ENCRYPTED_STORE_PREFIX: &str = "cloudcore-aes256gcm-v1:";
+#[cfg(feature = "library")]
+static NONCE_LENGTH: usize = 12;
+// Stores start with a line of this prefix and the sha256 of the rest of the file
+#[cfg(feature = "library")]
+static STORE_HEADER_PREFIX: &str = "cloudcore-store-";
+#[cfg(feature = "library")]
+static STORE_VERSION: &str = "v1";
+/// Length in bytes of the key `Cache::set_encryption_key` takes.
+#[cfg(feature = "library")]
+pub static CACHE_ENCRYPTION_KEY_LENGTH: usize = 32;
 
 #[derive(Serialize, Debug)]
 pub enum CacheDataValue {
@@ -48,16 +69,16 @@ pub trait CacheInteract {
     /// println!("{:?}", value.as_str());
     /// ```
     /// It is completely safe to unwrap since it can return None which avoids the lib to panic.
-    fn get_value(&self, path: String, key: String) -> Result<CacheDataValue, Box<dyn Error>>;
+    fn get_value(&self, path: String, key: String) -> Result<CacheDataValue, CloudCoreError>;
 
     /// Set data to the mutable content. You can pass in any type which can be
     /// serde::Serialize since it needs to be converted at some point to
     /// serde_json::Value to be stored.
-    fn set_value<T>(&mut self, path: String, key: String, value: T) -> Result<(), Box<dyn Error>> where T: Serialize;
+    fn set_value<T>(&mut self, path: String, key: String, value: T) -> Result<(), CloudCoreError> where T: Serialize;
 
     /// Remove the value for the key at the specified path. If the path does not exist
     /// an error is returned. If the key does not exist it is a NoOp
-    fn remove_value(&mut self, path: String, key: String) -> Result<(), Box<dyn Error>>;
+    fn remove_value(&mut self, path: String, key: String) -> Result<(), CloudCoreError>;
 }
 
 pub trait CacheDir {
@@ -67,7 +88,7 @@ pub trait CacheDir {
     /// to run once, however it calls recursively the create_dir_all.
     /// Once the child path has been set it gets inserted
     /// into the map for Cache for constant time lookup and easy storage.
-    fn make_dir_for_child<'a>(&mut self, path: &'a str) -> Result<PathBuf, Box<dyn Error>>;
+    fn make_dir_for_child<'a>(&mut self, path: &'a str) -> Result<PathBuf, CloudCoreError>;
 
     /// If the parent_dir exist and its been given
     /// by the consuming OS we can then delete the
@@ -75,7 +96,7 @@ pub trait CacheDir {
     /// to run once, however it calls recursively the remove_dir_all.
     /// Once the child path has been deleted it gets removed
     /// from the map.
-    fn remove_dir_for_child<'a>(&mut self, path: &'a str) -> Result<(), Box<dyn Error>>;
+    fn remove_dir_for_child<'a>(&mut self, path: &'a str) -> Result<(), CloudCoreError>;
 
     /// Remove key from child_paths
     fn remove_child_path(&mut self, key: String);
@@ -84,12 +105,13 @@ pub trait CacheDir {
     /// In the module we can read and mutate content but then the
     /// file needs to be recreated. This does not hurt performance
     /// or cause any poor behavior. All file is always up to date and no file or data is lingering.
-    fn touch_file_for_child(&self, child_dir: &Path, bytes: Option<String>) -> Result<(), Box<dyn Error>>;
+    /// The file is written next to the store and renamed over it, so a crash never leaves it half written.
+    fn touch_file_for_child(&self, child_dir: &Path, bytes: Option<String>) -> Result<(), CloudCoreError>;
 
     /// Read the contents of the files stored in the cache directory by a
     /// given path. Retrieve as a io buffer stream and return a
     /// string construct from it for easy parsing of data to structs or T types.
-    fn stream_buffer_from_child<'a>(&self, path: &'a str) -> Result<String, Box<dyn Error>>;
+    fn stream_buffer_from_child<'a>(&self, path: &'a str) -> Result<String, CloudCoreError>;
 }
 
 #[derive(Debug)]
@@ -105,6 +127,10 @@ pub struct Cache {
     /// ```../parent_path/child_paths...```
     #[cfg(feature = "library")]
     child_paths: HashMap<String, PathBuf>,
+
+    /// Stores are encrypted with this key when it is set, see `set_encryption_key`.
+    #[cfg(feature = "library")]
+    encryption_key: Option<Secret<[u8; 32]>>,
     #[allow(dead_code)]
     lock: Mutex<usize>,
 }
@@ -130,9 +156,9 @@ impl Cache {
     /// OS file directory where we can have access to
     /// read and write to persist our data. We pass in the OS file dir
     /// as a String and then it gets formatted into a path to be consumed by the lib
-    pub fn new(os_dir: String) -> Result<Self, Box<dyn Error>> {
+    pub fn new(os_dir: String) -> Result<Self, CloudCoreError> {
         if os_dir.is_empty() {
-            return Err(Box::new(ErrorUtil::path_empty_error()));
+            return Err(CloudCoreError::Cache(CacheError::PathEmpty));
         }
 
         let uri = format!("{}/{}", &os_dir, CRATE_WORKSPACE);
@@ -141,10 +167,10 @@ impl Cache {
         let file_dir = Path::new(&uri).to_path_buf();
         if !file_dir.exists() {
             let error = format!("Malformed path or incorrect path was given -> {:?}", &file_dir);
-            return Err(Box::new(ErrorUtil::malformed_or_incorrect_path(error)));
+            return Err(CloudCoreError::Cache(CacheError::MalformedPath(error)));
         }
 
-        let mut instance = Self { parent_path: file_dir, child_paths: HashMap::new(), lock: Mutex::new(0) };
+        let mut instance = Self { parent_path: file_dir, child_paths: HashMap::new(), encryption_key: None, lock: Mutex::new(0) };
         debug!("Parent Path: {:?}", &instance.parent_path.as_path());
 
         let mut children = HashMap::new();
@@ -188,6 +214,129 @@ impl Cache {
     }
 }
 
+#[cfg(feature = "library")]
+// Encryption at rest
+impl Cache {
+    /// Encrypt every store with AES-256-GCM from now on. Stores still in plain JSON are
+    /// encrypted right away. A store that was encrypted with another key can not be read
+    /// anymore, so it is emptied.
+    pub fn set_encryption_key(&mut self, key: [u8; 32]) -> Result<(), CloudCoreError> {
+        let _lock = self.lock.lock().map_err(|err| CloudCoreError::Cache(CacheError::Io(err.to_string())))?;
+        self.encryption_key = Some(Secret::new(key));
+        for (child, path) in &self.child_paths {
+            let store = format!("{}{}", path.to_string_lossy(), CACHE_HIDDEN_FILE_NAME);
+            if !Path::new(&store).is_file() {
+                continue;
+            }
+            let contents = read_from_disk_to_string(&store)?;
+            match self.open_store(contents).and_then(|contents| self.decrypt_store(contents)) {
+                Ok(contents) => self.touch_file_for_child(path, Some(contents))?,
+                Err(err) => {
+                    error!("Emptying cache {} that can not be read: {}", child, err);
+                    self.touch_file_for_child(path, None)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption_key.is_some()
+    }
+
+    fn encrypt_store(&self, contents: String) -> Result<String, CloudCoreError> {
+        let key = match self.encryption_key.as_ref() {
+            Some(key) => key,
+            None => return Ok(contents),
+        };
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.expose()));
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), contents.as_bytes())
+            .map_err(|_| CloudCoreError::Cache(CacheError::Encryption("Could not encrypt cache".to_string())))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(format!("{}{}", ENCRYPTED_STORE_PREFIX, base64::encode(sealed)))
+    }
+
+    /// Plain JSON stores written before a key was set are returned as they are.
+    fn decrypt_store(&self, contents: String) -> Result<String, CloudCoreError> {
+        let sealed = match contents.strip_prefix(ENCRYPTED_STORE_PREFIX) {
+            Some(sealed) => sealed,
+            None => return Ok(contents),
+        };
+        let key = match self.encryption_key.as_ref() {
+            Some(key) => key,
+            None => return Err(CloudCoreError::Cache(CacheError::Encryption("Cache is encrypted and no key was set".to_string()))),
+        };
+        let sealed = base64::decode(sealed.trim())
+            .map_err(|err| CloudCoreError::Cache(CacheError::Encryption(format!("Encrypted cache is not base64: {}", err))))?;
+        if sealed.len() < NONCE_LENGTH {
+            return Err(CloudCoreError::Cache(CacheError::Encryption("Encrypted cache is too short".to_string())));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.expose()));
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| CloudCoreError::Cache(CacheError::Encryption("Could not decrypt cache, the key is wrong or the store is corrupt".to_string())))?;
+        String::from_utf8(plaintext).map_err(|err| CloudCoreError::Cache(CacheError::Encryption(err.to_string())))
+    }
+}
+
+#[cfg(feature = "library")]
+// Integrity and recovery
+impl Cache {
+    fn seal_store(&self, contents: String) -> String {
+        format!("{}{} {}\n{}", STORE_HEADER_PREFIX, STORE_VERSION, store_checksum(&contents), contents)
+    }
+
+    /// Check the header and return what follows it. Stores written before the header
+    /// existed are returned as they are and get one on their next write.
+    fn open_store(&self, contents: String) -> Result<String, CloudCoreError> {
+        let sealed = match contents.strip_prefix(STORE_HEADER_PREFIX) {
+            Some(sealed) => sealed,
+            None => return Ok(contents),
+        };
+        let (header, body) = sealed
+            .split_once('\n')
+            .ok_or_else(|| CloudCoreError::Cache(CacheError::Corrupt("Store header is not terminated".to_string())))?;
+        let (version, checksum) = header
+            .split_once(' ')
+            .ok_or_else(|| CloudCoreError::Cache(CacheError::Corrupt("Store header has no checksum".to_string())))?;
+        if version != STORE_VERSION {
+            return Err(CloudCoreError::Cache(CacheError::Corrupt(format!("Unsupported store version {}", version))));
+        }
+        if checksum != store_checksum(body) {
+            return Err(CloudCoreError::Cache(CacheError::Corrupt("Store checksum does not match".to_string())));
+        }
+        Ok(body.to_string())
+    }
+
+    /// Read the store of `path`. A corrupt store is replaced by an empty one so the
+    /// cache keeps working, the values in it are lost.
+    fn read_cache_data(&self, path: &str) -> Result<CacheData, CloudCoreError> {
+        let cache = self
+            .stream_buffer_from_child(path)
+            .and_then(|io_buffer| serde_json::from_str::<CacheData>(&io_buffer).map_err(|err| CloudCoreError::Cache(CacheError::Corrupt(err.to_string()))));
+        match cache {
+            Err(CloudCoreError::Cache(CacheError::Corrupt(err))) => {
+                error!("Discarding corrupt cache {}: {}", path, err);
+                if let Some(hash_path) = self.child_paths.get(path) {
+                    self.touch_file_for_child(hash_path, None)?;
+                }
+                Ok(CacheData { data: HashMap::new() })
+            }
+            cache => cache,
+        }
+    }
+}
+
+#[cfg(feature = "library")]
+fn store_checksum(contents: &str) -> String {
+    Sha256::digest(contents.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
 #[cfg(feature = "library")]
 // Borrowed Getters
 impl Cache {
@@ -206,18 +355,18 @@ impl Cache {
 #[cfg(feature = "library")]
 // OS and File Dir Handlers
 impl CacheDir for Cache {
-    fn make_dir_for_child<'a>(&mut self, path: &'a str) -> Result<PathBuf, Box<dyn Error>> {
+    fn make_dir_for_child<'a>(&mut self, path: &'a str) -> Result<PathBuf, CloudCoreError> {
         if path.is_empty() {
-            return Err(Box::new(ErrorUtil::path_empty_error()));
+            return Err(CloudCoreError::Cache(CacheError::PathEmpty));
         }
 
         let parent_dir = self.parent_path();
         if !parent_dir.exists() {
-            return Err(Box::new(ErrorUtil::parent_directory_missing()));
+            return Err(CloudCoreError::Cache(CacheError::ParentDirectoryMissing));
         }
         let parent_dir_str = parent_dir.to_str();
         if parent_dir_str.is_none() {
-            return Err("Could not get parent directory as a string".into());
+            return Err(CloudCoreError::Cache(CacheError::MalformedPath("Could not get parent directory as a string".to_string())));
         }
         let mut uri = parent_dir_str.unwrap().to_string();
         uri.push_str("/");
@@ -227,7 +376,7 @@ impl CacheDir for Cache {
         let child_dir = Path::new(&uri);
         if !child_dir.exists() {
             let error = format!("{:?}", &child_dir);
-            return Err(Box::new(ErrorUtil::malformed_or_incorrect_path(error)));
+            return Err(CloudCoreError::Cache(CacheError::MalformedPath(error)));
         }
         debug!("Child Path: {:?}", &child_dir);
 
@@ -240,14 +389,14 @@ impl CacheDir for Cache {
         Ok(path_buf.to_owned())
     }
 
-    fn remove_dir_for_child<'a>(&mut self, path: &'a str) -> Result<(), Box<dyn Error>> {
+    fn remove_dir_for_child<'a>(&mut self, path: &'a str) -> Result<(), CloudCoreError> {
         if path.is_empty() {
-            return Err(Box::new(ErrorUtil::path_empty_error()));
+            return Err(CloudCoreError::Cache(CacheError::PathEmpty));
         }
 
         let parent_dir = self.parent_path();
         if !parent_dir.exists() {
-            return Err(Box::new(ErrorUtil::parent_directory_missing()));
+            return Err(CloudCoreError::Cache(CacheError::ParentDirectoryMissing));
         }
 
         match fs::remove_dir_all(path) {
@@ -255,10 +404,7 @@ impl CacheDir for Cache {
                 self.child_paths.remove(path);
                 Ok(())
             }
-            Err(err) => Err(Box::new(MantleError {
-                error_type: ErrorType::DiskError,
-                description: err.to_string()
-            }))
+            Err(err) => Err(err.into())
         }
     }
 
@@ -266,13 +412,13 @@ impl CacheDir for Cache {
         self.child_paths.remove(&key);
     }
 
-    fn touch_file_for_child(&self, child_dir: &Path, bytes: Option<String>) -> Result<(), Box<dyn Error>> {
+    fn touch_file_for_child(&self, child_dir: &Path, bytes: Option<String>) -> Result<(), CloudCoreError> {
         if !child_dir.exists() {
-            return Err(Box::new(ErrorUtil::path_empty_error()));
+            return Err(CloudCoreError::Cache(CacheError::PathEmpty));
         }
         let child_dir_str = child_dir.to_str();
         if child_dir_str.is_none() {
-            return Err("Could not get child directory as a string".into());
+            return Err(CloudCoreError::Cache(CacheError::MalformedPath("Could not get child directory as a string".to_string())));
         }
         let mut uri = child_dir_str.unwrap().to_string();
         uri.push_str(CACHE_HIDDEN_FILE_NAME);
@@ -282,20 +428,22 @@ impl CacheDir for Cache {
             Some(b) => {  object = b; }
             None => {}
         }
-        write_to_disk(Path::new(&uri), Bytes::from(object.into_bytes()))
+        let object = self.seal_store(self.encrypt_store(object)?);
+        write_to_disk_atomically(Path::new(&uri), Bytes::from(object.into_bytes()))
     }
 
-    fn stream_buffer_from_child<'a>(&self, path: &'a str) -> Result<String, Box<dyn Error>> {
+    fn stream_buffer_from_child<'a>(&self, path: &'a str) -> Result<String, CloudCoreError> {
         if let Some(hash_path) = self.child_paths.get(path) {
             if let Some(hash_path_str) = hash_path.to_str() {
                 let mut child = hash_path_str.to_string();
                 child.push_str(CACHE_HIDDEN_FILE_NAME);
-                read_from_disk_to_string(&child)
+                let contents = self.open_store(read_from_disk_to_string(&child)?)?;
+                self.decrypt_store(contents)
             } else {
-                Err("Could not get hash path as a string to stream buffer from child".into())
+                Err(CloudCoreError::Cache(CacheError::MalformedPath("Could not get hash path as a string to stream buffer from child".to_string())))
             }
         } else {
-            Err("Could not get hash path to stream buffer from child".into())
+            Err(CloudCoreError::Cache(CacheError::MalformedPath("Could not get hash path to stream buffer from child".to_string())))
         }
     }
 }
@@ -303,21 +451,19 @@ impl CacheDir for Cache {
 #[cfg(feature = "library")]
 // User Facing Handlers
 impl CacheInteract for Cache {
-    fn get_value(&self, path: String, key: String) -> Result<CacheDataValue, Box<dyn Error>> {
+    fn get_value(&self, path: String, key: String) -> Result<CacheDataValue, CloudCoreError> {
         match self.lock.lock() {
             Ok(_) => {
                 if path.is_empty() {
-                    return Err(Box::new(ErrorUtil::path_empty_error()));
+                    return Err(CloudCoreError::Cache(CacheError::PathEmpty));
                 }
 
                 if key.is_empty() {
-                    return Err(Box::new(ErrorUtil::cache_key_missing()));
+                    return Err(CloudCoreError::Cache(CacheError::KeyMissing));
                 }
 
                 if self.child_paths.contains_key(&path) {
-                    let io_buffer = self.stream_buffer_from_child(&path)?;
-
-                    let cache: CacheData = serde_json::from_str(&io_buffer)?;
+                    let cache = self.read_cache_data(&path)?;
                     let value = cache.data.get(&key).unwrap_or(&Value::Null);
 
                     let result = match value {
@@ -332,14 +478,14 @@ impl CacheInteract for Cache {
                                     let i = integer as i32;
                                     CacheDataValue::IntegerValue(i)
                                 } else {
-                                    return Err(Box::new(ErrorUtil::int_parse_error()));
+                                    return Err(CloudCoreError::Parse("Not able to parse i64 to i32".to_string()));
                                 }
                             } else if it.is_f64() {
                                 let dbl = it.as_f64();
                                 if let Some(dub) = dbl {
                                     CacheDataValue::DoubleValue(dub)
                                 } else {
-                                    return Err(Box::new(ErrorUtil::double_parse_error()));
+                                    return Err(CloudCoreError::Parse("Not able to retrieve double".to_string()));
                                 }
                             } else {
                                 CacheDataValue::NullValue
@@ -360,21 +506,20 @@ impl CacheInteract for Cache {
 
                     Ok(result)
                 } else {
-                    return Err(Box::new(ErrorUtil::child_directory_missing()));
+                    return Err(CloudCoreError::Cache(CacheError::ChildDirectoryMissing));
                 }
             }
             Err(err) => {
-                let e = err.to_string();
-                return Err(e.into())
+                return Err(CloudCoreError::Cache(CacheError::Io(err.to_string())))
             }
         }
     }
 
-    fn set_value<T>(&mut self, path: String, key: String, value: T) -> Result<(), Box<dyn Error>> where T: Serialize {
+    fn set_value<T>(&mut self, path: String, key: String, value: T) -> Result<(), CloudCoreError> where T: Serialize {
         match self.lock.lock() {
             Ok(_) => {
                 if path.is_empty() {
-                    return Err(Box::new(ErrorUtil::path_empty_error()));
+                    return Err(CloudCoreError::Cache(CacheError::PathEmpty));
                 }
 
                 if self.child_paths.contains_key(&path) {
@@ -383,9 +528,7 @@ impl CacheInteract for Cache {
                             let mut child = hash_path_str.to_string();
                             child.push_str(CACHE_HIDDEN_FILE_NAME);
 
-                            let io_buffer = self.stream_buffer_from_child(&path)?;
-
-                            let mut cache: CacheData = serde_json::from_str(&io_buffer)?;
+                            let mut cache = self.read_cache_data(&path)?;
                             let value = serde_json::to_value(&value)?;
                             cache.data.insert(key.to_string(), value);
 
@@ -395,27 +538,26 @@ impl CacheInteract for Cache {
 
                             Ok(())
                         } else {
-                            Err("Could not get hash path as a string to set value".into())
+                            Err(CloudCoreError::Cache(CacheError::MalformedPath("Could not get hash path as a string to set value".to_string())))
                         }
                     } else {
-                        Err("Could not get hash path to set value".into())
+                        Err(CloudCoreError::Cache(CacheError::MalformedPath("Could not get hash path to set value".to_string())))
                     }
                 } else {
-                    return Err(Box::new(ErrorUtil::child_directory_missing()));
+                    return Err(CloudCoreError::Cache(CacheError::ChildDirectoryMissing));
                 }
             }
             Err(err) => {
-                let e = err.to_string();
-                return Err(e.into())
+                return Err(CloudCoreError::Cache(CacheError::Io(err.to_string())))
             }
         }
     }
 
-    fn remove_value(&mut self, path: String, key: String) -> Result<(), Box<dyn Error>> {
+    fn remove_value(&mut self, path: String, key: String) -> Result<(), CloudCoreError> {
         match self.lock.lock() {
             Ok(_) => {
                 if path.is_empty() {
-                    return Err(Box::new(ErrorUtil::path_empty_error()));
+                    return Err(CloudCoreError::Cache(CacheError::PathEmpty));
                 }
 
                 if self.child_paths.contains_key(&path) {
@@ -424,9 +566,7 @@ impl CacheInteract for Cache {
                             let mut child = hash_path_str.to_string();
                             child.push_str(CACHE_HIDDEN_FILE_NAME);
 
-                            let io_buffer = self.stream_buffer_from_child(&path)?;
-
-                            let mut cache: CacheData = serde_json::from_str(&io_buffer)?;
+                            let mut cache = self.read_cache_data(&path)?;
                             if cache.data.remove(&key).is_none() {
                                 debug!("No value for '{}' in cache", key);
                             } else {
@@ -434,19 +574,18 @@ impl CacheInteract for Cache {
                                 self.touch_file_for_child(hash_path, Some(out_buffer))?;
                             }
                         } else {
-                            return Err("Could not get hash path as a string to remove value".into())
+                            return Err(CloudCoreError::Cache(CacheError::MalformedPath("Could not get hash path as a string to remove value".to_string())))
                         }
                     } else {
-                        return Err("Could not get hash path to remove value".into())
+                        return Err(CloudCoreError::Cache(CacheError::MalformedPath("Could not get hash path to remove value".to_string())))
                     }
                     Ok(())
                 } else {
-                    return Err(Box::new(ErrorUtil::child_directory_missing()));
+                    return Err(CloudCoreError::Cache(CacheError::ChildDirectoryMissing));
                 }
             }
             Err(err) => {
-                let e = err.to_string();
-                return Err(e.into())
+                return Err(CloudCoreError::Cache(CacheError::Io(err.to_string())))
             }
         }
     }
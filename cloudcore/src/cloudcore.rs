@@ -1,6 +1,8 @@
 #[cfg(feature = "library")]
 use std::collections::HashMap;
 #[cfg(feature = "library")]
+use std::fmt;
+#[cfg(feature = "library")]
 use std::mem::size_of;
 #[cfg(feature = "library")]
 use std::ptr::null_mut;
@@ -21,17 +23,36 @@ use serde::Serialize;
 use crate::authentication::{CACHE_USER_DIR, CACHE_USER_SESSION_KEY};
 use crate::authentication::UserSession;
 #[cfg(feature = "library")]
-use crate::cache::{CacheDataValue, CacheDir, CacheInteract};
+use crate::cache::{CacheDataValue, CacheDir, CacheInteract, CACHE_ENCRYPTION_KEY_LENGTH};
+#[cfg(feature = "library")]
+use crate::cloudcore_error::CacheError;
+#[cfg(feature = "library")]
+use crate::CloudCoreError;
 use crate::cache::Cache;
+use crate::secret::Secret;
+#[cfg(feature = "library")]
+use crate::retry_policy::RetryPolicy;
+#[cfg(feature = "library")]
+use crate::pairing::pairing_log::PairingLogConfig;
 #[cfg(feature = "library")]
 use crate::cloudcore::AylaRegionEnvironment::{CNProd, CNDev, EUProd, NADev, NAProd};
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Clone)]
 pub struct ApplicationInfo {
     pub app_id: String,
     pub app_secret: String,
 }
 
+// The app secret stays a plain field for the FFI, so it is left out by hand
+impl std::fmt::Debug for ApplicationInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApplicationInfo")
+            .field("app_id", &self.app_id)
+            .field("app_secret", &Secret::new(&self.app_secret))
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct SessionParameters {
     pub app_info: ApplicationInfo,
@@ -39,17 +60,30 @@ pub struct SessionParameters {
     pub device_url: String,
 }
 
-#[derive(Debug)]
+/// Picks the Ayla region environment for a cached country code and whether dev is requested.
+#[cfg(feature = "library")]
+pub type AylaRegionResolver = dyn Fn(Option<&str>, bool) -> AylaRegionEnvironment + Send + Sync;
+
+#[cfg_attr(not(feature = "library"), derive(Debug))]
 pub struct CloudCore {
     pub user_session: Option<UserSession>,
     #[cfg(feature = "library")]
     pub selected_ayla_region_environment: AylaRegionEnvironment,
     #[cfg(feature = "library")]
     pub ayla_region_environment_map: HashMap<AylaRegionEnvironment, SessionParameters>,
+    #[cfg(feature = "library")]
+    region_resolver: Option<Box<AylaRegionResolver>>,
     pub cache: Cache,
     #[cfg(feature = "library")]
     client: Option<reqwest::Client>,
     #[cfg(feature = "library")]
+    retry_policy: RetryPolicy,
+    #[cfg(feature = "library")]
+    pairing_log_config: PairingLogConfig,
+    /// Base types of the properties read so far, by dsn and then property name.
+    #[cfg(feature = "library")]
+    pub(crate) property_base_types: Mutex<HashMap<String, HashMap<String, String>>>,
+    #[cfg(feature = "library")]
     blocking_client: Option<reqwest::blocking::Client>
 }
 
@@ -97,19 +131,22 @@ impl CloudCore {
                 let _ = cache.remove_dir_for_child(&path);
             }
         }
-        let us = get_user_session(&cache);
+        let us = get_user_session(&mut cache);
         let mut cc = CloudCore {
             user_session: us.clone(),
             selected_ayla_region_environment: NAProd,
             ayla_region_environment_map: HashMap::new(),
+            region_resolver: None,
             cache,
             client: None,
+            retry_policy: RetryPolicy::default(),
+            pairing_log_config: PairingLogConfig::default(),
+            property_base_types: Mutex::new(HashMap::new()),
             blocking_client: None,
         };
         if let Some(us) = us.as_ref() {
             let _ = &cc.set_ayla_region_environment(us.use_dev());
         }
-        cc.clear_log();
         cc.create_log();
         let boxed = Box::into_raw(Box::new(cc));
         let addr = boxed as usize;
@@ -119,6 +156,25 @@ impl CloudCore {
         boxed
     }
 
+    /// Encrypt the cache with `key`, 32 random bytes in base64 that the app keeps in the
+    /// Keychain or Keystore and passes in on every launch, right after creating CloudCore.
+    /// Plain stores from before are encrypted in place, and the user session is loaded again
+    /// if it could not be read without the key.
+    pub fn set_cache_encryption_key(&mut self, key: String) -> Result<(), CloudCoreError> {
+        let key = base64::decode(key.trim())
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .ok_or_else(|| CloudCoreError::Cache(CacheError::Encryption(format!("The key has to be {} bytes in base64", CACHE_ENCRYPTION_KEY_LENGTH))))?;
+        self.cache.set_encryption_key(key)?;
+        if self.user_session.is_none() {
+            self.user_session = get_user_session(&mut self.cache);
+            if let Some(use_dev) = self.user_session.as_ref().map(|session| session.use_dev()) {
+                self.set_ayla_region_environment(use_dev);
+            }
+        }
+        Ok(())
+    }
+
     pub fn shared() -> &'static mut CloudCore {
         let mutex_guard = SHARED.lock().unwrap();
         let cloudcore = to_static_ref::<CloudCore>(*mutex_guard);
@@ -141,32 +197,38 @@ impl CloudCore {
             CacheDataValue::StringValue(string) => Some(string),
             _ => None
         };
-        let region_env = match country {
-            Some(country) => {
-                debug!("have country set: {}", country);
-                match country.as_str() {
-                    "AT" | "BE" | "BG" | "HR" | "CY" | "DK" | "EE" | "FI" | "FR" | "DE" |
-                    "GR" | "HU" | "IE" | "IT" | "LV" | "LT" | "LU" | "MT" | "NL" | "PL" |
-                    "PT" | "RO" | "SK" | "SI" | "ES" | "SE" | "GB" | "UK" | "CZ" | "NO" |
-                    "LI" | "CH"//EU countries
-                    => EUProd,
-                    "CN" | "ZH"  //china
-                    => CNProd,
-                    "US" | "CA" | "JP" | _ => { //united states, canada, japan, country not in list
-                        if use_dev {
-                            debug!("Using NA dev environment");
-                            NADev
-                        } else {
-                            NAProd
-                        }
-                    }
-                }
-            }
-            None => NAProd
+        if let Some(country) = country.as_ref() {
+            debug!("have country set: {}", country);
+        }
+        let region_env = match self.region_resolver.as_ref() {
+            Some(resolver) => resolver(country.as_deref(), use_dev),
+            None => default_ayla_region_environment(country.as_deref(), use_dev)
         };
+        if !self.ayla_region_environment_map.contains_key(&region_env) {
+            error!("Resolved Ayla region: {:?} has no session parameters registered", &region_env);
+        }
         self.selected_ayla_region_environment = region_env;
     }
 
+    /// Register the user and device URLs for a region environment, replacing any existing entry.
+    /// Use `AylaRegionEnvironment::Custom` for a staging tenant or a local stand-in.
+    pub fn register_ayla_region_environment(&mut self, region: AylaRegionEnvironment, session_parameters: SessionParameters) {
+        debug!("Registering Ayla region: {:?} user url: {} device url: {}", &region, &session_parameters.user_url, &session_parameters.device_url);
+        self.ayla_region_environment_map.insert(region, session_parameters);
+    }
+
+    /// Replace the country to region mapping used by `set_ayla_region_environment`.
+    /// `default_ayla_region_environment` can be called from the resolver as a fallback.
+    pub fn set_ayla_region_resolver<F>(&mut self, resolver: F)
+        where F: Fn(Option<&str>, bool) -> AylaRegionEnvironment + Send + Sync + 'static {
+        self.region_resolver = Some(Box::new(resolver));
+    }
+
+    /// Go back to the built in country to region mapping.
+    pub fn clear_ayla_region_resolver(&mut self) {
+        self.region_resolver = None;
+    }
+
     // For now this can't be used because a user can input a US number for China region user
     #[allow(dead_code)]
     fn user_country_code(&self) -> String {
@@ -190,6 +252,22 @@ impl CloudCore {
     pub fn set_client(&mut self, client: reqwest::Client) {
         self.client = Some(client);
     }
+    /// Get a reference to cloudcore's retry policy.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+    /// Replace the retry policy used by every Ayla call.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+    /// Get a reference to how much pairing log is kept.
+    pub fn pairing_log_config(&self) -> &PairingLogConfig {
+        &self.pairing_log_config
+    }
+    /// Takes effect the next time the pairing log is written or a pairing starts.
+    pub fn set_pairing_log_config(&mut self, pairing_log_config: PairingLogConfig) {
+        self.pairing_log_config = pairing_log_config;
+    }
     pub fn set_blocking_client(&mut self, blocking_client: reqwest::blocking::Client) {
         self.blocking_client = Some(blocking_client);
     }
@@ -199,24 +277,70 @@ impl CloudCore {
 }
 
 #[cfg(feature = "library")]
-fn get_user_session(cache: &Cache) -> Option<UserSession> {
+impl fmt::Debug for CloudCore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloudCore")
+            .field("user_session", &self.user_session)
+            .field("selected_ayla_region_environment", &self.selected_ayla_region_environment)
+            .field("ayla_region_environment_map", &self.ayla_region_environment_map)
+            .field("region_resolver", &self.region_resolver.as_ref().map(|_| "custom"))
+            .field("retry_policy", &self.retry_policy)
+            .field("pairing_log_config", &self.pairing_log_config)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+/// The built in country to region mapping. Countries not listed use NA.
+#[cfg(feature = "library")]
+pub fn default_ayla_region_environment(country: Option<&str>, use_dev: bool) -> AylaRegionEnvironment {
+    match country {
+        Some(country) => {
+            match country {
+                "AT" | "BE" | "BG" | "HR" | "CY" | "DK" | "EE" | "FI" | "FR" | "DE" |
+                "GR" | "HU" | "IE" | "IT" | "LV" | "LT" | "LU" | "MT" | "NL" | "PL" |
+                "PT" | "RO" | "SK" | "SI" | "ES" | "SE" | "GB" | "UK" | "CZ" | "NO" |
+                "LI" | "CH"//EU countries
+                => EUProd,
+                "CN" | "ZH"  //china
+                => CNProd,
+                "US" | "CA" | "JP" | _ => { //united states, canada, japan, country not in list
+                    if use_dev {
+                        debug!("Using NA dev environment");
+                        NADev
+                    } else {
+                        NAProd
+                    }
+                }
+            }
+        }
+        None => NAProd
+    }
+}
+
+#[cfg(feature = "library")]
+fn get_user_session(cache: &mut Cache) -> Option<UserSession> {
     let cache_data = cache.get_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string());
     if let Ok(data) = cache_data {
-        match data {
-            CacheDataValue::ObjectValue(val) => {
-                let us: Option<UserSession> = serde_json::from_value(val).unwrap_or_else(|err| {
-                    error!("Error getting saved user session: {}", err);
-                    panic!("Error getting saved user session: {}", err)
-                });
+        let us = match data {
+            CacheDataValue::ObjectValue(val) => serde_json::from_value::<Option<UserSession>>(val).map_err(|err| err.to_string()),
+            CacheDataValue::NullValue => Ok(None),
+            _ => Err("User session not saved as CacheDataValue::ObjectValue".to_string()),
+        };
+        match us {
+            Ok(us) => {
                 if us.is_some() {
                     debug!("Have cached user session");
                 }
                 us
             }
-            CacheDataValue::NullValue => None,
-            _ => {
-                error!("User session not saved as CacheDataValue::ObjectValue");
-                panic!("User session not saved as CacheDataValue::ObjectValue")
+            Err(err) => {
+                // The user has to sign in again rather than the app crashing on every launch
+                error!("Discarding saved user session that can not be read: {}", err);
+                if let Err(err) = cache.remove_value(CACHE_USER_DIR.to_string(), CACHE_USER_SESSION_KEY.to_string()) {
+                    error!("Could not discard saved user session: {}", err);
+                }
+                None
             }
         }
     } else {
@@ -232,6 +356,8 @@ pub enum AylaRegionEnvironment {
     EUProd,
     CNProd,
     CNDev,
+    /// Any other tenant, e.g. staging. URLs are registered with `register_ayla_region_environment`.
+    Custom(String),
 }
 
 pub static NA_PROD_USER_URL: &str = "https://user-field-39a9391a.aylanetworks.com";
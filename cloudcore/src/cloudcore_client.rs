@@ -1,16 +1,26 @@
 #[cfg(feature = "library")]
-use std::error::Error;
+use crate::CloudCoreError;
 #[cfg(feature = "library")]
 use reqwest::{Method, RequestBuilder, Response};
 #[cfg(feature = "library")]
-use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use reqwest::header::{ACCEPT, CONTENT_TYPE, RETRY_AFTER};
+#[cfg(feature = "library")]
+use std::time::Duration;
+#[cfg(feature = "library")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "library")]
+use tokio::time::sleep;
 #[cfg(feature = "library")]
 use serde::Serialize;
+#[cfg(feature = "library")]
+use log::{debug, error};
 
 #[cfg(feature = "library")]
 use crate::{CloudCore, urls};
 #[cfg(feature = "library")]
-use crate::ErrorUtil;
+use crate::cloudcore_error::{AuthError, InputError};
+#[cfg(feature = "library")]
+use crate::retry_policy::RetryPolicy;
 
 #[cfg(feature = "library")]
 pub enum CloudCoreBaseURL {
@@ -24,6 +34,7 @@ pub struct CloudCoreRequest<T: Serialize + Sized> {
     pub endpoint: String,
     pub method: Method,
     pub requires_auth: bool,
+    pub query: Vec<(&'static str, String)>,
     pub body: Option<T>,
 }
 
@@ -35,16 +46,13 @@ impl CloudCore {
         &self,
         cc: &mut CloudCore,
         request: RequestBuilder,
-    ) -> Result<RequestBuilder, Box<dyn Error>> {
+    ) -> Result<RequestBuilder, CloudCoreError> {
         if cc.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
+            return Err(CloudCoreError::SessionMissing);
         }
         if !cc.logged_in() {
-            let refresh_result = cc.refresh_session().await;
-            match refresh_result {
-                Ok(_) => println!("Token refreshed for API call."),
-                Err(_) => return Err("Refresh token for API call failed.".into())
-            }
+            debug!("Access token expired, refreshing session before API call");
+            self.refresh_for_request(cc).await?;
         }
         let token = cc.user_session.as_ref().unwrap().access_token();
         Ok(
@@ -52,32 +60,65 @@ impl CloudCore {
                            format!("{} {}", urls::AUTHORIZATION_BEARER, token))
         )
     }
+
+    async fn refresh_for_request(&self, cc: &mut CloudCore) -> Result<(), CloudCoreError> {
+        match cc.refresh_session().await {
+            Ok(_) => {
+                debug!("Token refreshed for API call.");
+                Ok(())
+            }
+            Err(err) => {
+                error!("Refresh token for API call failed: {}", err);
+                Err(CloudCoreError::Auth(AuthError::RefreshFailed))
+            }
+        }
+    }
+
+    /// Every Ayla API call goes through here. Authorized requests get a fresh access token
+    /// when the cached one has expired, and are sent once more with a refreshed token if
+    /// Ayla still answers 401. Failures are retried as the `RetryPolicy` set on `CloudCore` allows.
     pub async fn send_request<T: Serialize>(
         &self,
         cloudcore_request: CloudCoreRequest<T>,
-    ) -> Result<Response, Box<dyn Error>> {
+    ) -> Result<Response, CloudCoreError> {
         let cc = CloudCore::shared();
+        // No region is selected until the user signs in, check the session first
+        if cloudcore_request.requires_auth && cc.user_session.is_none() {
+            return Err(CloudCoreError::SessionMissing);
+        }
         let params = cc.session_params();
         let base_url = match cloudcore_request.base_url {
             CloudCoreBaseURL::DEVICE => String::from(params.device_url.to_string()),
             CloudCoreBaseURL::USER => String::from(params.user_url.to_string())
         };
         let needs_auth = cloudcore_request.requires_auth;
-        let mut request = self.create_request(base_url, cloudcore_request)?;
-        if needs_auth {
-            request = self.get_access_token(cc, request).await?;
+        let method = cloudcore_request.method.clone();
+        let retry_policy = cc.retry_policy().clone();
+        let request = self.create_request(base_url, cloudcore_request)?;
+        if !needs_auth {
+            return self.get_response(request, &method, &retry_policy).await;
+        }
+        let retry_request = request.try_clone();
+        let request = self.get_access_token(cc, request).await?;
+        match self.get_response(request, &method, &retry_policy).await {
+            Err(err) if err.status() == Some(401) && retry_request.is_some() => {
+                debug!("Access token was rejected, refreshing session and retrying once");
+                self.refresh_for_request(cc).await?;
+                let request = self.get_access_token(cc, retry_request.unwrap()).await?;
+                self.get_response(request, &method, &retry_policy).await
+            }
+            result => result,
         }
-        self.get_response(request).await
     }
 
     fn create_request<T: Serialize>(
         &self,
         mut url: String,
         cloudcore_request: CloudCoreRequest<T>,
-    ) -> Result<RequestBuilder, Box<dyn Error>> {
+    ) -> Result<RequestBuilder, CloudCoreError> {
         url.push_str(&cloudcore_request.endpoint);
         let client = self.client();
-        let request = match cloudcore_request.method {
+        let mut request = match cloudcore_request.method {
             Method::GET => {
                 client.get(url)
                     .header(ACCEPT, "application/json")
@@ -86,43 +127,82 @@ impl CloudCore {
                 client.put(url)
                     .header(CONTENT_TYPE, "application/json")
                     .header(ACCEPT, "application/json")
-                    .json(&cloudcore_request.body.unwrap())
             }
             Method::POST => {
                 client.post(url)
                     .header(CONTENT_TYPE, "application/json")
                     .header(ACCEPT, "application/json")
-                    .json(&cloudcore_request.body.unwrap())
             }
             Method::DELETE => {
                 client.delete(url)
             }
-            _ => return Err(Box::new(ErrorUtil::invalid_method()))
+            _ => return Err(CloudCoreError::InvalidInput(InputError::InvalidMethod))
         };
+        if !cloudcore_request.query.is_empty() {
+            request = request.query(&cloudcore_request.query);
+        }
+        let sends_body = cloudcore_request.method == Method::PUT || cloudcore_request.method == Method::POST;
+        if let (true, Some(body)) = (sends_body, cloudcore_request.body.as_ref()) {
+            request = request.json(body);
+        }
         Ok(request)
     }
 
     async fn get_response(
         &self,
         request_builder: RequestBuilder,
-    ) -> Result<Response, Box<dyn Error>> {
-        let response = request_builder.send().await?;
-        let status = response.status();
-        if status.is_success() {
-            Ok(response)
-        } else {
-            let error_payload = response.text().await?;
-            let error = match status.as_u16() {
-                422 => {
-                    if error_payload.contains(ERROR_PATTERN_TOO_MANY_INSTANCES) {
-                        ErrorUtil::too_many_instances_error(error_payload)
-                    } else {
-                        ErrorUtil::server_error(error_payload)
+        method: &Method,
+        retry_policy: &RetryPolicy,
+    ) -> Result<Response, CloudCoreError> {
+        let mut request_builder = request_builder;
+        let mut attempt = 1;
+        loop {
+            // Requests with a streamed body can't be cloned, those are only sent once
+            let retry_request = if attempt < retry_policy.max_attempts() {
+                request_builder.try_clone()
+            } else {
+                None
+            };
+            let wait = match request_builder.send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status().as_u16();
+                    if retry_request.is_none() || !retry_policy.should_retry_status(method, status) {
+                        return Err(CloudCoreError::from_response(response).await);
                     }
-                },
-                _ => ErrorUtil::server_error(error_payload)
+                    match retry_after(&response) {
+                        Some(wait) if wait > retry_policy.max_backoff() => {
+                            debug!("Retry-After of {:?} is longer than the retry policy allows", wait);
+                            return Err(CloudCoreError::from_response(response).await);
+                        }
+                        Some(wait) => wait,
+                        None => retry_policy.backoff(attempt),
+                    }
+                }
+                Err(err) => {
+                    if retry_request.is_none() || !retry_policy.should_retry_error(method, &err) {
+                        return Err(err.into());
+                    }
+                    debug!("Request failed: {}", err);
+                    retry_policy.backoff(attempt)
+                }
             };
-            Err(Box::new(error))
+            attempt += 1;
+            debug!("Retrying request in {:?}, attempt {} of {}", wait, attempt, retry_policy.max_attempts());
+            sleep(wait).await;
+            request_builder = retry_request.unwrap();
         }
     }
+}
+
+/// The wait asked for in a `Retry-After` header, given either in seconds or as an HTTP date.
+#[cfg(feature = "library")]
+fn retry_after(response: &Response) -> Option<Duration> {
+    let value = response.headers().get(RETRY_AFTER)?.to_str().ok()?;
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    let seconds = (date.with_timezone(&Utc) - Utc::now()).num_seconds().max(0);
+    Some(Duration::from_secs(seconds as u64))
 }
\ No newline at end of file
@@ -0,0 +1,247 @@
+use std::error::Error;
+use std::fmt;
+use mantle_utilities::{ErrorType, MantleError};
+#[cfg(feature = "library")]
+use reqwest::Response;
+
+use crate::cloudcore_client::ERROR_PATTERN_TOO_MANY_INSTANCES;
+use crate::pairing::pairing_diagnostics::PairingFailure;
+use crate::pairing::wifi_state::WifiPairingState;
+
+/// Error returned by every `CloudCore` API.
+///
+/// Variants describe what went wrong rather than where, so callers can tell an expired
+/// session from an unreachable cloud or a rejected request. FFI layers convert it into a
+/// [`MantleError`] with `From` or [`IntoMantleResult`].
+#[derive(Debug, Clone)]
+pub enum CloudCoreError {
+    /// The request was sent but never got a response (timeout, dropped connection...).
+    Network(String),
+    /// No connection to Ayla could be made, so the request was never sent.
+    Connect(String),
+    /// Ayla answered with a non-success status.
+    Http { status: u16, body: String },
+    /// Signing in or refreshing the session was rejected.
+    Auth(AuthError),
+    /// There is no user session, the user needs to log in.
+    SessionMissing,
+    /// The requested property, datapoint or url does not exist.
+    NotFound(String),
+    /// Reading or writing the local cache failed.
+    Cache(CacheError),
+    /// A payload or value could not be parsed into the expected type.
+    Parse(String),
+    /// Talking to a device during wifi pairing failed.
+    Pairing(String),
+    /// Wifi pairing gave up, `state` is the step it failed in.
+    PairingFailed { failure: PairingFailure, state: WifiPairingState, message: String },
+    /// Talking to a device over the local network failed.
+    Lan(String),
+    /// Waiting on the cloud or a device took longer than allowed.
+    Timeout(String),
+    /// The arguments passed in can not be used to build a request.
+    InvalidInput(InputError),
+}
+
+#[derive(Debug, Clone)]
+pub enum AuthError {
+    InvalidCredentials(String),
+    AccountLocked(String),
+    RefreshFailed,
+}
+
+#[derive(Debug, Clone)]
+pub enum CacheError {
+    PathEmpty,
+    MalformedPath(String),
+    ParentDirectoryMissing,
+    ChildDirectoryMissing,
+    KeyMissing,
+    DirectoryUnavailable,
+    FileName,
+    Io(String),
+    /// The cache is encrypted and could not be decrypted, or the key given is not usable.
+    Encryption(String),
+    /// A store failed its checksum or could not be parsed, it was half written or changed on disk.
+    Corrupt(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum InputError {
+    EmailOrPhoneNumberMissing,
+    PasswordsMismatch,
+    InvalidMethod,
+    InvalidTimestamp(String),
+    InvalidPropertyValue(String),
+    Invalid(String),
+}
+
+impl CloudCoreError {
+    /// Build an `Http` error out of a non-success response, consuming its body.
+    #[cfg(feature = "library")]
+    pub async fn from_response(response: Response) -> Self {
+        let status = response.status().as_u16();
+        match response.text().await {
+            Ok(body) => CloudCoreError::Http { status, body },
+            Err(err) => err.into(),
+        }
+    }
+
+    /// Build the error for a rejected sign in out of the status and body Ayla returned.
+    pub fn from_login_response(status: u16, body: String) -> Self {
+        if body.contains("Invalid email or password") {
+            CloudCoreError::Auth(AuthError::InvalidCredentials(body))
+        } else if body.contains("Your account is locked") {
+            CloudCoreError::Auth(AuthError::AccountLocked(body))
+        } else {
+            CloudCoreError::Http { status, body }
+        }
+    }
+
+    /// The HTTP status Ayla answered with, if any.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            CloudCoreError::Http { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether Ayla refused to create another trigger, schedule... for the device.
+    pub fn is_too_many_instances(&self) -> bool {
+        match self {
+            CloudCoreError::Http { status: 422, body } => body.contains(ERROR_PATTERN_TOO_MANY_INSTANCES),
+            _ => false,
+        }
+    }
+
+    fn error_type(&self) -> ErrorType {
+        match self {
+            CloudCoreError::Network(_) | CloudCoreError::Connect(_) => ErrorType::GenericError,
+            CloudCoreError::Http { .. } if self.is_too_many_instances() => ErrorType::TooManyInstancesError,
+            CloudCoreError::Http { .. } => ErrorType::ServerError,
+            CloudCoreError::Auth(AuthError::InvalidCredentials(_)) => ErrorType::InvalidEmailOrPassword,
+            CloudCoreError::Auth(AuthError::AccountLocked(_)) => ErrorType::AccountLocked,
+            CloudCoreError::Auth(AuthError::RefreshFailed) => ErrorType::RefreshTokenFailed,
+            CloudCoreError::SessionMissing => ErrorType::UserSessionNotFound,
+            CloudCoreError::NotFound(_) => ErrorType::NotFound,
+            CloudCoreError::Cache(CacheError::PathEmpty) => ErrorType::PathEmpty,
+            CloudCoreError::Cache(CacheError::MalformedPath(_)) => ErrorType::MalformedOrIncorrectPath,
+            CloudCoreError::Cache(CacheError::ParentDirectoryMissing) => ErrorType::ParentDirectoryMissing,
+            CloudCoreError::Cache(CacheError::ChildDirectoryMissing) => ErrorType::ChildDirectoryMissing,
+            CloudCoreError::Cache(CacheError::KeyMissing) => ErrorType::CacheKeyMissing,
+            CloudCoreError::Cache(CacheError::DirectoryUnavailable) => ErrorType::CachedDirectoryError,
+            CloudCoreError::Cache(CacheError::FileName) => ErrorType::LocalFileNameError,
+            CloudCoreError::Cache(CacheError::Io(_)) => ErrorType::DiskError,
+            CloudCoreError::Cache(CacheError::Encryption(_)) => ErrorType::DiskError,
+            CloudCoreError::Cache(CacheError::Corrupt(_)) => ErrorType::DiskError,
+            CloudCoreError::Parse(_) => ErrorType::InvalidFormat,
+            CloudCoreError::Pairing(_) => ErrorType::GenericError,
+            CloudCoreError::PairingFailed { .. } => ErrorType::GenericError,
+            CloudCoreError::Lan(_) => ErrorType::GenericError,
+            CloudCoreError::Timeout(_) => ErrorType::GenericError,
+            CloudCoreError::InvalidInput(InputError::EmailOrPhoneNumberMissing) => ErrorType::EmailOrPhoneNumberMissing,
+            CloudCoreError::InvalidInput(InputError::PasswordsMismatch) => ErrorType::PasswordsMismatch,
+            CloudCoreError::InvalidInput(InputError::InvalidMethod) => ErrorType::InvalidMethod,
+            CloudCoreError::InvalidInput(InputError::InvalidTimestamp(_)) => ErrorType::InvalidFormat,
+            CloudCoreError::InvalidInput(InputError::InvalidPropertyValue(_)) => ErrorType::InvalidFormat,
+            CloudCoreError::InvalidInput(InputError::Invalid(_)) => ErrorType::GenericError,
+        }
+    }
+}
+
+impl fmt::Display for CloudCoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloudCoreError::Network(message) => write!(f, "Network error -> {}", message),
+            CloudCoreError::Connect(message) => write!(f, "Connection failed -> {}", message),
+            CloudCoreError::Http { status, body } => write!(f, "Server responded with {} -> {}", status, body),
+            CloudCoreError::Auth(AuthError::InvalidCredentials(message)) => write!(f, "{}", message),
+            CloudCoreError::Auth(AuthError::AccountLocked(message)) => write!(f, "{}", message),
+            CloudCoreError::Auth(AuthError::RefreshFailed) => write!(f, "Refresh token for API call failed"),
+            CloudCoreError::SessionMissing => write!(f, "User session not found"),
+            CloudCoreError::NotFound(message) => write!(f, "Not found -> {}", message),
+            CloudCoreError::Cache(CacheError::PathEmpty) => write!(f, "Path is empty, not able to create a file directory"),
+            CloudCoreError::Cache(CacheError::MalformedPath(path)) => write!(f, "Malformed or incorrect path -> {}", path),
+            CloudCoreError::Cache(CacheError::ParentDirectoryMissing) => write!(f, "Parent directory does not exist"),
+            CloudCoreError::Cache(CacheError::ChildDirectoryMissing) => write!(f, "Child directory does not exist"),
+            CloudCoreError::Cache(CacheError::KeyMissing) => write!(f, "A key needs to provided to retrieve cache"),
+            CloudCoreError::Cache(CacheError::DirectoryUnavailable) => write!(f, "Could not get cache directory to save file"),
+            CloudCoreError::Cache(CacheError::FileName) => write!(f, "Error getting file name for local file"),
+            CloudCoreError::Cache(CacheError::Io(message)) => write!(f, "Disk error -> {}", message),
+            CloudCoreError::Cache(CacheError::Encryption(message)) => write!(f, "Cache encryption error -> {}", message),
+            CloudCoreError::Cache(CacheError::Corrupt(message)) => write!(f, "Corrupt cache -> {}", message),
+            CloudCoreError::Parse(message) => write!(f, "Value is not correct format -> {}", message),
+            CloudCoreError::Pairing(message) => write!(f, "Pairing error -> {}", message),
+            CloudCoreError::PairingFailed { failure, state, message } => write!(f, "Pairing failed with {:?} while {:?} -> {}", failure, state, message),
+            CloudCoreError::Lan(message) => write!(f, "LAN error -> {}", message),
+            CloudCoreError::Timeout(message) => write!(f, "Timed out -> {}", message),
+            CloudCoreError::InvalidInput(InputError::EmailOrPhoneNumberMissing) => write!(f, "Need either email or phone number to send confirmation"),
+            CloudCoreError::InvalidInput(InputError::PasswordsMismatch) => write!(f, "Passwords do not match"),
+            CloudCoreError::InvalidInput(InputError::InvalidMethod) => write!(f, "Invalid method"),
+            CloudCoreError::InvalidInput(InputError::InvalidTimestamp(value)) => write!(f, "Timestamp is not RFC 3339 -> {}", value),
+            CloudCoreError::InvalidInput(InputError::InvalidPropertyValue(message)) => write!(f, "Value does not match the property's base type -> {}", message),
+            CloudCoreError::InvalidInput(InputError::Invalid(message)) => write!(f, "Invalid input -> {}", message),
+        }
+    }
+}
+
+impl Error for CloudCoreError {}
+
+#[cfg(feature = "library")]
+impl From<reqwest::Error> for CloudCoreError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_decode() {
+            CloudCoreError::Parse(err.to_string())
+        } else if let Some(status) = err.status() {
+            CloudCoreError::Http { status: status.as_u16(), body: err.to_string() }
+        } else if err.is_connect() {
+            CloudCoreError::Connect(err.to_string())
+        } else {
+            CloudCoreError::Network(err.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for CloudCoreError {
+    fn from(err: serde_json::Error) -> Self {
+        CloudCoreError::Parse(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for CloudCoreError {
+    fn from(err: std::io::Error) -> Self {
+        CloudCoreError::Cache(CacheError::Io(err.to_string()))
+    }
+}
+
+impl From<CloudCoreError> for MantleError {
+    fn from(err: CloudCoreError) -> Self {
+        MantleError {
+            error_type: err.error_type(),
+            description: err.to_string(),
+        }
+    }
+}
+
+/// Converts `CloudCoreError` results into the `MantleError` backed results the FFI utilities expect.
+pub trait IntoMantleResult {
+    type Output;
+
+    fn into_mantle_result(self) -> Self::Output;
+}
+
+impl<T> IntoMantleResult for Result<T, CloudCoreError> {
+    type Output = Result<T, Box<dyn Error>>;
+
+    fn into_mantle_result(self) -> Self::Output {
+        self.map_err(|err| Box::new(MantleError::from(err)) as Box<dyn Error>)
+    }
+}
+
+impl<T> IntoMantleResult for (Result<T, CloudCoreError>, String) {
+    type Output = (Result<T, Box<dyn Error>>, String);
+
+    fn into_mantle_result(self) -> Self::Output {
+        (self.0.into_mantle_result(), self.1)
+    }
+}
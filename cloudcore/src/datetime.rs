@@ -0,0 +1,24 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::cloudcore_error::InputError;
+use crate::CloudCoreError;
+
+/// Parse an RFC 3339 timestamp, e.g. `2022-07-20T18:30:00Z`, into UTC.
+pub fn parse_datetime(value: &str) -> Result<DateTime<Utc>, CloudCoreError> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .map_err(|err| CloudCoreError::InvalidInput(InputError::InvalidTimestamp(format!("{} ({})", value, err))))
+}
+
+/// Same as `parse_datetime`, with None or an empty string meaning not set.
+pub fn parse_optional_datetime(value: Option<String>) -> Result<Option<DateTime<Utc>>, CloudCoreError> {
+    match value {
+        Some(value) if !value.trim().is_empty() => parse_datetime(&value).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Format a timestamp the way Ayla's datapoint filters expect it.
+pub fn format_datetime(datetime: &DateTime<Utc>) -> String {
+    datetime.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
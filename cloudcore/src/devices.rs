@@ -4,19 +4,25 @@ mod time_zone;
 pub use self::devices::IoTDevice;
 
 #[cfg(feature = "library")]
-use crate::ErrorUtil;
+use crate::cloudcore_error::CacheError;
 #[cfg(feature = "library")]
 use crate::cloudcore::CloudCore;
 #[cfg(feature = "library")]
+use crate::cloudcore_client::{CloudCoreBaseURL, CloudCoreRequest};
+#[cfg(feature = "library")]
 use crate::urls;
 #[cfg(feature = "library")]
+use reqwest::Method;
+#[cfg(feature = "library")]
 use log::debug;
 #[cfg(feature = "library")]
 use serde::Deserialize;
 #[cfg(feature = "library")]
 use serde::Serialize;
 #[cfg(feature = "library")]
-use std::error::Error;
+use crate::CloudCoreError;
+#[cfg(feature = "library")]
+use std::collections::HashMap;
 #[cfg(feature = "library")]
 use std::time::Duration;
 #[cfg(feature = "library")]
@@ -37,25 +43,18 @@ struct IoTDeviceResponse {
 #[cfg(feature = "library")]
 impl CloudCore {
     /// Fetch all the devices tied to the user's account.
-    pub async fn fetch_all_devices(&mut self) -> Result<Vec<IoTDevice>, Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-
-        let mut url = String::from(&self.session_params().device_url);
-        url.push_str(urls::AYLA_DEVICE_JSON);
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-
-        let auth_bearer = format!("auth_token {}", token);
-
-        let client = self.client();
-
-        let response = client
-            .get(url)
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
+    pub async fn fetch_all_devices(&mut self) -> Result<Vec<IoTDevice>, CloudCoreError> {
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: String::from(urls::AYLA_DEVICE_JSON),
+                method: Method::GET,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
         let text = response.text().await?;
         debug!("fetch devices payload: {:#?}", text);
         let devices_payload: Vec<IoTDeviceResponse> = serde_json::from_str(&text)?;
@@ -80,45 +79,57 @@ impl CloudCore {
         Ok(devices)
     }
 
+    /// Connection status of every device on the account by DSN, without touching the cache.
+    pub(crate) async fn fetch_connection_statuses(&self) -> Result<HashMap<String, String>, CloudCoreError> {
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: String::from(urls::AYLA_DEVICE_JSON),
+                method: Method::GET,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
+        let devices_payload = response.json::<Vec<IoTDeviceResponse>>().await?;
+        Ok(devices_payload
+            .into_iter()
+            .filter_map(|obj| Some((obj.device.dsn()?.to_string(), obj.device.connection_status()?.to_string())))
+            .collect())
+    }
+
     /// Fetch single device by using a Device Serial Number.
-    pub async fn fetch_device_with_dsn(&mut self, dsn: String) -> Result<IoTDevice, Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", urls::AUTHORIZATION_BEARER, token);
-        let url = format!("{}/apiv1/dsns/{}.json", &self.session_params().device_url, &dsn);
-        let response = self.client()
-            .get(url.to_string())
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            let device_payload = response.json::<IoTDeviceResponse>().await?;
-            let device = device_payload.device;
-            debug!("{:?}", device);
-            let dsn_result = device.dsn();
-            match dsn_result {
-                None => {
-                    debug!("cache creation skipped due to empty DSN");
-                }
-                Some(dsn) => {
-                    if !self.cache.child_paths().contains_key(dsn) {
-                        self.cache.make_dir_for_child(dsn).unwrap();
-                    }
+    pub async fn fetch_device_with_dsn(&mut self, dsn: String) -> Result<IoTDevice, CloudCoreError> {
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: format!("/apiv1/dsns/{}.json", &dsn),
+                method: Method::GET,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
+        let device_payload = response.json::<IoTDeviceResponse>().await?;
+        let device = device_payload.device;
+        debug!("{:?}", device);
+        let dsn_result = device.dsn();
+        match dsn_result {
+            None => {
+                debug!("cache creation skipped due to empty DSN");
+            }
+            Some(dsn) => {
+                if !self.cache.child_paths().contains_key(dsn) {
+                    self.cache.make_dir_for_child(dsn).unwrap();
                 }
             }
-            Ok(device.clone())
-        } else {
-            let error = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error)))
         }
+        Ok(device.clone())
     }
 
-    pub async fn rename_device_with_dsn(&self, dsn: String, new_name: String) -> Result<(),  Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
+    pub async fn rename_device_with_dsn(&self, dsn: String, new_name: String) -> Result<(),  CloudCoreError> {
         #[derive(Debug, Serialize)]
         struct IoTDeviceRenameDevice {
             product_name: String,
@@ -132,116 +143,76 @@ impl CloudCore {
                 product_name: new_name
             }
         };
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", urls::AUTHORIZATION_BEARER, token);
-        let url = format!("{}/apiv1/dsns/{}.json", &self.session_params().device_url, &dsn);
-        let response = self.client()
-            .put(url.to_string())
-            .json(&body)
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .send();
-
-        let r = response.await;
-        match r {
-            Ok(_res) => {
-                if _res.status().is_success() {
-                    Ok(())
-                } else {
-                    Err(Box::new(ErrorUtil::generic_error()))
-                }
-            },
-            Err(err) => {
-                Err(Box::new(ErrorUtil::server_error(err.to_string())))
+        self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: format!("/apiv1/dsns/{}.json", &dsn),
+                method: Method::PUT,
+                requires_auth: true,
+                query: vec![],
+                body: Some(body),
             }
-        }
+        ).await?;
+        Ok(())
     }
 
-    pub async fn reset_device(&self, key: u32) -> Result<(), Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", urls::AUTHORIZATION_BEARER, token);
-        let url = format!("{}/apiv1/devices/{}/cmds/factory_reset.json", &self.session_params().device_url, key);
-
-        let response = self.client()
-            .put(url.to_string())
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            let error = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error)))
-        }
+    pub async fn reset_device(&self, key: u32) -> Result<(), CloudCoreError> {
+        let body: Option<String> = None;
+        self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: format!("/apiv1/devices/{}/cmds/factory_reset.json", key),
+                method: Method::PUT,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
+        Ok(())
     }
 
-    pub async fn unregister_device(&mut self, key: u32, dsn: String) -> Result<(), Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", urls::AUTHORIZATION_BEARER, token);
-        let url = format!("{}/apiv1/devices/{}.json", &self.session_params().device_url, key);
-
-        let response = self.client()
-            .delete(url.to_string())
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            self.clear_device_cache(dsn.clone())
-        } else {
-            let error = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error)))
-        }
+    pub async fn unregister_device(&mut self, key: u32, dsn: String) -> Result<(), CloudCoreError> {
+        let body: Option<String> = None;
+        self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: format!("/apiv1/devices/{}.json", key),
+                method: Method::DELETE,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
+        self.clear_device_cache(dsn.clone())
     }
 
-    pub async fn device_timezone_offset(&self, key: u32) -> Result<String, Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", urls::AUTHORIZATION_BEARER, token);
-        let url = format!("{}/apiv1/devices/{}/time_zones.json", &self.session_params().device_url, key);
-
-        let response = self.client()
-            .get(url.to_string())
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
+    pub async fn device_timezone_offset(&self, key: u32) -> Result<String, CloudCoreError> {
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: format!("/apiv1/devices/{}/time_zones.json", key),
+                method: Method::GET,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
 
         #[derive(Deserialize)]
         struct TimeZoneResponse {
             time_zone: TimeZone
         }
 
-        if response.status().is_success() {
-            let tz_payload = response.json::<TimeZoneResponse>().await?;
-            if let Some(offset) = tz_payload.time_zone.utc_offset {
-                Ok(offset)
-            } else {
-                Err("no time zone offset".into())
-            }
+        let tz_payload = response.json::<TimeZoneResponse>().await?;
+        if let Some(offset) = tz_payload.time_zone.utc_offset {
+            Ok(offset)
         } else {
-            let error = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error)))
+            Err(CloudCoreError::NotFound("no time zone offset".to_string()))
         }
     }
 
-    pub async fn set_device_time_zone(&self, dsn: String) -> Result<(), Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-        let auth_bearer = format!("{} {}", urls::AUTHORIZATION_BEARER, token);
-        let url = format!("{}/apiv1/dsns/{}/time_zones.json", &self.session_params().device_url, dsn);
-
+    pub async fn set_device_time_zone(&self, dsn: String) -> Result<(), CloudCoreError> {
         #[derive(Serialize)]
         struct TimeZoneRequest {
             tz_id: String
@@ -250,50 +221,50 @@ impl CloudCore {
         let time_zone = self.cache.get_value("app".to_string(), "tz_id".to_string())?;
         let tz_id = match time_zone {
             CacheDataValue::StringValue(str) => str,
-            _ => return Err(Box::new(ErrorUtil::invalid_format()))
+            _ => return Err(CloudCoreError::Parse("Value is not correct format".to_string()))
         };
 
         let json = TimeZoneRequest {
             tz_id: tz_id.clone()
         };
 
-        let response = self.client()
-            .put(url.to_string())
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .json(&json)
-            .send()
-            .await?;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: format!("/apiv1/dsns/{}/time_zones.json", dsn),
+                method: Method::PUT,
+                requires_auth: true,
+                query: vec![],
+                body: Some(json),
+            }
+        ).await?;
 
         #[derive(Deserialize)]
         struct TimeZoneResponse {
             time_zone: TimeZone
         }
 
-        if response.status().is_success() {
-            let tz_payload = response.json::<TimeZoneResponse>().await?;
-            if let Some(ayla_tz_id) = tz_payload.time_zone.tz_id {
-                if ayla_tz_id == tz_id {
-                    Ok(())
-                } else {
-                    Err("Ayla time zone does not match time zone sent".into())
-                }
+        let tz_payload = response.json::<TimeZoneResponse>().await?;
+        if let Some(ayla_tz_id) = tz_payload.time_zone.tz_id {
+            if ayla_tz_id == tz_id {
+                Ok(())
             } else {
-                Err("no time zone offset".into())
+                Err(CloudCoreError::Parse("Ayla time zone does not match time zone sent".to_string()))
             }
         } else {
-            let error = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error)))
+            Err(CloudCoreError::NotFound("no time zone offset".to_string()))
         }
-
     }
 
-    pub async fn delete_device(&mut self, key: u32, dsn: String) -> Result<(), Box<dyn Error>> {
+    pub async fn delete_device(&mut self, key: u32, dsn: String) -> Result<(), CloudCoreError> {
         self.reset_wifi_datapoint(dsn.clone()).await;
         let _ = self.rename_device_with_dsn(dsn.clone(), "Robot 1".to_string()).await;
-        // TODO: For now just do these synchronously
-        let _ = self.set_property_value(dsn.clone(), "GET_Ack_Response".to_string(), IoTPropertyValue::Str("".to_string()), "".to_string()).await;
-        let _ = self.set_property_value(dsn.clone(), "SET_Quiet_Time".to_string(), IoTPropertyValue::Str("".to_string()), "".to_string()).await;
-        let _ = self.set_property_value(dsn.clone(), "SET_Find_Device".to_string(), IoTPropertyValue::Int(0), "".to_string()).await;
+        let values = vec![
+            ("GET_Ack_Response".to_string(), IoTPropertyValue::Str("".to_string())),
+            ("SET_Quiet_Time".to_string(), IoTPropertyValue::Str("".to_string())),
+            ("SET_Find_Device".to_string(), IoTPropertyValue::Int(0)),
+        ];
+        log_failed_writes(self.set_property_values(dsn.clone(), values, "".to_string()).await.0);
         let _set_reporting_periods = self.set_reporting_periods(dsn.clone()).await;
         let _ = self.clear_schedules(key.clone()).await;
         let _ = self.delete_all_triggers(dsn.clone(), "Get_ERROR_Code".to_string()).await;
@@ -308,9 +279,11 @@ impl CloudCore {
                 period_dock = 30;
                 period_undock = 5;
             }
-            // TODO: For now just do these synchronously
-            let _ = self.set_property_value(dsn.clone(), "ReportPeriodDock".to_string(), IoTPropertyValue::Int(period_dock), "".to_string()).await;
-            let _ = self.set_property_value(dsn.clone(), "ReportPeriodUndocked".to_string(), IoTPropertyValue::Int(period_undock), "".to_string()).await;
+            let values = vec![
+                ("ReportPeriodDock".to_string(), IoTPropertyValue::Int(period_dock)),
+                ("ReportPeriodUndocked".to_string(), IoTPropertyValue::Int(period_undock)),
+            ];
+            log_failed_writes(self.set_property_values(dsn, values, "".to_string()).await.0);
         }
     }
 
@@ -321,7 +294,7 @@ impl CloudCore {
         let _ = self.set_property_value(dsn.clone(), "SET_Reset_WiFi".to_string(), IoTPropertyValue::Int(0), "".to_string()).await.0;
     }
 
-    pub async fn factory_reset_device(&mut self, key: u32, dsn: String) -> Result<(), Box<dyn Error>> {
+    pub async fn factory_reset_device(&mut self, key: u32, dsn: String) -> Result<(), CloudCoreError> {
         self.set_property_value(dsn.clone(), "SET_Reset_Factory_Defaults".to_string(), IoTPropertyValue::Int(1), "".to_string()).await.0?;
         debug!("Sleeping for 60 seconds to allow Ayla to process everything");
         sleep(Duration::from_secs(60)).await;
@@ -330,7 +303,7 @@ impl CloudCore {
         Ok(())
     }
 
-    pub async fn delete_device_map(&mut self, dsn: String, re_explore: bool, partial_delete: bool) -> Result<(), Box<dyn Error>> {
+    pub async fn delete_device_map(&mut self, dsn: String, re_explore: bool, partial_delete: bool) -> Result<(), CloudCoreError> {
         let has_delete_map_prop = match self.get_properties(dsn.clone(), vec!["SET_DeleteMaps".to_string()], "".to_string()).await.0 {
             Ok(props) => !props.is_empty(),
             Err(_) => false
@@ -350,17 +323,27 @@ impl CloudCore {
         Ok(())
     }
 
-    pub fn clear_device_cache(&mut self, dsn: String) -> Result<(), Box<dyn Error>>  {
+    pub fn clear_device_cache(&mut self, dsn: String) -> Result<(), CloudCoreError>  {
         if let Some(child_path) = self.cache.child_paths().clone().get(&dsn).clone() {
             if let Some(path) = child_path.to_str() {
                 self.cache.remove_dir_for_child(&path.to_string())?;
                 self.cache.remove_child_path(dsn.clone());
                 Ok(())
             } else {
-                Err(Box::new(ErrorUtil::server_error("Could not get a path from cache child path".to_string())))
+                Err(CloudCoreError::Cache(CacheError::MalformedPath(dsn)))
             }
         } else {
             Ok(())
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(feature = "library")]
+fn log_failed_writes(results: Result<HashMap<String, Result<(), CloudCoreError>>, CloudCoreError>) {
+    match results {
+        Ok(results) => results
+            .iter()
+            .filter_map(|(name, result)| result.as_ref().err().map(|err| (name, err)))
+            .for_each(|(name, err)| debug!("Setting {} failed: {}", name, err)),
+        Err(err) => debug!("Setting properties failed: {}", err),
+    }
+}
@@ -3,35 +3,34 @@ use bytes::Bytes;
 #[cfg(feature = "library")]
 use serde::de::DeserializeOwned;
 #[cfg(feature = "library")]
-use std::error::Error;
+use crate::CloudCoreError;
 #[cfg(feature = "library")]
 use std::fs::{File, OpenOptions};
 #[cfg(feature = "library")]
 use std::io::{Cursor, Read, Write};
 #[cfg(feature = "library")]
-use std::path::Path;
+use std::path::{Path, PathBuf};
 #[cfg(feature = "library")]
-use crate::ErrorUtil;
+use crate::cloudcore_error::CacheError;
 
 #[cfg(feature = "library")]
 pub async fn download_resource(
     url: &str,
     file_path: &str,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<(), CloudCoreError> {
     let path = Path::new(file_path);
     if path.exists() {
         return Ok(())
     }
     let response = reqwest::get(url).await?;
     if !response.status().is_success() {
-        let error_payload = response.text().await?;
-        return Err(error_payload.into());
+        return Err(CloudCoreError::from_response(response).await);
     }
     write_to_disk(path, response.bytes().await?)
 }
 
 #[cfg(feature = "library")]
-pub fn write_to_disk(path: &Path, bytes: Bytes) -> Result<(), Box<dyn Error>> {
+pub fn write_to_disk(path: &Path, bytes: Bytes) -> Result<(), CloudCoreError> {
     if let Some(dir) = path.parent() {
         std::fs::create_dir_all(dir)?;
     }
@@ -42,8 +41,36 @@ pub fn write_to_disk(path: &Path, bytes: Bytes) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Write to a temporary file next to `path` and rename it over `path`, so a crash leaves
+/// either the old or the new contents and never a partly written file.
 #[cfg(feature = "library")]
-pub fn read_from_disk_to_string(path: &str) -> Result<String, Box<dyn Error>>
+pub fn write_to_disk_atomically(path: &Path, bytes: Bytes) -> Result<(), CloudCoreError> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    let mut file = File::create(&temp)?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    drop(file);
+    if let Err(err) = std::fs::rename(&temp, path) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(err.into());
+    }
+    // Make the rename itself durable
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "library")]
+pub fn read_from_disk_to_string(path: &str) -> Result<String, CloudCoreError>
 {
 
     match OpenOptions::new()
@@ -54,16 +81,16 @@ pub fn read_from_disk_to_string(path: &str) -> Result<String, Box<dyn Error>>
                 Ok(_) => Ok(input_buffer),
                 Err(err) => {
                     let message: String = format!("{}",err).into();
-                    return Err(Box::new(ErrorUtil::disk_read_error(message)))
+                    return Err(CloudCoreError::Cache(CacheError::Io(message)))
                 } 
             }
         }
-        Err(e) => Err(format!("Error::{:?}", e.kind()).into())
+        Err(e) => Err(CloudCoreError::Cache(CacheError::Io(format!("Error::{:?}", e.kind()))))
     }
 }
 
 #[cfg(feature = "library")]
-pub fn read_from_disk_to_deserialized<T>(path: &str) -> Result<T, Box<dyn Error>>
+pub fn read_from_disk_to_deserialized<T>(path: &str) -> Result<T, CloudCoreError>
     where
         T: DeserializeOwned,
 {
@@ -73,15 +100,12 @@ pub fn read_from_disk_to_deserialized<T>(path: &str) -> Result<T, Box<dyn Error>
             let result: T = serde_json::from_str(&string_buffer).unwrap();
             Ok(result)
         }
-        Err(err) => {
-            let message: String = format!("{}",err).into();
-            Err(Box::new(ErrorUtil::disk_read_error(message)))
-        }
+        Err(err) => Err(err)
     }
 }
 
 #[cfg(feature = "library")]
-pub fn read_from_disk_to_vec(path: &str) -> Result<Vec<u8>, Box<dyn Error>>
+pub fn read_from_disk_to_vec(path: &str) -> Result<Vec<u8>, CloudCoreError>
 {
     match File::open(path) {
         Ok(mut it) => {
@@ -90,13 +114,13 @@ pub fn read_from_disk_to_vec(path: &str) -> Result<Vec<u8>, Box<dyn Error>>
                 Ok(_) => Ok(input_buffer),
                 Err(err) => {
                     let message: String = format!("{}",err).into();
-                    Err(Box::new(ErrorUtil::disk_error(message)))
+                    Err(CloudCoreError::Cache(CacheError::Io(message)))
                 }
             }
         }
         Err(e) => {
             let message: String = format!("Error::{:?}", e.kind()).into();
-            Err(Box::new(ErrorUtil::disk_error(message)))
+            Err(CloudCoreError::Cache(CacheError::Io(message)))
         }
     }
 }
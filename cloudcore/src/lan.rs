@@ -0,0 +1,110 @@
+mod crypto;
+mod manager;
+
+#[cfg(feature = "mock")]
+pub(crate) use crypto::{KeyExchange, LanCipher, LanPayload, LanRole};
+#[cfg(feature = "mock")]
+pub(crate) use manager::{parse_query, LanMessage};
+
+#[cfg(feature = "library")]
+use crate::cloudcore::CloudCore;
+#[cfg(feature = "library")]
+use crate::cloudcore_client::{CloudCoreBaseURL, CloudCoreRequest};
+#[cfg(feature = "library")]
+use crate::devices::IoTDevice;
+#[cfg(feature = "library")]
+use crate::properties::property::{IoTProperty, PROPS_PATH_PARAMS_DSN};
+#[cfg(feature = "library")]
+use crate::properties::value::IoTPropertyValue;
+#[cfg(feature = "library")]
+use crate::cloudcore_error::InputError;
+#[cfg(feature = "library")]
+use crate::secret::Secret;
+#[cfg(feature = "library")]
+use crate::urls::AYLA_LAN_JSON;
+#[cfg(feature = "library")]
+use crate::CloudCoreError;
+#[cfg(feature = "library")]
+use manager::LAN_MANAGER;
+#[cfg(feature = "library")]
+use reqwest::Method;
+#[cfg(feature = "library")]
+use serde::Deserialize;
+
+/// The key the device and the app derive their LAN session keys from.
+#[cfg(feature = "library")]
+#[derive(Debug, Deserialize)]
+struct LanConfig {
+    lanip_key: Secret<String>,
+    lanip_key_id: u32,
+    status: Option<String>,
+}
+
+#[cfg(feature = "library")]
+#[derive(Debug, Deserialize)]
+struct LanConfigResponse {
+    lanip: LanConfig,
+}
+
+#[cfg(feature = "library")]
+impl CloudCore {
+    /// Start talking to `device` over the local network. While connected `get_properties`
+    /// and `set_property_value` go to the device first and fall back to the cloud when that fails.
+    pub async fn connect_lan(&self, device: &IoTDevice) -> Result<(), CloudCoreError> {
+        let dsn = device
+            .dsn()
+            .ok_or_else(|| CloudCoreError::InvalidInput(InputError::Invalid("Device has no DSN".to_string())))?;
+        let lan_ip = match (device.lan_enabled(), device.lan_ip()) {
+            (Some(true), Some(lan_ip)) if !lan_ip.is_empty() => lan_ip,
+            _ => return Err(CloudCoreError::Lan(format!("LAN mode is not enabled on {}", dsn))),
+        };
+        let config = self.fetch_lan_config(dsn).await?;
+        if config.status.as_deref().is_some_and(|status| status != "enable") {
+            return Err(CloudCoreError::Lan(format!("LAN mode is not enabled on {}", dsn)));
+        }
+        LAN_MANAGER.connect(dsn, lan_ip, config.lanip_key, config.lanip_key_id).await
+    }
+
+    /// Stop talking to the device with `dsn` over the local network.
+    pub fn disconnect_lan(&self, dsn: &str) {
+        LAN_MANAGER.disconnect(dsn);
+    }
+
+    /// Whether requests for the device with `dsn` go over the local network first.
+    pub fn lan_connected(&self, dsn: &str) -> bool {
+        LAN_MANAGER.is_connected(dsn)
+    }
+
+    async fn fetch_lan_config(&self, dsn: &str) -> Result<LanConfig, CloudCoreError> {
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: String::from(AYLA_LAN_JSON).replace(PROPS_PATH_PARAMS_DSN, dsn),
+                method: Method::GET,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
+        Ok(response.json::<LanConfigResponse>().await?.lanip)
+    }
+}
+
+/// Read `names` over LAN, `None` when there is no LAN session with the device.
+#[cfg(feature = "library")]
+pub(crate) async fn get_properties(dsn: &str, names: &[String]) -> Option<Result<Vec<IoTProperty>, CloudCoreError>> {
+    if names.is_empty() || !LAN_MANAGER.is_connected(dsn) {
+        return None;
+    }
+    Some(LAN_MANAGER.get_properties(dsn, names).await)
+}
+
+/// Write `prop_name` over LAN, `None` when there is no LAN session with the device.
+#[cfg(feature = "library")]
+pub(crate) async fn set_property_value(dsn: &str, prop_name: &str, value: IoTPropertyValue) -> Option<Result<(), CloudCoreError>> {
+    if !LAN_MANAGER.is_connected(dsn) {
+        return None;
+    }
+    Some(LAN_MANAGER.set_property_value(dsn, prop_name, value).await)
+}
@@ -0,0 +1,154 @@
+#[cfg(feature = "library")]
+use aes::Aes256;
+#[cfg(feature = "library")]
+use cbc::cipher::block_padding::NoPadding;
+#[cfg(feature = "library")]
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+#[cfg(feature = "library")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "library")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "library")]
+use sha2::Sha256;
+#[cfg(feature = "library")]
+use crate::CloudCoreError;
+
+#[cfg(feature = "library")]
+type HmacSha256 = Hmac<Sha256>;
+
+#[cfg(feature = "library")]
+const BLOCK_SIZE: usize = 16;
+
+/// An encrypted and signed message, the body of every LAN request after the key exchange.
+#[cfg(feature = "library")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct LanPayload {
+    pub(crate) enc: String,
+    pub(crate) sign: String,
+}
+
+/// Which end of the session the keys are for, the app encrypts with the keys the device decrypts with.
+#[cfg(feature = "library")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LanRole {
+    App,
+    #[cfg_attr(not(feature = "mock"), allow(dead_code))]
+    Device,
+}
+
+/// The randoms and times the device and the app swap in the key exchange.
+#[cfg(feature = "library")]
+#[derive(Debug, Clone)]
+pub(crate) struct KeyExchange {
+    pub(crate) random_1: String,
+    pub(crate) time_1: u64,
+    pub(crate) random_2: String,
+    pub(crate) time_2: u64,
+}
+
+#[cfg(feature = "library")]
+struct CbcKeys {
+    sign: Vec<u8>,
+    crypto: Vec<u8>,
+    /// Every message continues the CBC chain of the one before it
+    iv: [u8; BLOCK_SIZE],
+}
+
+/// Session keys derived from the device's LAN key, one set per direction.
+#[cfg(feature = "library")]
+pub(crate) struct LanCipher {
+    outgoing: CbcKeys,
+    incoming: CbcKeys,
+}
+
+#[cfg(feature = "library")]
+impl LanCipher {
+    pub(crate) fn new(lanip_key: &str, exchange: &KeyExchange, role: LanRole) -> Self {
+        let app_seed = [
+            exchange.random_1.as_str(),
+            exchange.random_2.as_str(),
+            &exchange.time_1.to_string(),
+            &exchange.time_2.to_string(),
+        ]
+        .concat();
+        let device_seed = [
+            exchange.random_2.as_str(),
+            exchange.random_1.as_str(),
+            &exchange.time_2.to_string(),
+            &exchange.time_1.to_string(),
+        ]
+        .concat();
+        let app_keys = CbcKeys::derive(lanip_key, &app_seed);
+        let device_keys = CbcKeys::derive(lanip_key, &device_seed);
+        match role {
+            LanRole::App => Self { outgoing: app_keys, incoming: device_keys },
+            LanRole::Device => Self { outgoing: device_keys, incoming: app_keys },
+        }
+    }
+
+    pub(crate) fn encrypt(&mut self, plain: &[u8]) -> LanPayload {
+        let sign = sign(&self.outgoing.sign, plain);
+        // Zero padded to the block size, the JSON inside never ends in a zero byte
+        let mut buffer = plain.to_vec();
+        buffer.resize(plain.len().div_ceil(BLOCK_SIZE).max(1) * BLOCK_SIZE, 0);
+        let length = buffer.len();
+        let encryptor = cbc::Encryptor::<Aes256>::new_from_slices(&self.outgoing.crypto, &self.outgoing.iv)
+            .expect("LAN keys are 32 bytes and IVs 16");
+        let encrypted = encryptor
+            .encrypt_padded_mut::<NoPadding>(&mut buffer, length)
+            .expect("buffer is a whole number of blocks");
+        self.outgoing.iv.copy_from_slice(&encrypted[length - BLOCK_SIZE..]);
+        LanPayload {
+            enc: base64::encode(encrypted),
+            sign: base64::encode(sign),
+        }
+    }
+
+    pub(crate) fn decrypt(&mut self, payload: &LanPayload) -> Result<Vec<u8>, CloudCoreError> {
+        let mut buffer = base64::decode(&payload.enc).map_err(|err| CloudCoreError::Lan(err.to_string()))?;
+        if buffer.is_empty() || buffer.len() % BLOCK_SIZE != 0 {
+            return Err(CloudCoreError::Lan("Encrypted LAN payload is not a whole number of blocks".to_string()));
+        }
+        let mut next_iv = [0; BLOCK_SIZE];
+        next_iv.copy_from_slice(&buffer[buffer.len() - BLOCK_SIZE..]);
+        let decryptor = cbc::Decryptor::<Aes256>::new_from_slices(&self.incoming.crypto, &self.incoming.iv)
+            .expect("LAN keys are 32 bytes and IVs 16");
+        let decrypted = decryptor
+            .decrypt_padded_mut::<NoPadding>(&mut buffer)
+            .map_err(|err| CloudCoreError::Lan(err.to_string()))?;
+        let length = decrypted.iter().rposition(|byte| *byte != 0).map_or(0, |last| last + 1);
+        let plain = decrypted[..length].to_vec();
+        let expected = base64::decode(&payload.sign).map_err(|err| CloudCoreError::Lan(err.to_string()))?;
+        let mut mac = HmacSha256::new_from_slice(&self.incoming.sign).expect("HMAC takes keys of any size");
+        mac.update(&plain);
+        mac.verify_slice(&expected)
+            .map_err(|_| CloudCoreError::Lan("LAN payload signature does not match".to_string()))?;
+        self.incoming.iv = next_iv;
+        Ok(plain)
+    }
+}
+
+#[cfg(feature = "library")]
+impl CbcKeys {
+    fn derive(lanip_key: &str, seed: &str) -> Self {
+        let key = |tag: &str| {
+            let seed = [seed, tag].concat();
+            let inner = sign(lanip_key.as_bytes(), seed.as_bytes());
+            sign(lanip_key.as_bytes(), &[inner.as_slice(), seed.as_bytes()].concat())
+        };
+        let mut iv = [0; BLOCK_SIZE];
+        iv.copy_from_slice(&key("2")[..BLOCK_SIZE]);
+        Self {
+            sign: key("0"),
+            crypto: key("1"),
+            iv,
+        }
+    }
+}
+
+#[cfg(feature = "library")]
+fn sign(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC takes keys of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
@@ -0,0 +1,487 @@
+#[cfg(feature = "library")]
+use std::collections::{HashMap, VecDeque};
+#[cfg(feature = "library")]
+use std::convert::Infallible;
+#[cfg(feature = "library")]
+use std::net::{IpAddr, SocketAddr, TcpListener, UdpSocket};
+#[cfg(feature = "library")]
+use std::sync::Mutex;
+#[cfg(feature = "library")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "library")]
+use futures_util::future::join_all;
+#[cfg(feature = "library")]
+use hyper::server::conn::AddrStream;
+#[cfg(feature = "library")]
+use hyper::service::{make_service_fn, service_fn};
+#[cfg(feature = "library")]
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+#[cfg(feature = "library")]
+use lazy_static::lazy_static;
+#[cfg(feature = "library")]
+use log::{debug, error};
+#[cfg(feature = "library")]
+use mantle_utilities::RUNTIME;
+#[cfg(feature = "library")]
+use rand::distributions::Alphanumeric;
+#[cfg(feature = "library")]
+use rand::Rng;
+#[cfg(feature = "library")]
+use serde::Deserialize;
+#[cfg(feature = "library")]
+use serde_json::{json, Value};
+#[cfg(feature = "library")]
+use tokio::sync::oneshot;
+#[cfg(feature = "library")]
+use tokio::time::timeout;
+#[cfg(feature = "library")]
+use crate::lan::crypto::{KeyExchange, LanCipher, LanPayload, LanRole};
+#[cfg(feature = "library")]
+use crate::properties::property::IoTProperty;
+#[cfg(feature = "library")]
+use crate::properties::value::{IoTPropertyBaseType, IoTPropertyValue};
+#[cfg(feature = "library")]
+use crate::secret::Secret;
+#[cfg(feature = "library")]
+use crate::urls::{LAN_COMMANDS_JSON, LAN_DATAPOINT_JSON, LAN_KEY_EXCHANGE_JSON, LAN_LOCAL_REG_JSON};
+#[cfg(feature = "library")]
+use crate::CloudCoreError;
+
+/// How long the device gets to start the key exchange after the app registers.
+#[cfg(feature = "library")]
+const LAN_CONNECT_TIMEOUT_SECS: u64 = 5;
+/// How long the device gets to fetch a command and answer it.
+#[cfg(feature = "library")]
+const LAN_REQUEST_TIMEOUT_SECS: u64 = 5;
+#[cfg(feature = "library")]
+const LAN_RANDOM_LENGTH: usize = 16;
+/// Path prefix the device puts in front of the app's endpoints.
+#[cfg(feature = "library")]
+const LAN_URI: &str = "/local_lan";
+
+#[cfg(feature = "library")]
+lazy_static! {
+    pub(crate) static ref LAN_MANAGER: LanManager = LanManager::new();
+}
+
+/// What the device sends before and after its encrypted body.
+#[cfg(feature = "library")]
+#[derive(Debug, Deserialize)]
+pub(crate) struct LanMessage<T> {
+    #[allow(dead_code)]
+    pub(crate) seq_no: Option<u32>,
+    pub(crate) data: T,
+}
+
+/// A property value the device sent over LAN.
+#[cfg(feature = "library")]
+#[derive(Debug, Deserialize)]
+pub(crate) struct LanDatapoint {
+    pub(crate) name: String,
+    pub(crate) base_type: Option<String>,
+    pub(crate) value: Option<Value>,
+}
+
+#[cfg(feature = "library")]
+impl LanDatapoint {
+    fn into_property(self) -> IoTProperty {
+        let value = self.value.and_then(|value| serde_json::from_value::<IoTPropertyValue>(value).ok());
+        IoTProperty::from_lan(self.name, self.base_type.unwrap_or_default(), value)
+    }
+}
+
+#[cfg(feature = "library")]
+#[derive(Debug, Deserialize)]
+struct KeyExchangeRequest {
+    key_exchange: KeyExchangeData,
+}
+
+#[cfg(feature = "library")]
+#[derive(Debug, Deserialize)]
+struct KeyExchangeData {
+    random_1: String,
+    time_1: u64,
+    key_id: u32,
+}
+
+/// A command waiting for the device to fetch it.
+#[cfg(feature = "library")]
+struct LanCommand {
+    data: Value,
+    fetched: Option<oneshot::Sender<()>>,
+}
+
+/// Everything the app knows about one device it talks to over LAN.
+#[cfg(feature = "library")]
+struct LanSession {
+    dsn: String,
+    lan_ip: String,
+    lanip_key: Secret<String>,
+    key_id: u32,
+    cipher: Option<LanCipher>,
+    key_exchanged: Option<oneshot::Sender<()>>,
+    seq_no: u32,
+    next_cmd_id: u32,
+    commands: VecDeque<LanCommand>,
+    pending_reads: HashMap<u32, oneshot::Sender<LanDatapoint>>,
+    base_types: HashMap<String, String>,
+}
+
+#[cfg(feature = "library")]
+impl LanSession {
+    fn host(&self) -> Option<IpAddr> {
+        self.lan_ip.split(':').next()?.parse().ok()
+    }
+
+    fn queue_read(&mut self, name: &str) -> oneshot::Receiver<LanDatapoint> {
+        self.next_cmd_id += 1;
+        let cmd_id = self.next_cmd_id;
+        let (sender, receiver) = oneshot::channel();
+        self.pending_reads.insert(cmd_id, sender);
+        self.commands.push_back(LanCommand {
+            data: json!({
+                "cmds": [{
+                    "cmd": {
+                        "cmd_id": cmd_id,
+                        "method": "GET",
+                        "resource": format!("property.json?name={}", name),
+                        "uri": LAN_DATAPOINT_JSON,
+                        "data": "none",
+                    }
+                }]
+            }),
+            fetched: None,
+        });
+        receiver
+    }
+
+    fn queue_write(&mut self, name: &str, base_type: &str, value: &IoTPropertyValue) -> oneshot::Receiver<()> {
+        let (sender, receiver) = oneshot::channel();
+        self.commands.push_back(LanCommand {
+            data: json!({
+                "properties": [{
+                    "property": {
+                        "base_type": base_type,
+                        "value": value,
+                        "name": name,
+                        "dsn": self.dsn,
+                    }
+                }]
+            }),
+            fetched: Some(sender),
+        });
+        receiver
+    }
+
+    fn key_exchange(&mut self, body: &[u8]) -> (StatusCode, Value) {
+        let request = match serde_json::from_slice::<KeyExchangeRequest>(body) {
+            Ok(request) => request.key_exchange,
+            Err(err) => return (StatusCode::BAD_REQUEST, json!({ "error": err.to_string() })),
+        };
+        // The device has a different LAN key, the app has to fetch the new one and connect again
+        if request.key_id != self.key_id {
+            self.key_exchanged.take();
+            return (StatusCode::PRECONDITION_FAILED, json!({}));
+        }
+        let random_2: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(LAN_RANDOM_LENGTH)
+            .map(char::from)
+            .collect();
+        let time_2 = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_micros() as u64).unwrap_or_default();
+        let exchange = KeyExchange {
+            random_1: request.random_1,
+            time_1: request.time_1,
+            random_2: random_2.to_string(),
+            time_2,
+        };
+        self.cipher = Some(LanCipher::new(self.lanip_key.expose(), &exchange, LanRole::App));
+        self.seq_no = 0;
+        if let Some(key_exchanged) = self.key_exchanged.take() {
+            let _ = key_exchanged.send(());
+        }
+        debug!("LAN key exchange with {} done", self.dsn);
+        (StatusCode::OK, json!({ "random_2": random_2, "time_2": time_2 }))
+    }
+
+    fn next_command(&mut self) -> (StatusCode, Value) {
+        let Some(cipher) = self.cipher.as_mut() else {
+            return (StatusCode::BAD_REQUEST, json!({ "error": "No key exchange yet" }));
+        };
+        let command = self.commands.pop_front();
+        self.seq_no += 1;
+        let data = command.as_ref().map_or(json!({}), |command| command.data.clone());
+        let plain = json!({ "seq_no": self.seq_no, "data": data }).to_string();
+        let payload = cipher.encrypt(plain.as_bytes());
+        if let Some(fetched) = command.and_then(|command| command.fetched) {
+            let _ = fetched.send(());
+        }
+        // Partial content tells the device to come back for the rest
+        let status = if self.commands.is_empty() { StatusCode::OK } else { StatusCode::PARTIAL_CONTENT };
+        (status, json!(payload))
+    }
+
+    fn receive_datapoint(&mut self, query: &[(String, String)], body: &[u8]) -> (StatusCode, Value) {
+        let Some(cipher) = self.cipher.as_mut() else {
+            return (StatusCode::BAD_REQUEST, json!({ "error": "No key exchange yet" }));
+        };
+        let message = serde_json::from_slice::<LanPayload>(body)
+            .map_err(CloudCoreError::from)
+            .and_then(|payload| cipher.decrypt(&payload))
+            .and_then(|plain| Ok(serde_json::from_slice::<LanMessage<LanDatapoint>>(&plain)?));
+        let datapoint = match message {
+            Ok(message) => message.data,
+            Err(err) => {
+                error!("Dropping LAN datapoint from {}: {}", self.dsn, err);
+                return (StatusCode::BAD_REQUEST, json!({ "error": err.to_string() }));
+            }
+        };
+        if let Some(base_type) = datapoint.base_type.as_ref() {
+            self.base_types.insert(datapoint.name.to_string(), base_type.to_string());
+        }
+        let cmd_id = query
+            .iter()
+            .find(|(key, _)| key == "cmd_id")
+            .and_then(|(_, cmd_id)| cmd_id.parse::<u32>().ok());
+        match cmd_id.and_then(|cmd_id| self.pending_reads.remove(&cmd_id)) {
+            Some(read) => {
+                let _ = read.send(datapoint);
+            }
+            None => debug!("{} sent {} over LAN unasked", self.dsn, datapoint.name),
+        }
+        (StatusCode::OK, json!({}))
+    }
+}
+
+/// Keeps the LAN sessions with devices and serves the endpoints the devices call back on.
+#[cfg(feature = "library")]
+pub(crate) struct LanManager {
+    sessions: Mutex<HashMap<String, LanSession>>,
+    port: Mutex<Option<u16>>,
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "library")]
+impl LanManager {
+    fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+            port: Mutex::new(None),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Register with the device at `lan_ip` and wait for it to exchange keys.
+    pub(crate) async fn connect(&self, dsn: &str, lan_ip: &str, lanip_key: Secret<String>, key_id: u32) -> Result<(), CloudCoreError> {
+        let port = self.server_port()?;
+        let (key_exchanged, exchanged) = oneshot::channel();
+        self.sessions.lock().unwrap().insert(dsn.to_string(), LanSession {
+            dsn: dsn.to_string(),
+            lan_ip: lan_ip.to_string(),
+            lanip_key,
+            key_id,
+            cipher: None,
+            key_exchanged: Some(key_exchanged),
+            seq_no: 0,
+            next_cmd_id: 0,
+            commands: VecDeque::new(),
+            pending_reads: HashMap::new(),
+            base_types: HashMap::new(),
+        });
+        let result = async {
+            self.register(lan_ip, port, false).await?;
+            timeout(Duration::from_secs(LAN_CONNECT_TIMEOUT_SECS), exchanged)
+                .await
+                .map_err(|_| CloudCoreError::Timeout(format!("{} did not exchange LAN keys", dsn)))?
+                .map_err(|_| CloudCoreError::Lan(format!("LAN key exchange with {} failed", dsn)))
+        }
+        .await;
+        if result.is_err() {
+            self.disconnect(dsn);
+        }
+        result
+    }
+
+    pub(crate) fn disconnect(&self, dsn: &str) {
+        if self.sessions.lock().unwrap().remove(dsn).is_some() {
+            debug!("LAN session with {} closed", dsn);
+        }
+    }
+
+    pub(crate) fn is_connected(&self, dsn: &str) -> bool {
+        self.sessions.lock().unwrap().get(dsn).is_some_and(|session| session.cipher.is_some())
+    }
+
+    pub(crate) async fn get_properties(&self, dsn: &str, names: &[String]) -> Result<Vec<IoTProperty>, CloudCoreError> {
+        let (lan_ip, reads) = self.with_session(dsn, |session| {
+            names.iter().map(|name| session.queue_read(name)).collect::<Vec<_>>()
+        })?;
+        let result: Result<Vec<IoTProperty>, CloudCoreError> = async {
+            self.notify(&lan_ip).await?;
+            let datapoints = timeout(Duration::from_secs(LAN_REQUEST_TIMEOUT_SECS), join_all(reads))
+                .await
+                .map_err(|_| CloudCoreError::Timeout(format!("{} did not answer over LAN", dsn)))?;
+            datapoints
+                .into_iter()
+                .map(|datapoint| {
+                    datapoint
+                        .map(LanDatapoint::into_property)
+                        .map_err(|_| CloudCoreError::Lan(format!("LAN session with {} was closed", dsn)))
+                })
+                .collect()
+        }
+        .await;
+        if result.is_err() {
+            self.disconnect(dsn);
+        }
+        result
+    }
+
+    pub(crate) async fn set_property_value(&self, dsn: &str, name: &str, value: IoTPropertyValue) -> Result<(), CloudCoreError> {
+        let known_base_type = self.with_session(dsn, |session| session.base_types.get(name).cloned())?.1;
+        let base_type = match known_base_type {
+            Some(base_type) => base_type,
+            None => self.get_properties(dsn, &[name.to_string()]).await?
+                .first()
+                .map(|property| property.base_type().to_string())
+                .unwrap_or_default(),
+        };
+        let value = value.for_base_type(&IoTPropertyBaseType::from(base_type.as_str()))?;
+        let (lan_ip, fetched) = self.with_session(dsn, |session| session.queue_write(name, &base_type, &value))?;
+        let result = async {
+            self.notify(&lan_ip).await?;
+            timeout(Duration::from_secs(LAN_REQUEST_TIMEOUT_SECS), fetched)
+                .await
+                .map_err(|_| CloudCoreError::Timeout(format!("{} did not fetch the LAN write", dsn)))?
+                .map_err(|_| CloudCoreError::Lan(format!("LAN session with {} was closed", dsn)))
+        }
+        .await;
+        if result.is_err() {
+            self.disconnect(dsn);
+        }
+        result
+    }
+
+    fn with_session<T>(&self, dsn: &str, f: impl FnOnce(&mut LanSession) -> T) -> Result<(String, T), CloudCoreError> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions
+            .get_mut(dsn)
+            .filter(|session| session.cipher.is_some())
+            .ok_or_else(|| CloudCoreError::Lan(format!("No LAN session with {}", dsn)))?;
+        Ok((session.lan_ip.to_string(), f(session)))
+    }
+
+    /// Let the device know there are commands to fetch.
+    async fn notify(&self, lan_ip: &str) -> Result<(), CloudCoreError> {
+        let port = self.server_port()?;
+        self.register(lan_ip, port, true).await
+    }
+
+    async fn register(&self, lan_ip: &str, port: u16, notify: bool) -> Result<(), CloudCoreError> {
+        let body = json!({
+            "local_reg": {
+                "uri": LAN_URI,
+                "notify": notify as u8,
+                "ip": local_ip(lan_ip)?.to_string(),
+                "port": port,
+            }
+        });
+        let method = if notify { reqwest::Method::PUT } else { reqwest::Method::POST };
+        let response = self
+            .client
+            .request(method, format!("http://{}{}", lan_ip, LAN_LOCAL_REG_JSON))
+            .timeout(Duration::from_secs(LAN_REQUEST_TIMEOUT_SECS))
+            .json(&body)
+            .send()
+            .await?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(CloudCoreError::Lan(format!("{} refused the LAN registration with {}", lan_ip, response.status())))
+        }
+    }
+
+    /// Port of the server devices call back on, started the first time it is needed.
+    fn server_port(&self) -> Result<u16, CloudCoreError> {
+        let mut port = self.port.lock().unwrap();
+        if let Some(port) = *port {
+            return Ok(port);
+        }
+        let listener = TcpListener::bind(SocketAddr::from(([0, 0, 0, 0], 0))).map_err(lan_error)?;
+        listener.set_nonblocking(true).map_err(lan_error)?;
+        let local_port = listener.local_addr().map_err(lan_error)?.port();
+        let _guard = RUNTIME.enter();
+        let make_service = make_service_fn(|connection: &AddrStream| {
+            let remote = connection.remote_addr();
+            async move { Ok::<_, Infallible>(service_fn(move |request| handle_request(remote, request))) }
+        });
+        let server = Server::from_tcp(listener)
+            .map_err(|err| CloudCoreError::Lan(err.to_string()))?
+            .serve(make_service);
+        RUNTIME.spawn(async move {
+            if let Some(err) = server.await.err() {
+                error!("LAN server stopped: {}", err);
+            }
+        });
+        debug!("LAN server listening on port {}", local_port);
+        *port = Some(local_port);
+        Ok(local_port)
+    }
+
+    fn route(&self, remote: IpAddr, method: &Method, path: &str, query: &[(String, String)], body: &[u8]) -> (StatusCode, Value) {
+        let mut sessions = self.sessions.lock().unwrap();
+        let Some(session) = sessions.values_mut().find(|session| session.host() == Some(remote)) else {
+            return (StatusCode::NOT_FOUND, json!({ "error": "No LAN session" }));
+        };
+        match *method {
+            Method::POST if path == LAN_KEY_EXCHANGE_JSON => session.key_exchange(body),
+            Method::GET if path == LAN_COMMANDS_JSON => session.next_command(),
+            Method::POST if path == LAN_DATAPOINT_JSON => session.receive_datapoint(query, body),
+            _ => (StatusCode::NOT_FOUND, json!({ "error": "Not found" })),
+        }
+    }
+}
+
+#[cfg(feature = "library")]
+async fn handle_request(remote: SocketAddr, request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = parse_query(request.uri().query());
+    let body = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+    let (status, payload) = LAN_MANAGER.route(remote.ip(), &method, &path, &query, &body);
+    debug!("LAN {} {} from {} -> {}", method, path, remote, status);
+    let response = Response::builder()
+        .status(status)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(payload.to_string()))
+        .unwrap_or_default();
+    Ok(response)
+}
+
+#[cfg(feature = "library")]
+pub(crate) fn parse_query(query: Option<&str>) -> Vec<(String, String)> {
+    query
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// The address of this host on the network the device at `lan_ip` is on.
+#[cfg(feature = "library")]
+fn local_ip(lan_ip: &str) -> Result<IpAddr, CloudCoreError> {
+    let device = lan_ip
+        .parse::<SocketAddr>()
+        .or_else(|_| format!("{}:80", lan_ip).parse::<SocketAddr>())
+        .map_err(|err| CloudCoreError::Lan(format!("Invalid LAN ip {} -> {}", lan_ip, err)))?;
+    // Connecting a UDP socket sends nothing, it only picks the route
+    let socket = UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], 0))).map_err(lan_error)?;
+    socket.connect(device).map_err(lan_error)?;
+    Ok(socket.local_addr().map_err(lan_error)?.ip())
+}
+
+#[cfg(feature = "library")]
+fn lan_error(err: std::io::Error) -> CloudCoreError {
+    CloudCoreError::Lan(err.to_string())
+}
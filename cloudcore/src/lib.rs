@@ -8,18 +8,37 @@ pub mod cache;
 pub mod urls;
 pub mod pairing;
 pub mod polling;
+pub mod lan;
 pub mod examples;
 pub mod schedules;
 pub mod triggers;
 pub mod cloudcore_client;
+pub mod cloudcore_error;
+pub mod datetime;
+pub mod retry_policy;
 pub mod notifications;
 pub mod error_utils;
+pub mod secret;
+pub mod mock;
 
 pub use crate::cloudcore::CloudCore;
 pub use pairing::wifi_state::WifiPairingState;
+pub use pairing::pairing_diagnostics::PairingFailure;
 pub use pairing::wifi_pairing::WifiPairing;
 pub use pairing::wifi_network::WifiNetwork;
 pub use error_utils::ErrorUtil;
+pub use cloudcore_error::{CloudCoreError, IntoMantleResult};
+pub use secret::Secret;
 
 #[cfg(feature = "library")]
 pub use pairing::wifi_manager;
+#[cfg(feature = "library")]
+pub use retry_policy::RetryPolicy;
+#[cfg(feature = "library")]
+pub use pairing::pairing_config::{PairingConfig, PairingStepConfig};
+#[cfg(feature = "library")]
+pub use pairing::pairing_progress::PairingProgress;
+#[cfg(feature = "library")]
+pub use pairing::pairing_diagnostics::{PairingDiagnostics, PairingStepReport};
+#[cfg(feature = "library")]
+pub use pairing::pairing_log::{PairingLogConfig, PairingLogEntry, PairingLogLevel};
@@ -0,0 +1,17 @@
+#[cfg(feature = "mock")]
+mod ayla_service;
+#[cfg(feature = "mock")]
+mod mock_device;
+#[cfg(feature = "mock")]
+mod lan_device;
+#[cfg(feature = "mock")]
+mod device_ap;
+
+#[cfg(feature = "mock")]
+pub use self::ayla_service::{MockAylaServer, MockRequest};
+#[cfg(feature = "mock")]
+pub use self::mock_device::{MockDevice, MockProperty};
+#[cfg(feature = "mock")]
+pub use self::lan_device::MockLanDevice;
+#[cfg(feature = "mock")]
+pub use self::device_ap::MockDeviceAp;
@@ -0,0 +1,1245 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::error::Error;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, TimeZone, Utc};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{debug, error};
+use mantle_utilities::RUNTIME;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use uuid::Uuid;
+
+use crate::cloudcore::{ApplicationInfo, SessionParameters};
+use crate::mock::mock_device::MockDevice;
+use crate::properties::value::IoTPropertyValue;
+use crate::urls::{
+    AUTHORIZATION_BEARER, AYLA_DATAPOINTS_FILTER_END_DATE_KEY,
+    AYLA_DATAPOINTS_FILTER_SINCE_DATE_KEY, AYLA_DATAPOINTS_LIMIT_KEY, AYLA_DATAPOINTS_PAGE_KEY,
+    AYLA_DATAPOINTS_PAGINATED_KEY, AYLA_DATAPOINTS_PER_PAGE_KEY, AYLA_PROPS_QUERY_PARAMS_KEY,
+};
+
+static MOCK_TOKEN_LIFETIME_SECS: i64 = 86400;
+static MOCK_DATAPOINT_LIMIT: usize = 100;
+
+/// A request received by the mock Ayla service.
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: Value,
+}
+
+/// In-process fake of the Ayla user and device services.
+///
+/// Both services are served from the same address so a single
+/// [`SessionParameters`] can be put in `ayla_region_environment_map`.
+/// The server is shut down when this is dropped.
+pub struct MockAylaServer {
+    url: String,
+    app_info: ApplicationInfo,
+    state: Arc<Mutex<MockAylaState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockAylaServer {
+    pub fn start(app_info: ApplicationInfo) -> Result<Self, Box<dyn Error>> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        listener.set_nonblocking(true)?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let state = Arc::new(Mutex::new(MockAylaState::new(app_info.clone())));
+
+        let _guard = RUNTIME.enter();
+        let service_state = Arc::clone(&state);
+        let make_service = make_service_fn(move |_| {
+            let state = Arc::clone(&service_state);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_request(Arc::clone(&state), request)
+                }))
+            }
+        });
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+        RUNTIME.spawn(async move {
+            if let Some(err) = server.await.err() {
+                error!("Mock Ayla service stopped: {}", err);
+            }
+        });
+        debug!("Mock Ayla service listening on {}", &url);
+
+        Ok(Self {
+            url,
+            app_info,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Get a reference to the mock server's base url.
+    pub fn url(&self) -> &str {
+        self.url.as_ref()
+    }
+
+    /// Session parameters pointing both the user and device services at this server.
+    pub fn session_parameters(&self) -> SessionParameters {
+        SessionParameters {
+            app_info: self.app_info.clone(),
+            user_url: self.url.to_string(),
+            device_url: self.url.to_string(),
+        }
+    }
+
+    /// Add a confirmed user and return its uuid.
+    pub fn add_user(&self, email: &str, password: &str) -> String {
+        self.with_state(|state| {
+            let user = state.insert_user(email.to_string(), password.to_string(), None);
+            user.confirmed = true;
+            user.uuid.to_string()
+        })
+    }
+
+    pub fn add_device(&self, device: MockDevice) {
+        self.with_state(|state| {
+            state.devices.retain(|existing| existing.dsn() != device.dsn());
+            state.devices.push(device);
+        })
+    }
+
+    pub fn device(&self, dsn: &str) -> Option<MockDevice> {
+        self.with_state(|state| state.device(dsn).cloned())
+    }
+
+    /// Whether `dsn` connected to the service with a setup token and is waiting for a user to register it.
+    pub fn device_online(&self, dsn: &str) -> bool {
+        self.with_state(|state| state.online_devices.iter().any(|(device, _)| device.dsn() == dsn))
+    }
+
+    pub fn set_connection_status(&self, dsn: &str, connection_status: &str) {
+        self.with_state(|state| {
+            if let Some(device) = state.device_mut(dsn) {
+                device.set_connection_status(connection_status.to_string());
+            }
+        })
+    }
+
+    pub fn property_value(&self, dsn: &str, prop_name: &str) -> Option<IoTPropertyValue> {
+        self.with_state(|state| {
+            state
+                .device(dsn)
+                .and_then(|device| device.properties().iter().find(|property| property.name() == prop_name))
+                .and_then(|property| property.value().cloned())
+        })
+    }
+
+    /// Simulate the device reporting a new value for a property.
+    pub fn set_property_value(&self, dsn: &str, prop_name: &str, value: IoTPropertyValue) -> bool {
+        self.with_state(|state| state.create_datapoint(dsn, prop_name, value, None).is_some())
+    }
+
+    pub fn datapoints(&self, dsn: &str, prop_name: &str) -> Vec<Value> {
+        self.with_state(|state| {
+            state
+                .datapoints
+                .get(&(dsn.to_string(), prop_name.to_string()))
+                .cloned()
+                .unwrap_or_default()
+        })
+    }
+
+    pub fn confirmation_token(&self, email: &str) -> Option<String> {
+        self.with_state(|state| state.user_by_login(email).map(|user| user.confirmation_token.to_string()))
+    }
+
+    pub fn reset_password_token(&self, email: &str) -> Option<String> {
+        self.with_state(|state| state.user_by_login(email).and_then(|user| user.reset_password_token.clone()))
+    }
+
+    /// Expire every issued access token so the next authenticated call gets a 401.
+    pub fn expire_access_tokens(&self) {
+        self.with_state(|state| {
+            state.access_tokens.values_mut().for_each(|token| token.expires_at = 0);
+        })
+    }
+
+    /// Lifetime reported in `expires_in` for tokens issued from now on.
+    pub fn set_token_lifetime(&self, seconds: i64) {
+        self.with_state(|state| state.token_lifetime = seconds)
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.with_state(|state| state.requests.clone())
+    }
+
+    pub fn clear_requests(&self) {
+        self.with_state(|state| state.requests.clear())
+    }
+
+    /// Answer the next `count` requests with `status`, and a `Retry-After` header when given.
+    pub fn fail_next_requests(&self, count: usize, status: u16, retry_after_secs: Option<u64>) {
+        self.queue_failures(None, count, status, retry_after_secs)
+    }
+
+    /// Same as `fail_next_requests`, only counting requests sent with `method` to `path`.
+    pub fn fail_next_requests_to(&self, method: &str, path: &str, count: usize, status: u16, retry_after_secs: Option<u64>) {
+        self.queue_failures(Some((method.to_string(), path.to_string())), count, status, retry_after_secs)
+    }
+
+    /// Acknowledge datapoints sent to `prop_name` properties with ack enabled once `after` has passed
+    /// since they were created, with the given ack status and message.
+    pub fn auto_ack(&self, prop_name: &str, ack_status: i32, ack_message: i32, after: Duration) {
+        self.with_state(|state| {
+            state.auto_acks.insert(prop_name.to_string(), MockAck { ack_status, ack_message, after });
+        })
+    }
+
+    /// Answer the batch datapoint endpoint with a 404, like an Ayla deployment without it.
+    pub fn set_batch_datapoints_enabled(&self, enabled: bool) {
+        self.with_state(|state| state.batch_datapoints_enabled = enabled)
+    }
+
+    /// Write `prop_name` in batch datapoint requests but leave it out of the response.
+    pub fn omit_from_batch_results(&self, prop_name: &str) {
+        self.with_state(|state| state.omitted_batch_results.push(prop_name.to_string()))
+    }
+
+    /// Write the next batch datapoint request but answer it with a body that isn't a list of results.
+    pub fn malform_next_batch_response(&self) {
+        self.with_state(|state| state.malformed_batch_responses += 1)
+    }
+
+    fn queue_failures(&self, target: Option<(String, String)>, count: usize, status: u16, retry_after_secs: Option<u64>) {
+        self.with_state(|state| {
+            for _ in 0..count {
+                state.queued_failures.push(QueuedFailure {
+                    target: target.clone(),
+                    status,
+                    retry_after_secs,
+                });
+            }
+        })
+    }
+
+    /// The state shared with the fake devices that connect to this service.
+    pub(crate) fn shared_state(&self) -> Arc<Mutex<MockAylaState>> {
+        Arc::clone(&self.state)
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut MockAylaState) -> R) -> R {
+        // A panic inside a handler should not take every later assertion down with it
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        f(&mut state)
+    }
+}
+
+impl Drop for MockAylaServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle_request(
+    state: Arc<Mutex<MockAylaState>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = parse_query(request.uri().query());
+    let auth_token = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix(AUTHORIZATION_BEARER))
+        .map(|token| token.trim().to_string());
+    let bytes = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+    let body: Value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+
+    let (status, payload, retry_after) = {
+        let mut state = match state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        state.requests.push(MockRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query.clone(),
+            body: body.clone(),
+        });
+        let failure = state.queued_failures.iter().position(|failure| match &failure.target {
+            Some((target_method, target_path)) => *target_method == method.as_str() && *target_path == path,
+            None => true,
+        });
+        match failure {
+            Some(index) => {
+                let failure = state.queued_failures.remove(index);
+                let status = StatusCode::from_u16(failure.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                let (status, payload) = error_response(status, "Injected failure");
+                (status, payload, failure.retry_after_secs)
+            }
+            None => {
+                let (status, payload) = state.route(&method, &path, &query, auth_token, &body);
+                (status, payload, None)
+            }
+        }
+    };
+    debug!("Mock Ayla {} {} -> {}", method, path, status);
+
+    let mut response = Response::builder();
+    if let Some(retry_after) = retry_after {
+        response = response.header(RETRY_AFTER, retry_after.to_string());
+    }
+    let response = response
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(payload.to_string()))
+        .unwrap_or_default();
+    Ok(response)
+}
+
+fn datapoint_page(datapoints: Vec<&Value>, query: &[(String, String)]) -> MockResponse {
+    let page = query_value(query, AYLA_DATAPOINTS_PAGE_KEY)
+        .and_then(|page| page.parse::<usize>().ok())
+        .unwrap_or(1)
+        .max(1);
+    let per_page = query_value(query, AYLA_DATAPOINTS_PER_PAGE_KEY)
+        .and_then(|per_page| per_page.parse::<usize>().ok())
+        .unwrap_or(MOCK_DATAPOINT_LIMIT)
+        .clamp(1, MOCK_DATAPOINT_LIMIT);
+    let total = datapoints.len();
+    let page_datapoints: Vec<Value> = datapoints
+        .into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .map(|datapoint| json!({ "datapoint": datapoint }))
+        .collect();
+    let page_url = |page: usize| format!("datapoints.json?paginated=true&page={}&per_page={}", page, per_page);
+    let next_page = if page * per_page < total { Some(page_url(page + 1)) } else { None };
+    let previous_page = if page > 1 { Some(page_url(page - 1)) } else { None };
+    (StatusCode::OK, json!({
+        "datapoints": page_datapoints,
+        "meta": {
+            "previous_page": previous_page,
+            "next_page": next_page,
+            "current_page_number": page,
+        }
+    }))
+}
+
+fn parse_query(query: Option<&str>) -> Vec<(String, String)> {
+    match query.and_then(|query| reqwest::Url::parse(&format!("http://localhost/?{}", query)).ok()) {
+        Some(url) => url
+            .query_pairs()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect(),
+        None => vec![],
+    }
+}
+
+fn query_value<'a>(query: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    query
+        .iter()
+        .find(|(query_key, _)| query_key == key)
+        .map(|(_, value)| value.as_str())
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_filter_date(date: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+        return Some(date.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()
+        .map(|date| Utc.from_utc_datetime(&date.and_hms(0, 0, 0)))
+}
+
+fn error_response(status: StatusCode, message: &str) -> (StatusCode, Value) {
+    (status, json!({ "error": message }))
+}
+
+fn not_found() -> (StatusCode, Value) {
+    error_response(StatusCode::NOT_FOUND, "Not found")
+}
+
+struct MockUser {
+    id: u32,
+    uuid: String,
+    email: String,
+    username: String,
+    password: String,
+    phone_number: Option<String>,
+    confirmed: bool,
+    confirmation_token: String,
+    reset_password_token: Option<String>,
+    created_at: String,
+}
+
+impl MockUser {
+    fn profile_json(&self) -> Value {
+        json!({
+            "uuid": self.uuid,
+            "username": self.username,
+            "email": self.email,
+            "firstname": "FirstName",
+            "lastname": "LastName",
+            "approved": true,
+            "confirmed": self.confirmed,
+            "city": null,
+            "company": null,
+            "confirmed_at": null,
+            "country": null,
+            "created_at": self.created_at,
+            "phone_country_code": null,
+            "phone": self.phone_number,
+            "primary_contact": if self.phone_number.is_some() { "phone" } else { "email" },
+            "state": null,
+            "street": null,
+            "updated_at": self.created_at,
+            "zip": null,
+            "dealers": [],
+        })
+    }
+
+    fn account_json(&self) -> Value {
+        json!({
+            "admin": false,
+            "alternate_email": null,
+            "approved": true,
+            "created_at": self.created_at,
+            "email": self.email,
+            "firstname": "FirstName",
+            "id": self.id,
+            "lastname": "LastName",
+            "oem_approved": true,
+            "origin_oem_id": 1,
+            "phone_number": self.phone_number,
+            "primary_contact": if self.phone_number.is_some() { "phone" } else { "email" },
+            "terms_accepted": true,
+            "terms_accepted_at": self.created_at,
+            "updated_at": self.created_at,
+            "username": self.username,
+            "uuid": self.uuid,
+        })
+    }
+}
+
+struct MockToken {
+    email: String,
+    expires_at: i64,
+}
+
+struct MockTrigger {
+    dsn: String,
+    property_name: String,
+    trigger: Value,
+}
+
+struct MockAck {
+    ack_status: i32,
+    ack_message: i32,
+    after: Duration,
+}
+
+struct QueuedFailure {
+    target: Option<(String, String)>,
+    status: u16,
+    retry_after_secs: Option<u64>,
+}
+
+pub(crate) struct MockAylaState {
+    app_info: ApplicationInfo,
+    users: Vec<MockUser>,
+    access_tokens: HashMap<String, MockToken>,
+    refresh_tokens: HashMap<String, String>,
+    devices: Vec<MockDevice>,
+    /// Devices that joined a network and wait to be registered with their setup token
+    online_devices: Vec<(MockDevice, String)>,
+    datapoints: HashMap<(String, String), Vec<Value>>,
+    triggers: Vec<MockTrigger>,
+    schedules: Vec<Value>,
+    requests: Vec<MockRequest>,
+    queued_failures: Vec<QueuedFailure>,
+    batch_datapoints_enabled: bool,
+    omitted_batch_results: Vec<String>,
+    malformed_batch_responses: usize,
+    auto_acks: HashMap<String, MockAck>,
+    datapoint_created: HashMap<String, Instant>,
+    token_lifetime: i64,
+    next_key: u32,
+}
+
+type MockResponse = (StatusCode, Value);
+
+impl MockAylaState {
+    fn new(app_info: ApplicationInfo) -> Self {
+        Self {
+            app_info,
+            users: vec![],
+            access_tokens: HashMap::new(),
+            refresh_tokens: HashMap::new(),
+            devices: vec![],
+            online_devices: vec![],
+            datapoints: HashMap::new(),
+            triggers: vec![],
+            schedules: vec![],
+            requests: vec![],
+            queued_failures: vec![],
+            batch_datapoints_enabled: true,
+            omitted_batch_results: vec![],
+            malformed_batch_responses: 0,
+            auto_acks: HashMap::new(),
+            datapoint_created: HashMap::new(),
+            token_lifetime: MOCK_TOKEN_LIFETIME_SECS,
+            next_key: 1000,
+        }
+    }
+
+    /// A device joined a network and connected to the service with `setup_token`.
+    pub(crate) fn add_online_device(&mut self, device: MockDevice, setup_token: String) {
+        self.online_devices.retain(|(online, _)| online.dsn() != device.dsn());
+        self.online_devices.push((device, setup_token));
+    }
+
+    fn next_key(&mut self) -> u32 {
+        self.next_key += 1;
+        self.next_key
+    }
+
+    fn route(
+        &mut self,
+        method: &Method,
+        path: &str,
+        query: &[(String, String)],
+        auth_token: Option<String>,
+        body: &Value,
+    ) -> MockResponse {
+        let segments: Vec<&str> = path.trim_start_matches('/').split('/').collect();
+        // Everything but sign up, sign in and the token/password flows needs a valid session
+        let email = match segments.as_slice() {
+            ["users", "sign_in.json"]
+            | ["users", "refresh_token.json"]
+            | ["users", "sign_out.json"]
+            | ["users", "confirmation.json"]
+            | ["users", "password.json"] => None,
+            ["users.json"] if method == Method::POST => None,
+            _ => match self.authorize(auth_token) {
+                Ok(email) => Some(email),
+                Err(response) => return response,
+            },
+        };
+        let email = email.unwrap_or_default();
+
+        match (method, segments.as_slice()) {
+            (&Method::POST, ["users", "sign_in.json"]) => self.sign_in(body),
+            (&Method::POST, ["users", "refresh_token.json"]) => self.refresh_token(body),
+            (&Method::POST, ["users", "sign_out.json"]) => self.sign_out(body),
+            (&Method::GET, ["users", "get_user_profile.json"]) => self.user_profile(&email),
+            (&Method::POST, ["users.json"]) => self.create_user(body),
+            (&Method::PUT, ["users.json"]) => self.change_password(&email, body),
+            (&Method::DELETE, ["users.json"]) => self.delete_user(&email),
+            (&Method::PUT, ["users", "confirmation.json"]) => self.confirm_user(query),
+            (&Method::POST, ["users", "confirmation.json"]) => self.resend_confirmation(body),
+            (&Method::POST, ["users", "password.json"]) => self.request_password_reset(body),
+            (&Method::PUT, ["users", "password.json"]) => self.reset_password(body),
+            (&Method::PUT, ["users", "update_email.json"]) => self.update_email(&email, query),
+            (&Method::GET, ["apiv1", "devices.json"]) => self.devices(),
+            (&Method::POST, ["apiv1", "devices.json"]) => self.register_device(body),
+            (&Method::GET, ["apiv1", "dsns", dsn_json]) => self.device_with_dsn(dsn_json),
+            (&Method::PUT, ["apiv1", "dsns", dsn_json]) => self.rename_device(dsn_json, body),
+            (&Method::PUT, ["apiv1", "devices", key, "cmds", "factory_reset.json"]) => self.factory_reset(key),
+            (&Method::DELETE, ["apiv1", "devices", key_json]) => self.unregister_device(key_json),
+            (&Method::GET, ["apiv1", "devices", key, "time_zones.json"]) => self.time_zone(key),
+            (&Method::PUT, ["apiv1", "dsns", dsn, "time_zones.json"]) => self.set_time_zone(dsn, body),
+            (&Method::GET, ["apiv1", "dsns", dsn, "lan.json"]) => self.lan_config(dsn),
+            (&Method::GET, ["apiv1", "dsns", dsn, "properties.json"]) => self.properties(dsn, query),
+            (&Method::GET, ["apiv1", "dsns", dsn, "properties", prop_name, "datapoints.json"]) => {
+                self.get_datapoints(dsn, prop_name, query)
+            }
+            (&Method::GET, ["apiv1", "dsns", dsn, "properties", prop_name, "datapoints", id_json]) => {
+                self.get_datapoint(dsn, prop_name, id_json)
+            }
+            (&Method::POST, ["apiv1", "dsns", dsn, "properties", prop_name, "datapoints.json"]) => {
+                self.post_datapoint(dsn, prop_name, body)
+            }
+            (&Method::POST, ["apiv1", "batch_datapoints.json"]) if self.batch_datapoints_enabled => {
+                self.post_batch_datapoints(body)
+            }
+            (&Method::GET, ["apiv1", "dsns", dsn, "properties", prop_name, "triggers.json"]) => {
+                self.triggers(dsn, prop_name)
+            }
+            (&Method::POST, ["apiv1", "dsns", dsn, "properties", prop_name, "triggers.json"]) => {
+                self.create_trigger(&email, dsn, prop_name, body)
+            }
+            (&Method::DELETE, ["apiv1", "triggers", key_json]) => self.delete_trigger(key_json),
+            (&Method::POST, ["apiv1", "triggers", key, "trigger_apps.json"]) => self.create_trigger_app(&email, key, body),
+            (&Method::PUT, ["apiv1", "trigger_apps", key_json]) => self.update_trigger_app(key_json, body),
+            (&Method::DELETE, ["apiv1", "trigger_apps", key_json]) => self.delete_trigger_app(key_json),
+            (&Method::GET, ["apiv1", "devices", device, "schedules.json"]) => self.device_schedules(device),
+            (&Method::POST, ["apiv1", "devices", device, "schedules.json"]) => self.create_schedule(device, body),
+            (&Method::PUT, ["apiv1", "devices", device, "schedules", key_json]) => self.update_schedule(device, key_json, body),
+            (&Method::GET, ["apiv1", "schedules", "all", "by_user.json"]) => self.all_schedules(),
+            _ => not_found(),
+        }
+    }
+
+    fn authorize(&self, auth_token: Option<String>) -> Result<String, MockResponse> {
+        let token = auth_token
+            .and_then(|token| self.access_tokens.get(&token))
+            .ok_or_else(|| error_response(StatusCode::UNAUTHORIZED, "Your access token is invalid"))?;
+        if token.expires_at <= Utc::now().timestamp() {
+            return Err(error_response(StatusCode::UNAUTHORIZED, "Your access token has expired"));
+        }
+        Ok(token.email.to_string())
+    }
+
+    fn insert_user(&mut self, email: String, password: String, phone_number: Option<String>) -> &mut MockUser {
+        let id = self.next_key();
+        let username = phone_number.clone().unwrap_or_else(|| email.to_string());
+        self.users.push(MockUser {
+            id,
+            uuid: Uuid::new_v4().to_string(),
+            email,
+            username,
+            password,
+            phone_number,
+            confirmed: false,
+            confirmation_token: Uuid::new_v4().simple().to_string(),
+            reset_password_token: None,
+            created_at: now(),
+        });
+        self.users.last_mut().unwrap()
+    }
+
+    fn user_by_login(&self, login: &str) -> Option<&MockUser> {
+        self.users.iter().find(|user| user.email == login || user.username == login)
+    }
+
+    fn user_by_login_mut(&mut self, login: &str) -> Option<&mut MockUser> {
+        self.users.iter_mut().find(|user| user.email == login || user.username == login)
+    }
+
+    fn valid_application(&self, body: &Value) -> bool {
+        body["user"]["application"]["app_id"] == self.app_info.app_id.as_str()
+            && body["user"]["application"]["app_secret"] == self.app_info.app_secret.as_str()
+    }
+
+    fn issue_tokens(&mut self, email: String) -> MockResponse {
+        let access_token = Uuid::new_v4().simple().to_string();
+        let refresh_token = Uuid::new_v4().simple().to_string();
+        let expires_in = self.token_lifetime.max(0);
+        self.access_tokens.insert(access_token.to_string(), MockToken {
+            email: email.to_string(),
+            expires_at: Utc::now().timestamp() + expires_in,
+        });
+        self.refresh_tokens.insert(refresh_token.to_string(), email);
+        (StatusCode::OK, json!({
+            "access_token": access_token,
+            "refresh_token": refresh_token,
+            "expires_in": expires_in,
+            "role": "EndUser",
+        }))
+    }
+
+    fn revoke_tokens(&mut self, email: &str) {
+        self.access_tokens.retain(|_, token| token.email != email);
+        self.refresh_tokens.retain(|_, token_email| token_email != email);
+    }
+
+    fn sign_in(&mut self, body: &Value) -> MockResponse {
+        if !self.valid_application(body) {
+            return error_response(StatusCode::UNAUTHORIZED, "Could not find application");
+        }
+        let login = body["user"]["email"].as_str().unwrap_or_default();
+        let password = body["user"]["password"].as_str().unwrap_or_default();
+        let (email, confirmed) = match self.user_by_login(login) {
+            Some(user) if user.password == password => (user.email.to_string(), user.confirmed),
+            _ => return error_response(StatusCode::UNAUTHORIZED, "Invalid email or password."),
+        };
+        if !confirmed {
+            return error_response(StatusCode::UNAUTHORIZED, "You have to confirm your account before continuing.");
+        }
+        self.issue_tokens(email)
+    }
+
+    fn refresh_token(&mut self, body: &Value) -> MockResponse {
+        let refresh_token = body["user"]["refresh_token"].as_str().unwrap_or_default();
+        match self.refresh_tokens.remove(refresh_token) {
+            Some(email) => self.issue_tokens(email),
+            None => error_response(StatusCode::UNAUTHORIZED, "Your refresh token is invalid"),
+        }
+    }
+
+    fn sign_out(&mut self, body: &Value) -> MockResponse {
+        let access_token = body["user"]["access_token"].as_str().unwrap_or_default();
+        match self.access_tokens.remove(access_token) {
+            Some(_) => (StatusCode::OK, json!({})),
+            None => error_response(StatusCode::UNAUTHORIZED, "Your access token is invalid"),
+        }
+    }
+
+    fn user_profile(&self, email: &str) -> MockResponse {
+        match self.user_by_login(email) {
+            Some(user) => (StatusCode::OK, user.profile_json()),
+            None => not_found(),
+        }
+    }
+
+    fn create_user(&mut self, body: &Value) -> MockResponse {
+        if !self.valid_application(body) {
+            return error_response(StatusCode::UNAUTHORIZED, "Could not find application");
+        }
+        let email = body["user"]["email"].as_str().unwrap_or_default().to_string();
+        let password = body["user"]["password"].as_str().unwrap_or_default().to_string();
+        let phone_number = body["user"]["phone_number"].as_str().map(|phone| phone.to_string());
+        if email.is_empty() || password.is_empty() {
+            return (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "email": ["can't be blank"] } }));
+        }
+        if self.user_by_login(&email).is_some() {
+            return (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "email": ["has already been taken"] } }));
+        }
+        let user = self.insert_user(email, password, phone_number);
+        (StatusCode::CREATED, user.account_json())
+    }
+
+    fn change_password(&mut self, email: &str, body: &Value) -> MockResponse {
+        let current_password = body["user"]["current_password"].as_str().unwrap_or_default();
+        let password = body["user"]["password"].as_str().unwrap_or_default().to_string();
+        match self.user_by_login_mut(email) {
+            Some(user) if user.password == current_password => user.password = password,
+            Some(_) => return (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "current_password": ["is invalid"] } })),
+            None => return not_found(),
+        }
+        self.revoke_tokens(email);
+        (StatusCode::OK, json!({}))
+    }
+
+    fn delete_user(&mut self, email: &str) -> MockResponse {
+        self.users.retain(|user| user.email != email);
+        self.revoke_tokens(email);
+        (StatusCode::OK, json!({}))
+    }
+
+    fn confirm_user(&mut self, query: &[(String, String)]) -> MockResponse {
+        let token = query_value(query, "confirmation_token").unwrap_or_default();
+        match self.users.iter_mut().find(|user| !token.is_empty() && user.confirmation_token == token) {
+            Some(user) => {
+                user.confirmed = true;
+                (StatusCode::OK, user.account_json())
+            }
+            None => (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "confirmation_token": ["is invalid"] } })),
+        }
+    }
+
+    fn resend_confirmation(&mut self, body: &Value) -> MockResponse {
+        let login = body["user"]["email"].as_str().or_else(|| body["user"]["username"].as_str()).unwrap_or_default();
+        match self.user_by_login_mut(login) {
+            Some(user) if user.confirmed => (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "email": ["was already confirmed"] } })),
+            Some(user) => {
+                user.confirmation_token = Uuid::new_v4().simple().to_string();
+                (StatusCode::CREATED, json!({}))
+            }
+            None => (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "email": ["not found"] } })),
+        }
+    }
+
+    fn request_password_reset(&mut self, body: &Value) -> MockResponse {
+        let login = body["user"]["email"].as_str().or_else(|| body["user"]["username"].as_str()).unwrap_or_default();
+        match self.user_by_login_mut(login) {
+            Some(user) => {
+                user.reset_password_token = Some(Uuid::new_v4().simple().to_string());
+                (StatusCode::CREATED, json!({}))
+            }
+            None => (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "email": ["not found"] } })),
+        }
+    }
+
+    fn reset_password(&mut self, body: &Value) -> MockResponse {
+        let token = body["user"]["reset_password_token"].as_str().unwrap_or_default();
+        let password = body["user"]["password"].as_str().unwrap_or_default().to_string();
+        let email = match self.users.iter_mut().find(|user| user.reset_password_token.as_deref() == Some(token)) {
+            Some(user) => {
+                user.password = password;
+                user.reset_password_token = None;
+                user.email.to_string()
+            }
+            None => return (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "reset_password_token": ["is invalid"] } })),
+        };
+        self.revoke_tokens(&email);
+        (StatusCode::OK, json!({}))
+    }
+
+    fn update_email(&mut self, email: &str, query: &[(String, String)]) -> MockResponse {
+        let new_email = query_value(query, "email").unwrap_or_default().to_string();
+        if new_email.is_empty() || self.user_by_login(&new_email).is_some() {
+            return (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "email": ["is invalid"] } }));
+        }
+        match self.user_by_login_mut(email) {
+            Some(user) => {
+                if user.username == user.email {
+                    user.username = new_email.to_string();
+                }
+                user.email = new_email;
+            }
+            None => return not_found(),
+        }
+        self.revoke_tokens(email);
+        (StatusCode::OK, json!({}))
+    }
+
+    fn device(&self, dsn: &str) -> Option<&MockDevice> {
+        self.devices.iter().find(|device| device.dsn() == dsn)
+    }
+
+    fn device_mut(&mut self, dsn: &str) -> Option<&mut MockDevice> {
+        self.devices.iter_mut().find(|device| device.dsn() == dsn)
+    }
+
+    /// Devices are addressed by key on some endpoints and by dsn on others.
+    fn device_by_id(&self, id: &str) -> Option<&MockDevice> {
+        self.devices.iter().find(|device| device.dsn() == id || device.key().to_string() == id)
+    }
+
+    fn devices(&self) -> MockResponse {
+        let devices: Vec<Value> = self.devices.iter().map(|device| json!({ "device": device.to_json() })).collect();
+        (StatusCode::OK, Value::from(devices))
+    }
+
+    fn register_device(&mut self, body: &Value) -> MockResponse {
+        let dsn = body["device"]["dsn"].as_str().unwrap_or_default();
+        let setup_token = body["device"]["setup_token"].as_str().unwrap_or_default();
+        let index = self
+            .online_devices
+            .iter()
+            .position(|(device, token)| device.dsn() == dsn && token == setup_token);
+        match index {
+            Some(index) => {
+                let (device, _) = self.online_devices.remove(index);
+                let payload = json!({ "device": device.to_json() });
+                self.devices.retain(|existing| existing.dsn() != dsn);
+                self.devices.push(device);
+                (StatusCode::CREATED, payload)
+            }
+            None => (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "device": ["not found"] } })),
+        }
+    }
+
+    fn device_with_dsn(&self, dsn_json: &str) -> MockResponse {
+        match self.device(dsn_json.trim_end_matches(".json")) {
+            Some(device) => (StatusCode::OK, json!({ "device": device.to_json() })),
+            None => not_found(),
+        }
+    }
+
+    fn rename_device(&mut self, dsn_json: &str, body: &Value) -> MockResponse {
+        let product_name = body["device"]["product_name"].as_str().unwrap_or_default().to_string();
+        match self.device_mut(dsn_json.trim_end_matches(".json")) {
+            Some(device) => {
+                device.set_product_name(product_name);
+                (StatusCode::OK, json!({}))
+            }
+            None => not_found(),
+        }
+    }
+
+    fn factory_reset(&mut self, key: &str) -> MockResponse {
+        match self.device_by_id(key) {
+            Some(_) => (StatusCode::OK, json!({})),
+            None => not_found(),
+        }
+    }
+
+    fn unregister_device(&mut self, key_json: &str) -> MockResponse {
+        let key = key_json.trim_end_matches(".json");
+        let dsn = match self.device_by_id(key) {
+            Some(device) => device.dsn().to_string(),
+            None => return not_found(),
+        };
+        self.devices.retain(|device| device.dsn() != dsn);
+        self.datapoints.retain(|(datapoint_dsn, _), _| datapoint_dsn != &dsn);
+        self.triggers.retain(|trigger| trigger.dsn != dsn);
+        (StatusCode::OK, json!({}))
+    }
+
+    fn lan_config(&self, dsn: &str) -> MockResponse {
+        match self.device(dsn) {
+            Some(device) => (StatusCode::OK, device.lan_json()),
+            None => not_found(),
+        }
+    }
+
+    fn time_zone(&self, key: &str) -> MockResponse {
+        match self.device_by_id(key) {
+            Some(device) => (StatusCode::OK, json!({ "time_zone": device.time_zone_json() })),
+            None => not_found(),
+        }
+    }
+
+    fn set_time_zone(&mut self, dsn: &str, body: &Value) -> MockResponse {
+        let tz_id = body["tz_id"].as_str().unwrap_or_default().to_string();
+        match self.device_mut(dsn) {
+            Some(device) => {
+                device.set_tz_id(tz_id);
+                (StatusCode::OK, json!({ "time_zone": device.time_zone_json() }))
+            }
+            None => not_found(),
+        }
+    }
+
+    fn properties(&self, dsn: &str, query: &[(String, String)]) -> MockResponse {
+        let device = match self.device(dsn) {
+            Some(device) => device,
+            None => return not_found(),
+        };
+        let names: Vec<&str> = query
+            .iter()
+            .filter(|(key, _)| key == AYLA_PROPS_QUERY_PARAMS_KEY)
+            .map(|(_, value)| value.as_str())
+            .collect();
+        let properties: Vec<Value> = device
+            .properties()
+            .iter()
+            .filter(|property| names.is_empty() || names.contains(&property.name()))
+            .map(|property| json!({ "property": property.to_json(device) }))
+            .collect();
+        (StatusCode::OK, Value::from(properties))
+    }
+
+    fn get_datapoints(&self, dsn: &str, prop_name: &str, query: &[(String, String)]) -> MockResponse {
+        if self.device(dsn).is_none() {
+            return not_found();
+        }
+        let since = query_value(query, AYLA_DATAPOINTS_FILTER_SINCE_DATE_KEY).and_then(parse_filter_date);
+        let end = query_value(query, AYLA_DATAPOINTS_FILTER_END_DATE_KEY).and_then(parse_filter_date);
+        let limit = query_value(query, AYLA_DATAPOINTS_LIMIT_KEY)
+            .and_then(|limit| limit.parse::<usize>().ok())
+            .unwrap_or(MOCK_DATAPOINT_LIMIT);
+        let datapoints: Vec<&Value> = self
+            .datapoints
+            .get(&(dsn.to_string(), prop_name.to_string()))
+            .map(|datapoints| {
+                datapoints
+                    .iter()
+                    .filter(|datapoint| {
+                        let created_at = datapoint["created_at"].as_str().and_then(parse_filter_date);
+                        match created_at {
+                            Some(created_at) => {
+                                since.filter(|since| created_at < *since).is_none()
+                                    && end.filter(|end| created_at >= *end).is_none()
+                            }
+                            None => true,
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        if query_value(query, AYLA_DATAPOINTS_PAGINATED_KEY) == Some("true") {
+            return datapoint_page(datapoints, query);
+        }
+        // Ayla returns the most recent datapoints when a limit is applied
+        let skip = datapoints.len().saturating_sub(limit);
+        let datapoints: Vec<Value> = datapoints
+            .into_iter()
+            .skip(skip)
+            .map(|datapoint| json!({ "datapoint": datapoint }))
+            .collect();
+        (StatusCode::OK, Value::from(datapoints))
+    }
+
+    fn post_datapoint(&mut self, dsn: &str, prop_name: &str, body: &Value) -> MockResponse {
+        let value = match serde_json::from_value::<IoTPropertyValue>(body["datapoint"]["value"].clone()) {
+            Ok(value) => value,
+            Err(_) => return (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "value": ["is invalid"] } })),
+        };
+        let metadata = body["datapoint"]["metadata"].clone();
+        match self.create_datapoint(dsn, prop_name, value, Some(metadata)) {
+            Some(datapoint) => (StatusCode::CREATED, json!({ "datapoint": datapoint })),
+            None => not_found(),
+        }
+    }
+
+    fn post_batch_datapoints(&mut self, body: &Value) -> MockResponse {
+        let batch = match body["batch_datapoints"].as_array() {
+            Some(batch) => batch.clone(),
+            None => return (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "batch_datapoints": ["is missing"] } })),
+        };
+        let omitted = self.omitted_batch_results.clone();
+        let results: Vec<Value> = batch
+            .iter()
+            .map(|item| {
+                let dsn = item["dsn"].as_str().unwrap_or_default();
+                let name = item["name"].as_str().unwrap_or_default();
+                let value = serde_json::from_value::<IoTPropertyValue>(item["datapoint"]["value"].clone());
+                let metadata = item["datapoint"]["metadata"].clone();
+                match value.ok().and_then(|value| self.create_datapoint(dsn, name, value, Some(metadata))) {
+                    Some(datapoint) => json!({ "dsn": dsn, "name": name, "status": 201, "datapoint": datapoint }),
+                    None => json!({ "dsn": dsn, "name": name, "status": 404, "errors": "Property not found" }),
+                }
+            })
+            .filter(|result| !omitted.iter().any(|name| result["name"] == name.as_str()))
+            .collect();
+        if self.malformed_batch_responses > 0 {
+            self.malformed_batch_responses -= 1;
+            return (StatusCode::CREATED, json!("<html>Created</html>"));
+        }
+        (StatusCode::CREATED, Value::from(results))
+    }
+
+    fn create_datapoint(
+        &mut self,
+        dsn: &str,
+        prop_name: &str,
+        value: IoTPropertyValue,
+        metadata: Option<Value>,
+    ) -> Option<Value> {
+        let created_at = now();
+        let property = self.device_mut(dsn)?.property_mut(prop_name)?;
+        property.set_value(value.clone(), created_at.to_string());
+        let mut datapoint = json!({
+            "value": value,
+            "metadata": metadata.unwrap_or_else(|| json!({})),
+            "updated_at": created_at,
+            "created_at": created_at,
+            "echo": false,
+        });
+        if property.ack_enabled() {
+            let id = Uuid::new_v4().to_string();
+            self.datapoint_created.insert(id.to_string(), Instant::now());
+            datapoint["id"] = json!(id);
+            datapoint["ack_status"] = Value::Null;
+            datapoint["ack_message"] = Value::Null;
+            datapoint["acked_at"] = Value::Null;
+        }
+        self.datapoints
+            .entry((dsn.to_string(), prop_name.to_string()))
+            .or_default()
+            .push(datapoint.clone());
+        Some(datapoint)
+    }
+
+    fn get_datapoint(&mut self, dsn: &str, prop_name: &str, id_json: &str) -> MockResponse {
+        let id = id_json.trim_end_matches(".json");
+        let acked = match (self.auto_acks.get(prop_name), self.datapoint_created.get(id)) {
+            (Some(ack), Some(created)) if created.elapsed() >= ack.after => Some((ack.ack_status, ack.ack_message)),
+            _ => None,
+        };
+        let datapoint = self
+            .datapoints
+            .get_mut(&(dsn.to_string(), prop_name.to_string()))
+            .and_then(|datapoints| datapoints.iter_mut().find(|datapoint| datapoint["id"] == id));
+        match datapoint {
+            Some(datapoint) => {
+                if let (Some((ack_status, ack_message)), true) = (acked, datapoint["acked_at"].is_null()) {
+                    datapoint["ack_status"] = json!(ack_status);
+                    datapoint["ack_message"] = json!(ack_message);
+                    datapoint["acked_at"] = json!(now());
+                }
+                (StatusCode::OK, json!({ "datapoint": datapoint }))
+            }
+            None => not_found(),
+        }
+    }
+
+    fn triggers(&self, dsn: &str, prop_name: &str) -> MockResponse {
+        let triggers: Vec<Value> = self
+            .triggers
+            .iter()
+            .filter(|trigger| trigger.dsn == dsn && trigger.property_name == prop_name)
+            .map(|trigger| json!({ "trigger": trigger.trigger }))
+            .collect();
+        (StatusCode::OK, Value::from(triggers))
+    }
+
+    fn create_trigger(&mut self, email: &str, dsn: &str, prop_name: &str, body: &Value) -> MockResponse {
+        let property_key = match self
+            .device(dsn)
+            .and_then(|device| device.properties().iter().find(|property| property.name() == prop_name))
+        {
+            Some(property) => property.key(),
+            None => return not_found(),
+        };
+        let (user_uuid, user_id) = match self.user_by_login(email) {
+            Some(user) => (user.uuid.to_string(), user.id),
+            None => return not_found(),
+        };
+        let key = self.next_key();
+        let request = &body["trigger"];
+        let trigger = json!({
+            "key": key,
+            "property_key": property_key,
+            "property_name": prop_name,
+            "trigger_type": request["trigger_type"],
+            "compare_type": request["compare_type"],
+            "value": request["value"],
+            "trigger_apps": [],
+            "device_nickname": request["device_nickname"],
+            "property_nickname": request["property_nickname"],
+            "base_type": request["base_type"],
+            "period": "",
+            "asset": false,
+            "active": request["active"],
+            "user_uuid": user_uuid,
+            "user_id": user_id,
+            "trigger_at": null,
+        });
+        self.triggers.push(MockTrigger {
+            dsn: dsn.to_string(),
+            property_name: prop_name.to_string(),
+            trigger: trigger.clone(),
+        });
+        (StatusCode::CREATED, json!({ "trigger": trigger }))
+    }
+
+    fn delete_trigger(&mut self, key_json: &str) -> MockResponse {
+        let key = key_json.trim_end_matches(".json");
+        let count = self.triggers.len();
+        self.triggers.retain(|trigger| !key_matches(&trigger.trigger["key"], key));
+        if self.triggers.len() == count {
+            not_found()
+        } else {
+            (StatusCode::OK, json!({}))
+        }
+    }
+
+    fn create_trigger_app(&mut self, email: &str, trigger_key: &str, body: &Value) -> MockResponse {
+        let key = self.next_key();
+        let request = &body["trigger_app"];
+        let mut trigger_app = json!({
+            "key": key,
+            "username": email,
+            "trigger_key": trigger_key.parse::<u32>().unwrap_or_default(),
+        });
+        merge_trigger_app(&mut trigger_app, request);
+        match self
+            .triggers
+            .iter_mut()
+            .find(|trigger| key_matches(&trigger.trigger["key"], trigger_key))
+        {
+            Some(trigger) => {
+                if let Some(apps) = trigger.trigger["trigger_apps"].as_array_mut() {
+                    apps.push(trigger_app.clone());
+                }
+                (StatusCode::CREATED, json!({ "trigger_app": trigger_app }))
+            }
+            None => not_found(),
+        }
+    }
+
+    fn update_trigger_app(&mut self, key_json: &str, body: &Value) -> MockResponse {
+        let key = key_json.trim_end_matches(".json");
+        for trigger in self.triggers.iter_mut() {
+            if let Some(apps) = trigger.trigger["trigger_apps"].as_array_mut() {
+                if let Some(app) = apps.iter_mut().find(|app| key_matches(&app["key"], key)) {
+                    merge_trigger_app(app, &body["trigger_app"]);
+                    return (StatusCode::OK, json!({ "trigger_app": app }));
+                }
+            }
+        }
+        not_found()
+    }
+
+    fn delete_trigger_app(&mut self, key_json: &str) -> MockResponse {
+        let key = key_json.trim_end_matches(".json");
+        for trigger in self.triggers.iter_mut() {
+            if let Some(apps) = trigger.trigger["trigger_apps"].as_array_mut() {
+                let count = apps.len();
+                apps.retain(|app| !key_matches(&app["key"], key));
+                if apps.len() != count {
+                    return (StatusCode::OK, json!({}));
+                }
+            }
+        }
+        not_found()
+    }
+
+    fn device_schedules(&self, device: &str) -> MockResponse {
+        let device_key = match self.device_by_id(device) {
+            Some(device) => device.key(),
+            None => return not_found(),
+        };
+        let schedules: Vec<Value> = self
+            .schedules
+            .iter()
+            .filter(|schedule| schedule["device_id"] == device_key)
+            .map(|schedule| json!({ "schedule": schedule }))
+            .collect();
+        (StatusCode::OK, Value::from(schedules))
+    }
+
+    fn all_schedules(&self) -> MockResponse {
+        let schedules: Vec<Value> = self.schedules.iter().map(|schedule| json!({ "schedule": schedule })).collect();
+        (StatusCode::OK, Value::from(schedules))
+    }
+
+    fn create_schedule(&mut self, device: &str, body: &Value) -> MockResponse {
+        let device_key = match self.device_by_id(device) {
+            Some(device) => device.key(),
+            None => return not_found(),
+        };
+        let mut schedule = body["schedule"].clone();
+        if !schedule.is_object() {
+            return (StatusCode::UNPROCESSABLE_ENTITY, json!({ "errors": { "schedule": ["is invalid"] } }));
+        }
+        let key = self.next_key();
+        schedule["key"] = json!(key);
+        schedule["device_id"] = json!(device_key);
+        self.schedules.push(schedule.clone());
+        (StatusCode::CREATED, json!({ "schedule": schedule }))
+    }
+
+    fn update_schedule(&mut self, device: &str, key_json: &str, body: &Value) -> MockResponse {
+        let device_key = match self.device_by_id(device) {
+            Some(device) => device.key(),
+            None => return not_found(),
+        };
+        let key = key_json.trim_end_matches(".json");
+        let schedule = match self
+            .schedules
+            .iter_mut()
+            .find(|schedule| key_matches(&schedule["key"], key) && schedule["device_id"] == device_key)
+        {
+            Some(schedule) => schedule,
+            None => return not_found(),
+        };
+        if let (Some(existing), Some(update)) = (schedule.as_object_mut(), body["schedule"].as_object()) {
+            update
+                .iter()
+                .filter(|(field, _)| field.as_str() != "key" && field.as_str() != "device_id")
+                .for_each(|(field, value)| {
+                    existing.insert(field.to_string(), value.clone());
+                });
+        }
+        (StatusCode::OK, json!({ "schedule": schedule }))
+    }
+}
+
+fn key_matches(value: &Value, key: &str) -> bool {
+    value.as_u64().is_some() && value.as_u64() == key.parse::<u64>().ok()
+}
+
+fn merge_trigger_app(trigger_app: &mut Value, request: &Value) {
+    let fields = [
+        "name", "nickname", "contact_id", "repeat_freq", "push_sound", "push_mdata",
+        "email_template_id", "email_subject", "email_body_html",
+        "param1", "param2", "param3", "param4", "param5",
+    ];
+    for field in fields.iter() {
+        trigger_app[*field] = request[*field].clone();
+    }
+}
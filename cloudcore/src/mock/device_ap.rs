@@ -0,0 +1,360 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{debug, error};
+use mantle_utilities::RUNTIME;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+use crate::lan::parse_query;
+use crate::mock::ayla_service::{MockAylaServer, MockAylaState, MockRequest};
+use crate::mock::mock_device::MockDevice;
+
+/// In-process fake of a device's Wi-Fi setup access point, the app reaches it at the AP gateway address.
+///
+/// Once the app sends it the credentials of one of its networks it connects to the
+/// mock Ayla service it was started with, so the app can register it with the setup token.
+/// The server is shut down when this is dropped.
+pub struct MockDeviceAp {
+    address: String,
+    state: Arc<Mutex<MockDeviceApState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockDeviceAp {
+    pub fn start(server: &MockAylaServer, device: MockDevice) -> Result<Self, Box<dyn Error>> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?.to_string();
+        let state = Arc::new(Mutex::new(MockDeviceApState {
+            device,
+            ayla: server.shared_state(),
+            networks: vec![],
+            scanned: false,
+            setup_token: None,
+            joined_ssid: None,
+            access_point_stopped: false,
+            wifi_status_supported: true,
+            connect_history: vec![],
+            last_mtime: 0,
+            queued_failures: vec![],
+            requests: vec![],
+        }));
+
+        let _guard = RUNTIME.enter();
+        let service_state = Arc::clone(&state);
+        let make_service = make_service_fn(move |_| {
+            let state = Arc::clone(&service_state);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_request(Arc::clone(&state), request)
+                }))
+            }
+        });
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+        RUNTIME.spawn(async move {
+            if let Some(err) = server.await.err() {
+                error!("Mock device AP stopped: {}", err);
+            }
+        });
+        debug!("Mock device AP listening on {}", &address);
+
+        Ok(Self {
+            address,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Get a reference to the mock AP's address, to pass to `WifiPairing::start`.
+    pub fn address(&self) -> &str {
+        self.address.as_ref()
+    }
+
+    /// Add a network the device sees in its scans, `key` is `None` for open networks.
+    pub fn add_network(&self, ssid: &str, key: Option<&str>, signal: i32) {
+        self.with_state(|state| {
+            state.networks.push(MockWifiNetwork {
+                ssid: ssid.to_string(),
+                key: key.map(|key| key.to_string()),
+                signal,
+            })
+        })
+    }
+
+    /// Answer the next `count` requests sent with `method` to `path` with `status`.
+    pub fn fail_next_requests_to(&self, method: &str, path: &str, count: usize, status: u16) {
+        self.with_state(|state| {
+            for _ in 0..count {
+                state.queued_failures.push((method.to_string(), path.to_string(), status));
+            }
+        })
+    }
+
+    /// The setup token the app sent with the Wi-Fi credentials.
+    pub fn setup_token(&self) -> Option<String> {
+        self.with_state(|state| state.setup_token.clone())
+    }
+
+    /// The network the device joined, `None` until it got valid credentials.
+    pub fn joined_ssid(&self) -> Option<String> {
+        self.with_state(|state| state.joined_ssid.clone())
+    }
+
+    /// Answer `wifi_status.json` with 404, like firmware that doesn't have it.
+    pub fn set_wifi_status_supported(&self, supported: bool) {
+        self.with_state(|state| state.wifi_status_supported = supported)
+    }
+
+    pub fn access_point_stopped(&self) -> bool {
+        self.with_state(|state| state.access_point_stopped)
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.with_state(|state| state.requests.clone())
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut MockDeviceApState) -> R) -> R {
+        f(&mut lock(&self.state))
+    }
+}
+
+impl Drop for MockDeviceAp {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+struct MockWifiNetwork {
+    ssid: String,
+    key: Option<String>,
+    signal: i32,
+}
+
+impl MockWifiNetwork {
+    fn to_json(&self, index: usize) -> Value {
+        json!({
+            "ssid": self.ssid,
+            "type": "AP",
+            "chan": 1 + (index % 11),
+            "signal": self.signal,
+            "bars": ((self.signal + 100) / 15).clamp(0, 4),
+            "security": if self.key.is_some() { "WPA2_Personal" } else { "None" },
+            "bssid": format!("00aabbccdd{:02x}", index),
+        })
+    }
+}
+
+// Ayla's connect history error codes
+const WIFI_ERROR_NONE: u32 = 0;
+const WIFI_ERROR_SSID_NOT_FOUND: u32 = 4;
+const WIFI_ERROR_INCORRECT_KEY: u32 = 6;
+const WIFI_ERROR_IN_PROGRESS: u32 = 20;
+
+/// One `wifi_connect.json`, reported as in progress the first time `wifi_status.json` is read.
+struct MockConnectAttempt {
+    ssid: String,
+    error: u32,
+    mtime: i64,
+    reported: bool,
+}
+
+impl MockConnectAttempt {
+    fn to_json(&self) -> Value {
+        let error = if self.reported { self.error } else { WIFI_ERROR_IN_PROGRESS };
+        let msg = match error {
+            WIFI_ERROR_NONE => "none",
+            WIFI_ERROR_SSID_NOT_FOUND => "SSID not found",
+            WIFI_ERROR_INCORRECT_KEY => "Incorrect key",
+            _ => "In progress",
+        };
+        json!({
+            "ssid_info": self.ssid.chars().take(2).collect::<String>(),
+            "ssid_len": self.ssid.len(),
+            "bssid": "00aabbccdd00",
+            "error": error,
+            "msg": msg,
+            "mtime": self.mtime,
+            "last": 0,
+        })
+    }
+}
+
+struct MockDeviceApState {
+    device: MockDevice,
+    ayla: Arc<Mutex<MockAylaState>>,
+    networks: Vec<MockWifiNetwork>,
+    scanned: bool,
+    setup_token: Option<String>,
+    joined_ssid: Option<String>,
+    access_point_stopped: bool,
+    wifi_status_supported: bool,
+    connect_history: Vec<MockConnectAttempt>,
+    last_mtime: i64,
+    queued_failures: Vec<(String, String, u16)>,
+    requests: Vec<MockRequest>,
+}
+
+type MockResponse = (StatusCode, Option<Value>);
+
+impl MockDeviceApState {
+    fn route(&mut self, method: &Method, path: &str, query: &[(String, String)]) -> MockResponse {
+        match (method, path) {
+            (&Method::GET, "/status.json") => self.status(),
+            (&Method::POST, "/wifi_scan.json") => {
+                self.scanned = true;
+                (StatusCode::NO_CONTENT, None)
+            }
+            (&Method::GET, "/wifi_scan_results.json") => self.scan_results(),
+            (&Method::POST, "/wifi_connect.json") => self.connect(query),
+            (&Method::GET, "/wifi_status.json") if self.wifi_status_supported => self.wifi_status(),
+            (&Method::PUT, "/wifi_stop_ap.json") => {
+                self.access_point_stopped = true;
+                (StatusCode::NO_CONTENT, None)
+            }
+            _ => (StatusCode::NOT_FOUND, Some(json!({ "error": "Not found" }))),
+        }
+    }
+
+    fn status(&self) -> MockResponse {
+        (StatusCode::OK, Some(json!({
+            "api_version": "1.0",
+            "build": "mock",
+            "device_service": "ads-dev.aylanetworks.com",
+            "dsn": self.device.dsn(),
+            "mtime": Utc::now().timestamp(),
+            "version": "mock",
+        })))
+    }
+
+    fn scan_results(&self) -> MockResponse {
+        let results: Vec<Value> = match self.scanned {
+            true => self.networks.iter().enumerate().map(|(index, network)| network.to_json(index)).collect(),
+            false => vec![],
+        };
+        (StatusCode::OK, Some(json!({
+            "wifi_scan": {
+                "mtime": Utc::now().timestamp(),
+                "results": results,
+            }
+        })))
+    }
+
+    /// Like the real device the request is accepted before it tries to join the network.
+    fn connect(&mut self, query: &[(String, String)]) -> MockResponse {
+        let value = |key: &str| query.iter().find(|(query_key, _)| query_key == key).map(|(_, value)| value.to_string());
+        let (ssid, setup_token) = match (value("ssid"), value("setup_token")) {
+            (Some(ssid), Some(setup_token)) => (ssid, setup_token),
+            _ => return (StatusCode::BAD_REQUEST, Some(json!({ "error": "ssid and setup_token are required" }))),
+        };
+        let key = value("key");
+        self.setup_token = Some(setup_token.to_string());
+        let error = match self.networks.iter().find(|network| network.ssid == ssid) {
+            None => WIFI_ERROR_SSID_NOT_FOUND,
+            Some(network) if network.key != key => WIFI_ERROR_INCORRECT_KEY,
+            Some(_) => WIFI_ERROR_NONE,
+        };
+        let mtime = self.next_mtime();
+        self.connect_history.insert(0, MockConnectAttempt {
+            ssid: ssid.to_string(),
+            error,
+            mtime,
+            reported: false,
+        });
+        if error == WIFI_ERROR_NONE {
+            self.joined_ssid = Some(ssid);
+            lock(&self.ayla).add_online_device(self.device.clone(), setup_token);
+        }
+        (StatusCode::NO_CONTENT, None)
+    }
+
+    fn wifi_status(&mut self) -> MockResponse {
+        let history: Vec<Value> = self.connect_history.iter().map(|attempt| attempt.to_json()).collect();
+        self.connect_history.iter_mut().for_each(|attempt| attempt.reported = true);
+        (StatusCode::OK, Some(json!({
+            "wifi_status": {
+                "connect_history": history,
+                "dsn": self.device.dsn(),
+                "device_service": "ads-dev.aylanetworks.com",
+                "mac": "00aabbccddee",
+                "mtime": self.next_mtime(),
+                "host_symname": "mock",
+                "connected_ssid": self.joined_ssid.clone().unwrap_or_default(),
+                "ant": 0,
+                "wps": "n/a",
+                "rssi": -50,
+                "bars": 3,
+            }
+        })))
+    }
+
+    /// Seconds like the device's clock, but never the same twice so attempts can be told apart.
+    fn next_mtime(&mut self) -> i64 {
+        self.last_mtime = Utc::now().timestamp().max(self.last_mtime + 1);
+        self.last_mtime
+    }
+}
+
+async fn handle_request(
+    state: Arc<Mutex<MockDeviceApState>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = parse_query(request.uri().query());
+    let bytes = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+    let body: Value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+
+    let (status, payload) = {
+        let mut state = lock(&state);
+        state.requests.push(MockRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: query.clone(),
+            body,
+        });
+        let failure = state
+            .queued_failures
+            .iter()
+            .position(|(target_method, target_path, _)| target_method == method.as_str() && *target_path == path);
+        match failure {
+            Some(index) => {
+                let (_, _, status) = state.queued_failures.remove(index);
+                let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+                (status, Some(json!({ "error": "Injected failure" })))
+            }
+            None => state.route(&method, &path, &query),
+        }
+    };
+    debug!("Mock device AP {} {} -> {}", method, path, status);
+
+    let response = match payload {
+        Some(payload) => Response::builder()
+            .status(status)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(payload.to_string())),
+        None => Response::builder().status(status).body(Body::empty()),
+    };
+    Ok(response.unwrap_or_default())
+}
+
+fn lock<T>(state: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    // A panic inside a handler should not take every later assertion down with it
+    match state.lock() {
+        Ok(state) => state,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
@@ -0,0 +1,326 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{SecondsFormat, Utc};
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use log::{debug, error};
+use mantle_utilities::RUNTIME;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+use crate::lan::{parse_query, KeyExchange, LanCipher, LanMessage, LanPayload, LanRole};
+use crate::mock::mock_device::{MockDevice, MockProperty};
+use crate::mock::MockRequest;
+use crate::properties::value::IoTPropertyValue;
+use crate::CloudCoreError;
+
+#[derive(Debug, Deserialize)]
+struct LocalRegRequest {
+    local_reg: LocalReg,
+}
+
+#[derive(Debug, Deserialize)]
+struct LocalReg {
+    ip: String,
+    port: u16,
+    uri: String,
+    notify: u8,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyExchangeResponse {
+    random_2: String,
+    time_2: u64,
+}
+
+/// In-process fake of a device speaking Ayla's LAN protocol.
+///
+/// Once the app registers it exchanges keys with the app, and every time the app
+/// notifies it, it fetches the app's commands, answers property reads and applies writes.
+/// The server is shut down when this is dropped.
+pub struct MockLanDevice {
+    address: String,
+    state: Arc<Mutex<MockLanState>>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockLanDevice {
+    /// Start a LAN server for `device`, with its LAN key and a copy of its properties.
+    pub fn start(device: &MockDevice) -> Result<Self, Box<dyn Error>> {
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?.to_string();
+        let state = Arc::new(Mutex::new(MockLanState {
+            dsn: device.dsn().to_string(),
+            lanip_key: device.lanip_key().to_string(),
+            lanip_key_id: device.lanip_key_id(),
+            properties: device.properties().to_vec(),
+            stopped: false,
+            cipher: None,
+            seq_no: 0,
+            requests: vec![],
+        }));
+        // The device talks to the app one request at a time
+        let busy = Arc::new(tokio::sync::Mutex::new(()));
+
+        let _guard = RUNTIME.enter();
+        let service_state = Arc::clone(&state);
+        let make_service = make_service_fn(move |_| {
+            let state = Arc::clone(&service_state);
+            let busy = Arc::clone(&busy);
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_request(Arc::clone(&state), Arc::clone(&busy), request)
+                }))
+            }
+        });
+        let (shutdown, shutdown_rx) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                let _ = shutdown_rx.await;
+            });
+        RUNTIME.spawn(async move {
+            if let Some(err) = server.await.err() {
+                error!("Mock LAN device stopped: {}", err);
+            }
+        });
+        debug!("Mock LAN device listening on {}", &address);
+
+        Ok(Self {
+            address,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// Get a reference to the mock LAN device's address, to use as the device's `lan_ip`.
+    pub fn address(&self) -> &str {
+        self.address.as_ref()
+    }
+
+    pub fn property_value(&self, prop_name: &str) -> Option<IoTPropertyValue> {
+        self.with_state(|state| state.property(prop_name).and_then(|property| property.value().cloned()))
+    }
+
+    /// Simulate the device changing a property without the cloud knowing yet.
+    pub fn set_property_value(&self, prop_name: &str, value: IoTPropertyValue) {
+        self.with_state(|state| {
+            if let Some(property) = state.properties.iter_mut().find(|property| property.name() == prop_name) {
+                property.set_value(value, now());
+            }
+        })
+    }
+
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.with_state(|state| state.requests.clone())
+    }
+
+    fn with_state<R>(&self, f: impl FnOnce(&mut MockLanState) -> R) -> R {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        f(&mut state)
+    }
+}
+
+impl Drop for MockLanDevice {
+    fn drop(&mut self) {
+        // Kept alive connections outlive a graceful shutdown, they must not reach the device either
+        self.with_state(|state| state.stopped = true);
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+struct MockLanState {
+    dsn: String,
+    lanip_key: String,
+    lanip_key_id: u32,
+    properties: Vec<MockProperty>,
+    stopped: bool,
+    cipher: Option<LanCipher>,
+    seq_no: u32,
+    requests: Vec<MockRequest>,
+}
+
+impl MockLanState {
+    fn property(&self, prop_name: &str) -> Option<&MockProperty> {
+        self.properties.iter().find(|property| property.name() == prop_name)
+    }
+
+    /// Encrypt the next datapoint message for the app, `None` before the key exchange.
+    fn datapoint_payload(&mut self, prop_name: &str) -> Option<LanPayload> {
+        let datapoint = match self.property(prop_name) {
+            Some(property) => json!({
+                "name": property.name(),
+                "base_type": property.base_type(),
+                "value": property.value(),
+            }),
+            None => json!({ "name": prop_name }),
+        };
+        self.seq_no += 1;
+        let plain = json!({ "seq_no": self.seq_no, "data": datapoint }).to_string();
+        Some(self.cipher.as_mut()?.encrypt(plain.as_bytes()))
+    }
+
+    fn apply_write(&mut self, property: &Value) {
+        let name = property["name"].as_str().unwrap_or_default();
+        let value = serde_json::from_value::<IoTPropertyValue>(property["value"].clone());
+        match (self.properties.iter_mut().find(|existing| existing.name() == name), value) {
+            (Some(existing), Ok(value)) => existing.set_value(value, now()),
+            _ => error!("Mock LAN device {} can not write {}", self.dsn, property),
+        }
+    }
+}
+
+async fn handle_request(
+    state: Arc<Mutex<MockLanState>>,
+    busy: Arc<tokio::sync::Mutex<()>>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = parse_query(request.uri().query());
+    let bytes = hyper::body::to_bytes(request.into_body()).await.unwrap_or_default();
+    let body: Value = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+    lock(&state).requests.push(MockRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        query,
+        body: body.clone(),
+    });
+
+    let stopped = lock(&state).stopped;
+    let status = match (&method, path.as_str()) {
+        _ if stopped => StatusCode::SERVICE_UNAVAILABLE,
+        (&Method::POST | &Method::PUT, "/local_reg.json") => match serde_json::from_value::<LocalRegRequest>(body) {
+            Ok(request) => {
+                let local_reg = request.local_reg;
+                let app_url = format!("http://{}:{}{}", local_reg.ip, local_reg.port, local_reg.uri);
+                let state = Arc::clone(&state);
+                let register = method == Method::POST;
+                RUNTIME.spawn(async move {
+                    let _busy = busy.lock().await;
+                    let result = if register {
+                        exchange_keys(&state, &app_url).await
+                    } else if local_reg.notify == 1 {
+                        fetch_commands(&state, &app_url).await
+                    } else {
+                        Ok(())
+                    };
+                    if let Some(err) = result.err() {
+                        error!("Mock LAN device failed to talk to the app: {}", err);
+                    }
+                });
+                StatusCode::ACCEPTED
+            }
+            Err(_) => StatusCode::BAD_REQUEST,
+        },
+        _ => StatusCode::NOT_FOUND,
+    };
+    debug!("Mock LAN device {} {} -> {}", method, path, status);
+    let response = Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from("{}"))
+        .unwrap_or_default();
+    Ok(response)
+}
+
+async fn exchange_keys(state: &Mutex<MockLanState>, app_url: &str) -> Result<(), CloudCoreError> {
+    let random_1: String = rand::thread_rng().sample_iter(&Alphanumeric).take(16).map(char::from).collect();
+    let time_1 = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_micros() as u64).unwrap_or_default();
+    let (lanip_key, key_id) = {
+        let state = lock(state);
+        (state.lanip_key.to_string(), state.lanip_key_id)
+    };
+    let response = reqwest::Client::new()
+        .post(format!("{}/key_exchange.json", app_url))
+        .json(&json!({
+            "key_exchange": {
+                "ver": 1,
+                "random_1": random_1,
+                "time_1": time_1,
+                "proto": 1,
+                "key_id": key_id,
+            }
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<KeyExchangeResponse>()
+        .await?;
+    let exchange = KeyExchange {
+        random_1,
+        time_1,
+        random_2: response.random_2,
+        time_2: response.time_2,
+    };
+    let mut state = lock(state);
+    state.cipher = Some(LanCipher::new(&lanip_key, &exchange, LanRole::Device));
+    state.seq_no = 0;
+    Ok(())
+}
+
+/// Fetch and run the app's commands until it has none left.
+async fn fetch_commands(state: &Mutex<MockLanState>, app_url: &str) -> Result<(), CloudCoreError> {
+    let client = reqwest::Client::new();
+    loop {
+        let response = client.get(format!("{}/commands.json", app_url)).send().await?.error_for_status()?;
+        let more = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+        let payload = response.json::<LanPayload>().await?;
+        let plain = lock(state)
+            .cipher
+            .as_mut()
+            .ok_or_else(|| CloudCoreError::Lan("No key exchange yet".to_string()))?
+            .decrypt(&payload)?;
+        let message = serde_json::from_slice::<LanMessage<Value>>(&plain)?;
+        if let Some(properties) = message.data["properties"].as_array() {
+            let mut state = lock(state);
+            properties.iter().for_each(|property| state.apply_write(&property["property"]));
+        }
+        for cmd in message.data["cmds"].as_array().into_iter().flatten() {
+            let cmd = &cmd["cmd"];
+            let cmd_id = cmd["cmd_id"].as_u64().unwrap_or_default();
+            let prop_name = cmd["resource"]
+                .as_str()
+                .and_then(|resource| resource.strip_prefix("property.json?name="))
+                .unwrap_or_default();
+            let Some(payload) = lock(state).datapoint_payload(prop_name) else {
+                continue;
+            };
+            client
+                .post(format!("{}/property/datapoint.json", app_url))
+                .query(&[("cmd_id", cmd_id.to_string()), ("status", "200".to_string())])
+                .json(&payload)
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        if !more {
+            return Ok(());
+        }
+    }
+}
+
+fn lock(state: &Mutex<MockLanState>) -> std::sync::MutexGuard<'_, MockLanState> {
+    match state.lock() {
+        Ok(state) => state,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,264 @@
+use serde_json::{json, Value};
+
+use crate::properties::value::IoTPropertyValue;
+
+/// A device registered on the mock Ayla service.
+#[derive(Debug, Clone)]
+pub struct MockDevice {
+    key: u32,
+    dsn: String,
+    product_name: String,
+    model: String,
+    oem_model: String,
+    connection_status: String,
+    lan_ip: String,
+    lanip_key: String,
+    lanip_key_id: u32,
+    tz_id: String,
+    utc_offset: String,
+    properties: Vec<MockProperty>,
+}
+
+impl MockDevice {
+    pub fn new(key: u32, dsn: &str) -> Self {
+        Self {
+            key,
+            dsn: dsn.to_string(),
+            product_name: "Robot 1".to_string(),
+            model: "AY008MRO1".to_string(),
+            oem_model: "RV2000".to_string(),
+            connection_status: "Online".to_string(),
+            lan_ip: "192.168.0.2".to_string(),
+            lanip_key: format!("lanip-key-{}", dsn),
+            lanip_key_id: key,
+            tz_id: "America/New_York".to_string(),
+            utc_offset: "-05:00".to_string(),
+            properties: vec![],
+        }
+    }
+
+    pub fn with_product_name(mut self, product_name: &str) -> Self {
+        self.product_name = product_name.to_string();
+        self
+    }
+
+    pub fn with_connection_status(mut self, connection_status: &str) -> Self {
+        self.connection_status = connection_status.to_string();
+        self
+    }
+
+    pub fn with_lan_ip(mut self, lan_ip: &str) -> Self {
+        self.lan_ip = lan_ip.to_string();
+        self
+    }
+
+    pub fn with_lanip_key(mut self, lanip_key: &str, lanip_key_id: u32) -> Self {
+        self.lanip_key = lanip_key.to_string();
+        self.lanip_key_id = lanip_key_id;
+        self
+    }
+
+    pub fn with_time_zone(mut self, tz_id: &str, utc_offset: &str) -> Self {
+        self.tz_id = tz_id.to_string();
+        self.utc_offset = utc_offset.to_string();
+        self
+    }
+
+    pub fn with_property(mut self, property: MockProperty) -> Self {
+        self.properties.push(property);
+        self
+    }
+
+    /// Get a reference to the mock device's key.
+    pub fn key(&self) -> u32 {
+        self.key
+    }
+
+    /// Get a reference to the mock device's dsn.
+    pub fn dsn(&self) -> &str {
+        self.dsn.as_ref()
+    }
+
+    /// Get a reference to the mock device's product name.
+    pub fn product_name(&self) -> &str {
+        self.product_name.as_ref()
+    }
+
+    /// Get a reference to the mock device's connection status.
+    pub fn connection_status(&self) -> &str {
+        self.connection_status.as_ref()
+    }
+
+    /// Get a reference to the mock device's LAN key.
+    pub fn lanip_key(&self) -> &str {
+        self.lanip_key.as_ref()
+    }
+
+    /// Get the mock device's LAN key id.
+    pub fn lanip_key_id(&self) -> u32 {
+        self.lanip_key_id
+    }
+
+    /// Get a reference to the mock device's properties.
+    pub fn properties(&self) -> &[MockProperty] {
+        self.properties.as_ref()
+    }
+
+    pub(crate) fn set_product_name(&mut self, product_name: String) {
+        self.product_name = product_name;
+    }
+
+    pub(crate) fn set_connection_status(&mut self, connection_status: String) {
+        self.connection_status = connection_status;
+    }
+
+    pub(crate) fn set_tz_id(&mut self, tz_id: String) {
+        self.tz_id = tz_id;
+    }
+
+    pub(crate) fn property_mut(&mut self, name: &str) -> Option<&mut MockProperty> {
+        self.properties.iter_mut().find(|property| property.name == name)
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        json!({
+            "key": self.key,
+            "product_name": self.product_name,
+            "model": self.model,
+            "dsn": self.dsn,
+            "oem_model": self.oem_model,
+            "sw_version": "ADA-Ayla 2.0",
+            "template_id": 1,
+            "mac": "00aabbccddee",
+            "lan_ip": self.lan_ip,
+            "connected_at": "2022-01-01T00:00:00Z",
+            "lan_enabled": true,
+            "has_properties": !self.properties.is_empty(),
+            "connection_status": self.connection_status,
+            "lat": "0.0",
+            "lng": "0.0",
+            "device_type": "Wifi",
+        })
+    }
+
+    pub(crate) fn lan_json(&self) -> Value {
+        json!({
+            "lanip": {
+                "lanip_key": self.lanip_key,
+                "lanip_key_id": self.lanip_key_id,
+                "keep_alive": 30,
+                "auto_sync": 1,
+                "status": "enable",
+            }
+        })
+    }
+
+    pub(crate) fn time_zone_json(&self) -> Value {
+        json!({
+            "utc_offset": self.utc_offset,
+            "dst": false,
+            "dst_active": false,
+            "dst_next_change_time": null,
+            "tz_id": self.tz_id,
+        })
+    }
+}
+
+/// A property on a [`MockDevice`].
+#[derive(Debug, Clone)]
+pub struct MockProperty {
+    key: u32,
+    name: String,
+    base_type: String,
+    direction: String,
+    read_only: bool,
+    ack_enabled: bool,
+    value: Option<IoTPropertyValue>,
+    data_updated_at: Option<String>,
+}
+
+impl MockProperty {
+    /// An app to device property (`SET_` properties on the robot).
+    pub fn input(key: u32, name: &str, base_type: &str, value: Option<IoTPropertyValue>) -> Self {
+        Self::new(key, name, base_type, "input", false, value)
+    }
+
+    /// A device to app property (`GET_` properties on the robot).
+    pub fn output(key: u32, name: &str, base_type: &str, value: Option<IoTPropertyValue>) -> Self {
+        Self::new(key, name, base_type, "output", true, value)
+    }
+
+    fn new(key: u32, name: &str, base_type: &str, direction: &str, read_only: bool, value: Option<IoTPropertyValue>) -> Self {
+        Self {
+            key,
+            name: name.to_string(),
+            base_type: base_type.to_string(),
+            direction: direction.to_string(),
+            read_only,
+            ack_enabled: false,
+            value,
+            data_updated_at: None,
+        }
+    }
+
+    /// Have the device acknowledge datapoints sent to this property, see `MockAylaServer::auto_ack`.
+    pub fn with_ack_enabled(mut self) -> Self {
+        self.ack_enabled = true;
+        self
+    }
+
+    /// Get a reference to the mock property's name.
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    /// Get a reference to the mock property's base type.
+    pub fn base_type(&self) -> &str {
+        self.base_type.as_ref()
+    }
+
+    /// Get a reference to the mock property's value.
+    pub fn value(&self) -> Option<&IoTPropertyValue> {
+        self.value.as_ref()
+    }
+
+    pub(crate) fn key(&self) -> u32 {
+        self.key
+    }
+
+    pub(crate) fn ack_enabled(&self) -> bool {
+        self.ack_enabled
+    }
+
+    pub(crate) fn set_value(&mut self, value: IoTPropertyValue, updated_at: String) {
+        self.value = Some(value);
+        self.data_updated_at = Some(updated_at);
+    }
+
+    pub(crate) fn to_json(&self, device: &MockDevice) -> Value {
+        json!({
+            "type": "Property",
+            "name": self.name,
+            "base_type": self.base_type,
+            "read_only": self.read_only,
+            "direction": self.direction,
+            "scope": "user",
+            "data_updated_at": self.data_updated_at,
+            "key": self.key,
+            "device_key": device.key,
+            "product_name": device.product_name,
+            "track_only_changes": false,
+            "display_name": self.name,
+            "host_sw_version": false,
+            "time_series": false,
+            "derived": false,
+            "app_type": null,
+            "recipe": null,
+            "value": self.value,
+            "ack_enabled": self.ack_enabled,
+            "ack_status": null,
+            "ack_message": null,
+            "acked_at": null,
+        })
+    }
+}
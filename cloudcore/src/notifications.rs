@@ -1,7 +1,5 @@
 #[cfg(feature = "library")]
-use std::error::Error;
-#[cfg(feature = "library")]
-use std::time::SystemTime;
+use crate::CloudCoreError;
 
 #[cfg(feature = "library")]
 use chrono::{DateTime, Utc};
@@ -49,7 +47,7 @@ impl CloudCore {
 
     pub async fn delete_all_notifications(
         &mut self,
-        to: String) -> Result<(), Box<dyn Error>> {
+        to: DateTime<Utc>) -> Result<(), CloudCoreError> {
         let devices = self.fetch_all_devices().await?;
         let mut results = true;
         for device in devices {
@@ -59,7 +57,7 @@ impl CloudCore {
                     debug!("Invalid empty DSN")
                 }
                 Some(dsn) => {
-                    results = results && self.mark_notifications_as_deleted(dsn.to_string(), to.to_string()).await;
+                    results = results && self.mark_notifications_as_deleted(dsn.to_string(), to).await;
                 }
             }
         }
@@ -69,14 +67,15 @@ impl CloudCore {
     pub async fn delete_notification(
         &mut self,
         dsn: String,
-        id: String) -> Result<(), Box<dyn Error>> {
+        id: String) -> Result<(), CloudCoreError> {
         self.mark_notification_as_deleted(dsn.to_string(), id.to_string()).await;
         Ok(())
     }
 
+    /// Fetch notifications for every device, `from` defaults to `Notification::MAX_AGE_DAYS` ago.
     pub async fn fetch_all_notifications(
         &mut self,
-        from: String) -> Result<Vec<Notification>, Box<dyn Error>> {
+        from: Option<DateTime<Utc>>) -> Result<Vec<Notification>, CloudCoreError> {
         let devices = self.fetch_all_devices().await?;
         let mut results = vec![];
         for device in devices {
@@ -84,7 +83,7 @@ impl CloudCore {
             match opt_dsn {
                 None => {}
                 Some(dsn) => {
-                    let device_notifications = self.fetch_notifications(dsn.to_string(), from.to_string()).await?;
+                    let device_notifications = self.fetch_notifications(dsn.to_string(), from).await?;
                     for notification in device_notifications {
                         results.push(notification);
                     }
@@ -97,44 +96,44 @@ impl CloudCore {
     pub async fn fetch_notifications(
         &mut self,
         dsn: String,
-        from: String,
-    ) -> Result<Vec<Notification>, Box<dyn Error>> {
-        let oldest_age = Utc::now() - Duration::days(Notification::MAX_AGE_DAYS);
-        let qry_from = if from.is_empty() {
-            oldest_age.to_rfc3339()
-        } else {
-            from
-        };
-        let error_datapoints = self.get_datapoints(
+        from: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Notification>, CloudCoreError> {
+        let qry_from = from.unwrap_or_else(|| Utc::now() - Duration::days(Notification::MAX_AGE_DAYS));
+        let error_datapoints = self.get_all_datapoints(
             dsn.to_string(),
             ERROR_NOTIFICATION_PROPERTY_NAME.to_string(),
-            None,
             Some(qry_from),
             None,
             "".to_string(),
         ).await.0?;
 
         if self.user_session.is_none() {
-            return Err("no user session".into());
+            return Err(CloudCoreError::SessionMissing);
         }
         let user_uuid = self.user_session.as_ref().unwrap().user_uuid().unwrap().to_string();
         let mut fetched_notifications = vec![];
 
         let cached_notifications = self.get_cached_notifications(dsn.to_string()).await;
 
-        let dt: DateTime<Utc> = SystemTime::now().into();
-        let created_at = format!("{}", dt.format("%+"));
+        let created_at = Utc::now();
         error_datapoints.into_iter().for_each(|error_datapoint: IoTDatapoint| {
             let alert_id = Uuid::new_v4();
-            let subtype = error_datapoint.value().int_value().unwrap().clone();
+            let subtype = error_datapoint.value().int_value().copied().unwrap_or(0);
+            let datapoint_created_at = match error_datapoint.created_at() {
+                Some(datapoint_created_at) => *datapoint_created_at,
+                None => {
+                    debug!("Skipping error datapoint without a created_at date");
+                    return;
+                }
+            };
             if subtype != 0 {
                 fetched_notifications.push(
                     Notification::new(
                         user_uuid.to_string(),
                         alert_id.to_string(),
                         dsn.to_string(),
-                        created_at.to_string(),
-                        error_datapoint.created_at().unwrap().to_string(),
+                        created_at,
+                        datapoint_created_at,
                         false,
                         false,
                         NOTIFICATION_TYPE_ERROR,
@@ -182,7 +181,7 @@ impl CloudCore {
 
 
     pub async fn get_all_cached_notifications(
-        &mut self) -> Result<Vec<Notification>, Box<dyn Error>> {
+        &mut self) -> Result<Vec<Notification>, CloudCoreError> {
         let devices = self.fetch_all_devices().await?;
         let mut results = vec![];
         for device in devices {
@@ -231,7 +230,7 @@ impl CloudCore {
     }
 
     pub async fn mark_all_notifications_as_read(
-        &mut self) -> Result<(), Box<dyn Error>> {
+        &mut self) -> Result<(), CloudCoreError> {
         let devices = self.fetch_all_devices().await?;
         let mut results = true;
         for device in devices {
@@ -250,7 +249,8 @@ impl CloudCore {
         self.update_all(dsn, Notification::mark_as_read).await
     }
 
-    pub async fn mark_notifications_as_deleted(&mut self, dsn: String, to: String) -> bool {
+    /// Mark the notifications for datapoints created before `to` as deleted.
+    pub async fn mark_notifications_as_deleted(&mut self, dsn: String, to: DateTime<Utc>) -> bool {
         debug!("Marking Notifications to {} for Device({}) as DELETED", to, dsn);
         self.update_to(dsn, to, Notification::mark_as_deleted).await
     }
@@ -277,23 +277,18 @@ impl CloudCore {
         }
     }
 
-    async fn update_to(&mut self, dsn: String, to: String, update_fn: fn(&mut Notification)) -> bool {
+    async fn update_to(&mut self, dsn: String, to: DateTime<Utc>, update_fn: fn(&mut Notification)) -> bool {
         let cached_notifications = self.get_cached_notifications(dsn.to_string()).await;
         match cached_notifications {
             None => {
-                debug!("update_to: none to update for DSN {} up to {}", dsn.to_string(), to.to_string());
+                debug!("update_to: none to update for DSN {} up to {}", dsn, to);
                 false
             }
             Some(mut notifications) => {
-                debug!("update_to: {} to update for DSN {} up to {}",  notifications.len(), dsn.to_string(), to.to_string());
-                notifications.iter_mut().filter(|n| {
-                    let created_millis = DateTime::parse_from_rfc3339(n.datapoint_created_at.as_str()).unwrap().timestamp_millis();
-                    let to_millis = DateTime::parse_from_rfc3339(to.as_str()).unwrap().timestamp_millis();
-                    let res = created_millis < to_millis;
-                    debug!("datapoint_created_at {} < to {}", n.datapoint_created_at.as_str(), to.as_str());
-                    debug!("created_millis {} < to_millis {}: {}", created_millis, to_millis, res);
-                    return res;
-                }).for_each(|n| update_fn(n));
+                debug!("update_to: {} to update for DSN {} up to {}", notifications.len(), dsn, to);
+                notifications.iter_mut()
+                    .filter(|n| n.datapoint_created_at < to)
+                    .for_each(|n| update_fn(n));
                 self.cache_notifications(dsn.to_string(), notifications).await;
                 true
             }
@@ -1,5 +1,6 @@
 use std::str::FromStr;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -7,8 +8,8 @@ pub struct Notification {
     pub user_uuid: String,
     pub id: String,
     pub dsn: String,
-    pub created_at: String,
-    pub datapoint_created_at: String,
+    pub created_at: DateTime<Utc>,
+    pub datapoint_created_at: DateTime<Utc>,
     pub read: bool,
     pub deleted: bool,
     pub notification_type: i32,
@@ -63,8 +64,8 @@ impl Notification {
         user_uuid: String,
         id: String,
         dsn: String,
-        created_at: String,
-        datapoint_created_at: String,
+        created_at: DateTime<Utc>,
+        datapoint_created_at: DateTime<Utc>,
         read: bool,
         deleted: bool,
         notification_type: i32,
@@ -92,10 +93,10 @@ impl Notification {
     pub fn dsn(&self) -> &str {
         &self.dsn
     }
-    pub fn created_at(&self) -> &str {
+    pub fn created_at(&self) -> &DateTime<Utc> {
         &self.created_at
     }
-    pub fn datapoint_created_at(&self) -> &str {
+    pub fn datapoint_created_at(&self) -> &DateTime<Utc> {
         &self.datapoint_created_at
     }
     pub fn read(&self) -> bool {
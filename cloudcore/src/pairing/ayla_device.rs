@@ -1,10 +1,15 @@
+#[cfg(feature = "signatures")]
+use crate::secret::Secret;
+
 #[cfg(feature = "signatures")]
 #[derive(Debug, Clone)]
 pub struct AylaDeviceInfo {
     device_url: String,
     ip_address: Option<String>,
     dsn: Option<String>,
-    setup_token: Option<String>,
+    setup_token: Option<Secret<String>>,
+    ssid: Option<String>,
+    rejected_join_mtime: Option<u64>,
 }
 
 #[cfg(feature = "library")]
@@ -14,7 +19,9 @@ impl AylaDeviceInfo {
             device_url,
             ip_address: None,
             dsn: None,
-            setup_token: None
+            setup_token: None,
+            ssid: None,
+            rejected_join_mtime: None,
         }
     }
 
@@ -29,7 +36,14 @@ impl AylaDeviceInfo {
         self.dsn.as_ref()
     }
     pub fn setup_token(&self) -> Option<&String> {
-        self.setup_token.as_ref()
+        self.setup_token.as_ref().map(Secret::expose)
+    }
+    pub fn ssid(&self) -> Option<&String> {
+        self.ssid.as_ref()
+    }
+    /// The device's `mtime` of the last attempt to join a network it could not join.
+    pub fn rejected_join_mtime(&self) -> Option<u64> {
+        self.rejected_join_mtime
     }
 
     /// Setters
@@ -43,6 +57,12 @@ impl AylaDeviceInfo {
         self.dsn = dsn;
     }
     pub fn set_setup_token(&mut self, setup_token: Option<String>) {
-        self.setup_token = setup_token;
+        self.setup_token = setup_token.map(Secret::new);
+    }
+    pub fn set_ssid(&mut self, ssid: Option<String>) {
+        self.ssid = ssid;
+    }
+    pub fn set_rejected_join_mtime(&mut self, rejected_join_mtime: Option<u64>) {
+        self.rejected_join_mtime = rejected_join_mtime;
     }
 }
\ No newline at end of file
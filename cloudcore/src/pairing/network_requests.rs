@@ -1,15 +1,12 @@
-use std::error::Error;
 use std::time::Duration;
 use log::{debug, error};
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
+use crate::secret::REDACTED;
 use crate::pairing::wifi_network::WifiNetwork;
-use crate::{urls, WifiPairing};
+use crate::{urls, CloudCoreError, WifiPairing};
 
-static LAN_CONNECT_TIMEOUT: u64 = 10;
-static INTERNET_CONNECT_TIMEOUT: u64 = 20;
-
-pub async fn fetch_dsn(ip_address: String) -> Result<String, Box<dyn Error + Send + Sync>> {
+pub async fn fetch_dsn(ip_address: String, connect_timeout: Duration) -> Result<String, CloudCoreError> {
     #[derive(Deserialize, Debug)]
     struct AylaDeviceStatus {
         api_version: Option<String>,
@@ -22,7 +19,7 @@ pub async fn fetch_dsn(ip_address: String) -> Result<String, Box<dyn Error + Sen
     let url = format!("http://{}/status.json", ip_address);
     WifiPairing::log(format!("Sending request to: {}", &url));
     let client = Client::builder()
-        .connect_timeout(Duration::from_secs(LAN_CONNECT_TIMEOUT))
+        .connect_timeout(connect_timeout)
         .build()?;
     let response = client
         .get(url.to_string())
@@ -36,11 +33,11 @@ pub async fn fetch_dsn(ip_address: String) -> Result<String, Box<dyn Error + Sen
     } else {
         let error = response.text().await?;
         WifiPairing::log(format!("Request did not have success response: {}", &error));
-        Err(error.into())
+        Err(CloudCoreError::Pairing(error))
     }
 }
 
-pub async fn fetch_wifi_networks(ip_address: String) -> Result<Vec<WifiNetwork>, Box<dyn Error + Send + Sync>> {
+pub async fn fetch_wifi_networks(ip_address: String, connect_timeout: Duration) -> Result<Vec<WifiNetwork>, CloudCoreError> {
     #[derive(Deserialize, Debug)]
     struct WifiScan {
         mtime: Option<u32>,
@@ -53,7 +50,7 @@ pub async fn fetch_wifi_networks(ip_address: String) -> Result<Vec<WifiNetwork>,
     let url = format!("http://{}/wifi_scan_results.json", ip_address);
     WifiPairing::log(format!("Sending request to: {}", &url));
     let client = Client::builder()
-        .connect_timeout(Duration::from_secs(LAN_CONNECT_TIMEOUT))
+        .connect_timeout(connect_timeout)
         .build()?;
     let response = client
         .get(url.to_string())
@@ -68,15 +65,15 @@ pub async fn fetch_wifi_networks(ip_address: String) -> Result<Vec<WifiNetwork>,
     } else {
         let error = response.text().await?;
         WifiPairing::log(format!("Request did not have success response: {}", &error));
-        Err(error.into())
+        Err(CloudCoreError::Pairing(error))
     }
 }
 
-pub async fn start_wifi_scan(ip_address: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn start_wifi_scan(ip_address: String, connect_timeout: Duration) -> Result<(), CloudCoreError> {
     let url = format!("http://{}/wifi_scan.json", ip_address);
     WifiPairing::log(format!("Sending request to: {}", &url));
     let client = Client::builder()
-        .connect_timeout(Duration::from_secs(LAN_CONNECT_TIMEOUT))
+        .connect_timeout(connect_timeout)
         .build()?;
     let response = client
         .post(url.to_string())
@@ -90,11 +87,11 @@ pub async fn start_wifi_scan(ip_address: String) -> Result<(), Box<dyn Error + S
     } else {
         let error = response.text().await?;
         WifiPairing::log(format!("Request did not have success response: {}", &error));
-        Err(error.into())
+        Err(CloudCoreError::Pairing(error))
     }
 }
 
-pub async fn send_wifi_credentials_to_device(ip_address: String, wifi_network: WifiNetwork, setup_token: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn send_wifi_credentials_to_device(ip_address: String, wifi_network: WifiNetwork, setup_token: String, connect_timeout: Duration) -> Result<(), CloudCoreError> {
     let mut query: Vec<(&str, String)> = vec![
         ("ssid", wifi_network.ssid().unwrap().to_string()),
         ("setup_token", setup_token),
@@ -106,9 +103,16 @@ pub async fn send_wifi_credentials_to_device(ip_address: String, wifi_network: W
         }
     }
     let url = format!("http://{}/wifi_connect.json", ip_address);
-    WifiPairing::log(format!("Sending request to: {} with query: {:#?}", &url, &query));
+    let logged_query: Vec<(&str, &str)> = query
+        .iter()
+        .map(|(name, value)| match *name {
+            "key" | "setup_token" => (*name, REDACTED),
+            _ => (*name, value.as_str()),
+        })
+        .collect();
+    WifiPairing::log(format!("Sending request to: {} with query: {:#?}", &url, &logged_query));
     let client = Client::builder()
-        .connect_timeout(Duration::from_secs(LAN_CONNECT_TIMEOUT))
+        .connect_timeout(connect_timeout)
         .build()?;
     let response = client
         .post(url.to_string())
@@ -125,15 +129,107 @@ pub async fn send_wifi_credentials_to_device(ip_address: String, wifi_network: W
     } else {
         let text = response.text().await?;
         WifiPairing::log(format!("Request did not have success response: {}", &text));
-        Err(text.into())
+        Err(CloudCoreError::Pairing(text))
     }
 }
 
-pub async fn stop_device_access_point(ip_address: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+/// What the device's `wifi_status.json` says about joining the network it was sent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceWifiJoin {
+    Joined,
+    /// The key was rejected, with the `mtime` of the attempt in the device's connect history.
+    WrongPassword(u64),
+    /// The network was not found, with the `mtime` of the attempt in the device's connect history.
+    NetworkNotFound(u64),
+    /// The device firmware has no `wifi_status.json`.
+    Unsupported,
+}
+
+// Ayla's connect history error codes
+const WIFI_ERROR_NONE: u32 = 0;
+const WIFI_ERROR_INVALID_KEY: u32 = 3;
+const WIFI_ERROR_SSID_NOT_FOUND: u32 = 4;
+const WIFI_ERROR_NOT_AUTHENTICATED: u32 = 5;
+const WIFI_ERROR_INCORRECT_KEY: u32 = 6;
+
+/// Read how joining `ssid` went. Attempts that are still in progress, failed for another reason
+/// or are not newer than `after_mtime` are an error, so the step tries again.
+pub async fn fetch_device_wifi_status(ip_address: String, ssid: String, after_mtime: Option<u64>, connect_timeout: Duration) -> Result<DeviceWifiJoin, CloudCoreError> {
+    #[derive(Deserialize, Debug)]
+    struct ConnectHistory {
+        ssid_info: Option<String>,
+        ssid_len: Option<usize>,
+        error: u32,
+        msg: Option<String>,
+        mtime: Option<u64>,
+    }
+    #[derive(Deserialize, Debug)]
+    struct WifiStatus {
+        connect_history: Option<Vec<ConnectHistory>>,
+    }
+    #[derive(Deserialize, Debug)]
+    struct WifiStatusPayload {
+        wifi_status: WifiStatus,
+    }
+    let url = format!("http://{}/wifi_status.json", ip_address);
+    WifiPairing::log(format!("Sending request to: {}", &url));
+    let client = Client::builder()
+        .connect_timeout(connect_timeout)
+        .build()?;
+    let response = client
+        .get(url.to_string())
+        .send()
+        .await?;
+    WifiPairing::log(format!("Received status code: {:#?}", response.status()));
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(DeviceWifiJoin::Unsupported);
+    }
+    if !response.status().is_success() {
+        let error = response.text().await?;
+        WifiPairing::log(format!("Request did not have success response: {}", &error));
+        return Err(CloudCoreError::Pairing(error));
+    }
+    let status_payload = response.json::<WifiStatusPayload>().await?;
+    WifiPairing::log(format!("Response: {:#?}", &status_payload));
+    // The newest attempt comes first, the device only keeps the start of each SSID
+    let attempt = status_payload
+        .wifi_status
+        .connect_history
+        .unwrap_or_default()
+        .into_iter()
+        .find(|entry| {
+            entry.ssid_len == Some(ssid.len())
+                && entry.ssid_info.as_deref().is_some_and(|info| ssid.starts_with(info))
+        })
+        .filter(|entry| match after_mtime {
+            Some(after_mtime) => entry.mtime.unwrap_or_default() > after_mtime,
+            None => true,
+        });
+    match attempt {
+        None => Err(CloudCoreError::Pairing("Device has not tried joining the network yet".to_string())),
+        Some(entry) => {
+            let mtime = entry.mtime.unwrap_or_default();
+            match entry.error {
+                WIFI_ERROR_NONE => Ok(DeviceWifiJoin::Joined),
+                WIFI_ERROR_SSID_NOT_FOUND => Ok(DeviceWifiJoin::NetworkNotFound(mtime)),
+                WIFI_ERROR_INVALID_KEY | WIFI_ERROR_NOT_AUTHENTICATED | WIFI_ERROR_INCORRECT_KEY => {
+                    Ok(DeviceWifiJoin::WrongPassword(mtime))
+                }
+                error => Err(CloudCoreError::Pairing(format!(
+                    "Device joining the network: {} ({})",
+                    entry.msg.unwrap_or_default(),
+                    error
+                ))),
+            }
+        }
+    }
+}
+
+pub async fn stop_device_access_point(ip_address: String, connect_timeout: Duration) -> Result<(), CloudCoreError> {
     let url = format!("http://{}/wifi_stop_ap.json", ip_address);
     WifiPairing::log(format!("Sending request to: {}", &url));
     let client = Client::builder()
-        .connect_timeout(Duration::from_secs(LAN_CONNECT_TIMEOUT))
+        .connect_timeout(connect_timeout)
         .build()?;
     let response = client
         .put(url.to_string())
@@ -154,7 +250,7 @@ pub async fn stop_device_access_point(ip_address: String) -> Result<(), Box<dyn
     let url = format!("https://captive.apple.com/");
     WifiPairing::log(format!("Sending request to: {}", &url));
     let client = Client::builder()
-        .connect_timeout(Duration::from_secs(INTERNET_CONNECT_TIMEOUT))
+        .connect_timeout(connect_timeout)
         .build()?;
     let response = client
         .get(url.to_string())
@@ -171,7 +267,7 @@ pub async fn stop_device_access_point(ip_address: String) -> Result<(), Box<dyn
     }
 }*/
 
-pub async fn ayla_device_handshake(ayla_device_url: String, dsn: String, access_token: String, setup_token: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn ayla_device_handshake(ayla_device_url: String, dsn: String, access_token: String, setup_token: String, connect_timeout: Duration) -> Result<(), CloudCoreError> {
     #[derive(Serialize, Debug)]
     struct RequestDevice {
         dsn: String,
@@ -200,7 +296,7 @@ pub async fn ayla_device_handshake(ayla_device_url: String, dsn: String, access_
     debug!("Handshaking to: {}", &url);
     WifiPairing::log(format!("Sending request to: {} for handshake with Ayla", &url));
     let client = Client::builder()
-        .connect_timeout(Duration::from_secs(INTERNET_CONNECT_TIMEOUT))
+        .connect_timeout(connect_timeout)
         .build()?;
     let response = client
         .post(url.to_string())
@@ -216,22 +312,22 @@ pub async fn ayla_device_handshake(ayla_device_url: String, dsn: String, access_
             Ok(())
         } else {
             WifiPairing::log(format!("Wrong DSN returned. Expected {}, got {}", &dsn, &handshake_payload.device.dsn));
-            Err(format!("Wrong DSN returned. Expected {}, got {}", dsn, handshake_payload.device.dsn).into())
+            Err(CloudCoreError::Pairing(format!("Wrong DSN returned. Expected {}, got {}", dsn, handshake_payload.device.dsn)))
         }
     } else {
         let error = response.text().await?;
         WifiPairing::log(format!("Request did not have success response: {}", &error));
-        Err(error.into())
+        Err(CloudCoreError::Pairing(error))
     }
 }
 
-pub async fn get_device(ayla_device_url: String, dsn: String, access_token: String) -> Result<String, Box<dyn Error + Send + Sync>> {
+pub async fn get_device(ayla_device_url: String, dsn: String, access_token: String, connect_timeout: Duration) -> Result<String, CloudCoreError> {
     let auth_bearer = format!("{} {}", urls::AUTHORIZATION_BEARER, access_token);
     let url = format!("{}/apiv1/dsns/{}.json", ayla_device_url, &dsn);
     debug!("getting device with url {}", &url);
     WifiPairing::log(format!("Sending request to: {} to get device", &url));
     let client = Client::builder()
-        .connect_timeout(Duration::from_secs(INTERNET_CONNECT_TIMEOUT))
+        .connect_timeout(connect_timeout)
         .build()?;
     let response = client
         .get(url.to_string())
@@ -248,13 +344,13 @@ pub async fn get_device(ayla_device_url: String, dsn: String, access_token: Stri
     } else {
         let error = response.text().await?;
         WifiPairing::log(format!("Request did not have success response: {}", &error));
-        Err(error.into())
+        Err(CloudCoreError::Pairing(error))
     }
 }
 
 // Not sure if this has any benefit over just trying to get the device. But keeping it here for now.
 #[allow(dead_code)]
-pub async fn device_connected(ayla_device_url: String, dsn: String, setup_token: String) -> Result<(), Box<dyn Error + Send + Sync>> {
+pub async fn device_connected(ayla_device_url: String, dsn: String, setup_token: String, connect_timeout: Duration) -> Result<(), CloudCoreError> {
     let query: Vec<(&str, String)> = vec![("dsn", dsn), ("setup_token", setup_token)];
     #[derive(Deserialize, Debug)]
     struct ConnectedDevice {
@@ -270,7 +366,7 @@ pub async fn device_connected(ayla_device_url: String, dsn: String, setup_token:
     let url = format!("{}/apiv1/devices/connected.json", ayla_device_url);
     WifiPairing::log(format!("Sending request to: {}", &url));
     let client = Client::builder()
-        .connect_timeout(Duration::from_secs(INTERNET_CONNECT_TIMEOUT))
+        .connect_timeout(connect_timeout)
         .build()?;
     let response = client
         .get(url.to_string())
@@ -286,6 +382,6 @@ pub async fn device_connected(ayla_device_url: String, dsn: String, setup_token:
     } else {
         let error = response.text().await?;
         WifiPairing::log(format!("Request did not have success response: {}", &error));
-        Err(error.into())
+        Err(CloudCoreError::Pairing(error))
     }
 }
\ No newline at end of file
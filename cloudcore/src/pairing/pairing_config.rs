@@ -0,0 +1,197 @@
+#[cfg(feature = "library")]
+use std::time::Duration;
+
+#[cfg(feature = "library")]
+static DEFAULT_MAX_ATTEMPTS: u32 = 20;
+#[cfg(feature = "library")]
+static DEFAULT_QUICK_PAUSE_SECS: u64 = 3;
+#[cfg(feature = "library")]
+static DEFAULT_PAUSE_SECS: u64 = 5;
+// RTH 2022-07-20: This is to match gen 1 retry behavior
+#[cfg(feature = "library")]
+static DEFAULT_AYLA_CONNECTION_CHECK_PAUSE_SECS: u64 = 10;
+#[cfg(feature = "library")]
+static DEFAULT_AYLA_CONNECTION_CHECK_MAX_ATTEMPTS: u32 = 20;
+#[cfg(feature = "library")]
+static DEFAULT_LAN_CONNECT_TIMEOUT_SECS: u64 = 10;
+#[cfg(feature = "library")]
+static DEFAULT_INTERNET_CONNECT_TIMEOUT_SECS: u64 = 20;
+
+/// How many times one pairing step is tried and how long to pause between tries.
+#[cfg(feature = "library")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairingStepConfig {
+    max_attempts: u32,
+    pause: Duration,
+}
+
+#[cfg(feature = "library")]
+impl PairingStepConfig {
+    /// `max_attempts` includes the first try.
+    pub fn new(max_attempts: u32, pause: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            pause,
+        }
+    }
+
+    /// Get the step's max attempts.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+    /// Get the pause between the step's attempts.
+    pub fn pause(&self) -> Duration {
+        self.pause
+    }
+}
+
+/// Timeouts and retry budgets of a `WifiPairing`, the defaults match the gen 1 apps.
+///
+/// The `deadline` bounds the unattended parts of pairing: it is counted from `start`,
+/// `continue_pairing` and from when the user picked a network, so the time spent
+/// choosing a network doesn't count against it.
+#[cfg(feature = "library")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairingConfig {
+    fetch_dsn: PairingStepConfig,
+    wifi_scan: PairingStepConfig,
+    wifi_networks: PairingStepConfig,
+    wifi_networks_refresh: Duration,
+    send_credentials: PairingStepConfig,
+    device_wifi_status: PairingStepConfig,
+    user_wifi_wait: Duration,
+    ayla_handshake: PairingStepConfig,
+    ayla_device_check: PairingStepConfig,
+    lan_connect_timeout: Duration,
+    internet_connect_timeout: Duration,
+    deadline: Option<Duration>,
+}
+
+#[cfg(feature = "library")]
+impl Default for PairingConfig {
+    fn default() -> Self {
+        let quick_step = PairingStepConfig::new(DEFAULT_MAX_ATTEMPTS, Duration::from_secs(DEFAULT_QUICK_PAUSE_SECS));
+        let ayla_step = PairingStepConfig::new(
+            DEFAULT_AYLA_CONNECTION_CHECK_MAX_ATTEMPTS,
+            Duration::from_secs(DEFAULT_AYLA_CONNECTION_CHECK_PAUSE_SECS),
+        );
+        Self {
+            fetch_dsn: quick_step.clone(),
+            wifi_scan: quick_step.clone(),
+            wifi_networks: quick_step.clone(),
+            wifi_networks_refresh: Duration::from_secs(DEFAULT_PAUSE_SECS),
+            send_credentials: quick_step.clone(),
+            device_wifi_status: quick_step,
+            user_wifi_wait: Duration::from_secs(DEFAULT_PAUSE_SECS),
+            ayla_handshake: PairingStepConfig::new(DEFAULT_MAX_ATTEMPTS, Duration::from_secs(DEFAULT_AYLA_CONNECTION_CHECK_PAUSE_SECS)),
+            ayla_device_check: ayla_step,
+            lan_connect_timeout: Duration::from_secs(DEFAULT_LAN_CONNECT_TIMEOUT_SECS),
+            internet_connect_timeout: Duration::from_secs(DEFAULT_INTERNET_CONNECT_TIMEOUT_SECS),
+            deadline: None,
+        }
+    }
+}
+
+#[cfg(feature = "library")]
+impl PairingConfig {
+    /// Reading the DSN from the device's `status.json`.
+    pub fn with_fetch_dsn(mut self, step: PairingStepConfig) -> Self {
+        self.fetch_dsn = step;
+        self
+    }
+
+    /// Asking the device to scan for Wi-Fi networks.
+    pub fn with_wifi_scan(mut self, step: PairingStepConfig) -> Self {
+        self.wifi_scan = step;
+        self
+    }
+
+    /// Reading the device's scan results, `refresh` is the wait before reading them again
+    /// while the user hasn't picked a network.
+    pub fn with_wifi_networks(mut self, step: PairingStepConfig, refresh: Duration) -> Self {
+        self.wifi_networks = step;
+        self.wifi_networks_refresh = refresh;
+        self
+    }
+
+    /// Sending the chosen network's credentials to the device.
+    pub fn with_send_credentials(mut self, step: PairingStepConfig) -> Self {
+        self.send_credentials = step;
+        self
+    }
+
+    /// Reading the device's `wifi_status.json` until it joined the chosen network or
+    /// could not, every read where it is still joining counts as an attempt.
+    pub fn with_device_wifi_status(mut self, step: PairingStepConfig) -> Self {
+        self.device_wifi_status = step;
+        self
+    }
+
+    /// The wait for the phone to rejoin a network with internet after the device AP stopped.
+    pub fn with_user_wifi_wait(mut self, user_wifi_wait: Duration) -> Self {
+        self.user_wifi_wait = user_wifi_wait;
+        self
+    }
+
+    /// Registering the device with Ayla using the setup token.
+    pub fn with_ayla_handshake(mut self, step: PairingStepConfig) -> Self {
+        self.ayla_handshake = step;
+        self
+    }
+
+    /// Checking the device shows up on Ayla.
+    pub fn with_ayla_device_check(mut self, step: PairingStepConfig) -> Self {
+        self.ayla_device_check = step;
+        self
+    }
+
+    /// Connect timeouts of the requests to the device and to Ayla.
+    pub fn with_connect_timeouts(mut self, lan_connect_timeout: Duration, internet_connect_timeout: Duration) -> Self {
+        self.lan_connect_timeout = lan_connect_timeout;
+        self.internet_connect_timeout = internet_connect_timeout;
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: Option<Duration>) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Getters
+    pub fn fetch_dsn(&self) -> &PairingStepConfig {
+        &self.fetch_dsn
+    }
+    pub fn wifi_scan(&self) -> &PairingStepConfig {
+        &self.wifi_scan
+    }
+    pub fn wifi_networks(&self) -> &PairingStepConfig {
+        &self.wifi_networks
+    }
+    pub fn wifi_networks_refresh(&self) -> Duration {
+        self.wifi_networks_refresh
+    }
+    pub fn send_credentials(&self) -> &PairingStepConfig {
+        &self.send_credentials
+    }
+    pub fn device_wifi_status(&self) -> &PairingStepConfig {
+        &self.device_wifi_status
+    }
+    pub fn user_wifi_wait(&self) -> Duration {
+        self.user_wifi_wait
+    }
+    pub fn ayla_handshake(&self) -> &PairingStepConfig {
+        &self.ayla_handshake
+    }
+    pub fn ayla_device_check(&self) -> &PairingStepConfig {
+        &self.ayla_device_check
+    }
+    pub fn lan_connect_timeout(&self) -> Duration {
+        self.lan_connect_timeout
+    }
+    pub fn internet_connect_timeout(&self) -> Duration {
+        self.internet_connect_timeout
+    }
+    pub fn deadline(&self) -> Option<Duration> {
+        self.deadline
+    }
+}
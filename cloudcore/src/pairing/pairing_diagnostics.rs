@@ -0,0 +1,219 @@
+#[cfg(feature = "signatures")]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "library")]
+use crate::CloudCoreError;
+#[cfg(feature = "signatures")]
+use crate::pairing::wifi_state::WifiPairingState;
+
+/// Why a pairing failed, worked out from the state it failed in and the error it got.
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
+pub enum PairingFailure {
+    /// There is no user session to register the device with.
+    NoUserSession,
+    /// The device's access point did not answer or rejected the request.
+    DeviceUnreachable,
+    /// The device could not join the chosen network with the key it was sent.
+    WrongPassword,
+    /// The device could not find the chosen network.
+    NetworkNotFound,
+    /// The phone could not reach Ayla after leaving the device's access point.
+    AylaUnreachable,
+    /// Ayla refused to register the device with the setup token.
+    AylaHandshakeRejected,
+    /// Ayla registered the device but it never showed up on the account.
+    DeviceNeverCameOnline,
+    /// The pairing deadline passed.
+    Timeout,
+    /// Anything the app has to fix, like a missing callback.
+    Other,
+}
+
+#[cfg(feature = "library")]
+impl PairingFailure {
+    pub fn from_error(state: &WifiPairingState, err: &CloudCoreError) -> Self {
+        match (state, err) {
+            (_, CloudCoreError::Timeout(_)) => PairingFailure::Timeout,
+            (_, CloudCoreError::SessionMissing) => PairingFailure::NoUserSession,
+            (
+                WifiPairingState::FetchingDSN
+                | WifiPairingState::DeviceScanningWifi
+                | WifiPairingState::GettingWifiNetworks
+                | WifiPairingState::SendingWiFiCredentialsToDevice,
+                CloudCoreError::Network(_) | CloudCoreError::Connect(_) | CloudCoreError::Pairing(_) | CloudCoreError::Parse(_),
+            ) => PairingFailure::DeviceUnreachable,
+            (WifiPairingState::HandshakingWithAyla, CloudCoreError::Network(_) | CloudCoreError::Connect(_)) => PairingFailure::AylaUnreachable,
+            (WifiPairingState::HandshakingWithAyla, _) => PairingFailure::AylaHandshakeRejected,
+            (WifiPairingState::PollingDeviceOnAyla, _) => PairingFailure::DeviceNeverCameOnline,
+            _ => PairingFailure::Other,
+        }
+    }
+
+    /// The failure a pairing ended with when it was given up in `state`, if that state is one.
+    pub fn from_state(state: &WifiPairingState) -> Option<Self> {
+        match state {
+            WifiPairingState::WrongWifiPassword => Some(PairingFailure::WrongPassword),
+            WifiPairingState::WifiNetworkNotFound => Some(PairingFailure::NetworkNotFound),
+            _ => None,
+        }
+    }
+}
+
+/// How long one pairing state lasted and how many requests it took.
+#[cfg(feature = "signatures")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairingStepReport {
+    state: WifiPairingState,
+    started_at: DateTime<Utc>,
+    duration_ms: Option<i64>,
+    attempts: u32,
+    last_error: Option<String>,
+}
+
+#[cfg(feature = "library")]
+impl PairingStepReport {
+    fn new(state: WifiPairingState) -> Self {
+        Self {
+            state,
+            started_at: Utc::now(),
+            duration_ms: None,
+            attempts: 0,
+            last_error: None,
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.duration_ms.is_none() {
+            self.duration_ms = Some((Utc::now() - self.started_at).num_milliseconds());
+        }
+    }
+
+    /// Getters
+    pub fn state(&self) -> &WifiPairingState {
+        &self.state
+    }
+    pub fn started_at(&self) -> &DateTime<Utc> {
+        &self.started_at
+    }
+    /// `None` while the step is still running.
+    pub fn duration_ms(&self) -> Option<i64> {
+        self.duration_ms
+    }
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+    pub fn last_error(&self) -> Option<&String> {
+        self.last_error.as_ref()
+    }
+}
+
+/// What a `WifiPairing` went through, saved as JSON next to the pairing log once it
+/// connects, fails or is cancelled so support can triage failed setups.
+#[cfg(feature = "signatures")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairingDiagnostics {
+    session_id: Option<String>,
+    dsn: Option<String>,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+    steps: Vec<PairingStepReport>,
+    failure: Option<PairingFailure>,
+    failed_state: Option<WifiPairingState>,
+    error: Option<String>,
+}
+
+#[cfg(feature = "library")]
+impl Default for PairingDiagnostics {
+    fn default() -> Self {
+        Self {
+            session_id: None,
+            dsn: None,
+            started_at: Utc::now(),
+            finished_at: None,
+            steps: vec![],
+            failure: None,
+            failed_state: None,
+            error: None,
+        }
+    }
+}
+
+#[cfg(feature = "library")]
+impl PairingDiagnostics {
+    /// Close the running step and start timing `state`, unless it is already the running one.
+    /// The states pairing ends in are not steps.
+    pub(crate) fn enter_state(&mut self, state: &WifiPairingState) {
+        if matches!(state, WifiPairingState::Connected | WifiPairingState::Done | WifiPairingState::Cancelled) {
+            self.finish_step();
+            return;
+        }
+        if self.steps.last().map(|step| step.state == *state && step.duration_ms.is_none()) == Some(true) {
+            return;
+        }
+        self.finish_step();
+        self.steps.push(PairingStepReport::new(state.clone()));
+    }
+
+    /// Count a request made by the running step, with the error it failed with.
+    pub(crate) fn record_attempt(&mut self, error: Option<&CloudCoreError>) {
+        if let Some(step) = self.steps.last_mut() {
+            step.attempts += 1;
+            if let Some(error) = error {
+                step.last_error = Some(error.to_string());
+            }
+        }
+    }
+
+    pub(crate) fn set_session_id(&mut self, session_id: Option<String>) {
+        self.session_id = session_id;
+    }
+
+    pub(crate) fn set_dsn(&mut self, dsn: Option<String>) {
+        self.dsn = dsn;
+    }
+
+    pub(crate) fn fail(&mut self, failure: PairingFailure, state: WifiPairingState, error: String) {
+        self.failure = Some(failure);
+        self.failed_state = Some(state);
+        self.error = Some(error);
+        self.finish();
+    }
+
+    pub(crate) fn finish(&mut self) {
+        self.finish_step();
+        self.finished_at = Some(Utc::now());
+    }
+
+    fn finish_step(&mut self) {
+        if let Some(step) = self.steps.last_mut() {
+            step.finish();
+        }
+    }
+
+    /// Getters
+    /// The pairing log session to look up this pairing's log lines with.
+    pub fn session_id(&self) -> Option<&String> {
+        self.session_id.as_ref()
+    }
+    pub fn dsn(&self) -> Option<&String> {
+        self.dsn.as_ref()
+    }
+    pub fn started_at(&self) -> &DateTime<Utc> {
+        &self.started_at
+    }
+    pub fn finished_at(&self) -> Option<&DateTime<Utc>> {
+        self.finished_at.as_ref()
+    }
+    pub fn steps(&self) -> &Vec<PairingStepReport> {
+        &self.steps
+    }
+    pub fn failure(&self) -> Option<&PairingFailure> {
+        self.failure.as_ref()
+    }
+    pub fn failed_state(&self) -> Option<&WifiPairingState> {
+        self.failed_state.as_ref()
+    }
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+}
@@ -0,0 +1,457 @@
+#[cfg(feature = "library")]
+use std::fs::{read_dir, remove_file, rename, OpenOptions};
+#[cfg(feature = "library")]
+use std::io::Write;
+#[cfg(feature = "library")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "library")]
+use std::sync::Mutex;
+#[cfg(feature = "library")]
+use std::time::Duration;
+#[cfg(feature = "library")]
+use bytes::Bytes;
+#[cfg(feature = "signatures")]
+use chrono::{DateTime, Utc};
+#[cfg(feature = "library")]
+use lazy_static::lazy_static;
+#[cfg(feature = "library")]
+use log::error;
+#[cfg(feature = "signatures")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "library")]
+use uuid::Uuid;
+#[cfg(feature = "library")]
+use crate::io::{read_from_disk_to_string, write_to_disk};
+#[cfg(feature = "library")]
+use crate::{CloudCore, CloudCoreError};
+
+#[cfg(feature = "library")]
+static PAIRING_LOG_FILE_PREFIX: &str = "pairing_log";
+#[cfg(feature = "library")]
+static PAIRING_LOG_FILE_EXTENSION: &str = "jsonl";
+// The free text log written before the log was structured
+#[cfg(feature = "library")]
+static LEGACY_PAIRING_LOG_FILE: &str = "pairing_logging";
+#[cfg(feature = "library")]
+static DEFAULT_MAX_FILE_BYTES: u64 = 256 * 1024;
+#[cfg(feature = "library")]
+static DEFAULT_MAX_FILES: usize = 4;
+#[cfg(feature = "library")]
+static DEFAULT_MAX_AGE_DAYS: u64 = 14;
+#[cfg(feature = "library")]
+static DEFAULT_MAX_SESSIONS: usize = 5;
+#[cfg(feature = "library")]
+static REDACTED: &str = "<redacted>";
+// Keys whose values identify the user's network or give access to the device or account
+#[cfg(feature = "library")]
+static REDACTED_KEYS: [&str; 9] = [
+    "ssid", "bssid", "key", "password", "setup_token", "access_token", "refresh_token", "auth_token", "lanip_key",
+];
+
+#[cfg(feature = "library")]
+lazy_static! {
+    static ref PAIRING_LOCK: Mutex<()> = Mutex::new(());
+    static ref PAIRING_LOG_SESSION: Mutex<Option<String>> = Mutex::new(None);
+}
+
+#[cfg(feature = "signatures")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PairingLogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// One line of the pairing log. `session_id` is the pairing that wrote it, `None` for lines
+/// the app wrote while no pairing manager was created yet.
+#[cfg(feature = "signatures")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairingLogEntry {
+    timestamp: DateTime<Utc>,
+    session_id: Option<String>,
+    level: PairingLogLevel,
+    message: String,
+}
+
+#[cfg(feature = "library")]
+impl PairingLogEntry {
+    pub fn new(session_id: Option<String>, level: PairingLogLevel, message: String) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            session_id,
+            level,
+            message,
+        }
+    }
+
+    /// The same entry with SSIDs, Wi-Fi keys and tokens in its message masked.
+    pub fn redacted(&self) -> Self {
+        Self {
+            message: redact_pairing_log_message(&self.message),
+            ..self.clone()
+        }
+    }
+
+    /// Getters
+    pub fn timestamp(&self) -> &DateTime<Utc> {
+        &self.timestamp
+    }
+    pub fn session_id(&self) -> Option<&String> {
+        self.session_id.as_ref()
+    }
+    pub fn level(&self) -> &PairingLogLevel {
+        &self.level
+    }
+    pub fn message(&self) -> &String {
+        &self.message
+    }
+}
+
+/// How much pairing log is kept. The log is rotated into a new file once it grows past
+/// `max_file_bytes`, only the newest `max_files` files are kept, and lines older than `max_age`
+/// or from before the last `max_sessions` pairings are dropped when a pairing starts.
+#[cfg(feature = "library")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairingLogConfig {
+    max_file_bytes: u64,
+    max_files: usize,
+    max_age: Duration,
+    max_sessions: usize,
+}
+
+#[cfg(feature = "library")]
+impl Default for PairingLogConfig {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+            max_age: Duration::from_secs(DEFAULT_MAX_AGE_DAYS * 24 * 60 * 60),
+            max_sessions: DEFAULT_MAX_SESSIONS,
+        }
+    }
+}
+
+#[cfg(feature = "library")]
+impl PairingLogConfig {
+    pub fn with_max_file_bytes(mut self, max_file_bytes: u64) -> Self {
+        self.max_file_bytes = max_file_bytes;
+        self
+    }
+
+    /// Includes the file being written to.
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files.max(1);
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub fn with_max_sessions(mut self, max_sessions: usize) -> Self {
+        self.max_sessions = max_sessions.max(1);
+        self
+    }
+
+    /// Getters
+    pub fn max_file_bytes(&self) -> u64 {
+        self.max_file_bytes
+    }
+    pub fn max_files(&self) -> usize {
+        self.max_files
+    }
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+    pub fn max_sessions(&self) -> usize {
+        self.max_sessions
+    }
+}
+
+/// Mask the values given to an SSID, Wi-Fi key or token name in `message`, the way they show
+/// up in the pairing log: `ssid: Some("Home")`, `("key", "secret")`, `"setup_token":"abc"` and
+/// `setup_token=abc` in urls.
+#[cfg(feature = "library")]
+pub fn redact_pairing_log_message(message: &str) -> String {
+    let mut redacted = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(c) = rest.chars().next() {
+        let preceded_by_identifier = redacted.chars().last().is_some_and(is_identifier);
+        let value = if preceded_by_identifier {
+            None
+        } else {
+            REDACTED_KEYS.iter().find_map(|key| redacted_value(rest, key))
+        };
+        match value {
+            Some((value_start, value_end)) => {
+                redacted.push_str(&rest[..value_start]);
+                redacted.push_str(REDACTED);
+                rest = &rest[value_end..];
+            }
+            None => {
+                redacted.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    redacted
+}
+
+/// Where the value given to `key` at the start of `text` is, without its quotes.
+#[cfg(feature = "library")]
+fn redacted_value(text: &str, key: &str) -> Option<(usize, usize)> {
+    let after_key = text.strip_prefix(key)?;
+    if after_key.starts_with(is_identifier) {
+        return None;
+    }
+    // Skip what separates a key from its value: quotes, `:`, `=`, `,`, spaces and `Some(`
+    let mut value = after_key;
+    loop {
+        let trimmed = value.trim_start_matches(|c: char| c.is_whitespace() || "\"':=,".contains(c));
+        match trimmed.strip_prefix("Some(") {
+            Some(inner) => value = inner,
+            None => {
+                value = trimmed;
+                break;
+            }
+        }
+    }
+    let separator = &after_key[..after_key.len() - value.len()];
+    let quoted = separator.ends_with('"');
+    // Free text like "the key was wrong" has no value to hide
+    if !quoted && !separator.contains(|c: char| ":=,".contains(c)) {
+        return None;
+    }
+    let value_len = if quoted {
+        value.find('"')?
+    } else {
+        value.find(|c: char| c.is_whitespace() || "&,;)}]\"".contains(c)).unwrap_or(value.len())
+    };
+    if value_len == 0 || &value[..value_len] == "None" {
+        return None;
+    }
+    let value_start = text.len() - value.len();
+    Some((value_start, value_start + value_len))
+}
+
+#[cfg(feature = "library")]
+fn is_identifier(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[cfg(feature = "library")]
+impl CloudCore {
+    /// Start tagging pairing log lines with a new session id, dropping the lines of pairings
+    /// that are too old or too many to keep alongside it.
+    pub fn start_pairing_log_session(&self) -> String {
+        let session_id = Uuid::new_v4().to_string();
+        let _lock = lock(&PAIRING_LOCK);
+        if let Some(err) = self.prune_pairing_log(self.pairing_log_config().max_sessions() - 1).err() {
+            error!("Failed to prune pairing log: {}", err);
+        }
+        *lock(&PAIRING_LOG_SESSION) = Some(session_id.clone());
+        session_id
+    }
+
+    /// Add an `Info` line to the pairing log.
+    pub fn write_to_pairing_log(&self, content: String) -> Result<(), CloudCoreError> {
+        self.write_pairing_log_entry(PairingLogLevel::Info, content)
+    }
+
+    pub fn write_pairing_log_entry(&self, level: PairingLogLevel, content: String) -> Result<(), CloudCoreError> {
+        let session_id = lock(&PAIRING_LOG_SESSION).clone();
+        let entry = PairingLogEntry::new(session_id, level, content);
+        let _lock = lock(&PAIRING_LOCK);
+        self.append_pairing_log_entries(&[entry])
+    }
+
+    /// Called on start up. The log is kept across app launches, only lines past their age are dropped.
+    pub fn create_log(&self) {
+        let _lock = lock(&PAIRING_LOCK);
+        if let Some(err) = self.import_legacy_pairing_log().err() {
+            error!("Failed to import legacy pairing log: {}", err);
+        }
+        if let Some(err) = self.prune_pairing_log(self.pairing_log_config().max_sessions()).err() {
+            error!("Failed to prune pairing log: {}", err);
+        }
+    }
+
+    /// Delete the whole pairing log.
+    pub fn clear_log(&self) {
+        let _lock = lock(&PAIRING_LOCK);
+        for path in self.pairing_log_files() {
+            if let Some(err) = remove_file(&path).err() {
+                error!("Failed to clear pairing log {:?}: {}", path, err);
+            }
+        }
+    }
+
+    /// The pairing log as JSON lines, oldest first.
+    pub fn get_pairing_log(&self) -> Result<String, CloudCoreError> {
+        self.export_pairing_log(false)
+    }
+
+    /// The pairing log as JSON lines, oldest first, with SSIDs, Wi-Fi keys and tokens masked
+    /// when `redacted` so it can be uploaded for support.
+    pub fn export_pairing_log(&self, redacted: bool) -> Result<String, CloudCoreError> {
+        let mut log = String::new();
+        for entry in self.pairing_log_entries()? {
+            let entry = if redacted { entry.redacted() } else { entry };
+            log.push_str(&serde_json::to_string(&entry)?);
+            log.push('\n');
+        }
+        Ok(log)
+    }
+
+    pub fn pairing_log_entries(&self) -> Result<Vec<PairingLogEntry>, CloudCoreError> {
+        let _lock = lock(&PAIRING_LOCK);
+        self.read_pairing_log_entries()
+    }
+
+    fn read_pairing_log_entries(&self) -> Result<Vec<PairingLogEntry>, CloudCoreError> {
+        let mut entries = vec![];
+        for path in self.pairing_log_files() {
+            let contents = read_from_disk_to_string(&path.to_string_lossy())?;
+            // A line cut short by a crash is skipped rather than losing the whole log
+            entries.extend(contents.lines().filter_map(|line| serde_json::from_str::<PairingLogEntry>(line).ok()));
+        }
+        Ok(entries)
+    }
+
+    /// Move the lines of the free text log into the structured one, as entries without a session
+    /// ahead of the ones written since.
+    fn import_legacy_pairing_log(&self) -> Result<(), CloudCoreError> {
+        let legacy_path = self.cache.parent_path().join(LEGACY_PAIRING_LOG_FILE);
+        if !legacy_path.exists() {
+            return Ok(());
+        }
+        let contents = read_from_disk_to_string(&legacy_path.to_string_lossy())?;
+        // The free text log has no timestamps, its lines are at most as old as its last write
+        let written_at = legacy_path.metadata()?.modified().map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now());
+        let mut entries: Vec<PairingLogEntry> = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| PairingLogEntry {
+                timestamp: written_at,
+                session_id: None,
+                level: PairingLogLevel::Info,
+                message: line.to_string(),
+            })
+            .collect();
+        entries.extend(self.read_pairing_log_entries()?);
+        for path in self.pairing_log_files() {
+            remove_file(path)?;
+        }
+        self.append_pairing_log_entries(&entries)?;
+        remove_file(legacy_path)?;
+        Ok(())
+    }
+
+    /// Drop lines past their age and from before the newest `keep_sessions` pairings.
+    fn prune_pairing_log(&self, keep_sessions: usize) -> Result<(), CloudCoreError> {
+        let entries = self.read_pairing_log_entries()?;
+        let max_age = chrono::Duration::from_std(self.pairing_log_config().max_age()).unwrap_or_else(|_| chrono::Duration::max_value());
+        let cutoff = Utc::now().checked_sub_signed(max_age);
+        let mut sessions: Vec<&String> = vec![];
+        for session_id in entries.iter().rev().filter_map(|entry| entry.session_id.as_ref()) {
+            if !sessions.contains(&session_id) {
+                sessions.push(session_id);
+            }
+        }
+        sessions.truncate(keep_sessions);
+        let kept: Vec<PairingLogEntry> = entries
+            .iter()
+            .filter(|entry| cutoff.is_none_or(|cutoff| entry.timestamp >= cutoff))
+            .filter(|entry| entry.session_id.as_ref().is_none_or(|session_id| sessions.contains(&session_id)))
+            .cloned()
+            .collect();
+        if kept.len() == entries.len() {
+            return Ok(());
+        }
+        for path in self.pairing_log_files() {
+            remove_file(path)?;
+        }
+        self.append_pairing_log_entries(&kept)
+    }
+
+    /// Append to the current file, rotating it whenever it would grow past `max_file_bytes`.
+    fn append_pairing_log_entries(&self, entries: &[PairingLogEntry]) -> Result<(), CloudCoreError> {
+        let path = self.current_pairing_log_path();
+        for entry in entries {
+            let line = format!("{}\n", serde_json::to_string(entry)?);
+            let size = path.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+            if size > 0 && size + line.len() as u64 > self.pairing_log_config().max_file_bytes() {
+                self.rotate_pairing_log()?;
+            }
+            if !path.exists() {
+                write_to_disk(path.as_path(), Bytes::new())?;
+            }
+            let mut file = OpenOptions::new().append(true).open(&path)?;
+            file.write_all(line.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn rotate_pairing_log(&self) -> Result<(), CloudCoreError> {
+        // Archives sort by their stamp, so it has to be after the newest one even within a millisecond
+        let current = self.current_pairing_log_path();
+        let newest_stamp = self
+            .pairing_log_files()
+            .iter()
+            .filter(|path| **path != current)
+            .filter_map(|path| path.file_name()?.to_str()?.split('.').nth(1)?.parse::<i64>().ok())
+            .max();
+        let stamp = Utc::now().timestamp_millis().max(newest_stamp.map_or(0, |stamp| stamp + 1));
+        rename(current, self.archived_pairing_log_path(stamp))?;
+        let archives = self.pairing_log_files();
+        // The file being rotated to counts towards `max_files`
+        let excess = (archives.len() + 1).saturating_sub(self.pairing_log_config().max_files());
+        for path in archives.into_iter().take(excess) {
+            remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// The archived files, oldest first, followed by the current one.
+    fn pairing_log_files(&self) -> Vec<PathBuf> {
+        let archive_prefix = format!("{}.", PAIRING_LOG_FILE_PREFIX);
+        let mut archives: Vec<PathBuf> = match read_dir(self.cache.parent_path()) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+                    name.starts_with(&archive_prefix) && name.split('.').count() == 3
+                })
+                .collect(),
+            Err(err) => {
+                error!("Failed to list pairing logs: {}", err);
+                vec![]
+            }
+        };
+        archives.sort();
+        let current = self.current_pairing_log_path();
+        if current.exists() {
+            archives.push(current);
+        }
+        archives
+    }
+
+    fn current_pairing_log_path(&self) -> PathBuf {
+        self.cache.parent_path().join(Path::new(&format!("{}.{}", PAIRING_LOG_FILE_PREFIX, PAIRING_LOG_FILE_EXTENSION)))
+    }
+
+    fn archived_pairing_log_path(&self, stamp: i64) -> PathBuf {
+        self.cache.parent_path().join(Path::new(&format!("{}.{:013}.{}", PAIRING_LOG_FILE_PREFIX, stamp, PAIRING_LOG_FILE_EXTENSION)))
+    }
+}
+
+#[cfg(feature = "library")]
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    // Logging must keep working after a panic elsewhere
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(feature = "library")]
+use log::error;
+#[cfg(feature = "signatures")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "library")]
+use crate::cache::{CacheDataValue, CacheInteract};
+#[cfg(feature = "library")]
+use crate::cloudcore::CACHE_APP_DIR;
+#[cfg(feature = "library")]
+use crate::CloudCore;
+#[cfg(feature = "signatures")]
+use crate::pairing::wifi_state::WifiPairingState;
+#[cfg(feature = "signatures")]
+use crate::secret::Secret;
+
+#[cfg(feature = "library")]
+static PAIRING_PROGRESS_CACHE_KEY: &str = "pairing_progress";
+
+/// What a `WifiPairing` knows about the device it is pairing, saved in the cache on every state change.
+#[cfg(feature = "signatures")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairingProgress {
+    dsn: Option<String>,
+    setup_token: Option<Secret<String>>,
+    ssid: Option<String>,
+    state: WifiPairingState,
+}
+
+#[cfg(feature = "library")]
+impl PairingProgress {
+    pub(crate) fn new(dsn: Option<String>, setup_token: Option<String>, ssid: Option<String>, state: WifiPairingState) -> Self {
+        Self {
+            dsn,
+            setup_token: setup_token.map(Secret::new),
+            ssid,
+            state,
+        }
+    }
+
+    /// The state pairing picks up from, `None` when the credentials were never sent to the device
+    /// and pairing has to start over on the device's access point.
+    pub fn resume_state(&self) -> Option<WifiPairingState> {
+        if self.dsn.is_none() || self.setup_token.is_none() {
+            return None;
+        }
+        match self.state {
+            WifiPairingState::SendingWiFiCredentialsToDevice
+            | WifiPairingState::PollingDeviceWifiStatus
+            | WifiPairingState::EndingAccessPointsScanning
+            | WifiPairingState::PollingUserInternetConnection
+            | WifiPairingState::HandshakingWithAyla => Some(WifiPairingState::HandshakingWithAyla),
+            WifiPairingState::PollingDeviceOnAyla => Some(WifiPairingState::PollingDeviceOnAyla),
+            _ => None,
+        }
+    }
+
+    /// Getters
+    pub fn dsn(&self) -> Option<&String> {
+        self.dsn.as_ref()
+    }
+    pub fn setup_token(&self) -> Option<&String> {
+        self.setup_token.as_ref().map(Secret::expose)
+    }
+    pub fn ssid(&self) -> Option<&String> {
+        self.ssid.as_ref()
+    }
+    pub fn state(&self) -> &WifiPairingState {
+        &self.state
+    }
+}
+
+#[cfg(feature = "library")]
+impl CloudCore {
+    /// The progress of the last pairing that neither finished nor was cancelled.
+    pub fn pairing_progress(&self) -> Option<PairingProgress> {
+        match self.cache.get_value(CACHE_APP_DIR.to_string(), PAIRING_PROGRESS_CACHE_KEY.to_string()) {
+            Ok(CacheDataValue::ObjectValue(value)) => serde_json::from_value(value).ok(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn save_pairing_progress(&mut self, progress: &PairingProgress) {
+        let result = self.cache.set_value(CACHE_APP_DIR.to_string(), PAIRING_PROGRESS_CACHE_KEY.to_string(), progress);
+        if let Some(err) = result.err() {
+            error!("Failed to save pairing progress: {}", err);
+        }
+    }
+
+    pub fn clear_pairing_progress(&mut self) {
+        let result = self.cache.remove_value(CACHE_APP_DIR.to_string(), PAIRING_PROGRESS_CACHE_KEY.to_string());
+        if let Some(err) = result.err() {
+            error!("Failed to clear pairing progress: {}", err);
+        }
+    }
+}
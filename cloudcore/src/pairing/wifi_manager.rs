@@ -1,7 +1,10 @@
 use crate::pairing::network_requests::{
-    ayla_device_handshake, fetch_dsn, fetch_wifi_networks, get_device,
-    send_wifi_credentials_to_device, start_wifi_scan, stop_device_access_point,
+    ayla_device_handshake, fetch_device_wifi_status, fetch_dsn, fetch_wifi_networks, get_device,
+    send_wifi_credentials_to_device, start_wifi_scan, stop_device_access_point, DeviceWifiJoin,
 };
+use crate::pairing::pairing_config::PairingStepConfig;
+use crate::pairing::pairing_diagnostics::PairingFailure;
+use crate::pairing::pairing_log::PairingLogLevel;
 use crate::pairing::wifi_network::WifiNetwork;
 use crate::pairing::wifi_pairing::WifiPairing;
 use crate::pairing::wifi_state::WifiPairingState;
@@ -9,19 +12,14 @@ use async_recursion::async_recursion;
 use log::{debug, error};
 use mantle_utilities::RUNTIME;
 use rand::Rng;
-use std::error::Error;
-use std::time::Duration;
-use tokio::time::sleep;
-use crate::CloudCore;
-
-static MAX_FAILS: u32 = 20;
-static QUICK_PAUSE_SECS: u64 = 3;
-static PAUSE_SECS: u64 = 5;
-static AYLA_CONNECTION_CHECK_PAUSE_SECS: u64 = 10; // RTH 2022-07-20: This is to match gen 1 retry behavior
-static AYLA_CONNECTION_CHECK_MAX_FAILS: u32 = 20; // RTH 2022-07-20: This is to match gen 1 retry behavior
+use std::future::Future;
+use tokio::time::{sleep, sleep_until, timeout_at, Instant};
+use crate::{CloudCore, CloudCoreError};
 
 pub fn handle_wifi_network(manager: &'static mut WifiPairing, wifi_network: WifiNetwork) {
-    if manager.state().clone() as u8 >= WifiPairingState::SendingWiFiCredentialsToDevice as u8 {
+    // The device is still on its AP after it could not join a network, so it can be sent other credentials
+    let rejected = matches!(manager.state(), WifiPairingState::WrongWifiPassword | WifiPairingState::WifiNetworkNotFound);
+    if !rejected && manager.state().credentials_sent() {
         error!("Already sent wifi credentials to device");
         WifiPairing::log("Already sent wifi credentials to device".to_string());
         return;
@@ -30,35 +28,34 @@ pub fn handle_wifi_network(manager: &'static mut WifiPairing, wifi_network: Wifi
     manager
         .ayla_device_info()
         .set_setup_token(Some(setup_token.to_owned()));
+    manager.ayla_device_info().set_ssid(wifi_network.ssid().cloned());
     manager.set_state(WifiPairingState::SendingWiFiCredentialsToDevice);
+    manager.restart_deadline();
     let ip_address = manager.ayla_device_info().ip_address().unwrap().to_owned();
+    let ssid = wifi_network.ssid().cloned().unwrap_or_default();
+    let step = manager.config().send_credentials().clone();
+    let connect_timeout = manager.config().lan_connect_timeout();
+    let deadline = manager.deadline();
     let ptr_manager = manager as *mut WifiPairing;
     let handle = RUNTIME.spawn(async move {
-        let mut fail_count = 0;
-        let mut result: Result<(), Box<dyn Error + Send + Sync>> = Err("".into());
-        while fail_count < MAX_FAILS && result.is_err() {
+        let result = manager.retry_step(step, deadline, || {
             debug!("sending credentials to device to join selected SSID");
             WifiPairing::log("sending credentials to device to join selected SSID".to_string());
-            result = send_wifi_credentials_to_device(
+            send_wifi_credentials_to_device(
                 ip_address.to_owned(),
                 wifi_network.clone(),
                 setup_token.to_owned(),
+                connect_timeout,
             )
-            .await;
-            if result.is_err() {
-                fail_count += 1;
-            }
-            if result.is_err() && fail_count < MAX_FAILS {
-                sleep(Duration::from_secs(QUICK_PAUSE_SECS)).await
-            }
-        }
+        })
+        .await;
         match result {
             Ok(_) => {
                 debug!("🎉 connected device IP {} to wifi network {}", &ip_address, &wifi_network.ssid().unwrap());
-                WifiPairing::log(format!("🎉 connected device IP {} to wifi network {}", &ip_address, &wifi_network.ssid().unwrap()));
-                manager.stop_device_access_point().await
+                WifiPairing::log(format!("🎉 connected device IP {} to wifi network with ssid: {:?}", &ip_address, &ssid));
+                manager.poll_device_wifi_status(ssid).await
             }
-            Err(err) => manager.handle_error(err.to_string()),
+            Err(err) => manager.handle_error(err),
         }
     });
     let manager = unsafe { &mut *ptr_manager };
@@ -70,22 +67,16 @@ impl WifiPairing {
     pub async fn start_fetching_dsn(&'static mut self) {
         self.set_state(WifiPairingState::FetchingDSN);
         let ip_address = self.ayla_device_info().ip_address().unwrap().to_owned();
-        let mut fail_count = 0;
-        let mut result: Result<String, Box<dyn Error + Send + Sync>> = Err("".into());
-        while fail_count < MAX_FAILS && result.is_err() {
+        let connect_timeout = self.config().lan_connect_timeout();
+        let result = self.retry_step(self.config().fetch_dsn().clone(), self.deadline(), || {
             debug!("fetching dsn for IP {}", &ip_address);
             WifiPairing::log(format!("fetching dsn for IP {}", &ip_address));
-            result = fetch_dsn(ip_address.to_owned()).await;
-            if result.is_err() {
-                fail_count += 1;
-            }
-            if result.is_err() && fail_count < MAX_FAILS {
-                sleep(Duration::from_secs(QUICK_PAUSE_SECS)).await
-            }
-        }
+            fetch_dsn(ip_address.to_owned(), connect_timeout)
+        })
+        .await;
         match result {
             Ok(dsn) => self.handle_dsn(dsn).await,
-            Err(err) => self.handle_error(err.to_string()),
+            Err(err) => self.handle_error(err),
         };
     }
 
@@ -95,27 +86,21 @@ impl WifiPairing {
         self.ayla_device_info().set_dsn(Some(dsn));
         self.set_state(WifiPairingState::DeviceScanningWifi);
         let ip_address = self.ayla_device_info().ip_address().unwrap().to_owned();
-        let mut fail_count = 0;
-        let mut result: Result<(), Box<dyn Error + Send + Sync>> = Err("".into());
-        while fail_count < MAX_FAILS && result.is_err() {
+        let connect_timeout = self.config().lan_connect_timeout();
+        let result = self.retry_step(self.config().wifi_scan().clone(), self.deadline(), || {
             debug!("starting wifi scan for IP {}", &ip_address);
             WifiPairing::log(format!("starting wifi scan for IP {}", &ip_address));
-            result = start_wifi_scan(ip_address.to_owned()).await;
-            if result.is_err() {
-                fail_count += 1;
-            }
-            if result.is_err() && fail_count < MAX_FAILS {
-                sleep(Duration::from_secs(QUICK_PAUSE_SECS)).await
-            }
-        }
+            start_wifi_scan(ip_address.to_owned(), connect_timeout)
+        })
+        .await;
         match result {
             Ok(_) => self.get_wifi_networks().await,
-            Err(err) => self.handle_error(err.to_string()),
+            Err(err) => self.handle_error(err),
         };
     }
 
     pub async fn get_wifi_networks(&'static mut self) {
-        if self.state().clone() as u8 >= WifiPairingState::SendingWiFiCredentialsToDevice as u8 {
+        if self.state().credentials_sent() {
             return;
         }
         self.set_state(WifiPairingState::GettingWifiNetworks);
@@ -124,32 +109,27 @@ impl WifiPairing {
 
     #[async_recursion]
     async fn run_get_wifi_networks_loop(&'static mut self) {
-        if self.state().clone() as u8 >= WifiPairingState::SendingWiFiCredentialsToDevice as u8 {
+        if self.state().credentials_sent() {
             return;
         }
-        let mut fail_count = 0;
-        let mut result: Result<Vec<WifiNetwork>, Box<dyn Error + Send + Sync>> = Err("".into());
         let ip_address = self.ayla_device_info().ip_address().unwrap().to_owned();
-        while fail_count < MAX_FAILS && result.is_err() {
+        let connect_timeout = self.config().lan_connect_timeout();
+        // Waiting for the user to pick a network, the deadline doesn't apply
+        let result = self.retry_step(self.config().wifi_networks().clone(), None, || {
             debug!("fetching visible wifi networks for IP {}", &ip_address);
             WifiPairing::log(format!("starting wifi scan for IP {}", &ip_address));
-            result = fetch_wifi_networks(ip_address.to_owned()).await;
-            if result.is_err() {
-                fail_count += 1;
-            }
-            if result.is_err() && fail_count < MAX_FAILS {
-                sleep(Duration::from_secs(QUICK_PAUSE_SECS)).await
-            }
-        }
+            fetch_wifi_networks(ip_address.to_owned(), connect_timeout)
+        })
+        .await;
         match result {
             Ok(networks) => {
                 debug!("wireless networks for IP {}: {:?}", &ip_address, &networks);
                 WifiPairing::log(format!("wireless networks for IP {}: {:?}", &ip_address, &networks));
                 self.get_desired_wifi_network(networks);
-                sleep(Duration::from_secs(PAUSE_SECS)).await;
+                sleep(self.config().wifi_networks_refresh()).await;
                 self.run_get_wifi_networks_loop().await;
             }
-            Err(err) => self.handle_error(err.to_string()),
+            Err(err) => self.handle_error(err),
         };
     }
 
@@ -157,22 +137,57 @@ impl WifiPairing {
         if matches!(self.state(), WifiPairingState::GettingWifiNetworks) {
             match self.wifi_networks_callback() {
                 Some(callback) => callback(wifi_networks),
-                None => self.handle_error(
+                None => self.handle_error(CloudCoreError::Pairing(
                     "Need a wifi network connected to the internet for the device".to_string(),
-                ),
+                )),
             }
         }
     }
 
+    pub async fn poll_device_wifi_status(&'static mut self, ssid: String) {
+        self.set_state(WifiPairingState::PollingDeviceWifiStatus);
+        let ip_address = self.ayla_device_info().ip_address().unwrap().to_owned();
+        let after_mtime = self.ayla_device_info().rejected_join_mtime();
+        let connect_timeout = self.config().lan_connect_timeout();
+        let result = self.retry_step(self.config().device_wifi_status().clone(), self.deadline(), || {
+            debug!("checking wifi status of device IP {}", &ip_address);
+            WifiPairing::log(format!("checking wifi status of device IP {}", &ip_address));
+            fetch_device_wifi_status(ip_address.to_owned(), ssid.to_owned(), after_mtime, connect_timeout)
+        })
+        .await;
+        match result {
+            Ok(DeviceWifiJoin::WrongPassword(mtime)) => {
+                self.handle_rejected_wifi_network(WifiPairingState::WrongWifiPassword, mtime)
+            }
+            Ok(DeviceWifiJoin::NetworkNotFound(mtime)) => {
+                self.handle_rejected_wifi_network(WifiPairingState::WifiNetworkNotFound, mtime)
+            }
+            Ok(DeviceWifiJoin::Joined) | Ok(DeviceWifiJoin::Unsupported) => self.stop_device_access_point().await,
+            Err(err @ CloudCoreError::Timeout(_)) => self.handle_error(err),
+            // The device never said how joining went, Ayla will tell whether it made it online
+            Err(_) => self.stop_device_access_point().await,
+        }
+    }
+
+    /// The device stays on its AP, so the user can pick another network or fix the password
+    /// and send the credentials again.
+    fn handle_rejected_wifi_network(&mut self, state: WifiPairingState, mtime: u64) {
+        debug!("device could not join the network: {:?}", &state);
+        WifiPairing::log_at(PairingLogLevel::Warn, format!("device could not join the network: {:?}", &state));
+        self.ayla_device_info().set_rejected_join_mtime(Some(mtime));
+        self.set_state(state);
+    }
+
     pub async fn stop_device_access_point(&'static mut self) {
         self.set_state(WifiPairingState::EndingAccessPointsScanning);
         let ip_address = self.ayla_device_info().ip_address().unwrap().to_owned();
+        let connect_timeout = self.config().lan_connect_timeout();
         // For now do not get a join handle, since it should just run once
         RUNTIME.spawn(async move {
             // This may fail. May not. Doesn't negatively affect anything.
             debug!("Sending command to stop AP for IP {}", &ip_address);
             WifiPairing::log(format!("Sending command to stop AP for IP {}", &ip_address));
-            let _ = stop_device_access_point(ip_address).await;
+            let _ = stop_device_access_point(ip_address, connect_timeout).await;
         });
         self.wait_for_user_wifi().await;
     }
@@ -180,9 +195,10 @@ impl WifiPairing {
     pub async fn wait_for_user_wifi(&'static mut self) {
         self.set_state(WifiPairingState::PollingUserInternetConnection);
         debug!("Starting wait to automatically rejoin known wifi with internet");
-        sleep(Duration::from_secs(PAUSE_SECS)).await;
+        let wait = Instant::now() + self.config().user_wifi_wait();
+        sleep_until(self.deadline().map_or(wait, |deadline| wait.min(deadline))).await;
         /*WifiPairing::log(format!("Starting wait to automatically rejoin known wifi with internet"));
-        let mut result: Result<(), Box<dyn Error + Send + Sync>> = Err("".into());
+        let mut result: Result<(), CloudCoreError> = Err(CloudCoreError::Pairing(String::new()));
         while result.is_err() {
             debug!("trying internet connection...");
             WifiPairing::log(format!("trying internet connection..."));
@@ -208,32 +224,26 @@ impl WifiPairing {
         let setup_token = self.ayla_device_info().setup_token().unwrap().to_owned();
         let access_token = self.access_token().as_ref().unwrap().to_string();
         let device_url = self.ayla_device_info().device_url().to_owned();
-        let mut fail_count = 0;
-        let mut result: Result<(), Box<dyn Error + Send + Sync>> = Err("".into());
-        while fail_count < MAX_FAILS && result.is_err() {
+        let connect_timeout = self.config().internet_connect_timeout();
+        let result = self.retry_step(self.config().ayla_handshake().clone(), self.deadline(), || {
             debug!("connecting device to ayla");
             WifiPairing::log("connecting device to ayla".to_string());
-            result = ayla_device_handshake(
+            ayla_device_handshake(
                 device_url.to_string(),
                 dsn.to_string(),
                 access_token.to_string(),
                 setup_token.to_string(),
+                connect_timeout,
             )
-            .await;
-            if result.is_err() {
-                fail_count += 1;
-            }
-            if result.is_err() && fail_count < MAX_FAILS {
-                sleep(Duration::from_secs(AYLA_CONNECTION_CHECK_PAUSE_SECS)).await
-            }
-        }
+        })
+        .await;
         match result {
             Ok(_) => {
                 debug!("Alya accepted device 🎉");
                 WifiPairing::log("Alya accepted device 🎉".to_string());
                 self.check_device_connected().await
             }
-            Err(err) => self.handle_error(err.to_string()),
+            Err(err) => self.handle_error(err),
         };
     }
 
@@ -242,24 +252,18 @@ impl WifiPairing {
         let dsn = self.ayla_device_info().dsn().unwrap().to_owned();
         let access_token = self.access_token().unwrap().to_owned();
         let device_url = self.ayla_device_info().device_url().to_owned();
-        let mut fail_count = 0;
-        let mut result: Result<String, Box<dyn Error + Send + Sync>> = Err("".into());
-        while fail_count < AYLA_CONNECTION_CHECK_MAX_FAILS && result.is_err() {
+        let connect_timeout = self.config().internet_connect_timeout();
+        let result = self.retry_step(self.config().ayla_device_check().clone(), self.deadline(), || {
             debug!("checking device with DSN {} on Ayla", &dsn);
             WifiPairing::log(format!("checking device with DSN {} on Ayla", &dsn));
-            result = get_device(
+            get_device(
                 device_url.to_string(),
                 dsn.to_string(),
                 access_token.to_string(),
+                connect_timeout,
             )
-            .await;
-            if result.is_err() {
-                fail_count += 1;
-            }
-            if result.is_err() && fail_count < AYLA_CONNECTION_CHECK_MAX_FAILS {
-                sleep(Duration::from_secs(AYLA_CONNECTION_CHECK_PAUSE_SECS)).await
-            }
-        }
+        })
+        .await;
         match result {
             Ok(dsn) => {
                 debug!("Setting reporting periods");
@@ -270,7 +274,7 @@ impl WifiPairing {
                 _ = CloudCore::shared().set_device_time_zone(dsn.clone()).await;
                 self.handle_connection_success(dsn);
             },
-            Err(err) => self.handle_error(err.to_string()),
+            Err(err) => self.handle_error(err),
         }
     }
 
@@ -278,24 +282,69 @@ impl WifiPairing {
         self.set_state(WifiPairingState::Connected);
         debug!("🎉🎉🎉 DSN {} is connected to the internet!", &dsn);
         WifiPairing::log(format!("🎉🎉🎉 DSN {} is connected to the internet!", &dsn));
+        self.finish_diagnostics(None);
+        // The app may call `done_pairing` from the callback, so nothing on self is touched after it
+        self.abort_runtime_task();
         if let Some(success_callback) = self.result_callback() {
             success_callback(Ok(dsn))
         }
-        self.abort_runtime_task();
     }
 
-    pub fn handle_error(&mut self, error: String) {
+    pub fn handle_error(&mut self, err: CloudCoreError) {
+        let failure = PairingFailure::from_error(self.state(), &err);
+        let error = err.to_string();
         // TODO: For now do not try to log the device info. It is causing crashes at times
-        WifiPairing::log(format!("Device failed with error: {}", &error));
-        error!("Device {:#?} failed with error: {}", self.ayla_device_info(), &error);
-        self.error_tracker().set_error(error.clone());
+        WifiPairing::log_at(PairingLogLevel::Error, format!("Device failed with {:?} error: {}", &failure, &error));
+        error!("Device {:#?} failed with {:?} error: {}", self.ayla_device_info(), &failure, &error);
+        self.finish_diagnostics(Some((failure.clone(), error.clone())));
+        self.abort_runtime_task();
         if let Some(err_callback) = self.result_callback() {
-            err_callback(Err(error.into()))
+            err_callback(Err(CloudCoreError::PairingFailed {
+                failure,
+                state: self.state().clone(),
+                message: error,
+            }))
+        }
+    }
+
+    /// Run `attempt` until it succeeds, the step's attempts are used up or `deadline` passes.
+    /// Every attempt is counted against the current state in the pairing diagnostics.
+    async fn retry_step<T, F, Fut>(&mut self, step: PairingStepConfig, deadline: Option<Instant>, mut attempt: F) -> Result<T, CloudCoreError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, CloudCoreError>>,
+    {
+        let mut fail_count = 0;
+        loop {
+            let result = match deadline {
+                Some(deadline) => timeout_at(deadline, attempt()).await.unwrap_or_else(|_| Err(deadline_error())),
+                None => attempt().await,
+            };
+            self.record_attempt(result.as_ref().err());
+            let err = match result {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            fail_count += 1;
+            if fail_count >= step.max_attempts() {
+                return Err(err);
+            }
+            let pause = Instant::now() + step.pause();
+            match deadline {
+                Some(deadline) if deadline <= pause => {
+                    sleep_until(deadline).await;
+                    return Err(deadline_error());
+                }
+                _ => sleep_until(pause).await,
+            }
         }
-        self.abort_runtime_task();
     }
 }
 
+fn deadline_error() -> CloudCoreError {
+    CloudCoreError::Timeout("Pairing did not finish before its deadline".to_string())
+}
+
 fn random_ayla_token() -> String {
     let base: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
     let length: usize = 8;
@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use crate::secret::Secret;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct WifiNetwork {
@@ -9,7 +10,7 @@ pub struct WifiNetwork {
     signal: Option<i32>,
     ssid: Option<String>,
     r#type: Option<String>,
-    password: Option<String>,
+    password: Option<Secret<String>>,
 }
 
 impl WifiNetwork {
@@ -32,7 +33,7 @@ impl WifiNetwork {
             signal,
             ssid,
             r#type,
-            password
+            password: password.map(Secret::new)
         }
     }
 
@@ -59,11 +60,11 @@ impl WifiNetwork {
         self.r#type.as_ref()
     }
     pub fn password(&self) -> Option<&String> {
-        self.password.as_ref()
+        self.password.as_ref().map(Secret::expose)
     }
 
     /// Setters
     pub fn set_password(&mut self, password: String) {
-        self.password = Some(password);
+        self.password = Some(Secret::new(password));
     }
 }
\ No newline at end of file
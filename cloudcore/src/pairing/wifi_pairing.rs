@@ -1,13 +1,21 @@
 #[cfg(feature = "library")]
 use crate::pairing::ayla_device::AylaDeviceInfo;
 #[cfg(feature = "library")]
+use crate::pairing::network_requests::stop_device_access_point;
+#[cfg(feature = "library")]
+use crate::pairing::pairing_config::PairingConfig;
+#[cfg(feature = "library")]
+use crate::pairing::pairing_diagnostics::{PairingDiagnostics, PairingFailure};
+#[cfg(feature = "library")]
+use crate::pairing::pairing_progress::PairingProgress;
+#[cfg(feature = "library")]
 use crate::pairing::wifi_network::WifiNetwork;
 #[cfg(feature = "library")]
 use crate::pairing::wifi_state::WifiPairingState;
 #[cfg(feature = "library")]
 use mantle_utilities::RUNTIME;
 #[cfg(feature = "library")]
-use std::error::Error;
+use crate::CloudCoreError;
 #[cfg(feature = "library")]
 use log::debug;
 #[cfg(feature = "library")]
@@ -17,39 +25,23 @@ use tokio::task::JoinHandle;
 #[cfg(feature = "library")]
 use crate::CloudCore;
 #[cfg(feature = "library")]
-use chrono::offset::Utc;
-
+use crate::pairing::pairing_log::PairingLogLevel;
 #[cfg(feature = "library")]
-pub struct WifiPairingFailureTracker {
-    last_error: Option<String>,
-    error_count: u32
-}
-
-#[cfg(feature = "library")]
-impl WifiPairingFailureTracker {
-    fn new() -> Self {
-        Self {
-            last_error: None,
-            error_count: 0
-        }
-    }
-    pub fn set_error(&mut self, error: String) {
-        self.error_count += 1;
-        self.last_error = Some(error);
-    }
-}
+use tokio::time::Instant;
 
 #[cfg(feature = "library")]
 pub struct WifiPairing {
     state_callback: Option<Box<dyn Fn(WifiPairingState) + Sync + Send + 'static>>,
     wifi_networks_callback: Option<Box<dyn Fn(Vec<WifiNetwork>) + Sync + Send + 'static>>,
     //stop_wifi_ap_scan: Option<fn(fn(stopped: bool))>,
-    result_callback: Option<Box<dyn Fn(Result<String, Box<dyn Error>>) + Sync + Send + 'static>>,
+    result_callback: Option<Box<dyn Fn(Result<String, CloudCoreError>) + Sync + Send + 'static>>,
     state: WifiPairingState,
     ayla_device_info: AylaDeviceInfo,
     access_token: Option<String>,
     join_handle: Option<JoinHandle<()>>,
-    error_tracker: WifiPairingFailureTracker
+    diagnostics: PairingDiagnostics,
+    config: PairingConfig,
+    deadline: Option<Instant>,
 }
 
 #[cfg(not(feature = "library"))]
@@ -66,15 +58,28 @@ impl WifiPairing {
             ayla_device_info: AylaDeviceInfo::new(device_url),
             access_token: None,
             join_handle: None,
-            error_tracker: WifiPairingFailureTracker::new()
+            diagnostics: PairingDiagnostics::default(),
+            config: PairingConfig::default(),
+            deadline: None,
         }
     }
 
+    pub fn with_config(mut self, config: PairingConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The pairing log session this pairing's lines are written under.
+    pub fn with_log_session(mut self, session_id: String) -> Self {
+        self.diagnostics.set_session_id(Some(session_id));
+        self
+    }
+
     pub fn configure(
         &mut self,
         state_callback: Box<dyn Fn(WifiPairingState) + Sync + Send + 'static>,
         wifi_networks_callback: Box<dyn Fn(Vec<WifiNetwork>) + Sync + Send + 'static>,
-        result_callback: Box<dyn Fn(Result<String, Box<dyn Error>>) + Sync + Send + 'static>,
+        result_callback: Box<dyn Fn(Result<String, CloudCoreError>) + Sync + Send + 'static>,
         access_token: Option<String>,
     ) {
         self.state_callback = Some(state_callback);
@@ -84,8 +89,10 @@ impl WifiPairing {
     }
 
     pub fn set_state(&mut self, state: WifiPairingState) {
-        if self.state != WifiPairingState::Done {
+        if !matches!(self.state, WifiPairingState::Done | WifiPairingState::Cancelled) {
             self.state = state;
+            self.diagnostics.enter_state(&self.state);
+            self.save_progress();
         }
         if let Some(callback) = &self.state_callback {
             callback(self.state.to_owned())
@@ -94,16 +101,19 @@ impl WifiPairing {
         }
     }
 
+    /// The task it replaces is aborted, so it can't outlive the manager when `done_pairing` is called.
     pub fn set_join_handle(&mut self, join_handle: JoinHandle<()>) {
-        self.join_handle = Some(join_handle);
+        if let Some(previous) = self.join_handle.replace(join_handle) {
+            previous.abort();
+        }
     }
 
     pub fn start(&'static mut self, ip_address: String) {
         WifiPairing::log(format!("Have gateway IP: {}", &ip_address));
         if self.access_token.is_none() {
-            self.handle_error("No user session!".to_string());
+            self.handle_error(CloudCoreError::SessionMissing);
             WifiPairing::log("No user session!".to_string());
-        } else if self.state.clone() as u8 >= WifiPairingState::SendingWiFiCredentialsToDevice as u8 {
+        } else if self.state.credentials_sent() {
             WifiPairing::log("Pairing already in process".to_string());
             error!("Pairing already in process");
         } else {
@@ -116,6 +126,7 @@ impl WifiPairing {
     /// This method is used to allow the process to continue where it left off.
     /// For example, if an error occurred.
     pub fn continue_pairing(&'static mut self) {
+        self.restart_deadline();
         let ptr_manager = self as *mut WifiPairing;
         let handle = RUNTIME.spawn(async move {
             match self.state() {
@@ -126,10 +137,16 @@ impl WifiPairing {
                 }
                 // If a user is getting networks or sending credentials, start over so there is no stale or bad data persisted
                 WifiPairingState::GettingWifiNetworks
-                | WifiPairingState::SendingWiFiCredentialsToDevice => {
+                | WifiPairingState::SendingWiFiCredentialsToDevice
+                | WifiPairingState::WrongWifiPassword
+                | WifiPairingState::WifiNetworkNotFound => {
                     self.set_state(WifiPairingState::GettingWifiNetworks);
                     self.get_wifi_networks().await
                 }
+                WifiPairingState::PollingDeviceWifiStatus => {
+                    let ssid = self.ayla_device_info().ssid().cloned().unwrap_or_default();
+                    self.poll_device_wifi_status(ssid).await
+                }
                 WifiPairingState::EndingAccessPointsScanning => self.stop_device_access_point().await,
                 WifiPairingState::PollingUserInternetConnection => self.wait_for_user_wifi().await,
                 WifiPairingState::HandshakingWithAyla => self.connect_device_to_ayla().await,
@@ -137,16 +154,62 @@ impl WifiPairing {
                 WifiPairingState::Connected => {
                     self.handle_connection_success(self.ayla_device_info.dsn().unwrap().to_string())
                 },
-                WifiPairingState::Done => {}
+                WifiPairingState::Done | WifiPairingState::Cancelled => {}
             }
         });
         let manager = unsafe { &mut *ptr_manager };
         manager.set_join_handle(handle);
     }
 
+    /// Pick up a pairing whose progress was saved after the credentials were sent to the device,
+    /// for example after the app was restarted mid-pairing. Ayla is asked to register the device
+    /// with the saved setup token, or only checked for it if that already happened.
+    pub fn resume(&'static mut self) -> Result<(), CloudCoreError> {
+        let progress = CloudCore::shared()
+            .pairing_progress()
+            .ok_or_else(|| CloudCoreError::Pairing("No pairing to resume".to_string()))?;
+        let state = progress
+            .resume_state()
+            .ok_or_else(|| CloudCoreError::Pairing(format!("Pairing can not resume from {:?}", progress.state())))?;
+        if self.access_token.is_none() {
+            return Err(CloudCoreError::Pairing("No user session!".to_string()));
+        }
+        WifiPairing::log(format!("Resuming pairing of {:?} at {:?}", progress.dsn(), &state));
+        self.ayla_device_info.set_dsn(progress.dsn().cloned());
+        self.ayla_device_info.set_setup_token(progress.setup_token().cloned());
+        self.ayla_device_info.set_ssid(progress.ssid().cloned());
+        self.state = state;
+        self.continue_pairing();
+        Ok(())
+    }
+
+    /// Stop pairing and report `Cancelled`. The device is asked to stop its access point when
+    /// pairing hadn't got that far, and the saved progress is dropped.
+    pub fn cancel(&mut self) {
+        if matches!(self.state, WifiPairingState::Done | WifiPairingState::Cancelled) {
+            return;
+        }
+        self.abort_runtime_task();
+        let on_device_ap = self.state.on_device_access_point();
+        if let (true, Some(ip_address)) = (on_device_ap, self.ayla_device_info.ip_address().cloned()) {
+            let connect_timeout = self.config.lan_connect_timeout();
+            RUNTIME.spawn(async move {
+                // Best effort like in the flow itself
+                let _ = stop_device_access_point(ip_address, connect_timeout).await;
+            });
+        }
+        WifiPairing::log_at(PairingLogLevel::Warn, format!("Pairing cancelled in state {:?}", self.state()));
+        // Giving up after the device could not join the network is how that pairing failed
+        let failure = PairingFailure::from_state(&self.state)
+            .map(|failure| (failure, format!("Pairing cancelled in state {:?}", self.state)));
+        self.finish_diagnostics(failure);
+        self.set_state(WifiPairingState::Cancelled);
+    }
+
     pub fn done_pairing(mut self) {
         self.state = WifiPairingState::Done;
         self.abort_runtime_task();
+        CloudCore::shared().clear_pairing_progress();
     }
 
     pub fn abort_runtime_task(&self) {
@@ -156,10 +219,51 @@ impl WifiPairing {
         }
     }
 
+    /// Finished, cancelled and done pairings have nothing to resume.
+    fn save_progress(&self) {
+        let cloudcore = CloudCore::shared();
+        match self.state {
+            WifiPairingState::Connected | WifiPairingState::Done | WifiPairingState::Cancelled => {
+                cloudcore.clear_pairing_progress()
+            }
+            _ => cloudcore.save_pairing_progress(&PairingProgress::new(
+                self.ayla_device_info.dsn().cloned(),
+                self.ayla_device_info.setup_token().cloned(),
+                self.ayla_device_info.ssid().cloned(),
+                self.state.clone(),
+            )),
+        }
+    }
+
+    pub(crate) fn record_attempt(&mut self, error: Option<&CloudCoreError>) {
+        self.diagnostics.record_attempt(error);
+    }
+
+    /// Close the diagnostics, with the failure and its error if pairing failed, and write them
+    /// next to the pairing log in place of the previous pairing's.
+    pub(crate) fn finish_diagnostics(&mut self, failure: Option<(PairingFailure, String)>) {
+        match failure {
+            Some((failure, error)) => self.diagnostics.fail(failure, self.state.clone(), error),
+            None => self.diagnostics.finish(),
+        }
+        self.diagnostics.set_dsn(self.ayla_device_info.dsn().cloned());
+        if let Some(err) = CloudCore::shared().write_pairing_diagnostics(&self.diagnostics).err() {
+            error!("Failed to save pairing diagnostics: {}", err);
+        }
+    }
+
+    /// Give the unattended steps from now on the whole `PairingConfig` deadline.
+    pub fn restart_deadline(&mut self) {
+        self.deadline = self.config.deadline().map(|deadline| Instant::now() + deadline);
+    }
+
     pub fn log(contents: String) {
+        WifiPairing::log_at(PairingLogLevel::Info, contents);
+    }
+
+    pub fn log_at(level: PairingLogLevel, contents: String) {
         let cc = CloudCore::shared();
-        let contents = format!("{}: {}", Utc::now(), contents);
-        let _ = cc.write_to_pairing_log(contents);
+        let _ = cc.write_pairing_log_entry(level, contents);
     }
 
     /// Getters
@@ -177,7 +281,7 @@ impl WifiPairing {
     }*/
     pub fn result_callback(
         &self,
-    ) -> Option<&Box<dyn Fn(Result<String, Box<dyn Error>>) + Sync + Send + 'static>> {
+    ) -> Option<&Box<dyn Fn(Result<String, CloudCoreError>) + Sync + Send + 'static>> {
         self.result_callback.as_ref()
     }
     pub fn state(&self) -> &WifiPairingState {
@@ -192,7 +296,13 @@ impl WifiPairing {
     pub fn join_handle(&self) -> Option<&JoinHandle<()>> {
         self.join_handle.as_ref()
     }
-    pub fn error_tracker(&mut self) -> &mut WifiPairingFailureTracker {
-        &mut self.error_tracker
+    pub fn diagnostics(&self) -> &PairingDiagnostics {
+        &self.diagnostics
+    }
+    pub fn config(&self) -> &PairingConfig {
+        &self.config
+    }
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
     }
 }
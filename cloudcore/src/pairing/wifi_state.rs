@@ -1,16 +1,48 @@
+use serde::{Deserialize, Serialize};
 
+/// Passed by value through the FFI, so the discriminants are part of the API:
+/// new states are appended and existing ones keep their values.
 #[repr(C)]
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub enum WifiPairingState {
-    Idle,
-    FetchingDSN,
-    DeviceScanningWifi,
-    GettingWifiNetworks,
-    SendingWiFiCredentialsToDevice,
-    EndingAccessPointsScanning,
-    PollingUserInternetConnection,
-    HandshakingWithAyla,
-    PollingDeviceOnAyla,
-    Connected,
-    Done,
+    Idle = 0,
+    FetchingDSN = 1,
+    DeviceScanningWifi = 2,
+    GettingWifiNetworks = 3,
+    SendingWiFiCredentialsToDevice = 4,
+    EndingAccessPointsScanning = 5,
+    PollingUserInternetConnection = 6,
+    HandshakingWithAyla = 7,
+    PollingDeviceOnAyla = 8,
+    Connected = 9,
+    Done = 10,
+    Cancelled = 11,
+    PollingDeviceWifiStatus = 12,
+    WrongWifiPassword = 13,
+    WifiNetworkNotFound = 14,
+}
+
+impl WifiPairingState {
+    /// Whether pairing got as far as sending the Wi-Fi credentials to the device.
+    pub fn credentials_sent(&self) -> bool {
+        !matches!(
+            self,
+            WifiPairingState::Idle
+                | WifiPairingState::FetchingDSN
+                | WifiPairingState::DeviceScanningWifi
+                | WifiPairingState::GettingWifiNetworks
+        )
+    }
+
+    /// Whether the device may still be running its access point.
+    pub fn on_device_access_point(&self) -> bool {
+        !self.credentials_sent()
+            || matches!(
+                self,
+                WifiPairingState::SendingWiFiCredentialsToDevice
+                    | WifiPairingState::PollingDeviceWifiStatus
+                    | WifiPairingState::WrongWifiPassword
+                    | WifiPairingState::WifiNetworkNotFound
+            )
+    }
 }
\ No newline at end of file
@@ -1,8 +1,20 @@
 mod poll;
 mod manager;
+mod change;
+mod event;
+mod subscription;
 
 #[cfg(feature = "signatures")]
 pub use poll::PollConfig;
+#[cfg(feature = "signatures")]
+pub use event::{PollCallback, PollEvent};
+#[cfg(feature = "signatures")]
+pub use subscription::PropertyEvent;
+pub use change::IoTPropertyChange;
+#[cfg(feature = "library")]
+pub use poll::PollStats;
+#[cfg(feature = "library")]
+pub use subscription::Subscription;
 #[cfg(feature = "library")]
 use crate::CloudCore;
 #[cfg(feature = "library")]
@@ -11,22 +23,45 @@ use manager::POLL_MANAGER;
 #[cfg(feature = "library")]
 impl CloudCore {
     pub fn start_polling_manager(&self) {
-        POLL_MANAGER::get_static_ref().start_polling();
+        POLL_MANAGER.start_polling();
     }
 
-    pub fn add_poll(&'static self, config: PollConfig) -> u32 {
-        POLL_MANAGER::get_static_ref().add_poll(config, self)
+    pub fn add_poll(&self, config: PollConfig) -> u32 {
+        POLL_MANAGER.add_poll(config)
     }
 
     pub fn update_poll(&self, poll_id: u32, new_config: PollConfig) {
-        POLL_MANAGER::get_static_ref().update_poll(poll_id, new_config);
+        POLL_MANAGER.update_poll(poll_id, new_config);
     }
 
     pub fn remove_poll(&self, poll_id: u32) {
-        POLL_MANAGER::get_static_ref().remove_poll(poll_id);
+        POLL_MANAGER.remove_poll(poll_id);
     }
 
     pub fn stop_polling_manager(&self) {
-        POLL_MANAGER::get_static_ref().stop_polling();
+        POLL_MANAGER.stop_polling();
     }
+
+    /// When the poll last succeeded, how many times in a row it has failed since
+    /// and when it runs next, or `None` for an unknown poll.
+    pub fn poll_stats(&self, poll_id: u32) -> Option<PollStats> {
+        POLL_MANAGER.poll_stats(poll_id)
+    }
+
+    /// Subscribe to `property_names` of `dsn`, or every property of it when empty.
+    /// Events only come in while the polling manager runs.
+    pub fn subscribe(&self, dsn: String, property_names: Vec<String>) -> Subscription {
+        Subscription::new(dsn, property_names)
+    }
+
+    /// End a subscription that is not at hand to drop, its stream ends.
+    pub fn unsubscribe(&self, subscription_id: u32) {
+        POLL_MANAGER.remove_poll(subscription_id);
+    }
+}
+
+/// Let the polls of `dsn` know one of its properties was just written.
+#[cfg(feature = "library")]
+pub(crate) fn poll_soon(dsn: &str) {
+    POLL_MANAGER.poll_soon(dsn);
 }
@@ -0,0 +1,73 @@
+#[cfg(feature = "library")]
+use std::collections::HashMap;
+#[cfg(feature = "library")]
+use crate::properties::property::IoTProperty;
+use crate::properties::value::IoTPropertyValue;
+
+/// How a property moved between two polls of a device.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IoTPropertyChange {
+    /// The property showed up for the first time.
+    Added {
+        name: String,
+        value: Option<IoTPropertyValue>,
+    },
+    Changed {
+        name: String,
+        old_value: Option<IoTPropertyValue>,
+        new_value: Option<IoTPropertyValue>,
+    },
+    /// The property was in the last poll but is missing from this one.
+    Removed {
+        name: String,
+        old_value: Option<IoTPropertyValue>,
+    },
+}
+
+impl IoTPropertyChange {
+    /// Get a reference to the changed property's name.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Added { name, .. } | Self::Changed { name, .. } | Self::Removed { name, .. } => name,
+        }
+    }
+}
+
+/// Last seen value of every polled property, per DSN.
+#[cfg(feature = "library")]
+#[derive(Debug, Default)]
+pub(crate) struct PropertySnapshots {
+    values: HashMap<String, HashMap<String, Option<IoTPropertyValue>>>,
+}
+
+#[cfg(feature = "library")]
+impl PropertySnapshots {
+    /// Record the properties just polled for `dsn` and return what changed since the last poll.
+    pub(crate) fn diff(&mut self, dsn: &str, properties: &[IoTProperty]) -> Vec<IoTPropertyChange> {
+        let mut last_seen = self.values.remove(dsn).unwrap_or_default();
+        let mut seen = HashMap::new();
+        let mut changes = vec![];
+        for property in properties {
+            let name = property.name().to_string();
+            let value = property.value().cloned();
+            match last_seen.remove(&name) {
+                None => changes.push(IoTPropertyChange::Added {
+                    name: name.to_string(),
+                    value: value.clone(),
+                }),
+                Some(old_value) if old_value != value => changes.push(IoTPropertyChange::Changed {
+                    name: name.to_string(),
+                    old_value,
+                    new_value: value.clone(),
+                }),
+                Some(_) => {}
+            }
+            seen.insert(name, value);
+        }
+        let mut removed: Vec<(String, Option<IoTPropertyValue>)> = last_seen.into_iter().collect();
+        removed.sort_by(|(a, _), (b, _)| a.cmp(b));
+        changes.extend(removed.into_iter().map(|(name, old_value)| IoTPropertyChange::Removed { name, old_value }));
+        self.values.insert(dsn.to_string(), seen);
+        changes
+    }
+}
@@ -0,0 +1,45 @@
+#[cfg(feature = "signatures")]
+use crate::properties::property::IoTProperty;
+#[cfg(feature = "signatures")]
+use crate::polling::change::IoTPropertyChange;
+#[cfg(feature = "signatures")]
+use crate::CloudCoreError;
+
+/// Called with everything that happens to a poll.
+#[cfg(feature = "signatures")]
+pub type PollCallback = Box<dyn Fn(PollEvent) + Send + Sync>;
+
+/// What a poll hands to its `PollCallback`.
+#[cfg(feature = "signatures")]
+#[derive(Debug, Clone)]
+pub enum PollEvent {
+    /// The polled properties, sent when something changed or snapshots are on.
+    Data {
+        dsn: String,
+        properties: Vec<IoTProperty>,
+    },
+    /// The properties added, changed or removed since the last poll.
+    Changes {
+        dsn: String,
+        changes: Vec<IoTPropertyChange>,
+    },
+    /// Polling the device failed, the poll backs off and tries again.
+    Error {
+        dsn: String,
+        error: CloudCoreError,
+    },
+    /// The poll was removed or the polling manager stopped.
+    Stopped {
+        dsn: String,
+    },
+}
+
+#[cfg(feature = "signatures")]
+impl PollEvent {
+    /// Get a reference to the DSN of the polled device.
+    pub fn dsn(&self) -> &str {
+        match self {
+            Self::Data { dsn, .. } | Self::Changes { dsn, .. } | Self::Error { dsn, .. } | Self::Stopped { dsn } => dsn,
+        }
+    }
+}
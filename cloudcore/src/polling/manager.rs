@@ -1,106 +1,323 @@
 #[cfg(feature = "library")]
 use std::collections::HashMap;
 #[cfg(feature = "library")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "library")]
 use lazy_static::lazy_static;
 #[cfg(feature = "library")]
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "library")]
+use futures_util::future::join_all;
+#[cfg(feature = "library")]
+use log::{debug, error};
+#[cfg(feature = "library")]
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+#[cfg(feature = "library")]
+use tokio::time::sleep;
+#[cfg(feature = "library")]
+use tokio_util::sync::CancellationToken;
 #[cfg(feature = "library")]
 use crate::CloudCore;
 #[cfg(feature = "library")]
-use crate::polling::poll::{Poll, PollConfig};
+use crate::polling::poll::{Poll, PollConfig, PollStats};
+#[cfg(feature = "library")]
+use mantle_utilities::RUNTIME;
+
+/// Polls due within this window of each other share a request.
+#[cfg(feature = "library")]
+const COALESCE_WINDOW_MILLIS: u64 = 250;
+/// How often a device gets polled for a while after one of its properties was written.
+#[cfg(feature = "library")]
+const FAST_POLL_INTERVAL_MILLIS: u64 = 1000;
+#[cfg(feature = "library")]
+const FAST_POLL_WINDOW_SECS: u64 = 10;
+#[cfg(feature = "library")]
+const OFFLINE_BACKOFF_FACTOR: u32 = 4;
+/// Consecutive failures double the interval up to 2^5 times.
 #[cfg(feature = "library")]
-use mantle_utilities::to_static_ref;
+const MAX_BACKOFF_EXPONENT: u32 = 5;
+/// Backing off never stretches the interval past this, unless a poll asked for longer.
+#[cfg(feature = "library")]
+const MAX_POLL_INTERVAL_MILLIS: u64 = 60_000;
+#[cfg(feature = "library")]
+const CONNECTION_CHECK_INTERVAL_SECS: u64 = 60;
 
 #[cfg(feature = "library")]
 lazy_static! {
-    pub static ref POLL_MANAGER: Mutex<usize> = {
-        let manager = PollManager::new();
-        let boxed = Box::into_raw(Box::new(manager));
-        let addr = boxed as usize;
-        Mutex::new(addr)
-    };
+    pub(crate) static ref POLL_MANAGER: Arc<PollManager> = Arc::new(PollManager::new());
 }
 
+/// What the manager knows about a polled device, shared by all of its polls.
 #[cfg(feature = "library")]
-impl POLL_MANAGER {
-    pub fn get_static_ref() -> &'static mut PollManager {
-        let addr = *POLL_MANAGER.lock().unwrap();
-        to_static_ref::<PollManager>(addr)
+#[derive(Debug, Default)]
+struct DeviceState {
+    offline: bool,
+    failure_count: u32,
+    fast_until: Option<Instant>,
+}
+
+#[cfg(feature = "library")]
+impl DeviceState {
+    /// Time until the next poll of a poll asking for `sleep_time`, backed off while the device
+    /// is offline or failing and shortened for a while after a write.
+    fn interval(&self, sleep_time: u64) -> Duration {
+        let requested = Duration::from_millis(sleep_time);
+        let mut interval = requested;
+        if self.offline {
+            interval = interval.saturating_mul(OFFLINE_BACKOFF_FACTOR);
+        }
+        interval = interval.saturating_mul(2u32.pow(self.failure_count.min(MAX_BACKOFF_EXPONENT)));
+        interval = interval.min(requested.max(Duration::from_millis(MAX_POLL_INTERVAL_MILLIS)));
+        if self.failure_count == 0 && self.fast_until.is_some_and(|until| until > Instant::now()) {
+            interval = interval.min(Duration::from_millis(FAST_POLL_INTERVAL_MILLIS));
+        }
+        interval
     }
 }
 
+#[cfg(feature = "library")]
+#[derive(Default)]
+struct PollState {
+    polls: HashMap<u32, Poll>,
+    devices: HashMap<String, DeviceState>,
+    connections_checked: Option<Instant>,
+    next_poll_id: u32,
+}
+
+/// Polls that are fetched together with one request for their device.
+#[cfg(feature = "library")]
+struct PollBatch {
+    dsn: String,
+    /// Empty when one of the polls wants every property
+    property_names: Vec<String>,
+    poll_ids: Vec<u32>,
+}
+
+/// The running scheduler task, woken up through `wake` whenever polls change.
+#[cfg(feature = "library")]
+struct Scheduler {
+    cancel: CancellationToken,
+    wake: UnboundedSender<()>,
+}
+
+/// Owns every poll and runs them from a single scheduler task, so it can be used from any thread.
 #[cfg(feature = "library")]
 pub struct PollManager {
-    poll_map: HashMap<u32, usize>,
-    polling: bool,
-    next_poll_id: u32
+    state: Mutex<PollState>,
+    scheduler: Mutex<Option<Scheduler>>,
 }
 
 #[cfg(feature = "library")]
 impl PollManager {
     fn new() -> Self {
         Self {
-            poll_map: HashMap::new(),
-            polling: false,
-            next_poll_id: 0
+            state: Mutex::new(PollState::default()),
+            scheduler: Mutex::new(None),
+        }
+    }
+
+    pub fn start_polling(self: &Arc<Self>) {
+        let mut scheduler = self.scheduler.lock().unwrap();
+        if scheduler.is_none() {
+            let cancel = CancellationToken::new();
+            let (wake, woken) = unbounded_channel();
+            RUNTIME.spawn(self.clone().run(cancel.clone(), woken));
+            *scheduler = Some(Scheduler { cancel, wake });
         }
     }
 
-    fn next_poll_id(&mut self) -> u32 {
-        self.next_poll_id += 1;
-        self.next_poll_id
+    pub fn stop_polling(&self) {
+        let scheduler = self.scheduler.lock().unwrap().take();
+        if let Some(scheduler) = scheduler {
+            scheduler.cancel.cancel();
+            self.state.lock().unwrap().polls.values().for_each(Poll::stopped);
+        }
+    }
+
+    fn wake(&self) {
+        if let Some(scheduler) = self.scheduler.lock().unwrap().as_ref() {
+            let _ = scheduler.wake.send(());
+        }
     }
 
-    pub fn start_polling(&'static mut self) {
-        if !self.polling {
-            self.polling = true;
-            for (_, address) in &self.poll_map {
-                PollManager::start_poll(*address);
+    pub fn add_poll(&self, config: PollConfig) -> u32 {
+        let poll_id = {
+            let mut state = self.state.lock().unwrap();
+            state.next_poll_id += 1;
+            let poll_id = state.next_poll_id;
+            let poll = Poll::new(poll_id, config);
+            if !state.devices.contains_key(poll.dsn()) {
+                // Find out whether the new device is online before backing off
+                state.devices.insert(poll.dsn().to_string(), DeviceState::default());
+                state.connections_checked = None;
             }
+            state.polls.insert(poll_id, poll);
+            poll_id
+        };
+        self.wake();
+        poll_id
+    }
+
+    pub fn update_poll(&self, poll_id: u32, new_config: PollConfig) {
+        {
+            let mut state = self.state.lock().unwrap();
+            let Some(poll) = state.polls.get_mut(&poll_id) else {
+                return;
+            };
+            poll.update(new_config);
+            let dsn = poll.dsn().to_string();
+            state.devices.entry(dsn).or_default();
         }
+        self.wake();
     }
 
-    pub fn stop_polling(&mut self) {
-        self.polling = false;
-        for (_, address) in &mut self.poll_map {
-            let poll = to_static_ref::<Poll>(*address);
-            poll.set_on(false)
+    pub fn remove_poll(&self, poll_id: u32) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(poll) = state.polls.remove(&poll_id) {
+            if !state.polls.values().any(|other| other.dsn() == poll.dsn()) {
+                state.devices.remove(poll.dsn());
+            }
+            poll.stopped();
         }
     }
 
-    pub fn add_poll(&mut self, config: PollConfig, cloudcore: &'static CloudCore) -> u32 {
-        let poll_id = self.next_poll_id();
-        let poll = Box::new(Poll::new(poll_id, config, cloudcore));
-        let raw = Box::into_raw(poll);
-        let addr = raw as usize;
-        self.poll_map.insert(poll_id, addr);
-        if self.polling {
-            PollManager::start_poll(addr);
+    pub fn poll_stats(&self, poll_id: u32) -> Option<PollStats> {
+        let state = self.state.lock().unwrap();
+        state.polls.get(&poll_id).map(|poll| poll.stats().clone())
+    }
+
+    /// Poll `dsn` again shortly, and more often for a while, to pick up the effect of a write.
+    pub(crate) fn poll_soon(&self, dsn: &str) {
+        {
+            let mut state = self.state.lock().unwrap();
+            let PollState { polls, devices, .. } = &mut *state;
+            let Some(device) = devices.get_mut(dsn) else {
+                return;
+            };
+            device.fast_until = Some(Instant::now() + Duration::from_secs(FAST_POLL_WINDOW_SECS));
+            let fast = Duration::from_millis(FAST_POLL_INTERVAL_MILLIS);
+            polls
+                .values_mut()
+                .filter(|poll| poll.dsn() == dsn && poll.next_poll() > Instant::now() + fast)
+                .for_each(|poll| poll.schedule(fast));
         }
-        poll_id
+        self.wake();
     }
 
-    fn start_poll(address: usize) {
-        let poll_for_handle = to_static_ref::<Poll>(address);
-        poll_for_handle.set_on(true);
-        let handle = poll_for_handle.run();
-        let poll = to_static_ref::<Poll>(address);
-        poll.set_handle(handle);
+    async fn run(self: Arc<Self>, cancel: CancellationToken, mut woken: UnboundedReceiver<()>) {
+        loop {
+            let round = async {
+                self.check_connections().await;
+                let batches = self.due_batches();
+                join_all(batches.into_iter().map(|batch| self.poll_device(batch, &cancel))).await;
+            };
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => break,
+                _ = round => {}
+            }
+            let wait = self.time_to_next_poll();
+            tokio::select! {
+                biased;
+                _ = cancel.cancelled() => break,
+                _ = woken.recv() => {}
+                _ = sleep(wait) => {}
+            }
+        }
+        debug!("Poll scheduler stopped");
     }
 
-    pub fn update_poll(&mut self, poll_id: u32, new_config: PollConfig) {
-        if let Some(address) = self.poll_map.get(&poll_id) {
-            let poll = to_static_ref::<Poll>(*address);
-            poll.update(new_config);
+    fn due_batches(&self) -> Vec<PollBatch> {
+        let state = self.state.lock().unwrap();
+        let mut batches: HashMap<&str, PollBatch> = HashMap::new();
+        for (poll_id, poll) in &state.polls {
+            if !poll.is_due(Duration::from_millis(COALESCE_WINDOW_MILLIS)) {
+                continue;
+            }
+            let batch = batches.entry(poll.dsn()).or_insert_with(|| PollBatch {
+                dsn: poll.dsn().to_string(),
+                property_names: poll.property_names().to_vec(),
+                poll_ids: vec![],
+            });
+            if poll.property_names().is_empty() {
+                batch.property_names.clear();
+            } else if !batch.poll_ids.is_empty() && !batch.property_names.is_empty() {
+                for name in poll.property_names() {
+                    if !batch.property_names.contains(name) {
+                        batch.property_names.push(name.to_string());
+                    }
+                }
+            }
+            batch.poll_ids.push(*poll_id);
         }
+        batches.into_values().collect()
     }
 
-    pub fn remove_poll(&mut self, poll_id: u32) {
-        if let Some(address) = self.poll_map.get(&poll_id) {
-            let ptr = *address as *mut Poll;
-            let mut poll = unsafe { *Box::from_raw(ptr) };
-            poll.set_on(false);
-            self.poll_map.remove(&poll_id);
+    async fn poll_device(&self, batch: PollBatch, cancel: &CancellationToken) {
+        let cloudcore = CloudCore::shared();
+        let result = cloudcore.get_properties(batch.dsn.to_string(), batch.property_names, "".to_string()).await.0;
+        let mut state = self.state.lock().unwrap();
+        // Polls were already told polling stopped
+        if cancel.is_cancelled() {
+            return;
+        }
+        let PollState { polls, devices, .. } = &mut *state;
+        let device = devices.entry(batch.dsn.to_string()).or_default();
+        match &result {
+            Ok(_) => device.failure_count = 0,
+            Err(err) => {
+                error!("Polling {} failed: {}", batch.dsn, err);
+                device.failure_count += 1;
+            }
+        }
+        for poll_id in batch.poll_ids {
+            // The poll may have been removed or moved to another device meanwhile
+            let Some(poll) = polls.get_mut(&poll_id).filter(|poll| poll.dsn() == batch.dsn) else {
+                continue;
+            };
+            match &result {
+                Ok(props) => poll.report(props),
+                Err(err) => poll.record_failure(err.clone()),
+            }
+            poll.schedule(device.interval(poll.sleep_time()));
         }
     }
-}
\ No newline at end of file
+
+    /// Refresh which polled devices are offline, at most every `CONNECTION_CHECK_INTERVAL_SECS`.
+    async fn check_connections(&self) {
+        {
+            let mut state = self.state.lock().unwrap();
+            let checked_recently = state
+                .connections_checked
+                .is_some_and(|checked| checked.elapsed() < Duration::from_secs(CONNECTION_CHECK_INTERVAL_SECS));
+            if checked_recently || !state.polls.values().any(Poll::on) {
+                return;
+            }
+            state.connections_checked = Some(Instant::now());
+        }
+        match CloudCore::shared().fetch_connection_statuses().await {
+            Ok(statuses) => {
+                let mut state = self.state.lock().unwrap();
+                for (dsn, device) in state.devices.iter_mut() {
+                    device.offline = statuses.get(dsn).is_some_and(|status| status == "Offline");
+                    if device.offline {
+                        debug!("{} is offline, polling it less often", dsn);
+                    }
+                }
+            }
+            Err(err) => debug!("Could not check device connections: {}", err),
+        }
+    }
+
+    fn time_to_next_poll(&self) -> Duration {
+        let state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state
+            .polls
+            .values()
+            .filter(|poll| poll.on())
+            .map(|poll| poll.next_poll().saturating_duration_since(now))
+            .min()
+            .unwrap_or(Duration::from_secs(CONNECTION_CHECK_INTERVAL_SECS))
+    }
+}
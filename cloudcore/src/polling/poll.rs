@@ -1,46 +1,92 @@
 #[cfg(feature = "library")]
-use std::time::Duration;
+use std::sync::Arc;
 #[cfg(feature = "library")]
-use log::{debug, error};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 #[cfg(feature = "library")]
 use mantle_utilities::RUNTIME;
 #[cfg(feature = "library")]
-use tokio::task::JoinHandle;
+use std::time::{Duration, Instant};
 #[cfg(feature = "library")]
-use tokio::time::sleep;
+use chrono::{DateTime, Utc};
 #[cfg(feature = "library")]
-use crate::CloudCore;
-#[cfg(feature = "signatures")]
 use crate::properties::property::IoTProperty;
+#[cfg(feature = "library")]
+use crate::CloudCoreError;
+#[cfg(feature = "library")]
+use crate::polling::change::PropertySnapshots;
+#[cfg(feature = "library")]
+use crate::polling::event::PollEvent;
+#[cfg(feature = "signatures")]
+use crate::polling::event::PollCallback;
+
+/// An event along with the callback it is for.
+#[cfg(feature = "library")]
+struct PollDelivery(Arc<dyn Fn(PollEvent) + Send + Sync>, PollEvent);
+
+/// How a poll has been doing, see `CloudCore::poll_stats`.
+#[cfg(feature = "library")]
+#[derive(Debug, Clone, Default)]
+pub struct PollStats {
+    last_success: Option<DateTime<Utc>>,
+    failure_count: u32,
+    next_poll_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(feature = "library")]
+impl PollStats {
+    /// Get a reference to the poll stats's last success.
+    pub fn last_success(&self) -> Option<&DateTime<Utc>> {
+        self.last_success.as_ref()
+    }
+    /// Get the poll stats's failure count, reset by every successful poll.
+    pub fn failure_count(&self) -> u32 {
+        self.failure_count
+    }
+    /// Get a reference to the poll stats's next poll at.
+    pub fn next_poll_at(&self) -> Option<&DateTime<Utc>> {
+        self.next_poll_at.as_ref()
+    }
+}
 
 #[cfg(feature = "library")]
-#[derive(Debug)]
 pub struct Poll {
     #[allow(dead_code)]
     id: u32,
     property_names: Vec<String>,
     on: bool,
-    running: bool,
-    handle: Option<JoinHandle<()>>,
     sleep_time: u64,
     dsn: String,
-    callback: fn(String, Vec<IoTProperty>),
-    cloudcore: &'static CloudCore
+    callback: Arc<dyn Fn(PollEvent) + Send + Sync>,
+    emit_snapshots: bool,
+    snapshots: PropertySnapshots,
+    stats: PollStats,
+    next_poll: Instant,
+    /// Events are queued here and handed to the callback in order by the poll's own task,
+    /// so the callback never runs while the manager holds its polls.
+    events: UnboundedSender<PollDelivery>,
 }
 
 #[cfg(feature = "library")]
 impl Poll {
-    pub fn new(new_id: u32, config: PollConfig, cloudcore: &'static CloudCore) -> Self {
+    pub fn new(new_id: u32, config: PollConfig) -> Self {
+        let (events, mut deliveries) = unbounded_channel::<PollDelivery>();
+        RUNTIME.spawn(async move {
+            while let Some(PollDelivery(callback, event)) = deliveries.recv().await {
+                callback(event);
+            }
+        });
         let mut poll = Self {
             id: new_id,
             property_names: vec![],
             on: true,
-            running: false,
-            handle: None,
             sleep_time: 5000,
             dsn: "".to_string(),
-            callback: default_callback,
-            cloudcore
+            callback: Arc::new(|_| {}),
+            emit_snapshots: false,
+            snapshots: PropertySnapshots::default(),
+            stats: PollStats::default(),
+            next_poll: Instant::now(),
+            events,
         };
         poll.update(config);
         poll
@@ -62,32 +108,56 @@ impl Poll {
             self.dsn = dsn
         }
         if let Some(callback) = config.callback {
-            self.callback = callback
+            self.callback = Arc::from(callback)
         }
+        if let Some(emit_snapshots) = config.emit_snapshots {
+            self.emit_snapshots = emit_snapshots
+        }
+        // Pick up the new config on the next round
+        self.schedule(Duration::ZERO);
     }
 
-    pub fn run(&'static mut self) -> JoinHandle<()> {
-        RUNTIME.spawn(async move {
-            if self.running {
-                return;
-            }
-            while self.on {
-                self.running = true;
-                debug!("Awaiting properties for {}", self.dsn());
-                match self.cloudcore.get_properties(self.dsn.clone(), self.property_names.clone(), "".to_string()).await.0 {
-                    Ok(props) => (self.callback)(self.dsn.clone(), props),
-                    Err(err) => error!("Error polling for properties: {:#?}", err)
-                }
-                debug!("Finished properties poll for {}. Sleeping for {} secs", self.dsn(), self.sleep_time()/1000);
-                if self.on {
-                    sleep(Duration::from_millis(self.sleep_time())).await;
-                    if !self.on {
-                        self.running = false;
-                    }
-                }
-            }
-            self.running = false;
-        })
+    /// Report the properties just polled: the changes since the last poll,
+    /// and the full list when something changed or snapshots are on.
+    pub(crate) fn report(&mut self, props: &[IoTProperty]) {
+        let props: Vec<IoTProperty> = props
+            .iter()
+            .filter(|prop| self.property_names.is_empty() || self.property_names.iter().any(|name| name == prop.name()))
+            .cloned()
+            .collect();
+        self.stats.last_success = Some(Utc::now());
+        self.stats.failure_count = 0;
+        let changes = self.snapshots.diff(&self.dsn, &props);
+        let changed = !changes.is_empty();
+        if changed {
+            self.send(PollEvent::Changes { dsn: self.dsn.clone(), changes });
+        }
+        if self.emit_snapshots || changed {
+            self.send(PollEvent::Data { dsn: self.dsn.clone(), properties: props });
+        }
+    }
+
+    pub(crate) fn record_failure(&mut self, error: CloudCoreError) {
+        self.stats.failure_count += 1;
+        self.send(PollEvent::Error { dsn: self.dsn.clone(), error });
+    }
+
+    pub(crate) fn stopped(&self) {
+        self.send(PollEvent::Stopped { dsn: self.dsn.clone() });
+    }
+
+    fn send(&self, event: PollEvent) {
+        // Only fails once the runtime is shutting down
+        let _ = self.events.send(PollDelivery(self.callback.clone(), event));
+    }
+
+    pub(crate) fn schedule(&mut self, wait: Duration) {
+        self.next_poll = Instant::now() + wait;
+        self.stats.next_poll_at = chrono::Duration::from_std(wait).ok().map(|wait| Utc::now() + wait);
+    }
+
+    pub(crate) fn is_due(&self, within: Duration) -> bool {
+        self.on && self.next_poll <= Instant::now() + within
     }
 
     pub fn sleep_time(&self) -> u64 {
@@ -96,30 +166,31 @@ impl Poll {
     pub fn dsn(&self) -> &str {
         &self.dsn
     }
+    pub fn property_names(&self) -> &[String] {
+        &self.property_names
+    }
+    pub fn on(&self) -> bool {
+        self.on
+    }
+    pub fn next_poll(&self) -> Instant {
+        self.next_poll
+    }
+    pub fn stats(&self) -> &PollStats {
+        &self.stats
+    }
     pub fn set_on(&mut self, on: bool) {
         self.on = on;
-        if !on {
-            if let Some(handle) = &self.handle {
-                handle.abort();
-            }
-            self.running = false;
-        }
-    }
-    pub fn set_handle(&mut self, handle: JoinHandle<()>) {
-        self.handle = Some(handle);
     }
 }
 
-#[cfg(feature = "library")]
-fn default_callback(_dsn: String, _prop_names: Vec<IoTProperty>) {}
-
 #[cfg(feature = "signatures")]
 pub struct PollConfig {
     property_names: Option<Vec<String>>,
     on: Option<bool>,
     sleep_time: Option<u64>,
     dsn: Option<String>,
-    callback: Option<fn(String, Vec<IoTProperty>)>
+    callback: Option<PollCallback>,
+    emit_snapshots: Option<bool>
 }
 
 #[cfg(feature = "signatures")]
@@ -128,13 +199,21 @@ impl PollConfig {
                on: Option<bool>,
                sleep_time: Option<u64>,
                dsn: Option<String>,
-               callback: Option<fn(String, Vec<IoTProperty>)>) -> Self {
+               callback: Option<PollCallback>) -> Self {
         Self {
             property_names,
             on,
             sleep_time,
             dsn,
-            callback
+            callback,
+            emit_snapshots: None
         }
     }
-}
\ No newline at end of file
+
+    /// By default `callback` only gets `PollEvent::Data` when something changed,
+    /// turn snapshots on to get it after every poll.
+    pub fn with_emit_snapshots(mut self, emit_snapshots: bool) -> Self {
+        self.emit_snapshots = Some(emit_snapshots);
+        self
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(feature = "library")]
+use std::pin::Pin;
+#[cfg(feature = "library")]
+use std::sync::Mutex;
+#[cfg(feature = "library")]
+use std::task::Context;
+#[cfg(feature = "library")]
+use futures_util::Stream;
+#[cfg(feature = "library")]
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+#[cfg(feature = "library")]
+use crate::polling::event::PollEvent;
+#[cfg(feature = "library")]
+use crate::polling::manager::POLL_MANAGER;
+#[cfg(feature = "library")]
+use crate::polling::poll::PollConfig;
+#[cfg(feature = "signatures")]
+use crate::polling::change::IoTPropertyChange;
+#[cfg(feature = "signatures")]
+use crate::CloudCoreError;
+
+/// What a `Subscription` yields.
+#[cfg(feature = "signatures")]
+#[derive(Debug, Clone)]
+pub enum PropertyEvent {
+    /// A subscribed property was added, changed or removed.
+    /// The first poll reports every property as added.
+    Changed {
+        dsn: String,
+        change: IoTPropertyChange,
+    },
+    /// Polling the device failed, the subscription keeps trying.
+    Error {
+        dsn: String,
+        error: CloudCoreError,
+    },
+}
+
+#[cfg(feature = "signatures")]
+impl PropertyEvent {
+    /// Get a reference to the DSN of the subscribed device.
+    pub fn dsn(&self) -> &str {
+        match self {
+            Self::Changed { dsn, .. } | Self::Error { dsn, .. } => dsn,
+        }
+    }
+}
+
+/// A stream of the property events of one device, see `CloudCore::subscribe`.
+/// It ends once the polling manager stops or the subscription is removed,
+/// and dropping it removes the subscription.
+#[cfg(feature = "library")]
+pub struct Subscription {
+    id: u32,
+    events: UnboundedReceiver<PropertyEvent>,
+}
+
+#[cfg(feature = "library")]
+impl Subscription {
+    pub(crate) fn new(dsn: String, property_names: Vec<String>) -> Self {
+        let (sender, events) = unbounded_channel();
+        // Dropping the sender ends the stream
+        let sender = Mutex::new(Some(sender));
+        let callback = move |event: PollEvent| {
+            let mut sender = sender.lock().unwrap();
+            let property_events = match event {
+                PollEvent::Changes { dsn, changes } => changes
+                    .into_iter()
+                    .map(|change| PropertyEvent::Changed { dsn: dsn.clone(), change })
+                    .collect(),
+                PollEvent::Error { dsn, error } => vec![PropertyEvent::Error { dsn, error }],
+                PollEvent::Data { .. } => vec![],
+                PollEvent::Stopped { .. } => {
+                    sender.take();
+                    vec![]
+                }
+            };
+            if let Some(sender) = sender.as_ref() {
+                // Only fails once the subscription was dropped
+                property_events.into_iter().for_each(|event| {
+                    let _ = sender.send(event);
+                });
+            }
+        };
+        let config = PollConfig::new(Some(property_names), None, None, Some(dsn), Some(Box::new(callback)));
+        let id = POLL_MANAGER.add_poll(config);
+        Self { id, events }
+    }
+
+    /// Get the subscription's id, for `CloudCore::unsubscribe`.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Wait for the next event, `None` once the subscription ended.
+    pub async fn recv(&mut self) -> Option<PropertyEvent> {
+        self.events.recv().await
+    }
+}
+
+#[cfg(feature = "library")]
+impl Stream for Subscription {
+    type Item = PropertyEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> std::task::Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+#[cfg(feature = "library")]
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        POLL_MANAGER.remove_poll(self.id);
+    }
+}
@@ -10,53 +10,88 @@ use crate::io::{download_resource, write_to_disk};
 #[cfg(feature = "library")]
 use crate::properties::datapoint::{DataPointResponse, IoTDatapointMessage};
 #[cfg(feature = "library")]
-use crate::properties::datapoint::{IoTDatapoint, IoTDatapointFile, IoTDatapointMetadata};
+use crate::properties::datapoint::{
+    IoTDatapoint, IoTDatapointAck, IoTDatapointFile, IoTDatapointMetadata, IoTDatapointPage,
+};
 #[cfg(feature = "library")]
 use crate::properties::property::{
     IoTProperty, PROPS_PATH_PARAMS_DATAPOINT_ID, PROPS_PATH_PARAMS_DSN, PROPS_PATH_PARAMS_PROP_NAME,
 };
 #[cfg(feature = "library")]
-use crate::properties::value::IoTPropertyValue;
+use crate::properties::value::{IoTPropertyBaseType, IoTPropertyValue};
 #[cfg(feature = "library")]
 use crate::urls::{
-    AUTHORIZATION_BEARER, AUTHORIZATION_HEADER, AYLA_DATAPOINTS_FILTER_END_DATE_KEY,
-    AYLA_DATAPOINTS_FILTER_SINCE_DATE_KEY, AYLA_DATAPOINTS_LIMIT_KEY, AYLA_PROPS_DATAPOINTS_JSON,
+    AYLA_DATAPOINTS_FILTER_END_DATE_KEY, AYLA_DATAPOINTS_FILTER_SINCE_DATE_KEY,
+    AYLA_DATAPOINTS_LIMIT_KEY, AYLA_DATAPOINTS_PAGE_KEY, AYLA_DATAPOINTS_PAGINATED_KEY,
+    AYLA_DATAPOINTS_PER_PAGE_KEY, AYLA_BATCH_DATAPOINTS_JSON, AYLA_PROPS_DATAPOINTS_JSON,
     AYLA_PROPS_JSON, AYLA_PROPS_MSG_DATAPOINTS_JSON, AYLA_PROPS_QUERY_PARAMS_KEY,
     AYLA_PROP_DATAPOINT_ID_JSON,
 };
 #[cfg(feature = "library")]
-use chrono::DateTime;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "library")]
+use crate::datetime::format_datetime;
+#[cfg(feature = "library")]
+use futures_util::future::join_all;
+#[cfg(feature = "library")]
+use futures_util::{stream, Stream, StreamExt};
 #[cfg(feature = "library")]
 use log::debug;
 #[cfg(feature = "library")]
-use reqwest::StatusCode;
+use reqwest::{Method, StatusCode};
+#[cfg(feature = "library")]
+use crate::cloudcore_client::{CloudCoreBaseURL, CloudCoreRequest};
 #[cfg(feature = "library")]
 use reqwest::{Body, Url};
 #[cfg(feature = "library")]
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "library")]
-use std::error::Error;
+use crate::CloudCoreError;
 #[cfg(feature = "library")]
 use std::fs;
 #[cfg(feature = "library")]
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "library")]
 use std::future::Future;
 #[cfg(feature = "library")]
 use std::path::Path;
 #[cfg(feature = "library")]
 use std::sync::{Arc, Mutex};
 #[cfg(feature = "library")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "library")]
 use log::error;
 #[cfg(feature = "library")]
-use mantle_utilities::{ErrorType, MantleError, RUNTIME};
+use mantle_utilities::RUNTIME;
 #[cfg(feature = "library")]
 use tokio::fs::File;
 #[cfg(feature = "library")]
+use tokio::time::sleep;
+#[cfg(feature = "library")]
 use tokio_util::codec::{BytesCodec, FramedRead};
 #[cfg(feature = "library")]
-use crate::ErrorUtil;
+use crate::cloudcore_error::{CacheError, InputError};
+#[cfg(feature = "library")]
+use crate::polling::poll_soon;
+#[cfg(feature = "library")]
+use crate::lan;
 
 #[cfg(feature = "library")]
 static MAX_DATAPOINT_COUNT: u32 = 100;
+#[cfg(feature = "library")]
+static ACK_POLL_INTERVAL_MILLIS: u64 = 500;
+
+#[cfg(feature = "library")]
+fn unknown_property(prop_name: &str) -> CloudCoreError {
+    CloudCoreError::InvalidInput(InputError::Invalid(format!("Unknown property {}", prop_name)))
+}
+
+/// Whether the batch datapoint request failed without anything being written: the endpoint
+/// isn't there or no connection could be made.
+#[cfg(feature = "library")]
+fn batch_unavailable(err: &CloudCoreError) -> bool {
+    matches!(err, CloudCoreError::Http { status: 404 | 405, .. } | CloudCoreError::Connect(_))
+}
 
 #[cfg(feature = "library")]
 impl CloudCore {
@@ -65,7 +100,7 @@ impl CloudCore {
         dsn: String,
         prop: String,
         callback_id: String,
-    ) -> (Result<Vec<IoTProperty>, Box<dyn Error>>, String) {
+    ) -> (Result<Vec<IoTProperty>, CloudCoreError>, String) {
         self.get_properties(dsn, vec![prop], callback_id).await
     }
 
@@ -74,25 +109,23 @@ impl CloudCore {
         dsn: String,
         props: Vec<String>,
         callback_id: String,
-    ) -> (Result<Vec<IoTProperty>, Box<dyn Error>>, String) {
+    ) -> (Result<Vec<IoTProperty>, CloudCoreError>, String) {
         let future = async move {
-            if self.user_session.is_none() {
-                let error = Box::new(ErrorUtil::user_session_not_found_error());
-                return Err(error.into());
-            }
             #[derive(Debug, Deserialize)]
             struct IoTPropertyListResponse {
                 pub property: IoTProperty,
             }
 
-            let mut url = String::from(&self.session_params().device_url);
-            let endpoint = String::from(AYLA_PROPS_JSON).replace(PROPS_PATH_PARAMS_DSN, &dsn);
-            url.push_str(&endpoint);
-
-            let token = self.user_session.as_ref().unwrap().access_token();
-            let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
+            match lan::get_properties(&dsn, &props).await {
+                Some(Ok(properties)) => {
+                    self.remember_base_types(&dsn, &properties);
+                    return Ok(properties);
+                }
+                Some(Err(err)) => error!("Reading {} over LAN failed, falling back to the cloud: {}", dsn, err),
+                None => {}
+            }
 
-            let client = self.client();
+            let endpoint = String::from(AYLA_PROPS_JSON).replace(PROPS_PATH_PARAMS_DSN, &dsn);
 
             let mut query: Vec<(&str, String)> = vec![];
             props.into_iter().for_each(|prop| {
@@ -100,18 +133,17 @@ impl CloudCore {
                 query.push(params);
             });
 
-            let response = client
-                .get(url)
-                .header(AUTHORIZATION_HEADER, auth_bearer)
-                .query(&query)
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                let error_payload = response.text().await?;
-                let error = Box::new(ErrorUtil::server_error(error_payload));
-                return Err(error.into());
-            }
+            let body: Option<String> = None;
+            let response = self.send_request(
+                CloudCoreRequest {
+                    base_url: CloudCoreBaseURL::DEVICE,
+                    endpoint,
+                    method: Method::GET,
+                    requires_auth: true,
+                    query,
+                    body,
+                }
+            ).await?;
             let text = response.text().await?;
             debug!("properties: {}", &text);
             let properties_payload: Vec<IoTPropertyListResponse> =
@@ -120,8 +152,9 @@ impl CloudCore {
             let mut properties: Vec<IoTProperty> = vec![];
             properties_payload.into_iter().for_each(|obj| {
                 debug!("{:?}", &obj.property);
-                properties.push(obj.property);
+                properties.push(obj.property.into_typed());
             });
+            self.remember_base_types(&dsn, &properties);
 
             Ok(properties)
         };
@@ -135,20 +168,14 @@ impl CloudCore {
         dsn: String,
         prop_name: String,
         count: Option<u32>,
-        // It is up to the caller to make sure these dates are formatted correctly
-        from: Option<String>,
-        to: Option<String>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
         callback_id: String,
-    ) -> (Result<Vec<IoTDatapoint>, Box<dyn Error>>, String) {
+    ) -> (Result<Vec<IoTDatapoint>, CloudCoreError>, String) {
         let future = async move {
-            if self.user_session.is_none() {
-                return Err(Box::new(ErrorUtil::user_session_not_found_error()).into());
-            }
-            let mut url = String::from(&self.session_params().device_url);
             let endpoint = String::from(AYLA_PROPS_DATAPOINTS_JSON)
                 .replace(PROPS_PATH_PARAMS_DSN, &dsn)
                 .replace(PROPS_PATH_PARAMS_PROP_NAME, &prop_name);
-            url.push_str(&endpoint);
 
             let mut limit = MAX_DATAPOINT_COUNT;
             if let Some(count) = count {
@@ -156,48 +183,25 @@ impl CloudCore {
                     limit = count
                 }
             }
-            let limit_query = vec![(AYLA_DATAPOINTS_LIMIT_KEY, limit)];
-
-            let mut date_query = vec![];
+            let mut query = vec![(AYLA_DATAPOINTS_LIMIT_KEY, limit.to_string())];
             if let Some(from) = from {
-                date_query.push((AYLA_DATAPOINTS_FILTER_SINCE_DATE_KEY, from));
+                query.push((AYLA_DATAPOINTS_FILTER_SINCE_DATE_KEY, format_datetime(&from)));
             }
             if let Some(to) = to {
-                date_query.push((AYLA_DATAPOINTS_FILTER_END_DATE_KEY, to));
-            }
-
-            let session = self.user_session.as_ref().unwrap();
-            let token = session.access_token();
-            let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
-
-            #[derive(Debug, Deserialize, Serialize)]
-            struct DataPointResponse {
-                datapoint: IoTDatapoint,
-            }
-
-            // Use if pagination is ever needed:
-            // https://docs.aylanetworks.com/reference/get-datapoints-by-dsn
-            #[derive(Debug, Deserialize)]
-            struct MetaResponse {
-                previous_page: Option<String>,
-                next_page: Option<String>,
-                current_page_number: Option<u32>,
+                query.push((AYLA_DATAPOINTS_FILTER_END_DATE_KEY, format_datetime(&to)));
             }
 
-            let client = self.client();
-
-            let response = client
-                .get(url)
-                .header(AUTHORIZATION_HEADER, auth_bearer)
-                .query(&limit_query)
-                .query(&date_query)
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                let error_payload = response.text().await?;
-                return Err(Box::new(ErrorUtil::server_error(error_payload)).into());
-            }
+            let body: Option<String> = None;
+            let response = self.send_request(
+                CloudCoreRequest {
+                    base_url: CloudCoreBaseURL::DEVICE,
+                    endpoint,
+                    method: Method::GET,
+                    requires_auth: true,
+                    query,
+                    body,
+                }
+            ).await?;
 
             let mut datapoints = vec![];
             let datapoints_payload = response.json::<Vec<DataPointResponse>>().await?;
@@ -213,12 +217,137 @@ impl CloudCore {
         (result, callback_id)
     }
 
+    /// Get one page of datapoints, oldest first. Pages start at 1, `per_page` defaults to
+    /// and is capped at 100. 'to' Is exclusive
+    pub async fn get_datapoint_page(
+        &self,
+        dsn: String,
+        prop_name: String,
+        page: u32,
+        per_page: Option<u32>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<IoTDatapointPage, CloudCoreError> {
+        let endpoint = String::from(AYLA_PROPS_DATAPOINTS_JSON)
+            .replace(PROPS_PATH_PARAMS_DSN, &dsn)
+            .replace(PROPS_PATH_PARAMS_PROP_NAME, &prop_name);
+
+        let per_page = match per_page {
+            Some(per_page) if per_page > 0 && per_page < MAX_DATAPOINT_COUNT => per_page,
+            _ => MAX_DATAPOINT_COUNT,
+        };
+        let page = page.max(1);
+        let mut query = vec![
+            (AYLA_DATAPOINTS_PAGINATED_KEY, true.to_string()),
+            (AYLA_DATAPOINTS_PAGE_KEY, page.to_string()),
+            (AYLA_DATAPOINTS_PER_PAGE_KEY, per_page.to_string()),
+        ];
+        if let Some(from) = from {
+            query.push((AYLA_DATAPOINTS_FILTER_SINCE_DATE_KEY, format_datetime(&from)));
+        }
+        if let Some(to) = to {
+            query.push((AYLA_DATAPOINTS_FILTER_END_DATE_KEY, format_datetime(&to)));
+        }
+
+        // https://docs.aylanetworks.com/reference/get-datapoints-by-dsn
+        #[derive(Debug, Deserialize)]
+        struct MetaResponse {
+            next_page: Option<String>,
+            current_page_number: Option<u32>,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct DataPointPageResponse {
+            datapoints: Vec<DataPointResponse>,
+            meta: MetaResponse,
+        }
+
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint,
+                method: Method::GET,
+                requires_auth: true,
+                query,
+                body,
+            }
+        ).await?;
+
+        let page_payload = response.json::<DataPointPageResponse>().await?;
+        let current_page = page_payload.meta.current_page_number.unwrap_or(page);
+        let next_page = page_payload.meta.next_page.map(|_| current_page + 1);
+        let datapoints = page_payload.datapoints
+            .into_iter()
+            .map(|datapoint_response| datapoint_response.datapoint)
+            .collect();
+        Ok(IoTDatapointPage::new(datapoints, current_page, next_page))
+    }
+
+    /// Get every datapoint between the dates, following Ayla's pages. 'to' Is exclusive
+    pub async fn get_all_datapoints(
+        &self,
+        dsn: String,
+        prop_name: String,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        callback_id: String,
+    ) -> (Result<Vec<IoTDatapoint>, CloudCoreError>, String) {
+        let future = async move {
+            let mut datapoints = vec![];
+            let mut next_page = Some(1);
+            while let Some(page) = next_page {
+                let datapoint_page = self
+                    .get_datapoint_page(dsn.to_string(), prop_name.to_string(), page, None, from, to)
+                    .await?;
+                debug!("datapoints page {} of {}/{}", page, &dsn, &prop_name);
+                next_page = datapoint_page.next_page();
+                datapoints.extend(datapoint_page.into_datapoints());
+            }
+            Ok(datapoints)
+        };
+        let result = future.await;
+        (result, callback_id)
+    }
+
+    /// Same as `get_all_datapoints`, but datapoints are handed out as each page arrives.
+    /// The stream ends after the last page or the first error.
+    pub fn datapoints_stream(
+        &self,
+        dsn: String,
+        prop_name: String,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> impl Stream<Item = Result<IoTDatapoint, CloudCoreError>> + '_ {
+        stream::unfold(Some(1), move |next_page| {
+            let dsn = dsn.to_string();
+            let prop_name = prop_name.to_string();
+            async move {
+                let page = next_page?;
+                match self.get_datapoint_page(dsn, prop_name, page, None, from, to).await {
+                    Ok(datapoint_page) => {
+                        let next_page = datapoint_page.next_page();
+                        Some((Ok(datapoint_page.into_datapoints()), next_page))
+                    }
+                    Err(err) => Some((Err(err), None)),
+                }
+            }
+        })
+        .flat_map(|page: Result<Vec<IoTDatapoint>, CloudCoreError>| {
+            let items: Vec<Result<IoTDatapoint, CloudCoreError>> = match page {
+                Ok(datapoints) => datapoints.into_iter().map(Ok).collect(),
+                Err(err) => vec![Err(err)],
+            };
+            stream::iter(items)
+        })
+    }
+
     pub async fn get_file_property(
         &self,
         dsn: String,
         prop_name: String,
         callback_id: String,
-    ) -> (Result<IoTDatapointFile, Box<dyn Error>>, String) {
+    ) -> (Result<IoTDatapointFile, CloudCoreError>, String) {
         let cb_id = callback_id.clone();
         let future = async move {
             let mut datapoints = self
@@ -231,27 +360,16 @@ impl CloudCore {
                     cb_id.to_string(),
                 )
                 .await.0?;
-            let string_to_int = |date: Option<&String>| {
-                if let Some(date) = date {
-                    if let Some(date) = DateTime::parse_from_rfc3339(date).ok() {
-                        return date.timestamp() as u64;
-                    }
-                }
-                0 as u64
-            };
-            datapoints.sort_by(|a, b| {
-                let a_timestamp = string_to_int(a.created_at());
-                let b_timestamp = string_to_int(b.created_at());
-                b_timestamp.cmp(&a_timestamp)
-            });
+            // Most recent first, datapoints without a date go last
+            datapoints.sort_by(|a, b| b.created_at().cmp(&a.created_at()));
             if datapoints.is_empty() {
-                return Err(Box::new(ErrorUtil::datapoints_missing()).into());
+                return Err(CloudCoreError::NotFound("Property has no datapoints".to_string()));
             }
             let property = datapoints.get(0).unwrap();
             let val = property.value();
             let url = val.string_value();
             if url.is_none() {
-                return Err(Box::new(ErrorUtil::property_not_string_type()).into());
+                return Err(CloudCoreError::Parse("Property value was not a string".to_string()));
             }
             let url = url.unwrap();
             self.get_datapoint_with_file_url(
@@ -271,7 +389,7 @@ impl CloudCore {
         dsn: String,
         prop_names: Vec<String>,
         callback_id: String,
-        callback: fn(result: (Result<Vec<IoTDatapointFile>, Box<dyn Error>>, String))
+        callback: fn(result: (Result<Vec<IoTDatapointFile>, CloudCoreError>, String))
     ) {
         let fv = Arc::new(Mutex::new(vec![]));
         let prop_names = Arc::new(prop_names);
@@ -304,31 +422,27 @@ impl CloudCore {
         dsn: String,
         prop_name: String,
         callback_id: String,
-    ) -> (Result<IoTDatapointFile, Box<dyn Error>>, String) {
+    ) -> (Result<IoTDatapointFile, CloudCoreError>, String) {
         let future = async move {
-            if self.user_session.is_none() {
-                return Err(Box::new(ErrorUtil::user_session_not_found_error()).into());
-            }
-            let token = self.user_session.as_ref().unwrap().access_token();
-            let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
-
             #[derive(Debug, Deserialize)]
             struct IoTDatapointFileResponse {
                 pub datapoint: IoTDatapointFile,
             }
 
-            let client = self.client();
-
-            let response = client
-                .get(url)
-                .header(AUTHORIZATION_HEADER, auth_bearer)
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                let error_payload = response.text().await?;
-                return Err(Box::new(ErrorUtil::server_error(error_payload)).into());
-            }
+            // File datapoint values are absolute Ayla urls, only the path is kept so the
+            // request goes to the current region
+            let url = Url::parse(&url).map_err(|err| CloudCoreError::Parse(err.to_string()))?;
+            let body: Option<String> = None;
+            let response = self.send_request(
+                CloudCoreRequest {
+                    base_url: CloudCoreBaseURL::DEVICE,
+                    endpoint: url.path().to_string(),
+                    method: Method::GET,
+                    requires_auth: true,
+                    query: vec![],
+                    body,
+                }
+            ).await?;
 
             let datapoint_payload = response.json::<IoTDatapointFileResponse>().await?;
             let mut datapoint = datapoint_payload.datapoint;
@@ -344,13 +458,13 @@ impl CloudCore {
                         datapoint.set_local_file(file_path);
                         Ok(datapoint)
                     } else {
-                        Err(Box::new(ErrorUtil::local_file_name_error()).into())
+                        Err(CloudCoreError::Cache(CacheError::FileName))
                     }
                 } else {
-                    Err(Box::new(ErrorUtil::local_file_name_error()).into())
+                    Err(CloudCoreError::Cache(CacheError::FileName))
                 }
             } else {
-                Err(Box::new(ErrorUtil::cached_directory_error()).into())
+                Err(CloudCoreError::Cache(CacheError::DirectoryUnavailable))
             }
         };
         let result = future.await;
@@ -362,7 +476,7 @@ impl CloudCore {
         dsn: String,
         prop_name: String,
         callback_id: String,
-    ) -> (Result<IoTDatapointMessage, Box<dyn Error>>, String) {
+    ) -> (Result<IoTDatapointMessage, CloudCoreError>, String) {
         let cb_id = callback_id.clone();
         let future = async move {
             let properties = self
@@ -373,26 +487,26 @@ impl CloudCore {
                 )
                 .await.0?;
             if properties.is_empty() {
-                return Err(Box::new(ErrorUtil::property_not_found()).into());
+                return Err(CloudCoreError::NotFound("No property returned".to_string()));
             }
             let property = properties.get(0).unwrap();
             let val = property.value();
             if val.is_none() {
-                return Err(Box::new(ErrorUtil::url_not_found()).into());
+                return Err(CloudCoreError::NotFound("No url was found for value".to_string()));
             }
             if property.base_type() != "message" {
-                return Err(Box::new(ErrorUtil::property_not_message_type()).into());
+                return Err(CloudCoreError::Parse("Property is not a message type".to_string()));
             }
             let url = val.unwrap().string_value();
             if url.is_none() {
-                return Err(Box::new(ErrorUtil::property_not_string_type()).into());
+                return Err(CloudCoreError::Parse("Property value was not a string".to_string()));
             }
             let components: Vec<&str> = url.unwrap().split("/").collect();
             if components.len() == 1
                 || components.first().is_none()
                 || components.first().unwrap() == url.unwrap()
             {
-                return Err(Box::new(ErrorUtil::invalid_format()).into());
+                return Err(CloudCoreError::Parse("Value is not correct format".to_string()));
             }
             let datapoint_id = components.last().unwrap();
             self.get_datapoint_with_id(
@@ -412,7 +526,7 @@ impl CloudCore {
         dsn: String,
         prop_names: Vec<String>,
         callback_id: String,
-        callback: fn(result: (Result<Vec<IoTDatapointMessage>, Box<dyn Error>>, String))
+        callback: fn(result: (Result<Vec<IoTDatapointMessage>, CloudCoreError>, String))
     ) {
         let fv = Arc::new(Mutex::new(vec![]));
         let prop_names = Arc::new(prop_names);
@@ -446,33 +560,24 @@ impl CloudCore {
         datapoint_id: String,
         prop_name: String,
         callback_id: String,
-    ) -> (Result<IoTDatapointMessage, Box<dyn Error>>, String) {
+    ) -> (Result<IoTDatapointMessage, CloudCoreError>, String) {
         let future = async move {
-            if self.user_session.is_none() {
-                return Err(Box::new(ErrorUtil::user_session_not_found_error()).into());
-            }
-            let token = self.user_session.as_ref().unwrap().access_token();
-            let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
-
-            let mut url = String::from(&self.session_params().device_url);
             let endpoint = String::from(AYLA_PROP_DATAPOINT_ID_JSON)
                 .replace(PROPS_PATH_PARAMS_DSN, &dsn)
                 .replace(PROPS_PATH_PARAMS_PROP_NAME, &prop_name)
                 .replace(PROPS_PATH_PARAMS_DATAPOINT_ID, &datapoint_id);
-            url.push_str(&endpoint);
-
-            let client = self.client();
 
-            let response = client
-                .get(url)
-                .header(AUTHORIZATION_HEADER, auth_bearer)
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                let error_payload = response.text().await?;
-                return Err(Box::new(ErrorUtil::server_error(error_payload)).into());
-            }
+            let body: Option<String> = None;
+            let response = self.send_request(
+                CloudCoreRequest {
+                    base_url: CloudCoreBaseURL::DEVICE,
+                    endpoint,
+                    method: Method::GET,
+                    requires_auth: true,
+                    query: vec![],
+                    body,
+                }
+            ).await?;
 
             let datapoint_payload = response.json::<DataPointResponse>().await?;
             let datapoint = datapoint_payload.datapoint;
@@ -495,13 +600,13 @@ impl CloudCore {
                         let msg_dp = IoTDatapointMessage::new(datapoint, file_path);
                         Ok(msg_dp)
                     } else {
-                        return Err(Box::new(ErrorUtil::local_file_name_error()).into());
+                        return Err(CloudCoreError::Cache(CacheError::FileName));
                     }
                 } else {
-                    return Err(Box::new(ErrorUtil::local_file_name_error()).into());
+                    return Err(CloudCoreError::Cache(CacheError::FileName));
                 }
             } else {
-                return Err(Box::new(ErrorUtil::cached_directory_error()).into());
+                return Err(CloudCoreError::Cache(CacheError::DirectoryUnavailable));
             }
         };
         let result = future.await;
@@ -514,63 +619,348 @@ impl CloudCore {
         prop_name: String,
         value: IoTPropertyValue,
         callback_id: String,
-    ) -> (Result<(), Box<dyn Error>>, String) {
+    ) -> (Result<(), CloudCoreError>, String) {
         let future = async move {
-            if self.user_session.is_none() {
-                return Err(Box::new(ErrorUtil::user_session_not_found_error()).into());
+            match lan::set_property_value(&dsn, &prop_name, value.clone()).await {
+                Some(Ok(())) => {
+                    poll_soon(&dsn);
+                    return Ok(());
+                }
+                Some(Err(err)) => error!("Writing {} over LAN failed, falling back to the cloud: {}", dsn, err),
+                None => {}
             }
-            let mut url = String::from(&self.session_params().device_url);
-            let endpoint = String::from(AYLA_PROPS_DATAPOINTS_JSON)
-                .replace(PROPS_PATH_PARAMS_DSN, &dsn)
-                .replace(PROPS_PATH_PARAMS_PROP_NAME, &prop_name);
-            url.push_str(&endpoint);
-
-            let session = self.user_session.as_ref().unwrap();
-            let token = session.access_token();
-            let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
-
-            let client = self.client();
-
-            let uuid = session.user_uuid().unwrap().to_owned();
-            let datapoint = IoTDatapoint::new(
-                value,
-                IoTDatapointMetadata::new(uuid),
-                None,
-                None,
-                None,
-            );
-            #[derive(Serialize)]
-            struct CreateDataPointRequest {
-                datapoint: IoTDatapoint,
-            }
-            let request_data = CreateDataPointRequest { datapoint };
-
-            let response = client
-                .post(url)
-                .json(&request_data)
-                .header(AUTHORIZATION_HEADER, auth_bearer)
-                .send()
-                .await?;
+            self.user_session.as_ref().ok_or(CloudCoreError::SessionMissing)?;
+            let value = self.validate_property_value(&dsn, &prop_name, value).await?;
+            self.create_datapoint(&dsn, &prop_name, value).await?;
+            poll_soon(&dsn);
+            Ok(())
+        };
+        let result = future.await;
+        (result, callback_id)
+    }
 
-            if response.status() == StatusCode::CREATED {
-                Ok(())
-            } else {
-                return Err(Box::new(ErrorUtil::create_datapoint_error(response.text().await?)).into());
+    /// Set several properties of a device with Ayla's batch datapoint endpoint, or with one
+    /// request per property sent together when the batch endpoint is unavailable. Any other
+    /// batch failure is every property's result, as the batch may have been written already.
+    /// Every property named gets its own result, so a partial failure doesn't hide the writes
+    /// that went through. A property named more than once is not written.
+    pub async fn set_property_values(
+        &self,
+        dsn: String,
+        values: Vec<(String, IoTPropertyValue)>,
+        callback_id: String,
+    ) -> (Result<HashMap<String, Result<(), CloudCoreError>>, CloudCoreError>, String) {
+        let future = async move {
+            self.user_session.as_ref().ok_or(CloudCoreError::SessionMissing)?;
+            let mut results = HashMap::new();
+            let mut seen = HashSet::new();
+            let repeated: HashSet<String> = values
+                .iter()
+                .filter(|(name, _)| !seen.insert(name.as_str()))
+                .map(|(name, _)| name.to_string())
+                .collect();
+            let names = values.iter().map(|(name, _)| name.to_string()).filter(|name| !repeated.contains(name)).collect();
+            self.look_up_base_types(&dsn, names).await;
+            let mut datapoints = vec![];
+            for (name, value) in values {
+                if repeated.contains(&name) {
+                    let err = CloudCoreError::InvalidInput(InputError::Invalid(format!("{} is set more than once", name)));
+                    results.insert(name, Err(err));
+                    continue;
+                }
+                match self.check_property_value(&dsn, &name, value) {
+                    Ok(value) => datapoints.push((name, value)),
+                    Err(err) => {
+                        results.insert(name, Err(err));
+                    }
+                }
+            }
+            if datapoints.is_empty() {
+                return Ok(results);
+            }
+            match self.create_batch_datapoints(&dsn, &datapoints).await {
+                Ok(mut batch_results) => {
+                    for (name, _) in datapoints {
+                        let result = batch_results
+                            .remove(&name)
+                            .unwrap_or_else(|| Err(CloudCoreError::Parse(format!("No batch datapoint result for {}", name))));
+                        results.insert(name, result);
+                    }
+                }
+                Err(err) if !batch_unavailable(&err) => {
+                    for (name, _) in datapoints {
+                        results.insert(name, Err(err.clone()));
+                    }
+                }
+                Err(err) => {
+                    debug!("Batch datapoints unavailable, sending {} datapoints separately: {}", datapoints.len(), err);
+                    let dsn = &dsn;
+                    let sent = join_all(datapoints.into_iter().map(|(name, value)| async move {
+                        let result = self.create_datapoint(dsn, &name, value).await.map(|_| ());
+                        (name, result)
+                    })).await;
+                    results.extend(sent);
+                }
+            }
+            if results.values().any(|result| result.is_ok()) {
+                poll_soon(&dsn);
+            }
+            Ok(results)
+        };
+        let result = future.await;
+        (result, callback_id)
+    }
+
+    /// Check `value` against the base type of the property it is meant for, converting it
+    /// to the variant Ayla expects for that base type. A base type that wasn't read yet is
+    /// looked up first, the value is only sent unchecked when that lookup fails.
+    async fn validate_property_value(
+        &self,
+        dsn: &str,
+        prop_name: &str,
+        value: IoTPropertyValue,
+    ) -> Result<IoTPropertyValue, CloudCoreError> {
+        self.look_up_base_types(dsn, vec![prop_name.to_string()]).await;
+        self.check_property_value(dsn, prop_name, value)
+    }
+
+    /// `validate_property_value` against the base types known so far.
+    fn check_property_value(
+        &self,
+        dsn: &str,
+        prop_name: &str,
+        value: IoTPropertyValue,
+    ) -> Result<IoTPropertyValue, CloudCoreError> {
+        match self.known_base_type(dsn, prop_name) {
+            Some(base_type) => value.for_base_type(&IoTPropertyBaseType::from(base_type.as_str())),
+            None => Ok(value),
+        }
+    }
+
+    /// Read the properties whose base type isn't known yet, reading them remembers it.
+    async fn look_up_base_types(&self, dsn: &str, prop_names: Vec<String>) {
+        let unknown: Vec<String> = prop_names
+            .into_iter()
+            .filter(|prop_name| self.known_base_type(dsn, prop_name).is_none())
+            .collect();
+        if unknown.is_empty() {
+            return;
+        }
+        if let Err(err) = self.get_properties(dsn.to_string(), unknown, "".to_string()).await.0 {
+            error!("Looking up base types on {} failed, sending the values unchecked: {}", dsn, err);
+        }
+    }
+
+    fn remember_base_types(&self, dsn: &str, properties: &[IoTProperty]) {
+        if let Ok(mut base_types) = self.property_base_types.lock() {
+            let base_types = base_types.entry(dsn.to_string()).or_default();
+            for property in properties {
+                base_types.insert(property.name().to_string(), property.base_type().to_string());
+            }
+        }
+    }
+
+    fn known_base_type(&self, dsn: &str, prop_name: &str) -> Option<String> {
+        let base_types = self.property_base_types.lock().ok()?;
+        base_types.get(dsn)?.get(prop_name).cloned()
+    }
+
+    fn new_datapoint(&self, value: IoTPropertyValue) -> Result<IoTDatapoint, CloudCoreError> {
+        let session = self.user_session.as_ref().ok_or(CloudCoreError::SessionMissing)?;
+        let uuid = session.user_uuid().unwrap().to_owned();
+        Ok(IoTDatapoint::new(
+            value,
+            IoTDatapointMetadata::new(uuid),
+            None,
+            None,
+            None,
+        ))
+    }
+
+    /// Send a new datapoint, returning the id Ayla gave it. Ayla only sets one on properties with `ack_enabled`.
+    async fn create_datapoint(
+        &self,
+        dsn: &str,
+        prop_name: &str,
+        value: IoTPropertyValue,
+    ) -> Result<Option<String>, CloudCoreError> {
+        let endpoint = String::from(AYLA_PROPS_DATAPOINTS_JSON)
+            .replace(PROPS_PATH_PARAMS_DSN, dsn)
+            .replace(PROPS_PATH_PARAMS_PROP_NAME, prop_name);
+
+        #[derive(Serialize)]
+        struct CreateDataPointRequest {
+            datapoint: IoTDatapoint,
+        }
+        let request_data = CreateDataPointRequest { datapoint: self.new_datapoint(value)? };
+
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint,
+                method: Method::POST,
+                requires_auth: true,
+                query: vec![],
+                body: Some(request_data),
+            }
+        ).await?;
+
+        if response.status() != StatusCode::CREATED {
+            return Err(CloudCoreError::from_response(response).await);
+        }
+        #[derive(Deserialize)]
+        struct CreatedDatapoint {
+            id: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct CreateDataPointResponse {
+            datapoint: CreatedDatapoint,
+        }
+        let text = response.text().await?;
+        Ok(serde_json::from_str::<CreateDataPointResponse>(&text)
+            .ok()
+            .and_then(|created| created.datapoint.id))
+    }
+
+    /// Set a property with `ack_enabled` and wait for the device to acknowledge the value.
+    /// The datapoint is polled on Ayla until it has been acked, or fails with `Timeout`
+    /// once `timeout` has passed.
+    pub async fn set_property_value_and_wait_ack(
+        &self,
+        dsn: String,
+        prop_name: String,
+        value: IoTPropertyValue,
+        timeout: Duration,
+        callback_id: String,
+    ) -> (Result<IoTDatapointAck, CloudCoreError>, String) {
+        let future = async move {
+            self.user_session.as_ref().ok_or(CloudCoreError::SessionMissing)?;
+            let properties = self.get_property(dsn.to_string(), prop_name.to_string(), "".to_string()).await.0?;
+            let property = properties
+                .iter()
+                .find(|property| property.name() == prop_name)
+                .ok_or_else(|| unknown_property(&prop_name))?;
+            if !property.ack_enabled() {
+                return Err(CloudCoreError::InvalidInput(InputError::Invalid(format!("{} does not have acks enabled", prop_name))));
+            }
+            let value = value.for_base_type(&property.property_base_type())?;
+            let datapoint_id = self
+                .create_datapoint(&dsn, &prop_name, value)
+                .await?
+                .ok_or_else(|| CloudCoreError::Parse(format!("No datapoint id returned for {}", prop_name)))?;
+            poll_soon(&dsn);
+
+            let deadline = Instant::now() + timeout;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                sleep(remaining.min(Duration::from_millis(ACK_POLL_INTERVAL_MILLIS))).await;
+                let ack = self.get_datapoint_ack(&dsn, &prop_name, &datapoint_id).await?;
+                if ack.acked_at().is_some() {
+                    debug!("{} acked with status {:?}", prop_name, ack.ack_status());
+                    return Ok(ack);
+                }
+                if Instant::now() >= deadline {
+                    return Err(CloudCoreError::Timeout(format!("{} was not acked within {:?}", prop_name, timeout)));
+                }
             }
         };
         let result = future.await;
         (result, callback_id)
     }
 
+    async fn get_datapoint_ack(
+        &self,
+        dsn: &str,
+        prop_name: &str,
+        datapoint_id: &str,
+    ) -> Result<IoTDatapointAck, CloudCoreError> {
+        let endpoint = String::from(AYLA_PROP_DATAPOINT_ID_JSON)
+            .replace(PROPS_PATH_PARAMS_DSN, dsn)
+            .replace(PROPS_PATH_PARAMS_PROP_NAME, prop_name)
+            .replace(PROPS_PATH_PARAMS_DATAPOINT_ID, datapoint_id);
+
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint,
+                method: Method::GET,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
+
+        #[derive(Deserialize)]
+        struct DatapointAckResponse {
+            datapoint: IoTDatapointAck,
+        }
+        Ok(response.json::<DatapointAckResponse>().await?.datapoint)
+    }
+
+    async fn create_batch_datapoints(
+        &self,
+        dsn: &str,
+        datapoints: &[(String, IoTPropertyValue)],
+    ) -> Result<HashMap<String, Result<(), CloudCoreError>>, CloudCoreError> {
+        #[derive(Serialize)]
+        struct BatchDatapoint<'a> {
+            dsn: &'a str,
+            name: &'a str,
+            datapoint: IoTDatapoint,
+        }
+        #[derive(Serialize)]
+        struct CreateBatchDatapointsRequest<'a> {
+            batch_datapoints: Vec<BatchDatapoint<'a>>,
+        }
+        #[derive(Deserialize)]
+        struct BatchDatapointResponse {
+            name: String,
+            status: u16,
+            errors: Option<serde_json::Value>,
+        }
+
+        let mut batch_datapoints = vec![];
+        for (name, value) in datapoints {
+            batch_datapoints.push(BatchDatapoint { dsn, name, datapoint: self.new_datapoint(value.clone())? });
+        }
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: AYLA_BATCH_DATAPOINTS_JSON.to_string(),
+                method: Method::POST,
+                requires_auth: true,
+                query: vec![],
+                body: Some(CreateBatchDatapointsRequest { batch_datapoints }),
+            }
+        ).await?;
+
+        let text = response.text().await?;
+        debug!("batch datapoints: {}", &text);
+        let batch_results: Vec<BatchDatapointResponse> = serde_json::from_str(&text)?;
+        Ok(batch_results
+            .into_iter()
+            .map(|item| {
+                let result = if StatusCode::from_u16(item.status).is_ok_and(|status| status.is_success()) {
+                    Ok(())
+                } else {
+                    let body = item.errors.map(|errors| errors.to_string()).unwrap_or_default();
+                    Err(CloudCoreError::Http { status: item.status, body })
+                };
+                (item.name, result)
+            })
+            .collect())
+    }
+
     pub async fn get_file_property_as_files_callback(
         &'static self,
         dsn: String,
         prop_name: String,
         count: Option<u32>,
-        from: Option<String>,
-        to: Option<String>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
         callback_id: String,
-        callback: fn(result: (Result<Vec<IoTDatapointFile>, Box<dyn Error>>, String))
+        callback: fn(result: (Result<Vec<IoTDatapointFile>, CloudCoreError>, String))
     ) {
         let datapoints = self
             .get_datapoints(
@@ -582,11 +972,13 @@ impl CloudCore {
                 "".to_string(),
             )
             .await.0;
-        if let Some(err) = datapoints.as_ref().err() {
-            callback((Err(Box::new(MantleError { error_type: ErrorType::MessageDatapointError, description: err.to_string() })), callback_id.clone()));
-            return;
-        }
-        let datapoints = datapoints.unwrap();
+        let datapoints = match datapoints {
+            Ok(datapoints) => datapoints,
+            Err(err) => {
+                callback((Err(err), callback_id));
+                return;
+            }
+        };
         let len = datapoints.len();
         let dps = Arc::new(datapoints);
         let fv = Arc::new(Mutex::new(vec![]));
@@ -625,11 +1017,10 @@ impl CloudCore {
         dsn: String,
         prop_name: String,
         count: Option<u32>,
-        // It is up to the caller to make sure these dates are formatted correctly
-        from: Option<String>,
-        to: Option<String>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
         callback_id: String,
-        callback: fn(result: (Result<Vec<IoTDatapointMessage>, Box<dyn Error>>, String))
+        callback: fn(result: (Result<Vec<IoTDatapointMessage>, CloudCoreError>, String))
     ) {
         let datapoints = self
             .get_datapoints(
@@ -641,11 +1032,13 @@ impl CloudCore {
                 "".to_string(),
             )
             .await.0;
-        if let Some(err) = datapoints.as_ref().err() {
-            callback((Err(Box::new(MantleError { error_type: ErrorType::MessageDatapointError, description: err.to_string() })), callback_id.clone()));
-            return;
-        }
-        let datapoints = datapoints.unwrap();
+        let datapoints = match datapoints {
+            Ok(datapoints) => datapoints,
+            Err(err) => {
+                callback((Err(err), callback_id));
+                return;
+            }
+        };
         let len = datapoints.len();
         let dps = Arc::new(datapoints);
         let fv = Arc::new(Mutex::new(vec![]));
@@ -702,12 +1095,8 @@ impl CloudCore {
         file_path: String,
         is_message: bool,
         callback_id: String,
-    ) -> (Result<(), Box<dyn Error>>, String) {
+    ) -> (Result<(), CloudCoreError>, String) {
         let future = async move {
-            if self.user_session.is_none() {
-                return Err(Box::new(ErrorUtil::user_session_not_found_error()).into());
-            }
-            let mut url = String::from(&self.session_params().device_url);
             let endpoint = if is_message {
                 String::from(AYLA_PROPS_MSG_DATAPOINTS_JSON)
             } else {
@@ -715,22 +1104,21 @@ impl CloudCore {
             }
             .replace(PROPS_PATH_PARAMS_DSN, &dsn)
             .replace(PROPS_PATH_PARAMS_PROP_NAME, &prop_name);
-            url.push_str(&endpoint);
-
-            let session = self.user_session.as_ref().unwrap();
-            let token = session.access_token();
-            let auth_bearer = format!("{} {}", AUTHORIZATION_BEARER, token);
 
-            let client = self.client();
-
-            let response = client
-                .post(url)
-                .header(AUTHORIZATION_HEADER, auth_bearer.clone())
-                .send()
-                .await?;
+            let body: Option<String> = None;
+            let response = self.send_request(
+                CloudCoreRequest {
+                    base_url: CloudCoreBaseURL::DEVICE,
+                    endpoint,
+                    method: Method::POST,
+                    requires_auth: true,
+                    query: vec![],
+                    body,
+                }
+            ).await?;
 
             if response.status() != StatusCode::CREATED {
-                return Err(Box::new(ErrorUtil::save_datapoint_error(response.text().await?)).into());
+                return Err(CloudCoreError::from_response(response).await);
             }
             #[derive(Debug, Deserialize)]
             struct IoTDatapointFileResponse {
@@ -740,7 +1128,8 @@ impl CloudCore {
             let datapoint = datapoint_payload.datapoint;
             let file = File::open(file_path.clone()).await?;
             let size = fs::metadata(Path::new(&file_path))?.len();
-            let response = client
+            // The file url is pre-signed, it does not take the Ayla auth token
+            let response = self.client()
                 .put(datapoint.file())
                 .header("Content-Type", "application/octet-stream")
                 .header("Content-Length", size)
@@ -748,27 +1137,26 @@ impl CloudCore {
                 .send()
                 .await?;
             if !response.status().is_success() {
-                return Err(Box::new(ErrorUtil::send_file_stream(response.text().await?)).into());
+                return Err(CloudCoreError::from_response(response).await);
             }
-            let url = Url::parse(datapoint.value())?;
+            let url = Url::parse(datapoint.value()).map_err(|err| CloudCoreError::Parse(err.to_string()))?;
             let mut components = url.path_segments().unwrap().collect::<Vec<_>>();
             if components.len() > 2 {
                 components.drain(0..1);
             }
             let location = components.join("/");
-            let mut put_url = String::from(&self.session_params().device_url);
-            put_url.push_str("/apiv1/");
-            put_url.push_str(&location);
-            let response = client
-                .put(put_url)
-                .header(AUTHORIZATION_HEADER, auth_bearer)
-                .send()
-                .await?;
-            if !response.status().is_success() {
-                let error = response.text().await?;
-                return Err(Box::new(ErrorUtil::file_mark_complete_error(error.to_string())).into());
-            }
-            return Ok(());
+            let body: Option<String> = None;
+            self.send_request(
+                CloudCoreRequest {
+                    base_url: CloudCoreBaseURL::DEVICE,
+                    endpoint: format!("/apiv1/{}", location),
+                    method: Method::PUT,
+                    requires_auth: true,
+                    query: vec![],
+                    body,
+                }
+            ).await?;
+            Ok(())
         };
         let result = future.await;
         (result, callback_id)
@@ -783,7 +1171,7 @@ fn file_to_body(file: File) -> Body {
 }
 
 #[cfg(feature = "library")]
-async fn run_msg_future_msg(f: impl Future<Output = (Result<IoTDatapointMessage, Box<dyn Error>>, String)>) -> IoTDatapointMessage {
+async fn run_msg_future_msg(f: impl Future<Output = (Result<IoTDatapointMessage, CloudCoreError>, String)>) -> IoTDatapointMessage {
     match f.await.0 {
         Ok(m) => m,
         Err(e) => {
@@ -794,7 +1182,7 @@ async fn run_msg_future_msg(f: impl Future<Output = (Result<IoTDatapointMessage,
 }
 
 #[cfg(feature = "library")]
-async fn run_file_future_file(f: impl Future<Output = (Result<IoTDatapointFile, Box<dyn Error>>, String)>) -> IoTDatapointFile {
+async fn run_file_future_file(f: impl Future<Output = (Result<IoTDatapointFile, CloudCoreError>, String)>) -> IoTDatapointFile {
     match f.await.0 {
         Ok(f) => f,
         Err(e) => {
@@ -1,4 +1,5 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 use crate::properties::value::IoTPropertyValue;
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -14,8 +15,8 @@ pub struct IoTDatapoint {
     //created_at_from_device: Option<String>,
     value: IoTPropertyValue,
     metadata: IoTDatapointMetadata,
-    updated_at: Option<String>,
-    created_at: Option<String>,
+    updated_at: Option<DateTime<Utc>>,
+    created_at: Option<DateTime<Utc>>,
     echo: Option<bool>,
 }
 
@@ -23,8 +24,8 @@ impl IoTDatapoint {
     pub fn new(
         value: IoTPropertyValue,
         metadata: IoTDatapointMetadata,
-        updated_at: Option<String>,
-        created_at: Option<String>,
+        updated_at: Option<DateTime<Utc>>,
+        created_at: Option<DateTime<Utc>>,
         echo: Option<bool>,
     ) -> Self {
         Self {
@@ -47,12 +48,12 @@ impl IoTDatapoint {
     }
 
     /// Get a reference to the iot datapoint's updated_at.
-    pub fn updated_at(&self) -> Option<&String> {
+    pub fn updated_at(&self) -> Option<&DateTime<Utc>> {
         self.updated_at.as_ref()
     }
 
-    /// Get a reference to the iot datapoint's updated_at.
-    pub fn created_at(&self) -> Option<&String> {
+    /// Get a reference to the iot datapoint's created_at.
+    pub fn created_at(&self) -> Option<&DateTime<Utc>> {
         self.created_at.as_ref()
     }
 
@@ -67,6 +68,113 @@ impl IoTDatapoint {
     }
 }
 
+/// The device's answer to a datapoint sent to a property with `ack_enabled`.
+/// `acked_at` stays empty until the device has acknowledged the datapoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IoTDatapointAck {
+    #[serde(default, deserialize_with = "string_or_number")]
+    ack_status: Option<String>,
+    #[serde(default, deserialize_with = "string_or_number")]
+    ack_message: Option<String>,
+    acked_at: Option<DateTime<Utc>>,
+}
+
+impl IoTDatapointAck {
+    pub fn new(ack_status: Option<String>, ack_message: Option<String>, acked_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            ack_status,
+            ack_message,
+            acked_at,
+        }
+    }
+
+    /// Get a reference to the iot datapoint ack's ack status.
+    pub fn ack_status(&self) -> Option<&String> {
+        self.ack_status.as_ref()
+    }
+
+    /// Get a reference to the iot datapoint ack's ack message.
+    pub fn ack_message(&self) -> Option<&String> {
+        self.ack_message.as_ref()
+    }
+
+    /// Get a reference to the iot datapoint ack's acked at.
+    pub fn acked_at(&self) -> Option<&DateTime<Utc>> {
+        self.acked_at.as_ref()
+    }
+}
+
+/// The outcome of one write from `set_property_values`, in a form the host apps can take.
+/// `error` is empty when the property was written.
+#[derive(Debug, Clone, Serialize)]
+pub struct IoTPropertyWriteResult {
+    name: String,
+    error: Option<String>,
+}
+
+impl IoTPropertyWriteResult {
+    pub fn new(name: String, error: Option<String>) -> Self {
+        Self { name, error }
+    }
+
+    /// Get a reference to the iot property write result's name.
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    /// Get a reference to the iot property write result's error.
+    pub fn error(&self) -> Option<&String> {
+        self.error.as_ref()
+    }
+}
+
+/// Ayla sends the ack status and message as numbers, keep them as the strings `IoTProperty` uses.
+fn string_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::String(value)) => Some(value),
+        Some(serde_json::Value::Null) | None => None,
+        Some(value) => Some(value.to_string()),
+    })
+}
+
+/// One page of a property's datapoints, oldest first.
+#[derive(Debug)]
+pub struct IoTDatapointPage {
+    datapoints: Vec<IoTDatapoint>,
+    page: u32,
+    next_page: Option<u32>,
+}
+
+impl IoTDatapointPage {
+    pub fn new(datapoints: Vec<IoTDatapoint>, page: u32, next_page: Option<u32>) -> Self {
+        Self {
+            datapoints,
+            page,
+            next_page,
+        }
+    }
+
+    /// Get a reference to the datapoint page's datapoints.
+    pub fn datapoints(&self) -> &[IoTDatapoint] {
+        self.datapoints.as_ref()
+    }
+
+    /// Get the datapoint page's number, starting at 1.
+    pub fn page(&self) -> u32 {
+        self.page
+    }
+
+    /// Get the number of the page after this one, None on the last page.
+    pub fn next_page(&self) -> Option<u32> {
+        self.next_page
+    }
+
+    /// Take the datapoints out of the page.
+    pub fn into_datapoints(self) -> Vec<IoTDatapoint> {
+        self.datapoints
+    }
+}
+
 #[allow(non_snake_case)]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IoTDatapointMetadata {
@@ -176,8 +284,8 @@ impl IoTDatapointFile {
 #[derive(Debug, Clone)]
 pub struct IoTDatapointMessage {
     pub metadata: IoTDatapointMetadata,
-    pub updated_at: Option<String>,
-    pub created_at: Option<String>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
     pub echo: Option<bool>,
     pub local_file: String,
 }
@@ -1,11 +1,12 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use crate::properties::value::IoTPropertyValue;
+use crate::properties::value::{IoTPropertyBaseType, IoTPropertyValue};
 
 pub static PROPS_PATH_PARAMS_DSN: &str = "<dsn>";
 pub static PROPS_PATH_PARAMS_PROP_NAME: &str = "<prop_name>";
 pub static PROPS_PATH_PARAMS_DATAPOINT_ID: &str = "<datapoint_id>";
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IoTProperty {
     r#type: String,
     name: String,
@@ -13,7 +14,7 @@ pub struct IoTProperty {
     read_only: bool,
     direction: String,
     scope: String,
-    data_updated_at: Option<String>,
+    data_updated_at: Option<DateTime<Utc>>,
     key: Option<u32>,
     device_key: Option<u32>,
     product_name: Option<String>,
@@ -39,7 +40,7 @@ impl IoTProperty {
         read_only: bool,
         direction: String,
         scope: String,
-        data_updated_at: Option<String>,
+        data_updated_at: Option<DateTime<Utc>>,
         key: Option<u32>,
         device_key: Option<u32>,
         product_name: String,
@@ -92,6 +93,9 @@ impl IoTProperty {
     pub fn base_type(&self) -> &str {
         &self.base_type
     }
+    pub fn property_base_type(&self) -> IoTPropertyBaseType {
+        IoTPropertyBaseType::from(self.base_type.as_str())
+    }
     pub fn read_only(&self) -> bool {
         self.read_only
     }
@@ -101,7 +105,7 @@ impl IoTProperty {
     pub fn scope(&self) -> &str {
         &self.scope
     }
-    pub fn data_updated_at(&self) -> Option<&String> {
+    pub fn data_updated_at(&self) -> Option<&DateTime<Utc>> {
         self.data_updated_at.as_ref()
     }
     pub fn key(&self) -> Option<u32> {
@@ -153,4 +157,43 @@ impl IoTProperty {
     pub fn acked_at(&self) -> Option<&String> {
         self.acked_at.as_ref()
     }
+
+    /// A property read from the device over LAN, which only tells its name, base type and value.
+    #[cfg(feature = "library")]
+    pub(crate) fn from_lan(name: String, base_type: String, value: Option<IoTPropertyValue>) -> Self {
+        Self {
+            r#type: "Property".to_string(),
+            display_name: name.to_string(),
+            name,
+            base_type,
+            read_only: false,
+            direction: "".to_string(),
+            scope: "user".to_string(),
+            data_updated_at: Some(Utc::now()),
+            key: None,
+            device_key: None,
+            product_name: None,
+            track_only_changes: false,
+            host_sw_version: false,
+            time_series: false,
+            derived: false,
+            app_type: None,
+            recipe: None,
+            value,
+            ack_enabled: false,
+            ack_status: None,
+            ack_message: None,
+            acked_at: None,
+        }
+        .into_typed()
+    }
+
+    /// Convert the value Ayla sent to the variant the property's base type calls for,
+    /// keeping it as it came when it doesn't match.
+    #[cfg(feature = "library")]
+    pub(crate) fn into_typed(mut self) -> Self {
+        let base_type = self.property_base_type();
+        self.value = self.value.map(|value| value.clone().for_base_type(&base_type).unwrap_or(value));
+        self
+    }
 }
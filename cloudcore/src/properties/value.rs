@@ -1,14 +1,53 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cloudcore_error::InputError;
+use crate::CloudCoreError;
 
 /// If there's no indication (besides structure) of result's type,
 ///  you can use the untagged enum representation. This will try
 /// deserializing to each variant in turn until a matching one is found:
+/// numbers fall through `Int`, `Int64` and `Decimal`, and objects or arrays end up in `Json`.
+/// Use `for_base_type` to get the variant the property's `base_type` calls for.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum IoTPropertyValue {
     Int(i32),
+    Int64(i64),
+    Decimal(f64),
     Str(String),
     Bool(bool),
+    Json(Value),
+}
+
+/// The `base_type` Ayla reports for a property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IoTPropertyBaseType {
+    Integer,
+    Decimal,
+    Boolean,
+    String,
+    Json,
+    Location,
+    Message,
+    File,
+    Other(String),
+}
+
+impl From<&str> for IoTPropertyBaseType {
+    fn from(base_type: &str) -> Self {
+        match base_type {
+            "integer" => Self::Integer,
+            "decimal" | "float" => Self::Decimal,
+            "boolean" => Self::Boolean,
+            "string" => Self::String,
+            "json" => Self::Json,
+            "location" => Self::Location,
+            "message" => Self::Message,
+            "file" => Self::File,
+            other => Self::Other(other.to_string()),
+        }
+    }
 }
 
 impl IoTPropertyValue {
@@ -50,10 +89,83 @@ impl IoTPropertyValue {
         }
     }
 
+    pub fn int64_value(&self) -> Option<i64> {
+        match &self {
+            Self::Int64(it) => Some(*it),
+            Self::Int(it) => Some(*it as i64),
+            Self::Bool(it) => Some(*it as i64),
+            _ => Option::None,
+        }
+    }
+
+    pub fn decimal_value(&self) -> Option<f64> {
+        match &self {
+            Self::Decimal(it) => Some(*it),
+            Self::Int(it) => Some(*it as f64),
+            Self::Int64(it) => Some(*it as f64),
+            _ => Option::None,
+        }
+    }
+
     pub fn string_value(&self) -> Option<&String> {
         match &self {
             Self::Str(it) => Some(it),
             _ => Option::None,
         }
     }
-}
\ No newline at end of file
+
+    pub fn json_value(&self) -> Option<&Value> {
+        match &self {
+            Self::Json(it) => Some(it),
+            _ => Option::None,
+        }
+    }
+
+    /// Latitude and longitude of a `location` value, sent by Ayla as `"<lat>,<long>"`.
+    pub fn location_value(&self) -> Option<(f64, f64)> {
+        let (lat, long) = self.string_value()?.split_once(',')?;
+        let lat = lat.trim().parse::<f64>().ok()?;
+        let long = long.trim().parse::<f64>().ok()?;
+        if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&long) {
+            Some((lat, long))
+        } else {
+            None
+        }
+    }
+
+    /// Convert the value to the variant a property of `base_type` holds, e.g. `Int(1)` to
+    /// `Decimal(1.0)` for a decimal property or a JSON string to `Json` for a json property.
+    /// Fails when the value can't represent that base type.
+    pub fn for_base_type(self, base_type: &IoTPropertyBaseType) -> Result<Self, CloudCoreError> {
+        let converted = match (base_type, &self) {
+            (IoTPropertyBaseType::Integer, Self::Int(_) | Self::Int64(_)) => Some(self.clone()),
+            (IoTPropertyBaseType::Integer, Self::Decimal(it)) if it.fract() == 0.0 => Some(Self::integer(*it as i64)),
+            (IoTPropertyBaseType::Integer, Self::Str(it)) => it.trim().parse::<i64>().ok().map(Self::integer),
+            (IoTPropertyBaseType::Decimal, Self::Str(it)) => it.trim().parse::<f64>().ok().map(Self::Decimal),
+            (IoTPropertyBaseType::Decimal, _) => self.decimal_value().map(Self::Decimal),
+            // Ayla sends booleans as 0 and 1
+            (IoTPropertyBaseType::Boolean, Self::Bool(_) | Self::Int(0 | 1)) => Some(self.clone()),
+            (IoTPropertyBaseType::String | IoTPropertyBaseType::File, Self::Str(_)) => Some(self.clone()),
+            (IoTPropertyBaseType::Json, Self::Json(_)) => Some(self.clone()),
+            (IoTPropertyBaseType::Json, Self::Str(it)) => serde_json::from_str::<Value>(it).ok().map(Self::Json),
+            (IoTPropertyBaseType::Message, Self::Json(_)) => Some(self.clone()),
+            (IoTPropertyBaseType::Message, Self::Str(it)) => Some(match serde_json::from_str::<Value>(it) {
+                Ok(json) if json.is_object() || json.is_array() => Self::Json(json),
+                _ => self.clone(),
+            }),
+            (IoTPropertyBaseType::Location, Self::Str(_)) => self.location_value().map(|_| self.clone()),
+            (IoTPropertyBaseType::Other(_), _) => Some(self.clone()),
+            _ => None,
+        };
+        converted.ok_or_else(|| {
+            CloudCoreError::InvalidInput(InputError::InvalidPropertyValue(format!("{:?} is not a valid {:?} value", self, base_type)))
+        })
+    }
+
+    fn integer(value: i64) -> Self {
+        match i32::try_from(value) {
+            Ok(value) => Self::Int(value),
+            Err(_) => Self::Int64(value),
+        }
+    }
+}
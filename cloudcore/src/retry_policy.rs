@@ -0,0 +1,132 @@
+#[cfg(feature = "library")]
+use std::time::Duration;
+#[cfg(feature = "library")]
+use rand::Rng;
+#[cfg(feature = "library")]
+use reqwest::Method;
+
+#[cfg(feature = "library")]
+static DEFAULT_MAX_ATTEMPTS: u32 = 3;
+#[cfg(feature = "library")]
+static DEFAULT_INITIAL_BACKOFF_MILLIS: u64 = 500;
+#[cfg(feature = "library")]
+static DEFAULT_MAX_BACKOFF_MILLIS: u64 = 10_000;
+#[cfg(feature = "library")]
+static DEFAULT_RETRYABLE_STATUSES: [u16; 6] = [408, 429, 500, 502, 503, 504];
+
+/// How `send_request` retries a failed Ayla call.
+///
+/// GET, PUT and DELETE are retried on timeouts, connection errors and the retryable statuses.
+/// POST creates things on Ayla (datapoints, schedules...) so it is only retried when the
+/// connection could not be made, unless `with_retry_non_idempotent` is set.
+/// A `Retry-After` header is waited for when it fits within `max_backoff`, otherwise the
+/// error is returned.
+#[cfg(feature = "library")]
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    retryable_statuses: Vec<u16>,
+    retry_non_idempotent: bool,
+}
+
+#[cfg(feature = "library")]
+impl RetryPolicy {
+    /// `max_attempts` includes the first try. The backoff doubles after each attempt up to `max_backoff`.
+    pub fn new(max_attempts: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff,
+            jitter: true,
+            retryable_statuses: DEFAULT_RETRYABLE_STATUSES.to_vec(),
+            retry_non_idempotent: false,
+        }
+    }
+
+    /// Send every request once.
+    pub fn none() -> Self {
+        Self::new(1, Duration::ZERO, Duration::ZERO)
+    }
+
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_retryable_statuses(mut self, retryable_statuses: Vec<u16>) -> Self {
+        self.retryable_statuses = retryable_statuses;
+        self
+    }
+
+    pub fn with_retry_non_idempotent(mut self, retry_non_idempotent: bool) -> Self {
+        self.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+
+    /// Get the retry policy's max attempts.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+    /// Get the retry policy's initial backoff.
+    pub fn initial_backoff(&self) -> Duration {
+        self.initial_backoff
+    }
+    /// Get the retry policy's max backoff.
+    pub fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+    /// Get the retry policy's jitter.
+    pub fn jitter(&self) -> bool {
+        self.jitter
+    }
+    /// Get a reference to the retry policy's retryable statuses.
+    pub fn retryable_statuses(&self) -> &[u16] {
+        self.retryable_statuses.as_ref()
+    }
+    /// Get the retry policy's retry non idempotent.
+    pub fn retry_non_idempotent(&self) -> bool {
+        self.retry_non_idempotent
+    }
+
+    pub(crate) fn is_idempotent(&self, method: &Method) -> bool {
+        self.retry_non_idempotent || *method != Method::POST
+    }
+
+    pub(crate) fn should_retry_status(&self, method: &Method, status: u16) -> bool {
+        self.is_idempotent(method) && self.retryable_statuses.contains(&status)
+    }
+
+    pub(crate) fn should_retry_error(&self, method: &Method, err: &reqwest::Error) -> bool {
+        // A refused connection never reached Ayla, so even a POST is safe to send again
+        err.is_connect() || (self.is_idempotent(method) && err.is_timeout())
+    }
+
+    /// Wait before the attempt after `attempt` (1 based), doubled each time and capped at `max_backoff`.
+    /// With jitter the wait is picked between half and all of it.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self.initial_backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_backoff);
+        if self.jitter && !backoff.is_zero() {
+            let millis = backoff.as_millis() as u64;
+            Duration::from_millis(rand::thread_rng().gen_range(millis / 2..=millis))
+        } else {
+            backoff
+        }
+    }
+}
+
+#[cfg(feature = "library")]
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_MAX_ATTEMPTS,
+            Duration::from_millis(DEFAULT_INITIAL_BACKOFF_MILLIS),
+            Duration::from_millis(DEFAULT_MAX_BACKOFF_MILLIS),
+        )
+    }
+}
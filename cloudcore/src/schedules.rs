@@ -12,7 +12,7 @@ use serde::Deserialize;
 #[cfg(feature = "library")]
 use serde::Serialize;
 #[cfg(feature = "library")]
-use std::error::Error;
+use crate::CloudCoreError;
 #[cfg(feature = "library")]
 use log::error;
 #[cfg(feature = "library")]
@@ -20,7 +20,9 @@ use crate::properties::property::{PROPS_PATH_PARAMS_DSN, PROPS_PATH_PARAMS_PROP_
 #[cfg(feature = "library")]
 use crate::urls;
 #[cfg(feature = "library")]
-use crate::ErrorUtil;
+use crate::cloudcore_client::{CloudCoreBaseURL, CloudCoreRequest};
+#[cfg(feature = "library")]
+use reqwest::Method;
 
 #[cfg(feature = "library")]
 #[derive(Debug, Deserialize)]
@@ -38,20 +40,8 @@ impl CloudCore {
         start_time_each_day: String,
         action_name: String,
         action_base_type: String
-    ) -> Result<Schedule, Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-
-        let mut url = String::from(&self.session_params().device_url);
+    ) -> Result<Schedule, CloudCoreError> {
         let endpoint = String::from(urls::AYLA_DEVICE_SCHEDULE_JSON).replace(PROPS_PATH_PARAMS_DSN, &dsn);
-        url.push_str(&endpoint);
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-
-        let auth_bearer = format!("auth_token {}", token);
-
-        let client = self.client();
 
         // local structs for schedules request
         #[derive(Serialize, Debug)]
@@ -101,27 +91,25 @@ impl CloudCore {
 
         debug!("create device schedules request data: {:#?}", post_data);
 
-        let response = client
-            .post(url)
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .json(&post_data)
-            .send()
-            .await?;
-
-        if response.status().is_success() {
-            let create_account_payload = response.json::<ScheduleResponse>().await?;
-            debug!("create device schedules payload: {:#?}", create_account_payload);
-            Ok(create_account_payload.schedule)
-        } else {
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint,
+                method: Method::POST,
+                requires_auth: true,
+                query: vec![],
+                body: Some(post_data),
+            }
+        ).await?;
+        let create_account_payload = response.json::<ScheduleResponse>().await?;
+        debug!("create device schedules payload: {:#?}", create_account_payload);
+        Ok(create_account_payload.schedule)
     }
 
     pub async fn fetch_schedules(
         &self,
         device_id: Option<u32>,
-    ) -> Result<Vec<Schedule>, Box<dyn Error>> {
+    ) -> Result<Vec<Schedule>, CloudCoreError> {
         match device_id {
             Some(val) => {
                 let schedules = self.fetch_device_schedules(val).await?;
@@ -137,27 +125,20 @@ impl CloudCore {
     pub async fn fetch_device_schedules(
         &self,
         device_id: u32
-    ) -> Result<Vec<Schedule>, Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-
-        let mut url = String::from(&self.session_params().user_url);
+    ) -> Result<Vec<Schedule>, CloudCoreError> {
         let endpoint = String::from(urls::AYLA_DEVICE_ID_SCHEDULE_JSON)
                     .replace(PROPS_PATH_PARAMS_DSN, &device_id.to_string());
-        url.push_str(&endpoint);
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-
-        let auth_bearer = format!("auth_token {}", token);
-
-        let client = self.client();
-
-        let response = client
-            .get(url)
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::USER,
+                endpoint,
+                method: Method::GET,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
         let schedules_payload = response.json::<Vec<ScheduleResponse>>().await?;
         let mut schedules: Vec<Schedule> = vec![];
         schedules_payload.into_iter().for_each(|response| {
@@ -169,25 +150,18 @@ impl CloudCore {
 
     pub async fn fetch_all_schedules(
         &self
-    ) -> Result<Vec<Schedule>, Box<dyn Error>> {
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-
-        let mut url = String::from(&self.session_params().device_url);
-        url.push_str(urls::AYLA_DEVICE_USER_SCHEDULES_JSON);
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-
-        let auth_bearer = format!("auth_token {}", token);
-
-        let client = self.client();
-
-        let response = client
-            .get(url)
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .send()
-            .await?;
+    ) -> Result<Vec<Schedule>, CloudCoreError> {
+        let body: Option<String> = None;
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint: String::from(urls::AYLA_DEVICE_USER_SCHEDULES_JSON),
+                method: Method::GET,
+                requires_auth: true,
+                query: vec![],
+                body,
+            }
+        ).await?;
         let schedules_payload = response.json::<Vec<ScheduleResponse>>().await?;
         let mut schedules: Vec<Schedule> = vec![];
         schedules_payload.into_iter().for_each(|response| {
@@ -200,23 +174,10 @@ impl CloudCore {
     pub async fn update_schedule(
         &self,
         schedule: Schedule,
-    ) -> Result<Schedule, Box<dyn Error>> {
-
-        if self.user_session.is_none() {
-            return Err(Box::new(ErrorUtil::user_session_not_found_error()));
-        }
-
-        let mut url = String::from(&self.session_params().device_url);
+    ) -> Result<Schedule, CloudCoreError> {
         let endpoint = String::from(urls::AYLA_DEVICE_UPDATE_SCHEDULE_JSON)
                                 .replace(PROPS_PATH_PARAMS_DSN, &schedule.device_id().unwrap().to_string())
                                 .replace(PROPS_PATH_PARAMS_PROP_NAME, &schedule.key().unwrap().to_string());
-        url.push_str(&endpoint);
-
-        let token = self.user_session.as_ref().unwrap().access_token();
-
-        let auth_bearer = format!("auth_token {}", token);
-
-        let client = self.client();
 
         // local structs for schedules request
         #[derive(Serialize, Debug)]
@@ -245,24 +206,22 @@ impl CloudCore {
 
         debug!("update device schedules request data: {:#?}", post_data);
 
-        let response = client
-            .put(url)
-            .header(urls::AUTHORIZATION_HEADER, auth_bearer)
-            .json(&post_data)
-            .send()
-            .await?;
-
-        if response.status().is_success() {
-            let update_account_payload = response.json::<ScheduleResponse>().await?;
-            debug!("update device schedules payload: {:#?}", update_account_payload);
-            Ok(update_account_payload.schedule)
-        } else {
-            let error_payload = response.text().await?;
-            Err(Box::new(ErrorUtil::server_error(error_payload)))
-        }
+        let response = self.send_request(
+            CloudCoreRequest {
+                base_url: CloudCoreBaseURL::DEVICE,
+                endpoint,
+                method: Method::PUT,
+                requires_auth: true,
+                query: vec![],
+                body: Some(post_data),
+            }
+        ).await?;
+        let update_account_payload = response.json::<ScheduleResponse>().await?;
+        debug!("update device schedules payload: {:#?}", update_account_payload);
+        Ok(update_account_payload.schedule)
     }
 
-    pub async fn clear_schedules(&self, key: u32) -> Result<(), Box<dyn Error>> {
+    pub async fn clear_schedules(&self, key: u32) -> Result<(), CloudCoreError> {
         let schedules = self.fetch_schedules(Some(key)).await?;
         // TODO: To do concurrently need to have a callback passed
         for mut sched in schedules {
@@ -0,0 +1,38 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+
+/// What `Debug` shows in place of a password, token or key.
+pub static REDACTED: &str = "<redacted>";
+
+/// A password, token or key. It is serialized as the value itself, so request payloads and the
+/// cache are unchanged, but `Debug` never shows it, so anything holding one can be logged.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The value itself, for sending it where it is needed. Never log it.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(REDACTED, f)
+    }
+}
@@ -1,7 +1,9 @@
 #[cfg(feature = "library")]
 use std::collections::HashMap;
 #[cfg(feature = "library")]
-use std::error::Error;
+use crate::CloudCoreError;
+#[cfg(feature = "library")]
+use crate::cloudcore_error::InputError;
 #[cfg(feature = "library")]
 use std::str::FromStr;
 #[cfg(feature = "library")]
@@ -53,7 +55,7 @@ impl CloudCore {
         registration_id: Option<String>,
         service: String,
         errors: HashMap<String, HashMap<u32, String>>,
-        callback: fn(result: Result<Vec<IoTTrigger>, Box<dyn Error>>),
+        callback: fn(result: Result<Vec<IoTTrigger>, CloudCoreError>),
     ) {
         debug!("DEBUG: create_error_push_triggers_callback");
         #[derive(Serialize, Debug)]
@@ -96,7 +98,7 @@ impl CloudCore {
             return;
         }
         if (service == NotificationService::PushAndroidFcm || service == NotificationService::PushiOS) && registration_id.is_none() {
-            callback(Err("Missing registration_id".into()));
+            callback(Err(CloudCoreError::InvalidInput(InputError::Invalid("Missing registration_id".to_string()))));
         }
         let registration_id = registration_id.unwrap_or("".to_string());
         let len = Arc::new(Mutex::new(AtomicUsize::new(0)));
@@ -185,7 +187,7 @@ impl CloudCore {
         property_name: String,
         trigger_request: TriggerRequest,
         trigger_app_request: TriggerAppRequest,
-    ) -> Result<IoTTrigger, Box<dyn Error>> {
+    ) -> Result<IoTTrigger, CloudCoreError> {
         let mut trigger = self.create_trigger(
             dsn, property_name, trigger_request,
         ).await?;
@@ -200,7 +202,7 @@ impl CloudCore {
         dsn: String,
         property_name: String,
         trigger_request: TriggerRequest,
-    ) -> Result<IoTTrigger, Box<dyn Error>> {
+    ) -> Result<IoTTrigger, CloudCoreError> {
         #[derive(Debug, Deserialize, Serialize)]
         struct TriggerResponse {
             trigger: IoTTrigger,
@@ -224,6 +226,7 @@ impl CloudCore {
                 endpoint,
                 method: Method::POST,
                 requires_auth: true,
+                query: vec![],
                 body: Some(request_body),
             }
         ).await?;
@@ -236,7 +239,7 @@ impl CloudCore {
         &self,
         trigger_key: u32,
         trigger_app_request: TriggerAppRequest,
-    ) -> Result<IoTTriggerApp, Box<dyn Error>> {
+    ) -> Result<IoTTriggerApp, CloudCoreError> {
         #[derive(Debug, Deserialize, Serialize)]
         struct TriggerAppResponse {
             trigger_app: IoTTriggerApp,
@@ -262,6 +265,7 @@ impl CloudCore {
                 endpoint,
                 method: Method::POST,
                 requires_auth: true,
+                query: vec![],
                 body: Some(request_body),
             }).await?;
 
@@ -273,7 +277,7 @@ impl CloudCore {
         &mut self,
         dsn: String,
         prop_name: String,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         let triggers = self.fetch_triggers(dsn, prop_name).await?;
 
         for trigger in triggers {
@@ -285,7 +289,7 @@ impl CloudCore {
     pub async fn delete_trigger(
         &mut self,
         trigger_key: u32,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         let endpoint = String::from(AYLA_TRIGGER_JSON)
             .replace(PROPS_PATH_PARAMS_TRIGGER_KEY, &*trigger_key.to_string());
 
@@ -295,13 +299,14 @@ impl CloudCore {
                 endpoint,
                 method: Method::DELETE,
                 requires_auth: true,
+                query: vec![],
                 body: Some(""),
             }
         ).await?;
         if response.status().is_success() {
             Ok(())
         } else {
-            Err("Unable to remove trigger".into())
+            Err(CloudCoreError::from_response(response).await)
         }
     }
 
@@ -310,7 +315,7 @@ impl CloudCore {
         dsn: String,
         prop_name: String,
         device_id: String,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         let trigger_apps = self.fetch_trigger_apps_for_device_id(dsn, prop_name, device_id).await?;
         if trigger_apps.len() > 0 {
             let mut all_success = true;
@@ -324,14 +329,14 @@ impl CloudCore {
             Ok(())
         } else {
             debug!("No trigger apps to delete.");
-            Err("No apps to delete".into())
+            Err(CloudCoreError::NotFound("No apps to delete".to_string()))
         }
     }
 
     pub async fn delete_trigger_app(
         &self,
         trigger_app_key: u32,
-    ) -> Result<bool, Box<dyn Error>> {
+    ) -> Result<bool, CloudCoreError> {
         let endpoint = String::from(AYLA_TRIGGER_APP)
             .replace(PROPS_PATH_PARAMS_TRIGGER_APP_KEY, &*trigger_app_key.to_string());
 
@@ -341,6 +346,7 @@ impl CloudCore {
                 endpoint,
                 method: Method::DELETE,
                 requires_auth: true,
+                query: vec![],
                 body: Some(""),
             }
         ).await?;
@@ -351,7 +357,7 @@ impl CloudCore {
         &mut self,
         dsn: String,
         prop_name: String,
-    ) -> Result<Vec<IoTTrigger>, Box<dyn Error>> {
+    ) -> Result<Vec<IoTTrigger>, CloudCoreError> {
         #[derive(Debug, Deserialize, Serialize)]
         struct TriggerResponse {
             trigger: IoTTrigger,
@@ -368,6 +374,7 @@ impl CloudCore {
                 endpoint,
                 method: Method::GET,
                 requires_auth: true,
+                query: vec![],
                 body,
             }
         ).await?;
@@ -386,7 +393,7 @@ impl CloudCore {
         prop_name: String,
         device_id: String,
         registration_id: String,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         let triggers = self.fetch_trigger_apps_for_device_id(dsn, prop_name, device_id).await?;
         let trigger_count = triggers.len();
         if trigger_count > 0 {
@@ -409,7 +416,7 @@ impl CloudCore {
             Ok(())
         } else {
             debug!("No trigger apps to update.");
-            Err("No apps to update".into())
+            Err(CloudCoreError::NotFound("No apps to update".to_string()))
         }
     }
 
@@ -417,7 +424,7 @@ impl CloudCore {
         &self,
         trigger_app_key: u32,
         trigger_app_request: TriggerAppRequest,
-    ) -> Result<IoTTriggerApp, Box<dyn Error>> {
+    ) -> Result<IoTTriggerApp, CloudCoreError> {
         #[derive(Debug, Deserialize, Serialize)]
         struct TriggerAppResponse {
             trigger_app: IoTTriggerApp,
@@ -440,6 +447,7 @@ impl CloudCore {
             endpoint: endpoint.to_string(),
             method: Method::PUT,
             requires_auth: true,
+            query: vec![],
             body: Some(request_body),
         };
         let response = self.send_request(req).await?;
@@ -451,7 +459,7 @@ impl CloudCore {
         &self,
         trigger_app: &IoTTriggerApp,
         registration_id: String,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), CloudCoreError> {
         let app = trigger_app.clone();
         let iot = IoTTriggerApp {
             param1: Some(registration_id),
@@ -476,7 +484,7 @@ impl CloudCore {
         dsn: String,
         prop_name: String,
         device_id: String,
-    ) -> Result<Vec<IoTTriggerApp>, Box<dyn Error>> {
+    ) -> Result<Vec<IoTTriggerApp>, CloudCoreError> {
         let triggers = self.fetch_triggers(
             dsn,
             prop_name,
@@ -29,6 +29,8 @@ pub static AYLA_PROPS_JSON: &str = "/apiv1/dsns/<dsn>/properties.json";
 pub static AYLA_PROPS_DATAPOINTS_JSON: &str =
     "/apiv1/dsns/<dsn>/properties/<prop_name>/datapoints.json";
 #[cfg(feature = "library")]
+pub static AYLA_BATCH_DATAPOINTS_JSON: &str = "/apiv1/batch_datapoints.json";
+#[cfg(feature = "library")]
 pub static AYLA_PROPS_MSG_DATAPOINTS_JSON: &str =
     "/apiv1/dsns/<dsn>/properties/<prop_name>/message_datapoints.json";
 #[cfg(feature = "library")]
@@ -40,6 +42,12 @@ pub static AYLA_DATAPOINTS_FILTER_SINCE_DATE_KEY: &str = "filter[created_at_sinc
 pub static AYLA_DATAPOINTS_FILTER_END_DATE_KEY: &str = "filter[created_at_end_date]";
 #[cfg(feature = "library")]
 pub static AYLA_DATAPOINTS_LIMIT_KEY: &str = "limit";
+#[cfg(feature = "library")]
+pub static AYLA_DATAPOINTS_PAGINATED_KEY: &str = "paginated";
+#[cfg(feature = "library")]
+pub static AYLA_DATAPOINTS_PAGE_KEY: &str = "page";
+#[cfg(feature = "library")]
+pub static AYLA_DATAPOINTS_PER_PAGE_KEY: &str = "per_page";
 
 #[cfg(feature = "library")]
 pub static AYLA_USER_PROFILE_JSON: &str = "/users/get_user_profile.json";
@@ -72,4 +80,17 @@ pub static AYLA_TRIGGER_JSON: &str =
     "/apiv1/triggers/<trigger_key>.json";
 #[cfg(feature = "library")]
 pub static AYLA_TRIGGER_APP: &str =
-    "/apiv1/trigger_apps/<trigger_app_key>.json";
\ No newline at end of file
+    "/apiv1/trigger_apps/<trigger_app_key>.json";
+
+#[cfg(feature = "library")]
+pub static AYLA_LAN_JSON: &str = "/apiv1/dsns/<dsn>/lan.json";
+/// Served by the device, registers the app for LAN mode.
+#[cfg(feature = "library")]
+pub static LAN_LOCAL_REG_JSON: &str = "/local_reg.json";
+/// Served by the app, called by the device once registered.
+#[cfg(feature = "library")]
+pub static LAN_KEY_EXCHANGE_JSON: &str = "/local_lan/key_exchange.json";
+#[cfg(feature = "library")]
+pub static LAN_COMMANDS_JSON: &str = "/local_lan/commands.json";
+#[cfg(feature = "library")]
+pub static LAN_DATAPOINT_JSON: &str = "/local_lan/property/datapoint.json";
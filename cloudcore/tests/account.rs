@@ -0,0 +1,72 @@
+mod common;
+
+use common::setup;
+
+#[tokio::test]
+async fn create_confirm_and_login() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    let email = "new.user@example.com";
+
+    cloudcore
+        .create_account("Secret123!".to_string(), Some(email.to_string()), None, None, None, None)
+        .await
+        .unwrap();
+    // Unconfirmed accounts can not sign in yet
+    assert!(cloudcore.login(Some(email.to_string()), None, "Secret123!".to_string()).await.is_err());
+
+    let token = context.server.confirmation_token(email).unwrap();
+    cloudcore.confirm_account(token).await.unwrap();
+    cloudcore.login(Some(email.to_string()), None, "Secret123!".to_string()).await.unwrap();
+
+    assert!(cloudcore.logged_in());
+}
+
+#[tokio::test]
+async fn reset_password_with_emailed_token() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+
+    cloudcore
+        .request_password_reset(Some(common::EMAIL.to_string()), None, None, None, None)
+        .await
+        .unwrap();
+    let token = context.server.reset_password_token(common::EMAIL).unwrap();
+    cloudcore
+        .reset_password(token, "Changed123!".to_string(), "Changed123!".to_string())
+        .await
+        .unwrap();
+
+    assert!(cloudcore.login(Some(common::EMAIL.to_string()), None, common::PASSWORD.to_string()).await.is_err());
+    cloudcore.login(Some(common::EMAIL.to_string()), None, "Changed123!".to_string()).await.unwrap();
+}
+
+#[tokio::test]
+async fn change_password_for_current_user_ends_session() {
+    let context = common::setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    cloudcore
+        .reset_password_for_current_user(common::PASSWORD.to_string(), "Changed123!".to_string())
+        .await
+        .unwrap();
+
+    assert!(cloudcore.get_session().is_err());
+    cloudcore.login(Some(common::EMAIL.to_string()), None, "Changed123!".to_string()).await.unwrap();
+}
+
+#[tokio::test]
+async fn update_email_and_delete_account() {
+    let context = common::setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let new_email = "moved@example.com";
+
+    cloudcore.update_email(new_email.to_string()).await.unwrap();
+    assert!(cloudcore.get_session().is_err());
+
+    cloudcore.login(Some(new_email.to_string()), None, common::PASSWORD.to_string()).await.unwrap();
+    cloudcore.delete_account().await.unwrap();
+
+    assert!(cloudcore.get_session().is_err());
+    assert!(cloudcore.login(Some(new_email.to_string()), None, common::PASSWORD.to_string()).await.is_err());
+}
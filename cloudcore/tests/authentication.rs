@@ -0,0 +1,61 @@
+mod common;
+
+use common::{setup, setup_logged_in, EMAIL, PASSWORD};
+use cloudcore::cloudcore_error::AuthError;
+use cloudcore::CloudCoreError;
+
+#[tokio::test]
+async fn login_creates_session_with_user_uuid() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+
+    cloudcore.login(Some(EMAIL.to_string()), None, PASSWORD.to_string()).await.unwrap();
+
+    assert!(cloudcore.logged_in());
+    let session = cloudcore.get_session().unwrap();
+    assert_eq!(session.auth_username(), EMAIL);
+    let profile = cloudcore.get_user_profile().await.unwrap();
+    assert_eq!(session.user_uuid(), Some(&profile.uuid().to_string()));
+}
+
+#[tokio::test]
+async fn login_with_wrong_password_is_rejected() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+
+    let err = cloudcore
+        .login(Some(EMAIL.to_string()), None, "not-the-password".to_string())
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, CloudCoreError::Auth(AuthError::InvalidCredentials(_))));
+    assert!(!cloudcore.logged_in());
+}
+
+#[tokio::test]
+async fn refresh_session_replaces_tokens() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let before = cloudcore.get_session().unwrap();
+
+    cloudcore.refresh_session().await.unwrap();
+
+    let after = cloudcore.get_session().unwrap();
+    assert_ne!(before.access_token(), after.access_token());
+    assert_ne!(before.refresh_token(), after.refresh_token());
+    assert_eq!(before.user_uuid(), after.user_uuid());
+    assert!(cloudcore.get_user_profile().await.is_ok());
+}
+
+#[tokio::test]
+async fn logout_clears_session() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    cloudcore.logout().await.unwrap();
+
+    assert!(cloudcore.get_session().is_err());
+    assert!(!cloudcore.logged_in());
+    let requests = context.server.requests();
+    assert!(requests.iter().any(|request| request.path == "/users/sign_out.json"));
+}
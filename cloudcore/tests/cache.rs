@@ -0,0 +1,96 @@
+mod common;
+
+use std::fs::{read_to_string, write};
+
+use cloudcore::authentication::CACHE_USER_DIR;
+use cloudcore::cache::{CacheDataValue, CacheInteract};
+use cloudcore::cloudcore::CACHE_APP_DIR;
+use cloudcore::CloudCore;
+use common::{cached_string, os_dir, setup, setup_logged_in, store_path};
+
+#[tokio::test]
+async fn stores_are_written_whole_with_a_checksum_header() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+
+    cloudcore.cache.set_value(CACHE_APP_DIR.to_string(), "robot".to_string(), "Kitchen").unwrap();
+
+    let store = read_to_string(store_path(cloudcore, CACHE_APP_DIR)).unwrap();
+    let (header, body) = store.split_once('\n').unwrap();
+    let checksum = header.strip_prefix("cloudcore-store-v1 ").unwrap();
+    assert_eq!(checksum.len(), 64);
+    assert!(checksum.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(body, r#"{"data":{"robot":"Kitchen"}}"#);
+    assert!(!cloudcore.cache.parent_path().join(CACHE_APP_DIR).join(".store.tmp").exists());
+}
+
+#[tokio::test]
+async fn a_corrupt_store_is_discarded() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.cache.set_value(CACHE_APP_DIR.to_string(), "robot".to_string(), "Kitchen").unwrap();
+    let path = store_path(cloudcore, CACHE_APP_DIR);
+    let store = read_to_string(&path).unwrap();
+    write(&path, store.replace("Kitchen", "Kitchex")).unwrap();
+
+    assert_eq!(cached_string(cloudcore, "robot"), None);
+
+    cloudcore.cache.set_value(CACHE_APP_DIR.to_string(), "dock".to_string(), "Hallway").unwrap();
+    assert_eq!(cached_string(cloudcore, "dock"), Some("Hallway".to_string()));
+}
+
+#[tokio::test]
+async fn stores_from_before_the_header_are_read_and_upgraded() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    let path = store_path(cloudcore, CACHE_APP_DIR);
+    write(&path, r#"{"data":{"robot":"Kitchen"}}"#).unwrap();
+
+    assert_eq!(cached_string(cloudcore, "robot"), Some("Kitchen".to_string()));
+
+    cloudcore.cache.set_value(CACHE_APP_DIR.to_string(), "dock".to_string(), "Hallway").unwrap();
+    assert!(read_to_string(&path).unwrap().starts_with("cloudcore-store-v1 "));
+    assert_eq!(cached_string(cloudcore, "robot"), Some("Kitchen".to_string()));
+
+    // A legacy store cut off mid write is discarded too
+    write(&path, r#"{"data":{"robot":"Kit"#).unwrap();
+    assert_eq!(cached_string(cloudcore, "robot"), None);
+}
+
+#[tokio::test]
+async fn a_left_over_temporary_file_does_not_change_the_store() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.cache.set_value(CACHE_APP_DIR.to_string(), "robot".to_string(), "Kitchen").unwrap();
+    // What a crash before the rename leaves behind
+    write(cloudcore.cache.parent_path().join(CACHE_APP_DIR).join(".store.tmp"), "cloudcore-store-v1 ").unwrap();
+
+    let _cc = CloudCore::new(os_dir(cloudcore));
+    let cloudcore = CloudCore::shared();
+
+    assert_eq!(cached_string(cloudcore, "robot"), Some("Kitchen".to_string()));
+    cloudcore.cache.set_value(CACHE_APP_DIR.to_string(), "robot".to_string(), "Hallway").unwrap();
+    assert_eq!(cached_string(cloudcore, "robot"), Some("Hallway".to_string()));
+}
+
+#[tokio::test]
+async fn a_corrupt_user_session_signs_the_user_out_instead_of_panicking() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let os_dir = os_dir(cloudcore);
+    let path = store_path(cloudcore, CACHE_USER_DIR);
+
+    // Truncated mid write
+    let store = read_to_string(&path).unwrap();
+    write(&path, &store[..store.len() / 2]).unwrap();
+    let _cc = CloudCore::new(os_dir.clone());
+    assert!(CloudCore::shared().user_session.is_none());
+
+    // Intact but not a session
+    write(&path, r#"{"data":{"session":{"access_token":42}}}"#).unwrap();
+    let _cc = CloudCore::new(os_dir);
+    let cloudcore = CloudCore::shared();
+    assert!(cloudcore.user_session.is_none());
+    let session = cloudcore.cache.get_value(CACHE_USER_DIR.to_string(), "session".to_string()).unwrap();
+    assert!(matches!(session, CacheDataValue::NullValue));
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use cloudcore::authentication::CACHE_USER_DIR;
+use cloudcore::cache::CacheInteract;
+use cloudcore::cloudcore::CACHE_APP_DIR;
+use cloudcore::cloudcore_error::CacheError;
+use cloudcore::{CloudCore, CloudCoreError};
+use common::{cached_string, os_dir, setup, setup_logged_in, store};
+
+// 32 bytes each, in base64
+static KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+static OTHER_KEY: &str = "ZmVkY2JhOTg3NjU0MzIxMGZlZGNiYTk4NzY1NDMyMTA=";
+
+#[tokio::test]
+async fn plain_stores_are_encrypted_when_the_key_is_set() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let access_token = cloudcore.get_session().unwrap().access_token().to_string();
+    cloudcore.cache.set_value(CACHE_APP_DIR.to_string(), "robot".to_string(), "Kitchen").unwrap();
+    assert!(store(cloudcore, CACHE_USER_DIR).contains(&access_token));
+
+    cloudcore.set_cache_encryption_key(KEY.to_string()).unwrap();
+
+    for child in [CACHE_USER_DIR, CACHE_APP_DIR] {
+        let store = store(cloudcore, child);
+        assert!(store.starts_with("cloudcore-aes256gcm-v1:"), "{} store is {}", child, store);
+        assert!(!store.contains("data"));
+    }
+    assert!(!store(cloudcore, CACHE_USER_DIR).contains(&access_token));
+    assert!(cloudcore.cache.is_encrypted());
+    assert_eq!(cached_string(cloudcore, "robot"), Some("Kitchen".to_string()));
+
+    // Writes stay encrypted
+    cloudcore.cache.set_value(CACHE_APP_DIR.to_string(), "robot".to_string(), "Hallway").unwrap();
+    assert!(!store(cloudcore, CACHE_APP_DIR).contains("Hallway"));
+    assert_eq!(cached_string(cloudcore, "robot"), Some("Hallway".to_string()));
+}
+
+#[tokio::test]
+async fn session_is_loaded_once_the_key_is_given_after_a_restart() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let session = cloudcore.get_session().unwrap();
+    cloudcore.set_cache_encryption_key(KEY.to_string()).unwrap();
+    let os_dir = os_dir(cloudcore);
+
+    let _cc = CloudCore::new(os_dir);
+    let cloudcore = CloudCore::shared();
+    assert!(cloudcore.user_session.is_none());
+    let err = cloudcore.cache.get_value(CACHE_USER_DIR.to_string(), "session".to_string()).unwrap_err();
+    assert!(matches!(err, CloudCoreError::Cache(CacheError::Encryption(_))));
+
+    cloudcore.set_cache_encryption_key(KEY.to_string()).unwrap();
+
+    let restored = cloudcore.get_session().unwrap();
+    assert_eq!(restored.access_token(), session.access_token());
+    assert_eq!(restored.refresh_token(), session.refresh_token());
+}
+
+#[tokio::test]
+async fn stores_encrypted_with_another_key_are_emptied() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.set_cache_encryption_key(KEY.to_string()).unwrap();
+    let os_dir = os_dir(cloudcore);
+
+    let _cc = CloudCore::new(os_dir);
+    let cloudcore = CloudCore::shared();
+    cloudcore.set_cache_encryption_key(OTHER_KEY.to_string()).unwrap();
+
+    assert!(cloudcore.user_session.is_none());
+    assert_eq!(cached_string(cloudcore, "robot"), None);
+    cloudcore.cache.set_value(CACHE_APP_DIR.to_string(), "robot".to_string(), "Kitchen").unwrap();
+    assert_eq!(cached_string(cloudcore, "robot"), Some("Kitchen".to_string()));
+}
+
+#[tokio::test]
+async fn keys_that_are_not_32_bytes_of_base64_are_rejected() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+
+    for key in ["not base64!", "c2hvcnQ=", ""] {
+        let err = cloudcore.set_cache_encryption_key(key.to_string()).unwrap_err();
+        assert!(matches!(err, CloudCoreError::Cache(CacheError::Encryption(_))), "{:?}", err);
+    }
+    assert!(!cloudcore.cache.is_encrypted());
+    assert!(store(cloudcore, CACHE_APP_DIR).starts_with('{'));
+}
@@ -0,0 +1,121 @@
+#![allow(dead_code)]
+
+use std::fs::read_to_string;
+use std::path::PathBuf;
+
+use cloudcore::cache::{CacheDataValue, CacheInteract};
+use cloudcore::cloudcore::{ApplicationInfo, AylaRegionEnvironment, CACHE_APP_DIR};
+use cloudcore::mock::{MockAylaServer, MockDevice, MockProperty};
+use cloudcore::properties::value::IoTPropertyValue;
+use cloudcore::CloudCore;
+use lazy_static::lazy_static;
+use tokio::sync::{Mutex, MutexGuard};
+use uuid::Uuid;
+
+pub static APP_ID: &str = "cloudcore-test-id";
+pub static APP_SECRET: &str = "cloudcore-test-secret";
+pub static EMAIL: &str = "owner@example.com";
+pub static PASSWORD: &str = "Password1!";
+pub static DSN: &str = "AC000W000000001";
+pub static DEVICE_KEY: u32 = 101;
+pub static MOCK_REGION: &str = "mock";
+
+lazy_static! {
+    // CloudCore::shared() is process wide, so tests that create a CloudCore run one at a time
+    static ref SHARED_LOCK: Mutex<()> = Mutex::new(());
+}
+
+pub struct TestContext {
+    pub server: MockAylaServer,
+    pub cloudcore: &'static mut CloudCore,
+    _guard: MutexGuard<'static, ()>,
+}
+
+pub fn app_info() -> ApplicationInfo {
+    ApplicationInfo {
+        app_id: APP_ID.to_string(),
+        app_secret: APP_SECRET.to_string(),
+    }
+}
+
+pub fn robot() -> MockDevice {
+    MockDevice::new(DEVICE_KEY, DSN)
+        .with_property(MockProperty::output(201, "GET_Battery_Capacity", "integer", Some(IoTPropertyValue::Int(87))))
+        .with_property(MockProperty::output(202, "Get_ERROR_Code", "integer", Some(IoTPropertyValue::Int(0))))
+        .with_property(MockProperty::input(203, "SET_Operating_Mode", "integer", Some(IoTPropertyValue::Int(0))))
+        .with_property(MockProperty::input(204, "SET_Find_Device", "boolean", Some(IoTPropertyValue::Int(0))))
+        .with_property(MockProperty::input(205, "SET_Quiet_Time", "string", Some(IoTPropertyValue::Str("".to_string()))))
+        .with_property(MockProperty::output(206, "GET_Total_Runtime", "integer", Some(IoTPropertyValue::Int64(5_000_000_000))))
+        .with_property(MockProperty::input(207, "SET_Suction_Level", "decimal", Some(IoTPropertyValue::Int(1))))
+        .with_property(MockProperty::input(208, "SET_Dock_Location", "location", Some(IoTPropertyValue::Str("0.0,0.0".to_string()))))
+        .with_property(MockProperty::input(209, "SET_Room_Config", "json", Some(IoTPropertyValue::Str("{\"rooms\":[]}".to_string()))))
+        .with_property(MockProperty::input(210, "SET_Power_Mode", "integer", Some(IoTPropertyValue::Int(0))).with_ack_enabled())
+}
+
+/// How many `method` requests the mock service got for `path`.
+pub fn requests_to(server: &MockAylaServer, method: &str, path: &str) -> usize {
+    server
+        .requests()
+        .iter()
+        .filter(|request| request.method == method && request.path == path)
+        .count()
+}
+
+/// Where the cache keeps the `child` store of `cloudcore`.
+pub fn store_path(cloudcore: &CloudCore, child: &str) -> PathBuf {
+    cloudcore.cache.parent_path().join(child).join(".store")
+}
+
+/// The `child` store without its checksum header.
+pub fn store(cloudcore: &CloudCore, child: &str) -> String {
+    let store = read_to_string(store_path(cloudcore, child)).unwrap();
+    store.split_once('\n').unwrap().1.to_string()
+}
+
+/// The directory `cloudcore` was created with, to create it again as after an app restart.
+pub fn os_dir(cloudcore: &CloudCore) -> String {
+    cloudcore.cache.parent_path().parent().unwrap().to_string_lossy().to_string()
+}
+
+/// The app cache's string value for `key`.
+pub fn cached_string(cloudcore: &CloudCore, key: &str) -> Option<String> {
+    match cloudcore.cache.get_value(CACHE_APP_DIR.to_string(), key.to_string()).unwrap() {
+        CacheDataValue::StringValue(value) => Some(value),
+        _ => None,
+    }
+}
+
+pub fn mock_region() -> AylaRegionEnvironment {
+    AylaRegionEnvironment::Custom(MOCK_REGION.to_string())
+}
+
+/// Start a mock Ayla service with one user and one robot and point a fresh CloudCore at it.
+pub async fn setup() -> TestContext {
+    let guard = SHARED_LOCK.lock().await;
+    let server = MockAylaServer::start(app_info()).expect("mock Ayla service should start");
+    server.add_user(EMAIL, PASSWORD);
+    server.add_device(robot());
+
+    let os_dir = std::env::temp_dir().join(format!("cloudcore-tests-{}", Uuid::new_v4()));
+    let _cc = CloudCore::new(os_dir.to_string_lossy().to_string());
+    let cloudcore = CloudCore::shared();
+    cloudcore.register_ayla_region_environment(mock_region(), server.session_parameters());
+    cloudcore.set_ayla_region_resolver(|_, _| mock_region());
+
+    TestContext {
+        server,
+        cloudcore,
+        _guard: guard,
+    }
+}
+
+/// Same as [`setup`] with the user already signed in.
+pub async fn setup_logged_in() -> TestContext {
+    let context = setup().await;
+    context
+        .cloudcore
+        .login(Some(EMAIL.to_string()), None, PASSWORD.to_string())
+        .await
+        .expect("login against the mock Ayla service should succeed");
+    context
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use cloudcore::cache::CacheInteract;
+use cloudcore::cloudcore::CACHE_APP_DIR;
+use common::{setup_logged_in, DEVICE_KEY, DSN};
+
+#[tokio::test]
+async fn fetch_all_devices_creates_device_cache() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let devices = cloudcore.fetch_all_devices().await.unwrap();
+
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].dsn(), Some(&DSN.to_string()));
+    assert_eq!(devices[0].id(), Some(DEVICE_KEY));
+    assert!(cloudcore.cache.child_paths().contains_key(DSN));
+}
+
+#[tokio::test]
+async fn fetch_and_rename_device() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    cloudcore.rename_device_with_dsn(DSN.to_string(), "Kitchen".to_string()).await.unwrap();
+    let device = cloudcore.fetch_device_with_dsn(DSN.to_string()).await.unwrap();
+
+    assert_eq!(device.product_name(), Some(&"Kitchen".to_string()));
+    assert!(cloudcore.fetch_device_with_dsn("UNKNOWN".to_string()).await.is_err());
+}
+
+#[tokio::test]
+async fn device_time_zone() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let offset = cloudcore.device_timezone_offset(DEVICE_KEY).await.unwrap();
+    assert_eq!(offset, "-05:00");
+
+    cloudcore
+        .cache
+        .set_value(CACHE_APP_DIR.to_string(), "tz_id".to_string(), "Europe/London")
+        .unwrap();
+    cloudcore.set_device_time_zone(DSN.to_string()).await.unwrap();
+}
+
+#[tokio::test]
+async fn reset_and_unregister_device() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.fetch_all_devices().await.unwrap();
+
+    cloudcore.reset_device(DEVICE_KEY).await.unwrap();
+    cloudcore.unregister_device(DEVICE_KEY, DSN.to_string()).await.unwrap();
+
+    assert!(context.server.device(DSN).is_none());
+    assert!(!cloudcore.cache.child_paths().contains_key(DSN));
+    assert!(cloudcore.fetch_all_devices().await.unwrap().is_empty());
+}
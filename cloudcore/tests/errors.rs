@@ -0,0 +1,53 @@
+mod common;
+
+use cloudcore::{CloudCoreError, IntoMantleResult};
+use common::{setup, setup_logged_in};
+use mantle_utilities::{ErrorType, MantleError};
+
+#[tokio::test]
+async fn calls_without_session_return_session_missing() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+
+    let err = cloudcore.fetch_all_devices().await.unwrap_err();
+
+    assert!(matches!(err, CloudCoreError::SessionMissing));
+}
+
+#[tokio::test]
+async fn unknown_device_returns_http_status_and_body() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let err = cloudcore.fetch_device_with_dsn("AC000W999999999".to_string()).await.unwrap_err();
+
+    match err {
+        CloudCoreError::Http { status, body } => {
+            assert_eq!(status, 404);
+            assert!(body.contains("Not found"));
+        }
+        other => panic!("expected an Http error, got {:?}", other),
+    }
+}
+
+#[test]
+fn too_many_instances_is_detected_from_the_body() {
+    let err = CloudCoreError::Http {
+        status: 422,
+        body: r#"{"error":"too many instances had been created already"}"#.to_string(),
+    };
+    assert!(err.is_too_many_instances());
+    assert!(matches!(MantleError::from(err).error_type, ErrorType::TooManyInstancesError));
+
+    let err = CloudCoreError::Http { status: 422, body: "invalid".to_string() };
+    assert!(!err.is_too_many_instances());
+    assert!(matches!(MantleError::from(err).error_type, ErrorType::ServerError));
+}
+
+#[test]
+fn ffi_results_carry_a_mantle_error() {
+    let err = Err::<(), _>(CloudCoreError::SessionMissing).into_mantle_result().unwrap_err();
+
+    let err = err.downcast_ref::<MantleError>().expect("FFI results should carry a MantleError");
+    assert!(matches!(err.error_type, ErrorType::UserSessionNotFound));
+}
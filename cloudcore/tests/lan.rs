@@ -0,0 +1,87 @@
+mod common;
+
+use cloudcore::cloudcore_error::CloudCoreError;
+use cloudcore::devices::IoTDevice;
+use cloudcore::mock::MockLanDevice;
+use cloudcore::properties::value::IoTPropertyValue;
+use common::{robot, setup_logged_in, TestContext, DSN};
+use std::time::Duration;
+
+async fn fetch_robot(context: &mut TestContext) -> IoTDevice {
+    let devices = context.cloudcore.fetch_all_devices().await.unwrap();
+    devices.into_iter().find(|device| device.dsn() == Some(&DSN.to_string())).unwrap()
+}
+
+#[tokio::test]
+async fn lan_reads_and_writes_go_to_the_device() {
+    let mut context = setup_logged_in().await;
+    let lan = MockLanDevice::start(&robot()).unwrap();
+    context.server.add_device(robot().with_lan_ip(lan.address()));
+    let device = fetch_robot(&mut context).await;
+    let cloudcore = &context.cloudcore;
+
+    cloudcore.connect_lan(&device).await.unwrap();
+    assert!(cloudcore.lan_connected(DSN));
+
+    lan.set_property_value("GET_Battery_Capacity", IoTPropertyValue::Int(55));
+    context.server.clear_requests();
+    let (result, _) = cloudcore
+        .get_property(DSN.to_string(), "GET_Battery_Capacity".to_string(), "".to_string())
+        .await;
+    let properties = result.unwrap();
+    assert_eq!(properties.len(), 1);
+    assert_eq!(properties[0].value(), Some(&IoTPropertyValue::Int(55)));
+
+    let (result, _) = cloudcore
+        .set_property_value(DSN.to_string(), "SET_Operating_Mode".to_string(), IoTPropertyValue::Int(2), "".to_string())
+        .await;
+    result.unwrap();
+    // The write is done once the device fetched it, it applies it right after
+    for _ in 0..50 {
+        if lan.property_value("SET_Operating_Mode") == Some(IoTPropertyValue::Int(2)) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(lan.property_value("SET_Operating_Mode"), Some(IoTPropertyValue::Int(2)));
+    assert_eq!(context.server.property_value(DSN, "SET_Operating_Mode"), Some(IoTPropertyValue::Int(0)));
+    assert!(!context.server.requests().iter().any(|request| request.path.contains("properties")));
+    assert!(lan.requests().iter().any(|request| request.method == "PUT" && request.path == "/local_reg.json"));
+
+    cloudcore.disconnect_lan(DSN);
+    assert!(!cloudcore.lan_connected(DSN));
+}
+
+#[tokio::test]
+async fn lan_falls_back_to_the_cloud_when_the_device_goes_away() {
+    let mut context = setup_logged_in().await;
+    let lan = MockLanDevice::start(&robot()).unwrap();
+    context.server.add_device(robot().with_lan_ip(lan.address()));
+    let device = fetch_robot(&mut context).await;
+    let cloudcore = &context.cloudcore;
+
+    cloudcore.connect_lan(&device).await.unwrap();
+    drop(lan);
+
+    let (result, _) = cloudcore
+        .get_property(DSN.to_string(), "GET_Battery_Capacity".to_string(), "".to_string())
+        .await;
+    assert_eq!(result.unwrap()[0].value(), Some(&IoTPropertyValue::Int(87)));
+    assert!(!cloudcore.lan_connected(DSN));
+}
+
+#[tokio::test]
+async fn lan_connect_fails_without_lan_mode_or_matching_key() {
+    let mut context = setup_logged_in().await;
+    context.server.add_device(robot().with_lan_ip(""));
+    let device = fetch_robot(&mut context).await;
+    let result = context.cloudcore.connect_lan(&device).await;
+    assert!(matches!(result, Err(CloudCoreError::Lan(_))));
+
+    let lan = MockLanDevice::start(&robot().with_lanip_key("another-key", 7)).unwrap();
+    context.server.add_device(robot().with_lan_ip(lan.address()));
+    let device = fetch_robot(&mut context).await;
+    let result = context.cloudcore.connect_lan(&device).await;
+    assert!(matches!(result, Err(CloudCoreError::Lan(_))));
+    assert!(!context.cloudcore.lan_connected(DSN));
+}
@@ -0,0 +1,506 @@
+mod common;
+
+use std::time::Duration;
+
+use cloudcore::cloudcore_error::CloudCoreError;
+use cloudcore::mock::{MockDevice, MockDeviceAp};
+use cloudcore::pairing::wifi_network::WifiNetwork;
+use cloudcore::{wifi_manager, CloudCore, PairingConfig, PairingDiagnostics, PairingFailure, PairingStepConfig, WifiPairing, WifiPairingState};
+use common::setup_logged_in;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::time::timeout;
+
+static NEW_DSN: &str = "AC000W000000002";
+static HOME_SSID: &str = "Home";
+static HOME_KEY: &str = "home-password";
+
+/// Drives a `WifiPairing` the way the apps do through the FFI, with its callbacks turned into channels.
+struct Pairing {
+    manager: *mut WifiPairing,
+    states: UnboundedReceiver<WifiPairingState>,
+    networks: UnboundedReceiver<Vec<WifiNetwork>>,
+    results: UnboundedReceiver<Result<String, CloudCoreError>>,
+}
+
+impl Pairing {
+    fn new(cloudcore: &CloudCore, config: PairingConfig) -> Self {
+        let (state_tx, states) = unbounded_channel();
+        let (networks_tx, networks) = unbounded_channel();
+        let (result_tx, results) = unbounded_channel();
+        let manager = cloudcore.create_pairing_manager(
+            Box::new(move |state| {
+                let _ = state_tx.send(state);
+            }),
+            Box::new(move |wifi_networks| {
+                let _ = networks_tx.send(wifi_networks);
+            }),
+            Box::new(move |result| {
+                let _ = result_tx.send(result);
+            }),
+            config,
+        );
+        Self {
+            manager: Box::into_raw(Box::new(manager)),
+            states,
+            networks,
+            results,
+        }
+    }
+
+    fn manager(&self) -> &'static mut WifiPairing {
+        unsafe { &mut *self.manager }
+    }
+
+    fn start(&self, ap: &MockDeviceAp) {
+        self.manager().start(ap.address().to_string());
+    }
+
+    /// Wait for the device's scan results and send it the credentials of `ssid`.
+    async fn join(&mut self, ssid: &str, key: &str) {
+        let networks = timeout(Duration::from_secs(60), self.networks.recv()).await.unwrap().unwrap();
+        let mut network = networks.into_iter().find(|network| network.ssid() == Some(&ssid.to_string())).unwrap();
+        network.set_password(key.to_string());
+        wifi_manager::handle_wifi_network(self.manager(), network);
+    }
+
+    async fn result(&mut self) -> Result<String, CloudCoreError> {
+        timeout(Duration::from_secs(120), self.results.recv()).await.unwrap().unwrap()
+    }
+
+    /// The states reported until `state`, which is the last one.
+    async fn states_until(&mut self, state: WifiPairingState) -> Vec<WifiPairingState> {
+        let mut states = vec![];
+        while states.last() != Some(&state) {
+            states.push(timeout(Duration::from_secs(60), self.states.recv()).await.unwrap().unwrap());
+        }
+        states
+    }
+
+    fn states(&mut self) -> Vec<WifiPairingState> {
+        let mut states = vec![];
+        while let Ok(state) = self.states.try_recv() {
+            states.push(state);
+        }
+        states
+    }
+}
+
+impl Drop for Pairing {
+    fn drop(&mut self) {
+        unsafe { Box::from_raw(self.manager) }.done_pairing();
+    }
+}
+
+/// The default budgets with every pause cut down, so failing steps give up quickly.
+fn fast_config() -> PairingConfig {
+    let step = PairingStepConfig::new(3, Duration::from_millis(50));
+    PairingConfig::default()
+        .with_fetch_dsn(step.clone())
+        .with_wifi_scan(step.clone())
+        .with_wifi_networks(step.clone(), Duration::from_millis(200))
+        .with_send_credentials(step.clone())
+        .with_device_wifi_status(step.clone())
+        .with_user_wifi_wait(Duration::from_millis(50))
+        .with_ayla_handshake(step.clone())
+        .with_ayla_device_check(step)
+        .with_connect_timeouts(Duration::from_secs(1), Duration::from_secs(1))
+}
+
+fn new_robot() -> MockDevice {
+    MockDevice::new(102, NEW_DSN).with_product_name("Robot 2")
+}
+
+fn start_ap(server: &cloudcore::mock::MockAylaServer) -> MockDeviceAp {
+    let ap = MockDeviceAp::start(server, new_robot()).unwrap();
+    ap.add_network(HOME_SSID, Some(HOME_KEY), -45);
+    ap.add_network("Neighbour", Some("not-ours"), -80);
+    ap.add_network("Cafe", None, -70);
+    ap
+}
+
+fn saved_diagnostics(cloudcore: &CloudCore) -> PairingDiagnostics {
+    serde_json::from_str(&cloudcore.get_pairing_diagnostics().unwrap()).unwrap()
+}
+
+fn step_attempts(diagnostics: &PairingDiagnostics, state: WifiPairingState) -> u32 {
+    diagnostics.steps().iter().find(|step| step.state() == &state).unwrap().attempts()
+}
+
+fn count_requests(ap: &MockDeviceAp, method: &str, path: &str) -> usize {
+    ap.requests().iter().filter(|request| request.method == method && request.path == path).count()
+}
+
+#[tokio::test]
+async fn pairing_walks_every_state_to_connected() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.start(&ap);
+    pairing.join(HOME_SSID, HOME_KEY).await;
+
+    assert_eq!(pairing.result().await.unwrap(), NEW_DSN);
+    assert_eq!(pairing.states(), vec![
+        WifiPairingState::FetchingDSN,
+        WifiPairingState::DeviceScanningWifi,
+        WifiPairingState::GettingWifiNetworks,
+        WifiPairingState::SendingWiFiCredentialsToDevice,
+        WifiPairingState::PollingDeviceWifiStatus,
+        WifiPairingState::EndingAccessPointsScanning,
+        WifiPairingState::PollingUserInternetConnection,
+        WifiPairingState::HandshakingWithAyla,
+        WifiPairingState::PollingDeviceOnAyla,
+        WifiPairingState::Connected,
+    ]);
+    assert_eq!(ap.joined_ssid(), Some(HOME_SSID.to_string()));
+    assert!(ap.access_point_stopped());
+    assert!(context.server.device(NEW_DSN).is_some());
+    let registration = context
+        .server
+        .requests()
+        .into_iter()
+        .find(|request| request.method == "POST" && request.path == "/apiv1/devices.json")
+        .unwrap();
+    assert_eq!(registration.body["device"]["setup_token"].as_str(), ap.setup_token().as_deref());
+
+    // The diagnostics point at this pairing's lines in the pairing log
+    let session_id = saved_diagnostics(context.cloudcore).session_id().cloned().unwrap();
+    let entries = context.cloudcore.pairing_log_entries().unwrap();
+    assert!(entries.iter().any(|entry| entry.message().contains("is connected to the internet")
+        && entry.session_id() == Some(&session_id)));
+
+    // The credentials were already sent, the device only ever gets them once
+    let network = WifiNetwork::new(None, None, None, None, None, Some(HOME_SSID.to_string()), None, Some(HOME_KEY.to_string()));
+    wifi_manager::handle_wifi_network(pairing.manager(), network);
+    assert_eq!(count_requests(&ap, "POST", "/wifi_connect.json"), 1);
+}
+
+#[tokio::test]
+async fn pairing_retries_failed_requests() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    ap.fail_next_requests_to("GET", "/status.json", 1, 500);
+    ap.fail_next_requests_to("POST", "/wifi_scan.json", 1, 503);
+    ap.fail_next_requests_to("GET", "/wifi_scan_results.json", 1, 500);
+    ap.fail_next_requests_to("POST", "/wifi_connect.json", 1, 500);
+    ap.fail_next_requests_to("PUT", "/wifi_stop_ap.json", 1, 500);
+    context.server.fail_next_requests_to("POST", "/apiv1/devices.json", 1, 500, None);
+    context.server.fail_next_requests_to("GET", &format!("/apiv1/dsns/{}.json", NEW_DSN), 1, 404, None);
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.start(&ap);
+    pairing.join(HOME_SSID, HOME_KEY).await;
+
+    assert_eq!(pairing.result().await.unwrap(), NEW_DSN);
+    assert_eq!(pairing.states().last(), Some(&WifiPairingState::Connected));
+    assert_eq!(count_requests(&ap, "GET", "/status.json"), 2);
+    assert_eq!(count_requests(&ap, "POST", "/wifi_scan.json"), 2);
+    assert_eq!(count_requests(&ap, "POST", "/wifi_connect.json"), 2);
+    // Stopping the AP is best effort and never retried
+    assert_eq!(count_requests(&ap, "PUT", "/wifi_stop_ap.json"), 1);
+    assert!(!ap.access_point_stopped());
+    assert!(context.server.device(NEW_DSN).is_some());
+
+    let diagnostics = saved_diagnostics(context.cloudcore);
+    assert_eq!(diagnostics.failure(), None);
+    assert!(diagnostics.finished_at().is_some());
+    assert_eq!(step_attempts(&diagnostics, WifiPairingState::FetchingDSN), 2);
+    assert_eq!(step_attempts(&diagnostics, WifiPairingState::DeviceScanningWifi), 2);
+    assert_eq!(step_attempts(&diagnostics, WifiPairingState::SendingWiFiCredentialsToDevice), 2);
+    assert_eq!(step_attempts(&diagnostics, WifiPairingState::HandshakingWithAyla), 2);
+    assert_eq!(step_attempts(&diagnostics, WifiPairingState::PollingDeviceOnAyla), 2);
+    assert!(diagnostics.steps().iter().all(|step| step.duration_ms().is_some()));
+}
+
+#[tokio::test]
+async fn pairing_with_an_open_network() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.start(&ap);
+    pairing.join("Cafe", "").await;
+
+    assert_eq!(pairing.result().await.unwrap(), NEW_DSN);
+    let connect = ap.requests().into_iter().find(|request| request.path == "/wifi_connect.json").unwrap();
+    assert!(!connect.query.iter().any(|(key, _)| key == "key"));
+    assert_eq!(ap.joined_ssid(), Some("Cafe".to_string()));
+}
+
+#[tokio::test]
+async fn pairing_fails_without_user_session() {
+    let context = setup_logged_in().await;
+    context.cloudcore.logout().await.unwrap();
+    let ap = start_ap(&context.server);
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.start(&ap);
+
+    assert!(matches!(
+        pairing.result().await,
+        Err(CloudCoreError::PairingFailed { failure: PairingFailure::NoUserSession, state: WifiPairingState::Idle, .. })
+    ));
+    assert!(pairing.states().is_empty());
+    assert!(ap.requests().is_empty());
+    assert!(!context.server.device_online(NEW_DSN));
+}
+
+#[tokio::test]
+async fn pairing_fails_when_the_device_is_unreachable() {
+    let context = setup_logged_in().await;
+    let address = start_ap(&context.server).address().to_string();
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.manager().start(address);
+
+    assert!(matches!(
+        pairing.result().await,
+        Err(CloudCoreError::PairingFailed { failure: PairingFailure::DeviceUnreachable, state: WifiPairingState::FetchingDSN, .. })
+    ));
+    assert_eq!(pairing.states(), vec![WifiPairingState::FetchingDSN]);
+}
+
+#[tokio::test]
+async fn pairing_with_a_wrong_password_is_rejected_by_ayla() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    // Without its wifi status the device can't say it didn't join
+    ap.set_wifi_status_supported(false);
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.start(&ap);
+    pairing.join(HOME_SSID, "wrong-password").await;
+
+    assert!(matches!(
+        pairing.result().await,
+        Err(CloudCoreError::PairingFailed { failure: PairingFailure::AylaHandshakeRejected, .. })
+    ));
+    assert_eq!(pairing.states().last(), Some(&WifiPairingState::HandshakingWithAyla));
+    assert_eq!(ap.joined_ssid(), None);
+    let handshakes = context
+        .server
+        .requests()
+        .iter()
+        .filter(|request| request.method == "POST" && request.path == "/apiv1/devices.json")
+        .count();
+    assert_eq!(handshakes, 3);
+    assert!(context.server.device(NEW_DSN).is_none());
+}
+
+#[tokio::test]
+async fn failed_pairing_writes_a_diagnostics_report() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    ap.set_wifi_status_supported(false);
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.start(&ap);
+    pairing.join(HOME_SSID, "wrong-password").await;
+    assert!(pairing.result().await.is_err());
+
+    let diagnostics = saved_diagnostics(context.cloudcore);
+    assert_eq!(&diagnostics, pairing.manager().diagnostics());
+    assert_eq!(diagnostics.dsn(), Some(&NEW_DSN.to_string()));
+    assert_eq!(diagnostics.failure(), Some(&PairingFailure::AylaHandshakeRejected));
+    assert_eq!(diagnostics.failed_state(), Some(&WifiPairingState::HandshakingWithAyla));
+    assert!(diagnostics.error().is_some());
+    let states: Vec<WifiPairingState> = diagnostics.steps().iter().map(|step| step.state().clone()).collect();
+    assert_eq!(states, vec![
+        WifiPairingState::FetchingDSN,
+        WifiPairingState::DeviceScanningWifi,
+        WifiPairingState::GettingWifiNetworks,
+        WifiPairingState::SendingWiFiCredentialsToDevice,
+        WifiPairingState::PollingDeviceWifiStatus,
+        WifiPairingState::EndingAccessPointsScanning,
+        WifiPairingState::PollingUserInternetConnection,
+        WifiPairingState::HandshakingWithAyla,
+    ]);
+    let handshake = diagnostics.steps().last().unwrap();
+    assert_eq!(handshake.attempts(), 3);
+    assert!(handshake.last_error().is_some());
+    assert!(handshake.duration_ms().unwrap() >= 100);
+    assert_eq!(step_attempts(&diagnostics, WifiPairingState::FetchingDSN), 1);
+
+    let report: serde_json::Value = serde_json::from_str(&context.cloudcore.get_pairing_diagnostics().unwrap()).unwrap();
+    assert_eq!(report["failure"], "AylaHandshakeRejected");
+    assert_eq!(report["steps"][0]["state"], "FetchingDSN");
+}
+
+#[tokio::test]
+async fn pairing_gives_up_at_its_deadline() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    ap.set_wifi_status_supported(false);
+    let config = fast_config()
+        .with_ayla_handshake(PairingStepConfig::new(1000, Duration::from_millis(50)))
+        .with_deadline(Some(Duration::from_secs(1)));
+    let mut pairing = Pairing::new(context.cloudcore, config);
+
+    pairing.start(&ap);
+    pairing.join(HOME_SSID, "wrong-password").await;
+    let sent_at = std::time::Instant::now();
+
+    match pairing.result().await {
+        Err(CloudCoreError::PairingFailed { failure: PairingFailure::Timeout, message, .. }) => {
+            assert!(message.contains("deadline"))
+        }
+        result => panic!("expected the deadline to end pairing, got {:?}", result),
+    }
+    let elapsed = sent_at.elapsed();
+    assert!(elapsed >= Duration::from_millis(900) && elapsed < Duration::from_secs(5), "{:?}", elapsed);
+    assert_eq!(pairing.states().last(), Some(&WifiPairingState::HandshakingWithAyla));
+}
+
+#[tokio::test]
+async fn wrong_password_is_reported_before_leaving_the_device_ap() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.start(&ap);
+    pairing.join(HOME_SSID, "wrong-password").await;
+
+    let states = pairing.states_until(WifiPairingState::WrongWifiPassword).await;
+    assert_eq!(states[states.len() - 3..], [
+        WifiPairingState::SendingWiFiCredentialsToDevice,
+        WifiPairingState::PollingDeviceWifiStatus,
+        WifiPairingState::WrongWifiPassword,
+    ]);
+    assert!(!ap.access_point_stopped());
+    assert_eq!(context.server.requests().iter().filter(|request| request.path == "/apiv1/devices.json").count(), 0);
+    assert!(pairing.results.try_recv().is_err());
+
+    // The user fixes the password while the phone is still on the device's AP
+    let network = WifiNetwork::new(None, None, None, None, None, Some(HOME_SSID.to_string()), None, Some(HOME_KEY.to_string()));
+    wifi_manager::handle_wifi_network(pairing.manager(), network);
+
+    assert_eq!(pairing.result().await.unwrap(), NEW_DSN);
+    assert_eq!(count_requests(&ap, "POST", "/wifi_connect.json"), 2);
+    assert_eq!(ap.joined_ssid(), Some(HOME_SSID.to_string()));
+    assert!(ap.access_point_stopped());
+}
+
+#[tokio::test]
+async fn missing_network_is_reported_and_cancelling_records_it() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.start(&ap);
+    timeout(Duration::from_secs(30), pairing.networks.recv()).await.unwrap().unwrap();
+    // A hidden network the device can't see
+    let network = WifiNetwork::new(None, None, None, None, None, Some("Hidden".to_string()), None, Some("secret".to_string()));
+    wifi_manager::handle_wifi_network(pairing.manager(), network);
+
+    pairing.states_until(WifiPairingState::WifiNetworkNotFound).await;
+    assert_eq!(ap.joined_ssid(), None);
+    assert!(!ap.access_point_stopped());
+
+    pairing.manager().cancel();
+    let diagnostics = saved_diagnostics(context.cloudcore);
+    assert_eq!(diagnostics.failure(), Some(&PairingFailure::NetworkNotFound));
+    assert_eq!(diagnostics.failed_state(), Some(&WifiPairingState::WifiNetworkNotFound));
+    assert_eq!(step_attempts(&diagnostics, WifiPairingState::PollingDeviceWifiStatus), 2);
+}
+
+#[test]
+fn default_pairing_config_keeps_the_gen_1_budgets() {
+    let config = PairingConfig::default();
+
+    assert_eq!(config.fetch_dsn(), &PairingStepConfig::new(20, Duration::from_secs(3)));
+    assert_eq!(config.ayla_device_check(), &PairingStepConfig::new(20, Duration::from_secs(10)));
+    assert_eq!(config.user_wifi_wait(), Duration::from_secs(5));
+    assert_eq!(config.lan_connect_timeout(), Duration::from_secs(10));
+    assert_eq!(config.internet_connect_timeout(), Duration::from_secs(20));
+    assert_eq!(config.deadline(), None);
+}
+
+#[tokio::test]
+async fn cancel_stops_the_access_point_and_reports_cancelled() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+
+    pairing.start(&ap);
+    timeout(Duration::from_secs(30), pairing.networks.recv()).await.unwrap().unwrap();
+    pairing.manager().cancel();
+
+    assert_eq!(pairing.states().last(), Some(&WifiPairingState::Cancelled));
+    assert_eq!(pairing.manager().state(), &WifiPairingState::Cancelled);
+    assert!(context.cloudcore.pairing_progress().is_none());
+    // Choosing a network afterwards does nothing
+    let network = WifiNetwork::new(None, None, None, None, None, Some(HOME_SSID.to_string()), None, Some(HOME_KEY.to_string()));
+    wifi_manager::handle_wifi_network(pairing.manager(), network);
+    for _ in 0..50 {
+        if ap.access_point_stopped() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(ap.access_point_stopped());
+    assert_eq!(count_requests(&ap, "POST", "/wifi_connect.json"), 0);
+    assert!(pairing.results.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn interrupted_pairing_resumes_at_the_ayla_handshake() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    context.server.fail_next_requests_to("POST", "/apiv1/devices.json", 3, 500, None);
+    let mut interrupted = Pairing::new(context.cloudcore, fast_config());
+
+    interrupted.start(&ap);
+    interrupted.join(HOME_SSID, HOME_KEY).await;
+    assert!(interrupted.result().await.is_err());
+
+    let progress = context.cloudcore.pairing_progress().unwrap();
+    assert_eq!(progress.state(), &WifiPairingState::HandshakingWithAyla);
+    assert_eq!(progress.dsn(), Some(&NEW_DSN.to_string()));
+    assert_eq!(progress.ssid(), Some(&HOME_SSID.to_string()));
+    assert_eq!(progress.setup_token(), ap.setup_token().as_ref());
+    assert_eq!(progress.resume_state(), Some(WifiPairingState::HandshakingWithAyla));
+
+    // As if the app was restarted, a new pairing manager picks the saved progress up
+    let mut resumed = Pairing::new(context.cloudcore, fast_config());
+    resumed.manager().resume().unwrap();
+
+    assert_eq!(resumed.result().await.unwrap(), NEW_DSN);
+    assert_eq!(resumed.states(), vec![
+        WifiPairingState::HandshakingWithAyla,
+        WifiPairingState::PollingDeviceOnAyla,
+        WifiPairingState::Connected,
+    ]);
+    assert!(context.server.device(NEW_DSN).is_some());
+    assert!(context.cloudcore.pairing_progress().is_none());
+}
+
+#[tokio::test]
+async fn pairing_can_not_resume_before_credentials_were_sent() {
+    let context = setup_logged_in().await;
+    let ap = start_ap(&context.server);
+    let resumed = Pairing::new(context.cloudcore, fast_config());
+    assert!(matches!(resumed.manager().resume(), Err(CloudCoreError::Pairing(_))));
+
+    let mut pairing = Pairing::new(context.cloudcore, fast_config());
+    pairing.start(&ap);
+    timeout(Duration::from_secs(30), pairing.networks.recv()).await.unwrap().unwrap();
+
+    let progress = context.cloudcore.pairing_progress().unwrap();
+    assert_eq!(progress.state(), &WifiPairingState::GettingWifiNetworks);
+    assert_eq!(progress.resume_state(), None);
+    assert!(matches!(resumed.manager().resume(), Err(CloudCoreError::Pairing(_))));
+    pairing.manager().cancel();
+}
+
+#[test]
+fn pairing_states_keep_their_ffi_values() {
+    assert_eq!(WifiPairingState::SendingWiFiCredentialsToDevice as u8, 4);
+    assert_eq!(WifiPairingState::EndingAccessPointsScanning as u8, 5);
+    assert_eq!(WifiPairingState::Done as u8, 10);
+    assert_eq!(WifiPairingState::Cancelled as u8, 11);
+    assert_eq!(WifiPairingState::WifiNetworkNotFound as u8, 14);
+    assert!(!WifiPairingState::GettingWifiNetworks.credentials_sent());
+    assert!(WifiPairingState::WrongWifiPassword.credentials_sent());
+    assert!(WifiPairingState::WrongWifiPassword.on_device_access_point());
+    assert!(!WifiPairingState::HandshakingWithAyla.on_device_access_point());
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use std::fs::{read_dir, write, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::Utc;
+use cloudcore::pairing::wifi_network::WifiNetwork;
+use cloudcore::{CloudCore, PairingLogConfig, PairingLogEntry, PairingLogLevel};
+use common::{os_dir, setup};
+
+fn log_files(cloudcore: &CloudCore) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = read_dir(cloudcore.cache.parent_path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.file_name().unwrap().to_string_lossy().starts_with("pairing_log"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn messages(cloudcore: &CloudCore) -> Vec<String> {
+    cloudcore.pairing_log_entries().unwrap().iter().map(|entry| entry.message().to_string()).collect()
+}
+
+#[tokio::test]
+async fn entries_are_json_lines_tagged_with_the_pairing_session() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+
+    let session_id = cloudcore.start_pairing_log_session();
+    cloudcore.write_to_pairing_log("fetching dsn".to_string()).unwrap();
+    cloudcore.write_pairing_log_entry(PairingLogLevel::Error, "device unreachable".to_string()).unwrap();
+
+    let log = cloudcore.get_pairing_log().unwrap();
+    let entries: Vec<PairingLogEntry> = log.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].message(), "fetching dsn");
+    assert_eq!(entries[0].level(), &PairingLogLevel::Info);
+    assert_eq!(entries[1].level(), &PairingLogLevel::Error);
+    assert!(entries.iter().all(|entry| entry.session_id() == Some(&session_id)));
+    assert!(entries[0].timestamp() <= entries[1].timestamp());
+    assert!(*entries[1].timestamp() <= Utc::now());
+}
+
+#[tokio::test]
+async fn log_is_rotated_by_size_and_only_the_newest_files_are_kept() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.set_pairing_log_config(PairingLogConfig::default().with_max_file_bytes(1024).with_max_files(3));
+    cloudcore.start_pairing_log_session();
+
+    for line in 0..100 {
+        cloudcore.write_to_pairing_log(format!("line {:03} {}", line, "x".repeat(40))).unwrap();
+    }
+
+    let files = log_files(cloudcore);
+    assert_eq!(files.len(), 3);
+    assert!(files.iter().all(|file| file.metadata().unwrap().len() <= 1024));
+    let messages = messages(cloudcore);
+    assert!(messages.len() < 100);
+    assert!(messages.last().unwrap().starts_with("line 099"));
+    // What is left is the newest lines in the order they were written
+    let first = messages[0][5..8].parse::<usize>().unwrap();
+    let expected: Vec<usize> = (first..100).collect();
+    let kept: Vec<usize> = messages.iter().map(|message| message[5..8].parse().unwrap()).collect();
+    assert_eq!(kept, expected);
+}
+
+#[tokio::test]
+async fn only_the_last_pairing_sessions_are_kept() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.set_pairing_log_config(PairingLogConfig::default().with_max_sessions(2));
+
+    for pairing in 1..=3 {
+        cloudcore.start_pairing_log_session();
+        cloudcore.write_to_pairing_log(format!("pairing {}", pairing)).unwrap();
+    }
+
+    assert_eq!(messages(cloudcore), vec!["pairing 2", "pairing 3"]);
+}
+
+#[tokio::test]
+async fn lines_past_their_age_are_dropped() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.start_pairing_log_session();
+    cloudcore.write_to_pairing_log("recent".to_string()).unwrap();
+    let old = serde_json::json!({
+        "timestamp": Utc::now() - chrono::Duration::days(30),
+        "session_id": "old-session",
+        "level": "Info",
+        "message": "a month ago",
+    });
+    let mut file = OpenOptions::new().append(true).open(log_files(cloudcore).last().unwrap()).unwrap();
+    writeln!(file, "{}", old).unwrap();
+    assert_eq!(messages(cloudcore), vec!["recent", "a month ago"]);
+
+    cloudcore.create_log();
+    assert_eq!(messages(cloudcore), vec!["recent"]);
+
+    cloudcore.set_pairing_log_config(PairingLogConfig::default().with_max_age(Duration::from_millis(1)));
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    cloudcore.start_pairing_log_session();
+    assert!(messages(cloudcore).is_empty());
+}
+
+#[tokio::test]
+async fn log_is_kept_when_cloudcore_is_created_again() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.start_pairing_log_session();
+    cloudcore.write_to_pairing_log("before restart".to_string()).unwrap();
+    let legacy_log = cloudcore.cache.parent_path().join("pairing_logging");
+    write(&legacy_log, "free text from an older version\nand its second line\n").unwrap();
+
+    let _cc = CloudCore::new(os_dir(cloudcore));
+    let cloudcore = CloudCore::shared();
+
+    // The older version's lines are kept without a session, ahead of the newer ones
+    assert_eq!(messages(cloudcore), vec!["free text from an older version", "and its second line", "before restart"]);
+    let entries = cloudcore.pairing_log_entries().unwrap();
+    assert!(entries[..2].iter().all(|entry| entry.session_id().is_none()));
+    assert!(entries[2].session_id().is_some());
+    assert!(!legacy_log.exists());
+
+    // and only imported once
+    let _cc = CloudCore::new(os_dir(cloudcore));
+    let cloudcore = CloudCore::shared();
+    assert_eq!(messages(cloudcore).len(), 3);
+
+    cloudcore.clear_log();
+    assert!(messages(cloudcore).is_empty());
+    assert!(log_files(cloudcore).is_empty());
+}
+
+#[tokio::test]
+async fn exported_log_can_be_redacted() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.start_pairing_log_session();
+    let network = WifiNetwork::new(Some(3), Some("aa:bb:cc:dd:ee:ff".to_string()), Some(6), Some("WPA2".to_string()), Some(-45), Some("Home Sweet Home".to_string()), None, Some("hunter22".to_string()));
+    cloudcore.write_to_pairing_log(format!("wireless networks for IP 192.168.0.1: {:?}", vec![network])).unwrap();
+    cloudcore.write_to_pairing_log("sending [(\"ssid\", \"Home Sweet Home\"), (\"key\", \"hunter22\"), (\"setup_token\", \"Tk3nTk3n\")]".to_string()).unwrap();
+    cloudcore.write_to_pairing_log("GET /apiv1/devices.json?access_token=a1b2c3&dsn=AC000W000000002".to_string()).unwrap();
+    cloudcore.write_to_pairing_log("the key was wrong, network with ssid: None".to_string()).unwrap();
+
+    let plain = cloudcore.export_pairing_log(false).unwrap();
+    assert!(plain.contains("hunter22") && plain.contains("Home Sweet Home") && plain.contains("a1b2c3"));
+
+    let redacted = cloudcore.export_pairing_log(true).unwrap();
+    for secret in ["hunter22", "Home Sweet Home", "aa:bb:cc:dd:ee:ff", "Tk3nTk3n", "a1b2c3"] {
+        assert!(!redacted.contains(secret), "{} in {}", secret, redacted);
+    }
+    let entries: Vec<PairingLogEntry> = redacted.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert!(entries[0].message().contains("ssid: Some(\"<redacted>\")"));
+    assert!(entries[0].message().contains("signal: Some(-45)"));
+    assert!(entries[1].message().contains("(\"key\", \"<redacted>\")"));
+    assert_eq!(entries[2].message(), "GET /apiv1/devices.json?access_token=<redacted>&dsn=AC000W000000002");
+    assert_eq!(entries[3].message(), "the key was wrong, network with ssid: None");
+    // The saved log itself is untouched
+    assert_eq!(cloudcore.export_pairing_log(false).unwrap(), plain);
+}
@@ -0,0 +1,403 @@
+mod common;
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use cloudcore::polling::{IoTPropertyChange, PollCallback, PollConfig, PollEvent, PropertyEvent};
+use cloudcore::properties::value::IoTPropertyValue;
+use cloudcore::{CloudCore, RetryPolicy};
+use common::{setup_logged_in, DSN};
+use futures_util::StreamExt;
+use tokio::time::{sleep, timeout, Instant};
+
+/// Everything a poll's callback was called with.
+#[derive(Clone, Default)]
+struct Events(Arc<Mutex<Vec<PollEvent>>>);
+
+impl Events {
+    fn callback(&self) -> PollCallback {
+        let events = self.clone();
+        Box::new(move |event| events.0.lock().unwrap().push(event))
+    }
+
+    fn changes(&self) -> Vec<Vec<IoTPropertyChange>> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                PollEvent::Changes { changes, .. } => Some(changes.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn count(&self, matches: fn(&PollEvent) -> bool) -> usize {
+        self.0.lock().unwrap().iter().filter(|event| matches(event)).count()
+    }
+
+    fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    fn stopped(&self) -> bool {
+        matches!(self.0.lock().unwrap().last(), Some(PollEvent::Stopped { .. }))
+    }
+
+    fn change_count(&self) -> usize {
+        self.count(|event| matches!(event, PollEvent::Changes { .. }))
+    }
+
+    fn snapshot_count(&self) -> usize {
+        self.count(|event| matches!(event, PollEvent::Data { .. }))
+    }
+}
+
+async fn wait_for(condition: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !condition() {
+        assert!(Instant::now() < deadline, "timed out waiting for poll events");
+        sleep(Duration::from_millis(20)).await;
+    }
+}
+
+fn properties_path() -> String {
+    format!("/apiv1/dsns/{}/properties.json", DSN)
+}
+
+fn poll_config(events: &Events) -> PollConfig {
+    PollConfig::new(
+        Some(vec!["GET_Battery_Capacity".to_string(), "SET_Operating_Mode".to_string()]),
+        None,
+        Some(100),
+        Some(DSN.to_string()),
+        Some(events.callback()),
+    )
+}
+
+#[tokio::test]
+async fn poll_only_reports_changes() {
+    let context = setup_logged_in().await;
+    let cloudcore: &CloudCore = context.cloudcore;
+    let events = Events::default();
+    cloudcore.start_polling_manager();
+    let poll_id = cloudcore.add_poll(poll_config(&events));
+
+    wait_for(|| events.change_count() == 1).await;
+    let first = events.changes()[0].clone();
+    assert_eq!(first.len(), 2);
+    assert!(first.iter().all(|change| matches!(change, IoTPropertyChange::Added { .. })));
+
+    // Several polls go by without anything new
+    sleep(Duration::from_millis(400)).await;
+    assert_eq!(events.change_count(), 1);
+    assert_eq!(events.snapshot_count(), 1);
+
+    context.server.set_property_value(DSN, "GET_Battery_Capacity", IoTPropertyValue::Int(50));
+    wait_for(|| events.change_count() == 2).await;
+    let second = events.changes()[1].clone();
+    assert_eq!(
+        second,
+        vec![IoTPropertyChange::Changed {
+            name: "GET_Battery_Capacity".to_string(),
+            old_value: Some(IoTPropertyValue::Int(87)),
+            new_value: Some(IoTPropertyValue::Int(50)),
+        }]
+    );
+
+    cloudcore.update_poll(poll_id, PollConfig::new(Some(vec!["SET_Operating_Mode".to_string()]), None, None, None, None));
+    wait_for(|| events.change_count() == 3).await;
+    let third = events.changes()[2].clone();
+    assert_eq!(
+        third,
+        vec![IoTPropertyChange::Removed {
+            name: "GET_Battery_Capacity".to_string(),
+            old_value: Some(IoTPropertyValue::Int(50)),
+        }]
+    );
+
+    cloudcore.remove_poll(poll_id);
+    cloudcore.stop_polling_manager();
+}
+
+#[tokio::test]
+async fn poll_can_still_emit_snapshots() {
+    let context = setup_logged_in().await;
+    let cloudcore: &CloudCore = context.cloudcore;
+    let events = Events::default();
+    cloudcore.start_polling_manager();
+    let poll_id = cloudcore.add_poll(poll_config(&events).with_emit_snapshots(true));
+
+    wait_for(|| events.snapshot_count() >= 3).await;
+    assert_eq!(events.change_count(), 1);
+
+    cloudcore.stop_polling_manager();
+    cloudcore.remove_poll(poll_id);
+    wait_for(|| events.count(|event| matches!(event, PollEvent::Stopped { dsn } if dsn == DSN)) == 2).await;
+}
+
+#[tokio::test]
+async fn polls_of_one_device_share_a_request() {
+    let context = setup_logged_in().await;
+    let cloudcore: &CloudCore = context.cloudcore;
+    let events = Events::default();
+    cloudcore.start_polling_manager();
+    let battery = cloudcore.add_poll(
+        PollConfig::new(Some(vec!["GET_Battery_Capacity".to_string()]), None, Some(200), Some(DSN.to_string()), Some(events.callback())),
+    );
+    let mode = cloudcore.add_poll(
+        PollConfig::new(Some(vec!["SET_Operating_Mode".to_string()]), None, Some(200), Some(DSN.to_string()), Some(events.callback())),
+    );
+
+    wait_for(|| events.change_count() == 2).await;
+    // Each poll only hears about its own properties
+    let changes = events.changes();
+    let mut names: Vec<&str> = changes.iter().flatten().map(|change| change.name()).collect();
+    names.sort();
+    assert_eq!(names, vec!["GET_Battery_Capacity", "SET_Operating_Mode"]);
+
+    // The first round may have gone out before the second poll was added
+    context.server.clear_requests();
+    sleep(Duration::from_millis(600)).await;
+    let polls: Vec<_> = context
+        .server
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == "GET" && request.path == properties_path())
+        .collect();
+    assert!(!polls.is_empty());
+    for request in polls {
+        let names: Vec<&str> = request.query.iter().map(|(_, name)| name.as_str()).collect();
+        assert!(names.contains(&"GET_Battery_Capacity") && names.contains(&"SET_Operating_Mode"), "{:?}", names);
+    }
+
+    cloudcore.remove_poll(battery);
+    cloudcore.remove_poll(mode);
+    cloudcore.stop_polling_manager();
+}
+
+#[tokio::test]
+async fn failing_polls_back_off() {
+    let context = setup_logged_in().await;
+    context.cloudcore.set_retry_policy(RetryPolicy::none());
+    let cloudcore: &CloudCore = context.cloudcore;
+    let events = Events::default();
+    context.server.fail_next_requests_to("GET", &properties_path(), 2, 500, None);
+    cloudcore.start_polling_manager();
+    let poll_id = cloudcore.add_poll(poll_config(&events));
+
+    wait_for(|| cloudcore.poll_stats(poll_id).unwrap().failure_count() == 2).await;
+    let stats = cloudcore.poll_stats(poll_id).unwrap();
+    assert!(stats.last_success().is_none());
+    let errors = events.count(|event| matches!(event, PollEvent::Error { error, .. } if error.status() == Some(500)));
+    assert_eq!(errors, 2);
+    // Two failures in a row wait four times as long as asked
+    let wait = *stats.next_poll_at().unwrap() - chrono::Utc::now();
+    assert!(wait > chrono::Duration::milliseconds(250), "{:?}", wait);
+
+    wait_for(|| events.change_count() == 1).await;
+    let stats = cloudcore.poll_stats(poll_id).unwrap();
+    assert_eq!(stats.failure_count(), 0);
+    assert!(stats.last_success().is_some());
+
+    cloudcore.remove_poll(poll_id);
+    cloudcore.stop_polling_manager();
+    assert!(cloudcore.poll_stats(poll_id).is_none());
+}
+
+#[tokio::test]
+async fn offline_devices_are_polled_less_often() {
+    let context = setup_logged_in().await;
+    let cloudcore: &CloudCore = context.cloudcore;
+    let events = Events::default();
+    context.server.set_connection_status(DSN, "Offline");
+    cloudcore.start_polling_manager();
+    let poll_id = cloudcore.add_poll(poll_config(&events));
+
+    wait_for(|| events.change_count() == 1).await;
+    let stats = cloudcore.poll_stats(poll_id).unwrap();
+    let wait = *stats.next_poll_at().unwrap() - *stats.last_success().unwrap();
+    assert!(wait >= chrono::Duration::milliseconds(350), "{:?}", wait);
+
+    cloudcore.remove_poll(poll_id);
+    cloudcore.stop_polling_manager();
+}
+
+#[tokio::test]
+async fn writes_speed_up_polling() {
+    let context = setup_logged_in().await;
+    let cloudcore: &CloudCore = context.cloudcore;
+    let events = Events::default();
+    cloudcore.start_polling_manager();
+    let slow = PollConfig::new(
+        Some(vec!["SET_Operating_Mode".to_string()]),
+        None,
+        Some(60_000),
+        Some(DSN.to_string()),
+        Some(events.callback()),
+    );
+    let poll_id = cloudcore.add_poll(slow);
+    wait_for(|| events.change_count() == 1).await;
+
+    cloudcore
+        .set_property_value(DSN.to_string(), "SET_Operating_Mode".to_string(), IoTPropertyValue::Int(3), "".to_string())
+        .await
+        .0
+        .unwrap();
+    wait_for(|| events.change_count() == 2).await;
+    assert_eq!(
+        events.changes()[1],
+        vec![IoTPropertyChange::Changed {
+            name: "SET_Operating_Mode".to_string(),
+            old_value: Some(IoTPropertyValue::Int(0)),
+            new_value: Some(IoTPropertyValue::Int(3)),
+        }]
+    );
+
+    cloudcore.remove_poll(poll_id);
+    cloudcore.stop_polling_manager();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn polls_can_be_managed_from_many_threads() {
+    let context = setup_logged_in().await;
+    let cloudcore: &CloudCore = context.cloudcore;
+    cloudcore.start_polling_manager();
+    let events: Vec<Events> = (0..8).map(|_| Events::default()).collect();
+
+    let poll_ids: Vec<u32> = thread::scope(|scope| {
+        let handles: Vec<_> = events
+            .iter()
+            .map(|events| {
+                scope.spawn(move || {
+                    let poll_id = cloudcore.add_poll(poll_config(events));
+                    for sleep_time in [50, 100, 150] {
+                        cloudcore.update_poll(poll_id, PollConfig::new(None, None, Some(sleep_time), None, None));
+                        assert!(cloudcore.poll_stats(poll_id).is_some());
+                    }
+                    poll_id
+                })
+            })
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    assert_eq!(poll_ids.iter().collect::<HashSet<_>>().len(), poll_ids.len());
+    for events in &events {
+        wait_for(|| events.change_count() == 1).await;
+    }
+
+    thread::scope(|scope| {
+        for poll_id in &poll_ids {
+            scope.spawn(move || cloudcore.remove_poll(*poll_id));
+        }
+    });
+    for events in &events {
+        wait_for(|| events.stopped()).await;
+    }
+    // Nothing comes after a removed poll said it stopped
+    let counts: Vec<usize> = events.iter().map(Events::len).collect();
+    sleep(Duration::from_millis(400)).await;
+    for (events, count) in events.iter().zip(counts) {
+        assert_eq!(events.len(), count);
+        assert!(events.stopped());
+    }
+    assert!(poll_ids.iter().all(|poll_id| cloudcore.poll_stats(*poll_id).is_none()));
+
+    cloudcore.stop_polling_manager();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn polling_can_be_started_and_stopped_from_many_threads() {
+    let context = setup_logged_in().await;
+    let cloudcore: &CloudCore = context.cloudcore;
+    let events = Events::default();
+    let poll_id = cloudcore.add_poll(poll_config(&events).with_emit_snapshots(true));
+
+    thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                for _ in 0..20 {
+                    cloudcore.start_polling_manager();
+                    cloudcore.stop_polling_manager();
+                }
+            });
+        }
+    });
+    cloudcore.start_polling_manager();
+    let snapshots = events.snapshot_count();
+    wait_for(|| events.snapshot_count() >= snapshots + 2).await;
+
+    // Once stopped, only the stop itself gets through
+    cloudcore.stop_polling_manager();
+    wait_for(|| events.stopped()).await;
+    let count = events.len();
+    sleep(Duration::from_millis(300)).await;
+    assert_eq!(events.len(), count);
+
+    cloudcore.remove_poll(poll_id);
+}
+
+#[tokio::test]
+async fn subscriptions_stream_property_changes() {
+    let context = setup_logged_in().await;
+    let cloudcore: &CloudCore = context.cloudcore;
+    cloudcore.start_polling_manager();
+    let mut subscription = cloudcore.subscribe(DSN.to_string(), vec!["SET_Operating_Mode".to_string()]);
+
+    let first = timeout(Duration::from_secs(5), subscription.next()).await.unwrap();
+    assert!(matches!(
+        first,
+        Some(PropertyEvent::Changed { change: IoTPropertyChange::Added { name, .. }, .. }) if name == "SET_Operating_Mode"
+    ));
+
+    cloudcore
+        .set_property_value(DSN.to_string(), "SET_Operating_Mode".to_string(), IoTPropertyValue::Int(2), "".to_string())
+        .await
+        .0
+        .unwrap();
+    let change = tokio::select! {
+        event = subscription.next() => event,
+        _ = sleep(Duration::from_secs(5)) => panic!("timed out waiting for the write"),
+    };
+    match change {
+        Some(PropertyEvent::Changed { dsn, change }) => {
+            assert_eq!(dsn, DSN);
+            assert_eq!(
+                change,
+                IoTPropertyChange::Changed {
+                    name: "SET_Operating_Mode".to_string(),
+                    old_value: Some(IoTPropertyValue::Int(0)),
+                    new_value: Some(IoTPropertyValue::Int(2)),
+                }
+            );
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    cloudcore.stop_polling_manager();
+    assert!(timeout(Duration::from_secs(5), subscription.next()).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn subscriptions_end_when_removed() {
+    let context = setup_logged_in().await;
+    let cloudcore: &CloudCore = context.cloudcore;
+    cloudcore.start_polling_manager();
+    let mut subscription = cloudcore.subscribe(DSN.to_string(), vec![]);
+    let subscription_id = subscription.id();
+    assert!(subscription.recv().await.is_some());
+
+    cloudcore.unsubscribe(subscription_id);
+    while timeout(Duration::from_secs(5), subscription.recv()).await.unwrap().is_some() {}
+    assert!(cloudcore.poll_stats(subscription_id).is_none());
+
+    let dropped = cloudcore.subscribe(DSN.to_string(), vec![]);
+    let dropped_id = dropped.id();
+    assert!(cloudcore.poll_stats(dropped_id).is_some());
+    drop(dropped);
+    assert!(cloudcore.poll_stats(dropped_id).is_none());
+
+    cloudcore.stop_polling_manager();
+}
@@ -0,0 +1,502 @@
+mod common;
+
+use chrono::Duration;
+use cloudcore::cloudcore_error::InputError;
+use cloudcore::datetime::{format_datetime, parse_datetime, parse_optional_datetime};
+use cloudcore::properties::value::IoTPropertyValue;
+use cloudcore::CloudCoreError;
+use common::{requests_to, setup_logged_in, DSN};
+use futures_util::StreamExt;
+use serde_json::json;
+
+#[tokio::test]
+async fn get_properties_by_name() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let properties = cloudcore
+        .get_properties(DSN.to_string(), vec!["GET_Battery_Capacity".to_string()], "".to_string())
+        .await
+        .0
+        .unwrap();
+
+    assert_eq!(properties.len(), 1);
+    assert_eq!(properties[0].name(), "GET_Battery_Capacity");
+    assert_eq!(properties[0].value(), Some(&IoTPropertyValue::Int(87)));
+}
+
+#[tokio::test]
+async fn set_property_value_creates_datapoint() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let uuid = cloudcore.get_session().unwrap().user_uuid().cloned();
+
+    let (result, callback_id) = cloudcore
+        .set_property_value(DSN.to_string(), "SET_Operating_Mode".to_string(), IoTPropertyValue::Int(2), "cb".to_string())
+        .await;
+    result.unwrap();
+
+    assert_eq!(callback_id, "cb");
+    assert_eq!(context.server.property_value(DSN, "SET_Operating_Mode"), Some(IoTPropertyValue::Int(2)));
+    let datapoints = cloudcore
+        .get_datapoints(DSN.to_string(), "SET_Operating_Mode".to_string(), None, None, None, "".to_string())
+        .await
+        .0
+        .unwrap();
+    assert_eq!(datapoints.len(), 1);
+    assert_eq!(datapoints[0].metadata().user_uuid(), uuid.as_ref());
+}
+
+#[tokio::test]
+async fn set_unknown_property_fails() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let result = cloudcore
+        .set_property_value(DSN.to_string(), "SET_Missing".to_string(), IoTPropertyValue::Int(1), "".to_string())
+        .await
+        .0;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn get_datapoints_applies_count() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    for value in 1..=5 {
+        assert!(context.server.set_property_value(DSN, "GET_Battery_Capacity", IoTPropertyValue::Int(value)));
+    }
+
+    let datapoints = cloudcore
+        .get_datapoints(DSN.to_string(), "GET_Battery_Capacity".to_string(), Some(2), None, None, "".to_string())
+        .await
+        .0
+        .unwrap();
+
+    let values: Vec<&IoTPropertyValue> = datapoints.iter().map(|datapoint| datapoint.value()).collect();
+    assert_eq!(values, vec![&IoTPropertyValue::Int(4), &IoTPropertyValue::Int(5)]);
+}
+
+#[tokio::test]
+async fn datapoint_page_reports_the_next_page() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    for value in 1..=5 {
+        assert!(context.server.set_property_value(DSN, "GET_Battery_Capacity", IoTPropertyValue::Int(value)));
+    }
+
+    let first = cloudcore
+        .get_datapoint_page(DSN.to_string(), "GET_Battery_Capacity".to_string(), 1, Some(2), None, None)
+        .await
+        .unwrap();
+    let last = cloudcore
+        .get_datapoint_page(DSN.to_string(), "GET_Battery_Capacity".to_string(), 3, Some(2), None, None)
+        .await
+        .unwrap();
+
+    assert_eq!(first.page(), 1);
+    assert_eq!(first.next_page(), Some(2));
+    assert_eq!(first.datapoints()[0].value(), &IoTPropertyValue::Int(1));
+    assert_eq!(last.next_page(), None);
+    assert_eq!(last.datapoints().len(), 1);
+    assert_eq!(last.datapoints()[0].value(), &IoTPropertyValue::Int(5));
+}
+
+#[tokio::test]
+async fn get_all_datapoints_is_not_capped_at_one_page() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    for value in 1..=150 {
+        assert!(context.server.set_property_value(DSN, "GET_Battery_Capacity", IoTPropertyValue::Int(value)));
+    }
+
+    let datapoints = cloudcore
+        .get_all_datapoints(DSN.to_string(), "GET_Battery_Capacity".to_string(), None, None, "".to_string())
+        .await
+        .0
+        .unwrap();
+
+    assert_eq!(datapoints.len(), 150);
+    assert_eq!(datapoints[0].value(), &IoTPropertyValue::Int(1));
+    assert_eq!(datapoints[149].value(), &IoTPropertyValue::Int(150));
+}
+
+#[tokio::test]
+async fn datapoints_stream_yields_every_page() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    for value in 1..=120 {
+        assert!(context.server.set_property_value(DSN, "GET_Battery_Capacity", IoTPropertyValue::Int(value)));
+    }
+
+    let datapoints: Vec<_> = cloudcore
+        .datapoints_stream(DSN.to_string(), "GET_Battery_Capacity".to_string(), None, None)
+        .collect()
+        .await;
+
+    assert_eq!(datapoints.len(), 120);
+    assert!(datapoints.iter().all(|datapoint| datapoint.is_ok()));
+    let pages = context
+        .server
+        .requests()
+        .iter()
+        .filter(|request| request.path.ends_with("GET_Battery_Capacity/datapoints.json"))
+        .count();
+    assert_eq!(pages, 2);
+}
+
+#[tokio::test]
+async fn datapoints_stream_ends_with_the_error() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let datapoints: Vec<_> = cloudcore
+        .datapoints_stream("UNKNOWN".to_string(), "GET_Battery_Capacity".to_string(), None, None)
+        .collect()
+        .await;
+
+    assert_eq!(datapoints.len(), 1);
+    assert!(matches!(datapoints[0], Err(CloudCoreError::Http { status: 404, .. })));
+}
+
+#[tokio::test]
+async fn datapoint_timestamps_are_typed_and_filter_by_date() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    for value in 1..=3 {
+        assert!(context.server.set_property_value(DSN, "GET_Battery_Capacity", IoTPropertyValue::Int(value)));
+    }
+
+    let datapoints = cloudcore
+        .get_datapoints(DSN.to_string(), "GET_Battery_Capacity".to_string(), None, None, None, "".to_string())
+        .await
+        .0
+        .unwrap();
+    assert_eq!(datapoints.len(), 3);
+    let first_created_at = *datapoints[0].created_at().unwrap();
+    let last_created_at = *datapoints[2].created_at().unwrap();
+    assert!(first_created_at <= last_created_at);
+
+    let since_first = cloudcore
+        .get_datapoints(DSN.to_string(), "GET_Battery_Capacity".to_string(), None, Some(first_created_at), None, "".to_string())
+        .await
+        .0
+        .unwrap();
+    assert_eq!(since_first.len(), 3);
+
+    let after_last = cloudcore
+        .get_datapoints(
+            DSN.to_string(),
+            "GET_Battery_Capacity".to_string(),
+            None,
+            Some(last_created_at + Duration::seconds(1)),
+            None,
+            "".to_string(),
+        )
+        .await
+        .0
+        .unwrap();
+    assert!(after_last.is_empty());
+}
+
+#[test]
+fn malformed_timestamps_are_rejected() {
+    let parsed = parse_datetime("2022-07-20T18:30:00+02:00").unwrap();
+    assert_eq!(format_datetime(&parsed), "2022-07-20T16:30:00Z");
+    assert_eq!(parse_optional_datetime(Some("".to_string())).unwrap(), None);
+
+    let err = parse_datetime("07/20/2022").unwrap_err();
+    assert!(matches!(err, CloudCoreError::InvalidInput(InputError::InvalidTimestamp(_))));
+}
+
+#[tokio::test]
+async fn property_values_follow_their_base_type() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let properties = cloudcore
+        .get_properties(
+            DSN.to_string(),
+            vec!["GET_Total_Runtime".to_string(), "SET_Suction_Level".to_string(), "SET_Room_Config".to_string()],
+            "".to_string(),
+        )
+        .await
+        .0
+        .unwrap();
+
+    let value = |name: &str| properties.iter().find(|property| property.name() == name).unwrap().value().cloned();
+    assert_eq!(value("GET_Total_Runtime"), Some(IoTPropertyValue::Int64(5_000_000_000)));
+    assert_eq!(value("SET_Suction_Level"), Some(IoTPropertyValue::Decimal(1.0)));
+    assert_eq!(value("SET_Room_Config"), Some(IoTPropertyValue::Json(json!({ "rooms": [] }))));
+}
+
+#[tokio::test]
+async fn set_property_value_converts_to_the_base_type() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let names = vec!["SET_Suction_Level".to_string(), "SET_Dock_Location".to_string()];
+    cloudcore.get_properties(DSN.to_string(), names, "".to_string()).await.0.unwrap();
+
+    cloudcore
+        .set_property_value(DSN.to_string(), "SET_Suction_Level".to_string(), IoTPropertyValue::Int(2), "".to_string())
+        .await
+        .0
+        .unwrap();
+    cloudcore
+        .set_property_value(DSN.to_string(), "SET_Dock_Location".to_string(), IoTPropertyValue::Str("45.5,-73.6".to_string()), "".to_string())
+        .await
+        .0
+        .unwrap();
+
+    assert_eq!(context.server.property_value(DSN, "SET_Suction_Level"), Some(IoTPropertyValue::Decimal(2.0)));
+    assert_eq!(context.server.property_value(DSN, "SET_Dock_Location").unwrap().location_value(), Some((45.5, -73.6)));
+}
+
+#[tokio::test]
+async fn set_property_value_rejects_a_mismatched_value() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let invalid = vec![
+        ("SET_Operating_Mode", IoTPropertyValue::Str("turbo".to_string())),
+        ("SET_Find_Device", IoTPropertyValue::Int(2)),
+        ("SET_Dock_Location", IoTPropertyValue::Str("95.0,0.0".to_string())),
+        ("SET_Room_Config", IoTPropertyValue::Str("{rooms".to_string())),
+    ];
+    let names = invalid.iter().map(|(name, _)| name.to_string()).collect();
+    cloudcore.get_properties(DSN.to_string(), names, "".to_string()).await.0.unwrap();
+    context.server.clear_requests();
+
+    for (name, value) in invalid {
+        let err = cloudcore
+            .set_property_value(DSN.to_string(), name.to_string(), value, "".to_string())
+            .await
+            .0
+            .unwrap_err();
+        assert!(matches!(err, CloudCoreError::InvalidInput(InputError::InvalidPropertyValue(_))), "{}: {:?}", name, err);
+    }
+
+    assert!(context.server.requests().iter().all(|request| request.method != "POST"));
+}
+
+#[tokio::test]
+async fn set_property_value_looks_up_the_base_type_of_a_property_never_read() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    context.server.clear_requests();
+
+    let err = cloudcore
+        .set_property_value(DSN.to_string(), "SET_Operating_Mode".to_string(), IoTPropertyValue::Str("turbo".to_string()), "".to_string())
+        .await
+        .0
+        .unwrap_err();
+
+    assert!(matches!(err, CloudCoreError::InvalidInput(InputError::InvalidPropertyValue(_))), "{:?}", err);
+    assert!(context.server.requests().iter().all(|request| request.method != "POST"));
+}
+
+#[tokio::test]
+async fn set_property_value_looks_up_a_base_type_once() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    context.server.clear_requests();
+
+    for value in [2, 3] {
+        cloudcore
+            .set_property_value(DSN.to_string(), "SET_Operating_Mode".to_string(), IoTPropertyValue::Int(value), "".to_string())
+            .await
+            .0
+            .unwrap();
+    }
+
+    let requests: Vec<String> = context.server.requests().into_iter().map(|request| request.method).collect();
+    assert_eq!(requests, vec!["GET".to_string(), "POST".to_string(), "POST".to_string()]);
+    assert_eq!(context.server.property_value(DSN, "SET_Operating_Mode"), Some(IoTPropertyValue::Int(3)));
+}
+
+#[tokio::test]
+async fn set_property_values_uses_one_batch_request() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    context.server.clear_requests();
+
+    let results = cloudcore
+        .set_property_values(
+            DSN.to_string(),
+            vec![
+                ("SET_Operating_Mode".to_string(), IoTPropertyValue::Int(3)),
+                ("SET_Quiet_Time".to_string(), IoTPropertyValue::Str("22:00".to_string())),
+            ],
+            "".to_string(),
+        )
+        .await
+        .0
+        .unwrap();
+
+    assert_eq!(results.len(), 2);
+    assert!(results.values().all(|result| result.is_ok()));
+    assert_eq!(context.server.property_value(DSN, "SET_Operating_Mode"), Some(IoTPropertyValue::Int(3)));
+    assert_eq!(context.server.property_value(DSN, "SET_Quiet_Time"), Some(IoTPropertyValue::Str("22:00".to_string())));
+    let posts: Vec<String> = context
+        .server
+        .requests()
+        .into_iter()
+        .filter(|request| request.method == "POST")
+        .map(|request| request.path)
+        .collect();
+    assert_eq!(posts, vec!["/apiv1/batch_datapoints.json".to_string()]);
+}
+
+#[tokio::test]
+async fn set_property_values_reports_partial_failures() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    context.server.set_batch_datapoints_enabled(false);
+    cloudcore.get_property(DSN.to_string(), "SET_Suction_Level".to_string(), "".to_string()).await.0.unwrap();
+
+    let results = cloudcore
+        .set_property_values(
+            DSN.to_string(),
+            vec![
+                ("SET_Operating_Mode".to_string(), IoTPropertyValue::Int(4)),
+                ("SET_Find_Device".to_string(), IoTPropertyValue::Int(1)),
+                ("SET_Suction_Level".to_string(), IoTPropertyValue::Str("high".to_string())),
+                ("SET_Missing".to_string(), IoTPropertyValue::Int(1)),
+            ],
+            "".to_string(),
+        )
+        .await
+        .0
+        .unwrap();
+
+    assert!(results["SET_Operating_Mode"].is_ok());
+    assert!(results["SET_Find_Device"].is_ok());
+    assert!(matches!(
+        results["SET_Suction_Level"],
+        Err(CloudCoreError::InvalidInput(InputError::InvalidPropertyValue(_)))
+    ));
+    assert!(results["SET_Missing"].is_err());
+    assert_eq!(context.server.property_value(DSN, "SET_Operating_Mode"), Some(IoTPropertyValue::Int(4)));
+    assert_eq!(context.server.property_value(DSN, "SET_Find_Device"), Some(IoTPropertyValue::Int(1)));
+}
+
+#[tokio::test]
+async fn set_property_values_does_not_resend_a_batch_that_may_have_been_written() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let values = vec![
+        ("SET_Operating_Mode".to_string(), IoTPropertyValue::Int(5)),
+        ("SET_Quiet_Time".to_string(), IoTPropertyValue::Str("23:00".to_string())),
+    ];
+    let names = values.iter().map(|(name, _)| name.to_string()).collect();
+    cloudcore.get_properties(DSN.to_string(), names, "".to_string()).await.0.unwrap();
+    context.server.clear_requests();
+
+    context.server.fail_next_requests_to("POST", "/apiv1/batch_datapoints.json", 1, 500, None);
+    let results = cloudcore.set_property_values(DSN.to_string(), values.clone(), "".to_string()).await.0.unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.values().all(|result| matches!(result, Err(err) if err.status() == Some(500))));
+
+    context.server.malform_next_batch_response();
+    let results = cloudcore.set_property_values(DSN.to_string(), values, "".to_string()).await.0.unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.values().all(|result| matches!(result, Err(CloudCoreError::Parse(_)))));
+
+    // No datapoint was sent again on its own, the batch with the malformed answer was written
+    assert_eq!(requests_to(&context.server, "POST", "/apiv1/batch_datapoints.json"), 2);
+    assert_eq!(context.server.requests().iter().filter(|request| request.method == "POST").count(), 2);
+    assert_eq!(context.server.property_value(DSN, "SET_Operating_Mode"), Some(IoTPropertyValue::Int(5)));
+}
+
+#[tokio::test]
+async fn set_property_values_has_a_result_for_every_name() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    context.server.omit_from_batch_results("SET_Quiet_Time");
+
+    let results = cloudcore
+        .set_property_values(
+            DSN.to_string(),
+            vec![
+                ("SET_Operating_Mode".to_string(), IoTPropertyValue::Int(1)),
+                ("SET_Find_Device".to_string(), IoTPropertyValue::Int(1)),
+                ("SET_Quiet_Time".to_string(), IoTPropertyValue::Str("21:00".to_string())),
+                ("SET_Find_Device".to_string(), IoTPropertyValue::Int(1)),
+            ],
+            "".to_string(),
+        )
+        .await
+        .0
+        .unwrap();
+
+    assert_eq!(results.len(), 3);
+    assert!(results["SET_Operating_Mode"].is_ok());
+    assert!(results["SET_Quiet_Time"].is_err());
+    assert!(matches!(results["SET_Find_Device"], Err(CloudCoreError::InvalidInput(InputError::Invalid(_)))));
+    // Neither write of the repeated name was sent
+    assert_eq!(context.server.property_value(DSN, "SET_Find_Device"), Some(IoTPropertyValue::Int(0)));
+}
+
+#[tokio::test]
+async fn set_property_value_and_wait_ack_returns_the_device_ack() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    context.server.auto_ack("SET_Power_Mode", 0, 3, std::time::Duration::from_millis(300));
+
+    let ack = cloudcore
+        .set_property_value_and_wait_ack(
+            DSN.to_string(),
+            "SET_Power_Mode".to_string(),
+            IoTPropertyValue::Int(2),
+            std::time::Duration::from_secs(5),
+            "".to_string(),
+        )
+        .await
+        .0
+        .unwrap();
+
+    assert_eq!(ack.ack_status(), Some(&"0".to_string()));
+    assert_eq!(ack.ack_message(), Some(&"3".to_string()));
+    assert!(ack.acked_at().is_some());
+    assert_eq!(context.server.property_value(DSN, "SET_Power_Mode"), Some(IoTPropertyValue::Int(2)));
+}
+
+#[tokio::test]
+async fn set_property_value_and_wait_ack_times_out() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let err = cloudcore
+        .set_property_value_and_wait_ack(
+            DSN.to_string(),
+            "SET_Power_Mode".to_string(),
+            IoTPropertyValue::Int(1),
+            std::time::Duration::from_millis(800),
+            "".to_string(),
+        )
+        .await
+        .0
+        .unwrap_err();
+
+    assert!(matches!(err, CloudCoreError::Timeout(_)));
+}
+
+#[tokio::test]
+async fn set_property_value_and_wait_ack_needs_acks_enabled() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let err = cloudcore
+        .set_property_value_and_wait_ack(
+            DSN.to_string(),
+            "SET_Operating_Mode".to_string(),
+            IoTPropertyValue::Int(1),
+            std::time::Duration::from_secs(1),
+            "".to_string(),
+        )
+        .await
+        .0
+        .unwrap_err();
+
+    assert!(matches!(err, CloudCoreError::InvalidInput(InputError::Invalid(_))));
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use cloudcore::mock::{MockDevice, MockDeviceAp};
+use cloudcore::{wifi_manager, PairingConfig};
+use common::{setup, setup_logged_in, APP_SECRET, EMAIL, PASSWORD};
+use lazy_static::lazy_static;
+use log::{LevelFilter, Log, Metadata, Record};
+use tokio::sync::mpsc::unbounded_channel;
+use tokio::time::timeout;
+
+static NEW_DSN: &str = "AC000W000000002";
+static HOME_SSID: &str = "Home";
+static HOME_KEY: &str = "home-password";
+static NEW_PASSWORD: &str = "Changed123!";
+
+lazy_static! {
+    static ref LINES: Mutex<Vec<String>> = Mutex::new(vec![]);
+}
+
+/// Keeps every line cloudcore logs at any level so tests can look for secrets in them.
+struct CapturingLogger;
+
+impl Log for CapturingLogger {
+    // reqwest logs whole urls at debug, query and all, so apps have to keep it at info or above
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target().starts_with("cloudcore")
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        LINES.lock().unwrap().push(format!("{}", record.args()));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger;
+
+fn capture_logs() {
+    // Every test installs it, only the first one gets to
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(LevelFilter::Trace);
+}
+
+fn logged() -> String {
+    LINES.lock().unwrap().join("\n")
+}
+
+fn assert_not_logged(secrets: &[&str]) {
+    let logged = logged();
+    for secret in secrets {
+        assert!(!logged.contains(secret), "{:?} was logged", secret);
+    }
+}
+
+#[tokio::test]
+async fn login_and_refresh_do_not_log_credentials_or_tokens() {
+    capture_logs();
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let session = cloudcore.get_session().unwrap();
+
+    cloudcore.refresh_session().await.unwrap();
+    let refreshed = cloudcore.get_session().unwrap();
+    log::debug!(target: "cloudcore::tests", "{:?}", cloudcore);
+
+    assert!(logged().contains("login request data"));
+    assert!(logged().contains("refresh payload"));
+    assert_not_logged(&[
+        PASSWORD,
+        APP_SECRET,
+        session.access_token(),
+        session.refresh_token(),
+        refreshed.access_token(),
+        refreshed.refresh_token(),
+    ]);
+    // The session still holds the real tokens
+    assert_ne!(refreshed.access_token(), "<redacted>");
+}
+
+#[tokio::test]
+async fn password_reset_does_not_log_the_token_or_new_password() {
+    capture_logs();
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+
+    cloudcore.request_password_reset(Some(EMAIL.to_string()), None, None, None, None).await.unwrap();
+    let token = context.server.reset_password_token(EMAIL).unwrap();
+    cloudcore.reset_password(token.clone(), NEW_PASSWORD.to_string(), NEW_PASSWORD.to_string()).await.unwrap();
+    cloudcore.login(Some(EMAIL.to_string()), None, NEW_PASSWORD.to_string()).await.unwrap();
+
+    assert!(logged().contains("User data for password request"));
+    assert_not_logged(&[APP_SECRET, &token, NEW_PASSWORD]);
+}
+
+#[tokio::test]
+async fn pairing_does_not_log_the_wifi_key_or_tokens() {
+    capture_logs();
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let access_token = cloudcore.get_session().unwrap().access_token().to_string();
+    let ap = MockDeviceAp::start(&context.server, MockDevice::new(102, NEW_DSN)).unwrap();
+    ap.add_network(HOME_SSID, Some(HOME_KEY), -45);
+    let (networks_tx, mut networks) = unbounded_channel();
+    let (result_tx, mut results) = unbounded_channel();
+    let manager = Box::into_raw(Box::new(cloudcore.create_pairing_manager(
+        Box::new(|_| {}),
+        Box::new(move |wifi_networks| {
+            let _ = networks_tx.send(wifi_networks);
+        }),
+        Box::new(move |result| {
+            let _ = result_tx.send(result);
+        }),
+        PairingConfig::default().with_user_wifi_wait(Duration::from_millis(50)),
+    )));
+    let manager_ref = || unsafe { &mut *manager };
+
+    manager_ref().start(ap.address().to_string());
+    let mut network = timeout(Duration::from_secs(60), networks.recv()).await.unwrap().unwrap().remove(0);
+    network.set_password(HOME_KEY.to_string());
+    log::debug!(target: "cloudcore::tests", "{:?}", network);
+    wifi_manager::handle_wifi_network(manager_ref(), network);
+    let result = timeout(Duration::from_secs(120), results.recv()).await.unwrap().unwrap();
+    unsafe { Box::from_raw(manager) }.done_pairing();
+
+    assert_eq!(result.unwrap(), NEW_DSN);
+    assert_eq!(ap.joined_ssid(), Some(HOME_SSID.to_string()));
+    let setup_token = ap.setup_token().unwrap();
+    let pairing_log = cloudcore.get_pairing_log().unwrap();
+    assert!(pairing_log.contains("wifi_connect.json"));
+    for secret in [HOME_KEY, setup_token.as_str(), access_token.as_str()] {
+        assert!(!pairing_log.contains(secret), "{:?} is in the pairing log", secret);
+    }
+    assert_not_logged(&[HOME_KEY, &setup_token, &access_token]);
+}
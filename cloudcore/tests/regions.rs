@@ -0,0 +1,47 @@
+mod common;
+
+use cloudcore::cache::CacheInteract;
+use cloudcore::cloudcore::{default_ayla_region_environment, AylaRegionEnvironment, SELECTED_REGION_CACHE_KEY};
+use cloudcore::authentication::CACHE_USER_DIR;
+use common::{mock_region, setup, EMAIL, PASSWORD};
+
+#[test]
+fn default_resolver_maps_countries() {
+    assert_eq!(default_ayla_region_environment(Some("DE"), false), AylaRegionEnvironment::EUProd);
+    assert_eq!(default_ayla_region_environment(Some("CN"), true), AylaRegionEnvironment::CNProd);
+    assert_eq!(default_ayla_region_environment(Some("US"), true), AylaRegionEnvironment::NADev);
+    assert_eq!(default_ayla_region_environment(Some("JP"), false), AylaRegionEnvironment::NAProd);
+    assert_eq!(default_ayla_region_environment(None, true), AylaRegionEnvironment::NAProd);
+}
+
+#[tokio::test]
+async fn login_uses_custom_region_from_resolver() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+
+    cloudcore.login(Some(EMAIL.to_string()), None, PASSWORD.to_string()).await.unwrap();
+
+    assert_eq!(cloudcore.selected_ayla_region_environment, mock_region());
+    assert_eq!(cloudcore.session_params().user_url, context.server.url());
+}
+
+#[tokio::test]
+async fn resolver_receives_cached_country() {
+    let context = setup().await;
+    let cloudcore = context.cloudcore;
+    cloudcore
+        .cache
+        .set_value(CACHE_USER_DIR.to_string(), SELECTED_REGION_CACHE_KEY.to_string(), "GB")
+        .unwrap();
+    cloudcore.set_ayla_region_resolver(|country, use_dev| match country {
+        Some("GB") => AylaRegionEnvironment::Custom("staging-eu".to_string()),
+        _ => default_ayla_region_environment(country, use_dev),
+    });
+
+    cloudcore.set_ayla_region_environment(false);
+    assert_eq!(cloudcore.selected_ayla_region_environment, AylaRegionEnvironment::Custom("staging-eu".to_string()));
+
+    cloudcore.clear_ayla_region_resolver();
+    cloudcore.set_ayla_region_environment(false);
+    assert_eq!(cloudcore.selected_ayla_region_environment, AylaRegionEnvironment::EUProd);
+}
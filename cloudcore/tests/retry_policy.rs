@@ -0,0 +1,84 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use cloudcore::properties::value::IoTPropertyValue;
+use cloudcore::{CloudCoreError, RetryPolicy};
+use common::{requests_to, setup_logged_in, DSN};
+
+fn quick_policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy::new(max_attempts, Duration::from_millis(10), Duration::from_secs(2)).with_jitter(false)
+}
+
+#[tokio::test]
+async fn get_is_retried_until_it_succeeds() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.set_retry_policy(quick_policy(3));
+    context.server.clear_requests();
+    context.server.fail_next_requests(2, 503, None);
+
+    let devices = cloudcore.fetch_all_devices().await.unwrap();
+
+    assert_eq!(devices.len(), 1);
+    assert_eq!(requests_to(&context.server, "GET", "/apiv1/devices.json"), 3);
+}
+
+#[tokio::test]
+async fn last_error_is_returned_once_attempts_run_out() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.set_retry_policy(quick_policy(2));
+    context.server.clear_requests();
+    context.server.fail_next_requests(5, 502, None);
+
+    let err = cloudcore.fetch_all_devices().await.unwrap_err();
+
+    assert_eq!(err.status(), Some(502));
+    assert_eq!(requests_to(&context.server, "GET", "/apiv1/devices.json"), 2);
+}
+
+#[tokio::test]
+async fn datapoint_creation_is_not_repeated() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.set_retry_policy(quick_policy(3));
+    context.server.clear_requests();
+    let path = format!("/apiv1/dsns/{}/properties/SET_Operating_Mode/datapoints.json", DSN);
+    context.server.fail_next_requests_to("POST", &path, 1, 503, None);
+
+    let (result, _) = cloudcore
+        .set_property_value(DSN.to_string(), "SET_Operating_Mode".to_string(), IoTPropertyValue::Int(2), "".to_string())
+        .await;
+
+    assert!(matches!(result, Err(CloudCoreError::Http { status: 503, .. })));
+    assert_eq!(requests_to(&context.server, "POST", &path), 1);
+}
+
+#[tokio::test]
+async fn retry_after_is_waited_for() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.set_retry_policy(quick_policy(2));
+    context.server.fail_next_requests(1, 429, Some(1));
+
+    let started = Instant::now();
+    let devices = cloudcore.fetch_all_devices().await.unwrap();
+
+    assert_eq!(devices.len(), 1);
+    assert!(started.elapsed() >= Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn retry_after_longer_than_the_policy_allows_is_not_waited_for() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    cloudcore.set_retry_policy(quick_policy(3));
+    context.server.clear_requests();
+    context.server.fail_next_requests(1, 503, Some(120));
+
+    let err = cloudcore.fetch_all_devices().await.unwrap_err();
+
+    assert_eq!(err.status(), Some(503));
+    assert_eq!(requests_to(&context.server, "GET", "/apiv1/devices.json"), 1);
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{setup_logged_in, DEVICE_KEY, DSN};
+
+#[tokio::test]
+async fn create_update_and_clear_schedules() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let schedule = cloudcore
+        .create_device_schedule(
+            DSN.to_string(),
+            "clean".to_string(),
+            "2022-01-01".to_string(),
+            "09:30:00".to_string(),
+            "SET_Operating_Mode".to_string(),
+            "integer".to_string(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(schedule.device_id(), Some(DEVICE_KEY));
+    assert!(schedule.key().is_some());
+
+    let mut schedules = cloudcore.fetch_schedules(Some(DEVICE_KEY)).await.unwrap();
+    assert_eq!(schedules.len(), 1);
+    let mut updated = schedules.remove(0);
+    updated.set_start_time_each_day("18:00:00".to_string());
+    let updated = cloudcore.update_schedule(updated).await.unwrap();
+    assert_eq!(updated.start_time_each_day(), "18:00:00");
+
+    cloudcore.clear_schedules(DEVICE_KEY).await.unwrap();
+    let schedules = cloudcore.fetch_schedules(None).await.unwrap();
+    assert_eq!(schedules.len(), 1);
+    assert_eq!(schedules[0].active(), Some(false));
+    assert_eq!(schedules[0].start_time_each_day(), "00:00:00");
+}
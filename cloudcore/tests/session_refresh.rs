@@ -0,0 +1,64 @@
+mod common;
+
+use cloudcore::authentication::UserSession;
+use cloudcore::cloudcore_error::AuthError;
+use cloudcore::CloudCoreError;
+use common::{requests_to, setup_logged_in, DSN};
+
+#[tokio::test]
+async fn expired_session_is_refreshed_before_the_call() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let session = cloudcore.get_session().unwrap();
+    cloudcore.set_session(UserSession::new(
+        session.access_token().to_string(),
+        session.refresh_token().to_string(),
+        0,
+        session.auth_username().to_string(),
+        session.user_uuid().cloned(),
+        session.use_dev(),
+    ));
+    context.server.clear_requests();
+
+    let devices = cloudcore.fetch_all_devices().await.unwrap();
+
+    assert_eq!(devices.len(), 1);
+    assert!(cloudcore.logged_in());
+    assert_ne!(cloudcore.get_session().unwrap().access_token(), session.access_token());
+    assert_eq!(requests_to(&context.server, "POST", "/users/refresh_token.json"), 1);
+    assert_eq!(requests_to(&context.server, "GET", "/apiv1/devices.json"), 1);
+}
+
+#[tokio::test]
+async fn rejected_token_is_refreshed_and_retried_once() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    context.server.expire_access_tokens();
+    context.server.clear_requests();
+
+    let device = cloudcore.fetch_device_with_dsn(DSN.to_string()).await.unwrap();
+
+    assert_eq!(device.dsn(), Some(&DSN.to_string()));
+    let dsn_path = format!("/apiv1/dsns/{}.json", DSN);
+    assert_eq!(requests_to(&context.server, "GET", &dsn_path), 2);
+    assert_eq!(requests_to(&context.server, "POST", "/users/refresh_token.json"), 1);
+}
+
+#[tokio::test]
+async fn failed_refresh_returns_refresh_failed() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    let session = cloudcore.get_session().unwrap();
+    cloudcore.set_session(UserSession::new(
+        session.access_token().to_string(),
+        "not-a-refresh-token".to_string(),
+        0,
+        session.auth_username().to_string(),
+        session.user_uuid().cloned(),
+        session.use_dev(),
+    ));
+
+    let err = cloudcore.fetch_all_devices().await.unwrap_err();
+
+    assert!(matches!(err, CloudCoreError::Auth(AuthError::RefreshFailed)));
+}
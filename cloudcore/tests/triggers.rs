@@ -0,0 +1,73 @@
+mod common;
+
+use cloudcore::properties::trigger::{TriggerAppRequest, TriggerRequest};
+use common::{setup_logged_in, DSN};
+
+static PROPERTY: &str = "Get_ERROR_Code";
+
+fn trigger_request() -> TriggerRequest {
+    TriggerRequest::new_error_request("Robot 1".to_string(), "300".to_string())
+}
+
+fn trigger_app_request(device_id: &str) -> TriggerAppRequest {
+    let push_metadata = format!("{{\"android_device_id\":\"{}\"}}", device_id);
+    TriggerAppRequest::fcm_request(
+        "registration-id".to_string(),
+        "com.example.robot".to_string(),
+        "Robot error".to_string(),
+        None,
+        push_metadata,
+    )
+}
+
+#[tokio::test]
+async fn create_fetch_and_delete_triggers() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+
+    let trigger = cloudcore
+        .create_trigger_and_app(DSN.to_string(), PROPERTY.to_string(), trigger_request(), trigger_app_request("phone-1"))
+        .await
+        .unwrap();
+    assert_eq!(trigger.trigger_apps.len(), 1);
+
+    let triggers = cloudcore.fetch_triggers(DSN.to_string(), PROPERTY.to_string()).await.unwrap();
+    assert_eq!(triggers.len(), 1);
+    assert_eq!(triggers[0].key, trigger.key);
+    assert_eq!(triggers[0].trigger_apps[0].param1, Some("registration-id".to_string()));
+
+    cloudcore.delete_all_triggers(DSN.to_string(), PROPERTY.to_string()).await.unwrap();
+    assert!(cloudcore.fetch_triggers(DSN.to_string(), PROPERTY.to_string()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn update_trigger_app_registration_ids_for_device() {
+    let context = setup_logged_in().await;
+    let cloudcore = context.cloudcore;
+    cloudcore
+        .create_trigger_and_app(DSN.to_string(), PROPERTY.to_string(), trigger_request(), trigger_app_request("phone-1"))
+        .await
+        .unwrap();
+
+    cloudcore
+        .update_all_trigger_apps_registration_id(DSN.to_string(), PROPERTY.to_string(), "phone-1".to_string(), "new-id".to_string())
+        .await
+        .unwrap();
+
+    let apps = cloudcore
+        .fetch_trigger_apps_for_device_id(DSN.to_string(), PROPERTY.to_string(), "phone-1".to_string())
+        .await
+        .unwrap();
+    assert_eq!(apps.len(), 1);
+    assert_eq!(apps[0].param1, Some("new-id".to_string()));
+
+    cloudcore
+        .delete_all_trigger_apps_by_device_id(DSN.to_string(), PROPERTY.to_string(), "phone-1".to_string())
+        .await
+        .unwrap();
+    let apps = cloudcore
+        .fetch_trigger_apps_for_device_id(DSN.to_string(), PROPERTY.to_string(), "phone-1".to_string())
+        .await
+        .unwrap();
+    assert!(apps.is_empty());
+}
@@ -1,4 +1,4 @@
-use cloudcore::CloudCore;
+use cloudcore::{CloudCore, IntoMantleResult};
 use std::os::raw::c_char;
 use ffi_utilities::{MantleStringPointer, RuntimeFFI};
 use mantle_utilities::MantleError;
@@ -33,7 +33,7 @@ pub unsafe extern "C" fn cloudcore_create_account(
                 email_subject,
                 email_body_html,
             )
-            .await
+            .await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -49,7 +49,7 @@ pub unsafe extern "C" fn cloudcore_confirm_account(
     let token = MantleStringPointer(token).to_string();
 
     let closure = async move {
-        cloudcore.confirm_account(token).await
+        cloudcore.confirm_account(token).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -81,7 +81,7 @@ pub unsafe extern "C" fn cloudcore_send_confirmation_instructions(
                 email_subject,
                 email_body_html,
             )
-            .await
+            .await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -95,7 +95,7 @@ pub unsafe extern "C" fn cloudcore_delete_account(
     let cloudcore = &mut *ptr_cloudcore;
 
     let closure = async move {
-        cloudcore.delete_account().await
+        cloudcore.delete_account().await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -121,7 +121,7 @@ pub unsafe extern "C" fn cloudcore_request_password_reset(
     let closure = async move {
         cloudcore
             .request_password_reset(email, phone_number, email_template_id, email_subject, email_body_html)
-            .await
+            .await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -143,7 +143,7 @@ pub unsafe extern "C" fn cloudcore_reset_password(
     let closure = async move {
         cloudcore
             .reset_password(token, password, password_confirmation)
-            .await
+            .await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -163,7 +163,7 @@ pub unsafe extern "C" fn cloudcore_reset_password_for_current_user(
     let closure = async move {
         cloudcore
             .reset_password_for_current_user(current_password, new_password)
-            .await
+            .await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -179,7 +179,7 @@ pub unsafe extern "C" fn cloudcore_update_email(
     let new_email = MantleStringPointer(new_email).to_string();
 
     let closure = async move {
-        cloudcore.update_email(new_email).await
+        cloudcore.update_email(new_email).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
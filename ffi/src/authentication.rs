@@ -1,4 +1,4 @@
-use cloudcore::CloudCore;
+use cloudcore::{CloudCore, IntoMantleResult};
 use std::os::raw::c_char;
 use ffi_utilities::{convert_to_using_mantle_error, MantleStringPointer, RuntimeFFI};
 use log::error;
@@ -19,7 +19,7 @@ pub unsafe extern "C" fn cloudcore_login(
     let password = MantleStringPointer(password).to_string();
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.login(email, phone_number, password).await
+        cloudcore.login(email, phone_number, password).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -30,7 +30,7 @@ pub unsafe extern "C" fn cloudcore_get_user_session(
     ptr_cloudcore: *mut CloudCore,
 ) -> Result<UserSession, Box<MantleError>> {
     let cloudcore = &mut *ptr_cloudcore;
-    convert_to_using_mantle_error(cloudcore.get_session())
+    convert_to_using_mantle_error(cloudcore.get_session().into_mantle_result())
 }
 
 #[no_mangle]
@@ -55,7 +55,7 @@ pub unsafe extern "C" fn cloudcore_refresh_session(
 ) {
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.refresh_session().await
+        cloudcore.refresh_session().await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -74,7 +74,7 @@ pub unsafe extern "C" fn cloudcore_logout(
 ) {
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.logout().await
+        cloudcore.logout().await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -1,4 +1,4 @@
-use cloudcore::{CloudCore};
+use cloudcore::{CloudCore, IntoMantleResult};
 use std::os::raw::{c_char};
 use ffi_utilities::{convert_to_using_mantle_error, MantleStringPointer, RuntimeFFI};
 use mantle_utilities::MantleError;
@@ -28,7 +28,7 @@ pub unsafe extern "C" fn cloudcore_set_value(
     };
  
     let closure = move || {
-        cloudcore.cache.set_value(path, key, value)
+        cloudcore.cache.set_value(path, key, value).into_mantle_result()
     };
     RuntimeFFI::exec_sync(closure, callback);
 }
@@ -54,7 +54,7 @@ pub unsafe extern "C" fn cloudcore_set_value_sync(
         CacheDataValue::NullValue => Value::Null
     };
 
-    convert_to_using_mantle_error(cloudcore.cache.set_value(path, key, value))
+    convert_to_using_mantle_error(cloudcore.cache.set_value(path, key, value).into_mantle_result())
 }
 
 #[no_mangle]
@@ -69,7 +69,7 @@ pub unsafe extern "C" fn cloudcore_get_value(
     let key = MantleStringPointer(key).to_string();
     let cloudcore = &mut *ptr_cloudcore;
     let closure = move || {
-        cloudcore.cache.get_value(path, key)
+        cloudcore.cache.get_value(path, key).into_mantle_result()
     };
     RuntimeFFI::exec_sync(closure, callback);
 }
@@ -84,5 +84,17 @@ pub unsafe extern "C" fn cloudcore_get_value_sync(
     let path = MantleStringPointer(path).to_string();
     let key = MantleStringPointer(key).to_string();
     let cloudcore = &mut *ptr_cloudcore;
-    convert_to_using_mantle_error(cloudcore.cache.get_value(path, key))
+    convert_to_using_mantle_error(cloudcore.cache.get_value(path, key).into_mantle_result())
+}
+
+/// `key` is 32 random bytes in base64, see `CloudCore::set_cache_encryption_key`.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn cloudcore_set_cache_encryption_key(
+    ptr_cloudcore: *mut CloudCore,
+    key: *const c_char,
+) -> Result<(), Box<MantleError>> {
+    let cloudcore = &mut *ptr_cloudcore;
+    let key = MantleStringPointer(key).to_string();
+    convert_to_using_mantle_error(cloudcore.set_cache_encryption_key(key).into_mantle_result())
 }
\ No newline at end of file
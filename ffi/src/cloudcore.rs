@@ -76,6 +76,46 @@ pub unsafe extern "C" fn cloudcore_selected_ayla_region(
     Box::into_raw(Box::new(cloudcore.selected_ayla_region_environment.clone()))
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn cloudcore_register_custom_region(
+    ptr_cloudcore: *mut CloudCore,
+    region: *const c_char,
+    app_id: *const c_char,
+    app_secret: *const c_char,
+    user_url: *const c_char,
+    device_url: *const c_char,
+) {
+    let cloudcore = &mut *ptr_cloudcore;
+    let region = MantleStringPointer(region).to_string();
+    let session_parameters = SessionParameters {
+        app_info: ApplicationInfo {
+            app_id: MantleStringPointer(app_id).to_string(),
+            app_secret: MantleStringPointer(app_secret).to_string(),
+        },
+        user_url: MantleStringPointer(user_url).to_string(),
+        device_url: MantleStringPointer(device_url).to_string(),
+    };
+    cloudcore.register_ayla_region_environment(AylaRegionEnvironment::Custom(region), session_parameters);
+}
+
+/// Send every user to a registered custom region regardless of country. A null region goes back to the country mapping.
+#[no_mangle]
+pub unsafe extern "C" fn cloudcore_use_custom_region(
+    ptr_cloudcore: *mut CloudCore,
+    region: *const c_char,
+) {
+    let cloudcore = &mut *ptr_cloudcore;
+    match MantleStringPointer(region).to_option_string() {
+        Some(region) => cloudcore.set_ayla_region_resolver(move |_, _| AylaRegionEnvironment::Custom(region.to_string())),
+        None => cloudcore.clear_ayla_region_resolver()
+    }
+    let use_dev = match cloudcore.user_session.as_ref() {
+        Some(session) => session.use_dev(),
+        None => false
+    };
+    cloudcore.set_ayla_region_environment(use_dev);
+}
+
 fn create_ayla_region_environment_map(app_map: HashMap<AylaRegionEnvironment, ApplicationInfo>) -> HashMap<AylaRegionEnvironment, SessionParameters> {
     let mut map: HashMap<AylaRegionEnvironment, SessionParameters> = HashMap::new();
 
@@ -1,4 +1,4 @@
-use cloudcore::{CloudCore};
+use cloudcore::{CloudCore, IntoMantleResult};
 use std::os::raw::c_char;
 use ffi_utilities::{MantleStringPointer, RuntimeFFI};
 use mantle_utilities::MantleError;
@@ -12,7 +12,7 @@ pub unsafe extern "C" fn cloudcore_devices(
 ) {
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.fetch_all_devices().await
+        cloudcore.fetch_all_devices().await.into_mantle_result()
     };
     RuntimeFFI::exec_list(closure, callback);
 }
@@ -27,7 +27,7 @@ pub unsafe extern "C" fn cloudcore_device(
     let cloudcore = &mut *ptr_cloudcore;
     let dsn = MantleStringPointer(dsn).to_string();
     let closure = async move {
-        cloudcore.fetch_device_with_dsn(dsn).await
+        cloudcore.fetch_device_with_dsn(dsn).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -44,7 +44,7 @@ pub unsafe extern "C" fn cloudcore_rename_device(
     let dsn = MantleStringPointer(dsn).to_string();
     let new_name = MantleStringPointer(new_name).to_string();
     let closure = async move {
-        cloudcore.rename_device_with_dsn(dsn, new_name).await
+        cloudcore.rename_device_with_dsn(dsn, new_name).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -61,7 +61,7 @@ pub unsafe extern "C" fn cloudcore_factory_reset_device(
     let dsn = MantleStringPointer(dsn).to_string();
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.factory_reset_device(dev_id, dsn).await
+        cloudcore.factory_reset_device(dev_id, dsn).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -78,7 +78,7 @@ pub unsafe extern "C" fn cloudcore_delete_device(
     let dsn = MantleStringPointer(dsn).to_string();
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.delete_device(dev_id, dsn).await
+        cloudcore.delete_device(dev_id, dsn).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -97,7 +97,7 @@ pub unsafe extern "C" fn cloudcore_delete_device_map(
     let dsn = MantleStringPointer(dsn).to_string();
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.delete_device_map(dsn, re_explore, partial_delete).await
+        cloudcore.delete_device_map(dsn, re_explore, partial_delete).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
\ No newline at end of file
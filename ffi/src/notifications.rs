@@ -3,7 +3,8 @@ use std::os::raw::c_char;
 use ffi_utilities::{MantleStringPointer, RuntimeFFI};
 use mantle_utilities::MantleError;
 
-use cloudcore::CloudCore;
+use cloudcore::{CloudCore, IntoMantleResult};
+use cloudcore::datetime::{parse_datetime, parse_optional_datetime};
 use cloudcore::notifications::notifications::Notification;
 
 #[no_mangle]
@@ -14,10 +15,13 @@ pub unsafe extern "C" fn cloudcore_fetch_all_notifications(
     callback: fn(result: Result<Vec<Notification>, Box<MantleError>>),
 ) {
     let cloudcore = &mut *ptr_cloudcore;
-    let from = MantleStringPointer(from).to_string();
+    let from = MantleStringPointer(from).to_option_string();
 
     let closure = async move {
-        cloudcore.fetch_all_notifications(from).await
+        match parse_optional_datetime(from) {
+            Ok(from) => cloudcore.fetch_all_notifications(from).await.into_mantle_result(),
+            Err(err) => Err::<Vec<Notification>, _>(err).into_mantle_result(),
+        }
     };
     RuntimeFFI::exec_list(closure, callback);
 }
@@ -30,7 +34,7 @@ pub unsafe extern "C" fn cloudcore_get_cached_notifications(
 ) {
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.get_all_cached_notifications().await
+        cloudcore.get_all_cached_notifications().await.into_mantle_result()
     };
     RuntimeFFI::exec_list(closure, callback);
 }
@@ -45,7 +49,10 @@ pub unsafe extern "C" fn cloudcore_delete_all_notifications(
     let cloudcore = &mut *ptr_cloudcore;
     let to = MantleStringPointer(to).to_string();
     let closure = async move {
-        cloudcore.delete_all_notifications(to).await
+        match parse_datetime(&to) {
+            Ok(to) => cloudcore.delete_all_notifications(to).await.into_mantle_result(),
+            Err(err) => Err::<(), _>(err).into_mantle_result(),
+        }
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -62,7 +69,7 @@ pub unsafe extern "C" fn cloudcore_delete_notification(
     let dsn = MantleStringPointer(dsn).to_string();
     let id = MantleStringPointer(id).to_string();
     let closure = async move {
-        cloudcore.delete_notification(dsn, id).await
+        cloudcore.delete_notification(dsn, id).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -75,7 +82,7 @@ pub unsafe extern "C" fn cloudcore_mark_all_notifications_as_read(
 ) {
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.mark_all_notifications_as_read().await
+        cloudcore.mark_all_notifications_as_read().await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
\ No newline at end of file
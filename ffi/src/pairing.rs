@@ -1,10 +1,39 @@
-use cloudcore::{CloudCore, wifi_manager, WifiPairing, WifiPairingState};
+use cloudcore::{CloudCore, CloudCoreError, IntoMantleResult, PairingConfig, PairingStepConfig, wifi_manager, WifiPairing, WifiPairingState};
 use std::os::raw::c_char;
+use std::time::Duration;
 use ffi_utilities::{convert_to_using_mantle_error, MantleStringPointer};
 use log::error;
 use mantle_utilities::MantleError;
 use cloudcore::pairing::wifi_network::WifiNetwork;
 
+/// `device_*` is the budget of every step talking to the device over its AP, `ayla_*` the one of
+/// registering the device and checking it shows up on Ayla. `deadline_millis` can be null for no deadline.
+#[no_mangle]
+pub unsafe extern "C" fn cloudcore_create_pairing_config(
+    device_max_attempts: u32,
+    device_pause_millis: u64,
+    ayla_max_attempts: u32,
+    ayla_pause_millis: u64,
+    deadline_millis: *const u64,
+) -> *mut PairingConfig {
+    let device_step = PairingStepConfig::new(device_max_attempts, Duration::from_millis(device_pause_millis));
+    let ayla_step = PairingStepConfig::new(ayla_max_attempts, Duration::from_millis(ayla_pause_millis));
+    let deadline = if deadline_millis.is_null() { None } else { Some(Duration::from_millis(*deadline_millis)) };
+    let config = PairingConfig::default();
+    let wifi_networks_refresh = config.wifi_networks_refresh();
+    let config = config
+        .with_fetch_dsn(device_step.clone())
+        .with_wifi_scan(device_step.clone())
+        .with_wifi_networks(device_step.clone(), wifi_networks_refresh)
+        .with_send_credentials(device_step.clone())
+        .with_device_wifi_status(device_step)
+        .with_ayla_handshake(ayla_step.clone())
+        .with_ayla_device_check(ayla_step)
+        .with_deadline(deadline);
+    Box::into_raw(Box::new(config))
+}
+
+/// `pairing_config` can be null to pair with `PairingConfig::default()`.
 #[no_mangle]
 #[allow(improper_ctypes_definitions)]
 pub unsafe extern "C" fn cloudcore_create_pairing_manager(
@@ -16,8 +45,10 @@ pub unsafe extern "C" fn cloudcore_create_pairing_manager(
         wifi_networks: Vec<WifiNetwork>,
     ),
     done_callback: fn(result: Result<String, Box<MantleError>>),
+    pairing_config: *mut PairingConfig,
 ) -> *mut WifiPairing {
     let cloudcore = &*ptr_cloudcore;
+    let config = if pairing_config.is_null() { PairingConfig::default() } else { *Box::from_raw(pairing_config) };
     let wifi_manager = cloudcore.create_pairing_manager(
         Box::new(move |state| {
             get_state_callback(
@@ -30,8 +61,9 @@ pub unsafe extern "C" fn cloudcore_create_pairing_manager(
             )
         }),
         Box::new(move |result| {
-            done_callback(convert_to_using_mantle_error(result));
+            done_callback(convert_to_using_mantle_error(result.into_mantle_result()));
         }),
+        config,
     );
     Box::into_raw(Box::new(wifi_manager))
 }
@@ -62,6 +94,31 @@ pub unsafe extern "C" fn cloudcore_continue_pairing(
     wifi_manager.continue_pairing()
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn cloudcore_cancel_pairing(
+    ptr_wifi_manager: *mut WifiPairing
+) {
+    if ptr_wifi_manager.is_null() {
+        error!("wifi pairing object pointer is null");
+        return;
+    }
+    let wifi_manager = &mut *ptr_wifi_manager;
+    wifi_manager.cancel()
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn cloudcore_resume_pairing(
+    ptr_wifi_manager: *mut WifiPairing
+) -> Result<(), Box<MantleError>> {
+    if ptr_wifi_manager.is_null() {
+        error!("wifi pairing object pointer is null");
+        return convert_to_using_mantle_error(Err(CloudCoreError::Pairing("Pairing manager is null".to_string())).into_mantle_result());
+    }
+    let wifi_manager = &mut *ptr_wifi_manager;
+    convert_to_using_mantle_error(wifi_manager.resume().into_mantle_result())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn cloudcore_done_pairing(
     ptr_wifi_manager: *mut WifiPairing
@@ -108,7 +165,7 @@ pub unsafe extern "C" fn cloudcore_write_to_pairing_log(
 ) -> Result<(), Box<MantleError>> {
     let cloudcore = &*ptr_cloudcore;
     let content = MantleStringPointer(c_content).to_string();
-    convert_to_using_mantle_error(cloudcore.write_to_pairing_log(content))
+    convert_to_using_mantle_error(cloudcore.write_to_pairing_log(content).into_mantle_result())
 }
 
 #[no_mangle]
@@ -117,5 +174,24 @@ pub unsafe extern "C" fn cloudcore_get_pairing_log(
     ptr_cloudcore: *const CloudCore,
 ) -> Result<String, Box<MantleError>> {
     let cloudcore = &*ptr_cloudcore;
-    convert_to_using_mantle_error(cloudcore.get_pairing_log())
+    convert_to_using_mantle_error(cloudcore.get_pairing_log().into_mantle_result())
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn cloudcore_export_pairing_log(
+    ptr_cloudcore: *const CloudCore,
+    redacted: bool,
+) -> Result<String, Box<MantleError>> {
+    let cloudcore = &*ptr_cloudcore;
+    convert_to_using_mantle_error(cloudcore.export_pairing_log(redacted).into_mantle_result())
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn cloudcore_get_pairing_diagnostics(
+    ptr_cloudcore: *const CloudCore,
+) -> Result<String, Box<MantleError>> {
+    let cloudcore = &*ptr_cloudcore;
+    convert_to_using_mantle_error(cloudcore.get_pairing_diagnostics().into_mantle_result())
 }
\ No newline at end of file
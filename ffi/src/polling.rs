@@ -1,5 +1,8 @@
 use cloudcore::CloudCore;
-use cloudcore::polling::PollConfig;
+use cloudcore::polling::{PollCallback, PollConfig, PollEvent, PropertyEvent};
+use ffi_utilities::MantleStringPointer;
+use mantle_utilities::RUNTIME;
+use std::os::raw::c_char;
 
 #[no_mangle]
 pub unsafe extern "C" fn cloudcore_start_polling_manager(
@@ -9,6 +12,28 @@ pub unsafe extern "C" fn cloudcore_start_polling_manager(
     cloudcore.start_polling_manager();
 }
 
+/// Null arguments are left out of the config, so the same config can be used to update a poll.
+/// Every event of the poll is passed to `callback` along with `callback_id`.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn cloudcore_create_poll_config(
+    dsn: *const c_char,
+    names: *mut Vec<String>,
+    sleep_time: *const u64,
+    emit_snapshots: bool,
+    callback_id: *const c_char,
+    callback: fn(event: (PollEvent, String)),
+) -> *mut PollConfig {
+    let dsn = MantleStringPointer(dsn).to_option_string();
+    let names = if names.is_null() { None } else { Some(*Box::from_raw(names)) };
+    let sleep_time = if sleep_time.is_null() { None } else { Some(*sleep_time) };
+    let poll_callback = MantleStringPointer(callback_id).to_option_string().map(|callback_id| {
+        Box::new(move |event| callback((event, callback_id.clone()))) as PollCallback
+    });
+    let config = PollConfig::new(names, None, sleep_time, dsn, poll_callback).with_emit_snapshots(emit_snapshots);
+    Box::into_raw(Box::new(config))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn cloudcore_add_poll(
     ptr_cloudcore: *mut CloudCore,
@@ -47,4 +72,38 @@ pub unsafe extern "C" fn cloudcore_stop_polling_manager(
 ) {
     let cloudcore = &mut *ptr_cloudcore;
     cloudcore.stop_polling_manager();
-}
\ No newline at end of file
+}
+
+/// Every event of the subscription is passed to `callback` along with `callback_id`.
+/// A null `names` subscribes to every property. Returns the id to unsubscribe with.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn cloudcore_subscribe(
+    ptr_cloudcore: *mut CloudCore,
+    dsn: *const c_char,
+    names: *mut Vec<String>,
+    callback_id: *const c_char,
+    callback: fn(event: (PropertyEvent, String)),
+) -> u32 {
+    let cloudcore = &mut *ptr_cloudcore;
+    let dsn = MantleStringPointer(dsn).to_string();
+    let names = if names.is_null() { vec![] } else { *Box::from_raw(names) };
+    let callback_id = MantleStringPointer(callback_id).to_string();
+    let mut subscription = cloudcore.subscribe(dsn, names);
+    let subscription_id = subscription.id();
+    RUNTIME.spawn(async move {
+        while let Some(event) = subscription.recv().await {
+            callback((event, callback_id.clone()));
+        }
+    });
+    subscription_id
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cloudcore_unsubscribe(
+    ptr_cloudcore: *mut CloudCore,
+    subscription_id: u32,
+) {
+    let cloudcore = &mut *ptr_cloudcore;
+    cloudcore.unsubscribe(subscription_id);
+}
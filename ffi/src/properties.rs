@@ -1,12 +1,14 @@
-use cloudcore::CloudCore;
+use cloudcore::{CloudCore, IntoMantleResult};
+use cloudcore::datetime::parse_optional_datetime;
 use std::os::raw::{c_char, c_int};
 use ffi_utilities::{convert_list_to_using_mantle_error_with_id, MantleStringPointer, RuntimeFFI};
 use mantle_utilities::{MantleError, RUNTIME};
-use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointFile, IoTDatapointMessage};
+use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointAck, IoTDatapointFile, IoTDatapointMessage, IoTPropertyWriteResult};
 use cloudcore::properties::property::IoTProperty;
 use cloudcore::properties::value::IoTPropertyValue;
 use lazy_static::lazy_static;
 use std::sync::Mutex;
+use std::time::Duration;
 
 lazy_static! {
     static ref PROP_MSGS_CB_STRUCT: Mutex<fn(result: (Result<Vec<IoTDatapointMessage>, Box<MantleError>>, String))> = Mutex::new(|_result|{});
@@ -29,7 +31,7 @@ pub unsafe extern "C" fn cloudcore_get_property(
     let callback_id = MantleStringPointer(callback_id).to_string();
 
     let closure = async move {
-        cloudcore.get_property(dsn, prop, callback_id).await
+        cloudcore.get_property(dsn, prop, callback_id).await.into_mantle_result()
     };
     RuntimeFFI::exec_list_id(closure, callback);
 }
@@ -49,7 +51,7 @@ pub unsafe extern "C" fn cloudcore_get_properties(
     let callback_id = MantleStringPointer(callback_id).to_string();
 
     let closure = async move {
-        cloudcore.get_properties(dsn, props, callback_id).await
+        cloudcore.get_properties(dsn, props, callback_id).await.into_mantle_result()
     };
     RuntimeFFI::exec_list_id(closure, callback);
 }
@@ -75,7 +77,37 @@ pub unsafe extern "C" fn cloudcore_get_data_points(
     let callback_id = MantleStringPointer(callback_id).to_string();
 
     let closure = async move {
-        cloudcore.get_datapoints(dsn, prop_name, count, from, to, callback_id).await
+        match (parse_optional_datetime(from), parse_optional_datetime(to)) {
+            (Ok(from), Ok(to)) => cloudcore.get_datapoints(dsn, prop_name, count, from, to, callback_id).await.into_mantle_result(),
+            (Err(err), _) | (_, Err(err)) => (Err::<Vec<IoTDatapoint>, _>(err), callback_id).into_mantle_result(),
+        }
+    };
+    RuntimeFFI::exec_list_id(closure, callback);
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn cloudcore_get_all_data_points(
+    ptr_cloudcore: *mut CloudCore,
+    dsn: *const c_char,
+    prop_name: *const c_char,
+    from: *const c_char,
+    to: *const c_char,
+    callback_id: *const c_char,
+    callback: fn(result: (Result<Vec<IoTDatapoint>, Box<MantleError>>, String)),
+) {
+    let cloudcore = &mut *ptr_cloudcore;
+    let dsn = MantleStringPointer(dsn).to_string();
+    let prop_name = MantleStringPointer(prop_name).to_string();
+    let from = MantleStringPointer(from).to_option_string();
+    let to = MantleStringPointer(to).to_option_string();
+    let callback_id = MantleStringPointer(callback_id).to_string();
+
+    let closure = async move {
+        match (parse_optional_datetime(from), parse_optional_datetime(to)) {
+            (Ok(from), Ok(to)) => cloudcore.get_all_datapoints(dsn, prop_name, from, to, callback_id).await.into_mantle_result(),
+            (Err(err), _) | (_, Err(err)) => (Err::<Vec<IoTDatapoint>, _>(err), callback_id).into_mantle_result(),
+        }
     };
     RuntimeFFI::exec_list_id(closure, callback);
 }
@@ -95,7 +127,7 @@ pub unsafe extern "C" fn cloudcore_get_file_property(
     let callback_id = MantleStringPointer(callback_id).to_string();
 
     let closure = async move {
-        cloudcore.get_file_property(dsn, prop_name, callback_id).await
+        cloudcore.get_file_property(dsn, prop_name, callback_id).await.into_mantle_result()
     };
     RuntimeFFI::exec_id(closure, callback);
 }
@@ -119,7 +151,7 @@ pub unsafe extern "C" fn cloudcore_get_file_properties(
     let closure = async move {
         cloudcore.get_file_properties_callback(dsn, props, callback_id, |result| {
             if let Some(cb) = FILES_CB_STRUCT.lock().ok() {
-                cb(convert_list_to_using_mantle_error_with_id(result))
+                cb(convert_list_to_using_mantle_error_with_id(result.into_mantle_result()))
             }
         }).await
     };
@@ -143,7 +175,7 @@ pub unsafe extern "C" fn cloudcore_get_datapoint_with_file_url(
     let callback_id = MantleStringPointer(callback_id).to_string();
 
     let closure = async move {
-        cloudcore.get_datapoint_with_file_url(url, dsn, prop_name, callback_id).await
+        cloudcore.get_datapoint_with_file_url(url, dsn, prop_name, callback_id).await.into_mantle_result()
     };
     RuntimeFFI::exec_id(closure, callback);
 }
@@ -163,7 +195,7 @@ pub unsafe extern "C" fn cloudcore_get_message_property(
     let callback_id = MantleStringPointer(callback_id).to_string();
 
     let closure = async move {
-        cloudcore.get_message_property(dsn, prop_name, callback_id).await
+        cloudcore.get_message_property(dsn, prop_name, callback_id).await.into_mantle_result()
     };
     RuntimeFFI::exec_id(closure, callback);
 }
@@ -187,7 +219,7 @@ pub unsafe extern "C" fn cloudcore_get_message_properties(
     let closure = async move {
         cloudcore.get_message_properties_callback(dsn, props, callback_id, |result| {
             if let Some(cb) = MSGS_CB_STRUCT.lock().ok() {
-                cb(convert_list_to_using_mantle_error_with_id(result))
+                cb(convert_list_to_using_mantle_error_with_id(result.into_mantle_result()))
             }
         }).await
     };
@@ -211,7 +243,7 @@ pub unsafe extern "C" fn cloudcore_get_datapoint_with_id(
     let callback_id = MantleStringPointer(callback_id).to_string();
 
     let closure = async move {
-        cloudcore.get_datapoint_with_id(dsn, datapoint_id, prop_name, callback_id).await
+        cloudcore.get_datapoint_with_id(dsn, datapoint_id, prop_name, callback_id).await.into_mantle_result()
     };
     RuntimeFFI::exec_id(closure, callback);
 }
@@ -233,11 +265,64 @@ pub unsafe extern "C" fn cloudcore_set_property_value(
     let callback_id = MantleStringPointer(callback_id).to_string();
 
     let closure = async move {
-        cloudcore.set_property_value(dsn, name, value, callback_id).await
+        cloudcore.set_property_value(dsn, name, value, callback_id).await.into_mantle_result()
+    };
+    RuntimeFFI::exec_id(closure, callback);
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn cloudcore_set_property_value_and_wait_ack(
+    ptr_cloudcore: *mut CloudCore,
+    dsn: *const c_char,
+    name: *const c_char,
+    value: *mut IoTPropertyValue,
+    timeout_millis: u64,
+    callback_id: *const c_char,
+    callback: fn(result: (Result<IoTDatapointAck, Box<MantleError>>, String)),
+) {
+    let cloudcore = &mut *ptr_cloudcore;
+    let dsn = MantleStringPointer(dsn).to_string();
+    let name = MantleStringPointer(name).to_string();
+    let value = *Box::from_raw(value);
+    let timeout = Duration::from_millis(timeout_millis);
+    let callback_id = MantleStringPointer(callback_id).to_string();
+
+    let closure = async move {
+        cloudcore.set_property_value_and_wait_ack(dsn, name, value, timeout, callback_id).await.into_mantle_result()
     };
     RuntimeFFI::exec_id(closure, callback);
 }
 
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn cloudcore_set_property_values(
+    ptr_cloudcore: *mut CloudCore,
+    dsn: *const c_char,
+    values: *mut Vec<(String, IoTPropertyValue)>,
+    callback_id: *const c_char,
+    callback: fn(result: (Result<Vec<IoTPropertyWriteResult>, Box<MantleError>>, String)),
+) {
+    let cloudcore = &mut *ptr_cloudcore;
+    let dsn = MantleStringPointer(dsn).to_string();
+    let values = *Box::from_raw(values);
+    let callback_id = MantleStringPointer(callback_id).to_string();
+
+    let closure = async move {
+        let (result, callback_id) = cloudcore.set_property_values(dsn, values, callback_id).await;
+        let result = result.map(|results| {
+            let mut results: Vec<IoTPropertyWriteResult> = results
+                .into_iter()
+                .map(|(name, result)| IoTPropertyWriteResult::new(name, result.err().map(|err| err.to_string())))
+                .collect();
+            results.sort_by(|a, b| a.name().cmp(b.name()));
+            results
+        });
+        (result, callback_id).into_mantle_result()
+    };
+    RuntimeFFI::exec_list_id(closure, callback);
+}
+
 
 #[no_mangle]
 #[allow(improper_ctypes_definitions)]
@@ -262,9 +347,18 @@ pub unsafe extern "C" fn cloudcore_get_file_property_as_files(
         *cb = callback;
     }
     let closure = async move {
+        let (from, to) = match (parse_optional_datetime(from), parse_optional_datetime(to)) {
+            (Ok(from), Ok(to)) => (from, to),
+            (Err(err), _) | (_, Err(err)) => {
+                if let Some(cb) = PROP_FILES_CB_STRUCT.lock().ok() {
+                    cb(convert_list_to_using_mantle_error_with_id((Err::<Vec<IoTDatapointFile>, _>(err), callback_id).into_mantle_result()))
+                }
+                return;
+            }
+        };
         cloudcore.get_file_property_as_files_callback(dsn, prop_name, count, from, to, callback_id, |result| {
             if let Some(cb) = PROP_FILES_CB_STRUCT.lock().ok() {
-                cb(convert_list_to_using_mantle_error_with_id(result))
+                cb(convert_list_to_using_mantle_error_with_id(result.into_mantle_result()))
             }
         }).await
     };
@@ -294,9 +388,18 @@ pub unsafe extern "C" fn cloudcore_get_message_property_as_files(
         *cb = callback;
     }
     let closure = async move {
+        let (from, to) = match (parse_optional_datetime(from), parse_optional_datetime(to)) {
+            (Ok(from), Ok(to)) => (from, to),
+            (Err(err), _) | (_, Err(err)) => {
+                if let Some(cb) = PROP_MSGS_CB_STRUCT.lock().ok() {
+                    cb(convert_list_to_using_mantle_error_with_id((Err::<Vec<IoTDatapointMessage>, _>(err), callback_id).into_mantle_result()))
+                }
+                return;
+            }
+        };
         cloudcore.get_message_property_as_files_callback(dsn, prop_name, count, from, to, callback_id, |result| {
             if let Some(cb) = PROP_MSGS_CB_STRUCT.lock().ok() {
-                cb(convert_list_to_using_mantle_error_with_id(result))
+                cb(convert_list_to_using_mantle_error_with_id(result.into_mantle_result()))
             }
         }).await
     };
@@ -321,7 +424,7 @@ pub unsafe extern "C" fn cloudcore_save_file(
     let is_message = *Box::from_raw(is_message as *mut bool);
 
     let closure = async move {
-        cloudcore.save_file(dsn, name, path, is_message, callback_id).await
+        cloudcore.save_file(dsn, name, path, is_message, callback_id).await.into_mantle_result()
     };
     RuntimeFFI::exec_id(closure, callback);
 }
@@ -1,4 +1,4 @@
-use cloudcore::{CloudCore, ErrorUtil};
+use cloudcore::{CloudCore, IntoMantleResult, ErrorUtil};
 use std::os::raw::c_char;
 use ffi_utilities::{MantleStringPointer, RuntimeFFI};
 use log::error;
@@ -25,7 +25,7 @@ pub unsafe extern "C" fn cloudcore_create_device_schedule(
     let action_base_type = MantleStringPointer(action_base_type).to_string();
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.create_device_schedule(dsn, name, start_date, start_time_each_day, action_name, action_base_type).await
+        cloudcore.create_device_schedule(dsn, name, start_date, start_time_each_day, action_name, action_base_type).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -40,7 +40,7 @@ pub unsafe extern "C" fn cloudcore_fetch_schedules(
     let dev_id = *Box::from_raw(device_id as *mut u32);
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.fetch_schedules(Some(dev_id)).await
+        cloudcore.fetch_schedules(Some(dev_id)).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -61,7 +61,7 @@ pub unsafe extern "C" fn cloudcore_update_device_schedule(
         let schedule = *Box::from_raw(schedule as *mut Schedule);
         let cloudcore = &mut *ptr_cloudcore;
         let closure = async move {
-            cloudcore.update_schedule(schedule).await
+            cloudcore.update_schedule(schedule).await.into_mantle_result()
         };
         RuntimeFFI::exec(closure, callback);
     }
@@ -7,7 +7,7 @@ use lazy_static::lazy_static;
 use log::debug;
 
 use mantle_utilities::{MantleError, RUNTIME};
-use cloudcore::CloudCore;
+use cloudcore::{CloudCore, IntoMantleResult};
 use cloudcore::properties::trigger::IoTTrigger;
 
 lazy_static! {
@@ -26,7 +26,7 @@ pub unsafe extern "C" fn cloudcore_fetch_triggers(
     let prop_name = MantleStringPointer(prop_name).to_string();
     let cloudcore = &mut *ptr_cloudcore;
     let closure = async move {
-        cloudcore.fetch_triggers(dsn, prop_name).await
+        cloudcore.fetch_triggers(dsn, prop_name).await.into_mantle_result()
     };
     RuntimeFFI::exec_list(closure, callback);
 }
@@ -68,7 +68,7 @@ pub unsafe extern "C" fn cloudcore_create_error_push_triggers(
             errors,
             |result| {
                 if let Some(cb) = CREATE_TRIGGS_CB_STRUCT.lock().ok() {
-                    cb(convert_list_to_using_mantle_error(result))
+                    cb(convert_list_to_using_mantle_error(result.into_mantle_result()))
                 }
             }
         ).await
@@ -88,7 +88,7 @@ pub unsafe extern "C" fn cloudcore_delete_all_triggers(
     let dsn = MantleStringPointer(dsn).to_string();
     let prop_name = MantleStringPointer(prop_name).to_string();
     let closure = async move {
-        cloudcore.delete_all_triggers(dsn, prop_name).await
+        cloudcore.delete_all_triggers(dsn, prop_name).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -114,7 +114,7 @@ pub unsafe extern "C" fn cloudcore_update_all_trigger_apps_registration_id(
             prop_name,
             device_id,
             registration_id,
-        ).await
+        ).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
@@ -137,7 +137,7 @@ pub unsafe extern "C" fn cloudcore_delete_all_trigger_apps_by_device_id(
             dsn,
             prop_name,
             device_id
-        ).await
+        ).await.into_mantle_result()
     };
     RuntimeFFI::exec(closure, callback);
 }
\ No newline at end of file
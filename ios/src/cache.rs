@@ -30,6 +30,10 @@ extern "C" {
         key: *const c_char,
         callback: fn(result: Result<CacheDataValue, Box<MantleError>>)
     );
+    fn cloudcore_set_cache_encryption_key(
+        ptr_cloudcore: *mut CloudCore,
+        key: *const c_char,
+    ) -> Result<(), Box<MantleError>>;
 }
 
 #[no_mangle]
@@ -64,3 +68,12 @@ pub unsafe extern "C" fn ios_cloudcore_get_value(
         GET_CB_STRUCT.lock().unwrap().run(result);
     });
 }
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn ios_cloudcore_set_cache_encryption_key(
+    ptr_cloudcore: *mut CloudCore,
+    key: *const c_char,
+) -> MantleResult<()> {
+    MantleResult::new_c_object(&cloudcore_set_cache_encryption_key(ptr_cloudcore, key))
+}
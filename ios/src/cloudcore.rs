@@ -14,6 +14,18 @@ extern "C" {
     fn cloudcore_destroy(
         ptr_cloudcore: *mut CloudCore,
     );
+    fn cloudcore_register_custom_region(
+        ptr_cloudcore: *mut CloudCore,
+        region: *const c_char,
+        app_id: *const c_char,
+        app_secret: *const c_char,
+        user_url: *const c_char,
+        device_url: *const c_char,
+    );
+    fn cloudcore_use_custom_region(
+        ptr_cloudcore: *mut CloudCore,
+        region: *const c_char,
+    );
 }
 
 #[no_mangle]
@@ -33,6 +45,26 @@ pub unsafe extern "C" fn ios_cloudcore_destroy(
     cloudcore_destroy(ptr_cloudcore)
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn ios_cloudcore_register_custom_region(
+    ptr_cloudcore: *mut CloudCore,
+    region: *const c_char,
+    app_id: *const c_char,
+    app_secret: *const c_char,
+    user_url: *const c_char,
+    device_url: *const c_char,
+) {
+    cloudcore_register_custom_region(ptr_cloudcore, region, app_id, app_secret, user_url, device_url)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ios_cloudcore_use_custom_region(
+    ptr_cloudcore: *mut CloudCore,
+    region: *const c_char,
+) {
+    cloudcore_use_custom_region(ptr_cloudcore, region)
+}
+
 fn create_ayla_region_environment_map() -> HashMap<AylaRegionEnvironment, ApplicationInfo> {
     let mut map: HashMap<AylaRegionEnvironment, ApplicationInfo> = HashMap::new();
 
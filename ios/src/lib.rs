@@ -5,5 +5,6 @@ pub mod devices;
 pub mod properties;
 pub mod cache;
 pub mod pairing;
+pub mod polling;
 pub mod schedules;
 pub mod notifications;
\ No newline at end of file
@@ -22,8 +22,8 @@ impl RustCBridge<Notification> for iOSNotification {
             user_uuid: MantleString(rust_object.user_uuid().to_owned()).to_ptr(),
             id: MantleString(rust_object.id().to_owned()).to_ptr(),
             dsn: MantleString(rust_object.dsn().to_owned()).to_ptr(),
-            created_at: MantleString(rust_object.created_at().to_owned()).to_ptr(),
-            datapoint_created_at: MantleString(rust_object.datapoint_created_at().to_owned()).to_ptr(),
+            created_at: MantleString(rust_object.created_at().to_rfc3339()).to_ptr(),
+            datapoint_created_at: MantleString(rust_object.datapoint_created_at().to_rfc3339()).to_ptr(),
             read: rust_object.read().to_owned(),
             deleted: rust_object.deleted().to_owned(),
             notification_type: rust_object.notification_type().to_owned(),
@@ -1,7 +1,7 @@
 mod wifi_network;
 
 use crate::pairing::wifi_network::WifiNetwork as iOSWifiNetwork;
-use cloudcore::{CloudCore, WifiPairing, WifiPairingState, WifiNetwork};
+use cloudcore::{CloudCore, PairingConfig, WifiPairing, WifiPairingState, WifiNetwork};
 use std::os::raw::c_char;
 use ffi_utilities::{MantleResult, RustCBridge, MantleList, CRustBridge};
 use ios_utilities::CallbackHolder;
@@ -29,7 +29,15 @@ extern "C" {
         result_callback: fn(
             result: Result<String, Box<MantleError>>
         ),
+        pairing_config: *mut PairingConfig,
     ) -> *mut WifiPairing;
+    fn cloudcore_create_pairing_config(
+        device_max_attempts: u32,
+        device_pause_millis: u64,
+        ayla_max_attempts: u32,
+        ayla_pause_millis: u64,
+        deadline_millis: *const u64,
+    ) -> *mut PairingConfig;
     fn cloudcore_start_pairing(
         ptr_wifi_manager: *mut WifiPairing,
         ip_address: *const c_char
@@ -37,6 +45,12 @@ extern "C" {
     fn cloudcore_continue_pairing(
         ptr_wifi_manager: *mut WifiPairing
     );
+    fn cloudcore_cancel_pairing(
+        ptr_wifi_manager: *mut WifiPairing
+    );
+    fn cloudcore_resume_pairing(
+        ptr_wifi_manager: *mut WifiPairing
+    ) -> Result<(), Box<MantleError>>;
     fn cloudcore_done_pairing(
         ptr: *mut WifiPairing
     );
@@ -51,8 +65,16 @@ extern "C" {
     fn cloudcore_get_pairing_log(
         ptr_cloudcore: *const CloudCore,
     ) -> Result<String, Box<MantleError>>;
+    fn cloudcore_export_pairing_log(
+        ptr_cloudcore: *const CloudCore,
+        redacted: bool,
+    ) -> Result<String, Box<MantleError>>;
+    fn cloudcore_get_pairing_diagnostics(
+        ptr_cloudcore: *const CloudCore,
+    ) -> Result<String, Box<MantleError>>;
 }
 
+/// `deadline_millis` can be null to pair without a deadline.
 #[no_mangle]
 #[allow(improper_ctypes_definitions)]
 pub unsafe extern "C" fn ios_cloudcore_create_pairing_manager(
@@ -64,7 +86,19 @@ pub unsafe extern "C" fn ios_cloudcore_create_pairing_manager(
         wifi_networks: *const MantleList<iOSWifiNetwork>,
     ),
     result_callback: fn(result: MantleResult<*const c_char>,),
+    device_max_attempts: u32,
+    device_pause_millis: u64,
+    ayla_max_attempts: u32,
+    ayla_pause_millis: u64,
+    deadline_millis: *const u64,
 ) -> *mut WifiPairing {
+    let config = cloudcore_create_pairing_config(
+        device_max_attempts,
+        device_pause_millis,
+        ayla_max_attempts,
+        ayla_pause_millis,
+        deadline_millis,
+    );
     STATE_CB.lock().unwrap().update(get_state_callback);
     NETWORKS_CB_STRUCT.lock().unwrap().update(get_wifi_networks_callback);
     RESULT_CB_STRUCT.lock().unwrap().update(result_callback);
@@ -72,7 +106,8 @@ pub unsafe extern "C" fn ios_cloudcore_create_pairing_manager(
         ptr_cloudcore,
         handle_state,
         handle_wifi_networks,
-        handle_result
+        handle_result,
+        config,
     )
 }
 
@@ -103,6 +138,17 @@ pub unsafe extern "C" fn ios_cloudcore_continue_pairing(ptr_wifi_manager: *mut W
     cloudcore_continue_pairing(ptr_wifi_manager);
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn ios_cloudcore_cancel_pairing(ptr_wifi_manager: *mut WifiPairing) {
+    cloudcore_cancel_pairing(ptr_wifi_manager);
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn ios_cloudcore_resume_pairing(ptr_wifi_manager: *mut WifiPairing) -> MantleResult<()> {
+    MantleResult::new_c_object(&cloudcore_resume_pairing(ptr_wifi_manager))
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn ios_cloudcore_done_pairing(ptr_wifi_manager: *mut WifiPairing) {
     if ptr_wifi_manager.is_null() {
@@ -139,4 +185,21 @@ pub unsafe extern "C" fn ios_cloudcore_get_pairing_log(
     ptr_cloudcore: *const CloudCore,
 ) -> MantleResult<*const c_char> {
     MantleResult::new_c_object(&cloudcore_get_pairing_log(ptr_cloudcore))
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn ios_cloudcore_export_pairing_log(
+    ptr_cloudcore: *const CloudCore,
+    redacted: bool,
+) -> MantleResult<*const c_char> {
+    MantleResult::new_c_object(&cloudcore_export_pairing_log(ptr_cloudcore, redacted))
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn ios_cloudcore_get_pairing_diagnostics(
+    ptr_cloudcore: *const CloudCore,
+) -> MantleResult<*const c_char> {
+    MantleResult::new_c_object(&cloudcore_get_pairing_diagnostics(ptr_cloudcore))
 }
\ No newline at end of file
@@ -0,0 +1,176 @@
+mod poll_event;
+mod property_event;
+
+use std::collections::{HashMap, HashSet};
+use std::os::raw::c_char;
+use cloudcore::CloudCore;
+use cloudcore::polling::{PollConfig, PollEvent, PropertyEvent};
+use ffi_utilities::{MantleList, MantleString, MantleStringPointer, RustCBridge};
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use crate::polling::poll_event::PollEvent as iOSPollEvent;
+use crate::polling::property_event::PropertyEvent as iOSPropertyEvent;
+
+lazy_static! {
+    // Polls keep calling back until they are removed, so callbacks stay here until then
+    static ref POLL_CB_STRUCTS: Mutex<HashMap<String, (fn(event: iOSPollEvent, callback_id: u64), u64)>> = Mutex::new(HashMap::new());
+    static ref POLL_CALLBACK_IDS: Mutex<HashMap<u32, String>> = Mutex::new(HashMap::new());
+    static ref REMOVED_CALLBACK_IDS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+    static ref SUBSCRIPTION_CB_STRUCTS: Mutex<HashMap<String, (fn(event: iOSPropertyEvent, callback_id: u64), u64)>> = Mutex::new(HashMap::new());
+    static ref SUBSCRIPTION_CALLBACK_IDS: Mutex<HashMap<u32, String>> = Mutex::new(HashMap::new());
+    static ref NEXT_CALLBACK_ID: Mutex<u64> = Mutex::new(0);
+}
+
+#[allow(improper_ctypes, improper_ctypes_definitions)]
+extern "C" {
+    fn cloudcore_start_polling_manager(
+        ptr_cloudcore: *mut CloudCore,
+    );
+    fn cloudcore_create_poll_config(
+        dsn: *const c_char,
+        names: *mut Vec<String>,
+        sleep_time: *const u64,
+        emit_snapshots: bool,
+        callback_id: *const c_char,
+        callback: fn(event: (PollEvent, String)),
+    ) -> *mut PollConfig;
+    fn cloudcore_add_poll(
+        ptr_cloudcore: *mut CloudCore,
+        poll_config: *mut PollConfig,
+    ) -> u32;
+    fn cloudcore_remove_poll(
+        ptr_cloudcore: *mut CloudCore,
+        poll_id: *mut u32,
+    );
+    fn cloudcore_stop_polling_manager(
+        ptr_cloudcore: *mut CloudCore,
+    );
+    fn cloudcore_subscribe(
+        ptr_cloudcore: *mut CloudCore,
+        dsn: *const c_char,
+        names: *mut Vec<String>,
+        callback_id: *const c_char,
+        callback: fn(event: (PropertyEvent, String)),
+    ) -> u32;
+    fn cloudcore_unsubscribe(
+        ptr_cloudcore: *mut CloudCore,
+        subscription_id: u32,
+    );
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ios_cloudcore_start_polling_manager(ptr_cloudcore: *mut CloudCore) {
+    cloudcore_start_polling_manager(ptr_cloudcore);
+}
+
+/// `ptr_names` can be null to poll every property of the device.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn ios_cloudcore_add_poll(
+    ptr_cloudcore: *mut CloudCore,
+    dsn: *const c_char,
+    ptr_names: *const MantleList<*const c_char>,
+    sleep_time: u64,
+    emit_snapshots: bool,
+    callback: fn(event: iOSPollEvent, callback_id: u64),
+    callback_id: u64,
+) -> u32 {
+    let boxed_names = if ptr_names.is_null() {
+        std::ptr::null_mut()
+    } else {
+        let c_list = &*ptr_names;
+        let names = c_list.map_list(|c_name| { MantleStringPointer(c_name).to_string() });
+        Box::into_raw(Box::new(names))
+    };
+    let next_id = next_callback_id();
+    POLL_CB_STRUCTS.lock().unwrap().insert(next_id.clone(), (callback, callback_id));
+    let config = cloudcore_create_poll_config(
+        dsn,
+        boxed_names,
+        &sleep_time,
+        emit_snapshots,
+        MantleString(next_id.clone()).to_ptr(),
+        handle_poll_event,
+    );
+    let poll_id = cloudcore_add_poll(ptr_cloudcore, config);
+    POLL_CALLBACK_IDS.lock().unwrap().insert(poll_id, next_id);
+    poll_id
+}
+
+fn handle_poll_event(event: (PollEvent, String)) {
+    let (event, callback_id) = event;
+    let stopped = matches!(event, PollEvent::Stopped { .. });
+    let callback = POLL_CB_STRUCTS.lock().unwrap().get(&callback_id).copied();
+    if let Some((callback, ios_callback_id)) = callback {
+        callback(iOSPollEvent::new_c_object(&event), ios_callback_id);
+    }
+    if stopped && REMOVED_CALLBACK_IDS.lock().unwrap().remove(&callback_id) {
+        POLL_CB_STRUCTS.lock().unwrap().remove(&callback_id);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ios_cloudcore_remove_poll(ptr_cloudcore: *mut CloudCore, poll_id: u32) {
+    // The callback still gets the poll's stopped event, it is dropped after that.
+    // Marked before removing, the stopped event can be handled before remove_poll returns.
+    if let Some(callback_id) = POLL_CALLBACK_IDS.lock().unwrap().remove(&poll_id) {
+        REMOVED_CALLBACK_IDS.lock().unwrap().insert(callback_id);
+    }
+    cloudcore_remove_poll(ptr_cloudcore, Box::into_raw(Box::new(poll_id)));
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ios_cloudcore_stop_polling_manager(ptr_cloudcore: *mut CloudCore) {
+    cloudcore_stop_polling_manager(ptr_cloudcore);
+}
+
+/// `ptr_names` can be null to subscribe to every property of the device.
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn ios_cloudcore_subscribe(
+    ptr_cloudcore: *mut CloudCore,
+    dsn: *const c_char,
+    ptr_names: *const MantleList<*const c_char>,
+    callback: fn(event: iOSPropertyEvent, callback_id: u64),
+    callback_id: u64,
+) -> u32 {
+    let boxed_names = if ptr_names.is_null() {
+        std::ptr::null_mut()
+    } else {
+        let c_list = &*ptr_names;
+        let names = c_list.map_list(|c_name| { MantleStringPointer(c_name).to_string() });
+        Box::into_raw(Box::new(names))
+    };
+    let next_id = next_callback_id();
+    SUBSCRIPTION_CB_STRUCTS.lock().unwrap().insert(next_id.clone(), (callback, callback_id));
+    let subscription_id = cloudcore_subscribe(
+        ptr_cloudcore,
+        dsn,
+        boxed_names,
+        MantleString(next_id.clone()).to_ptr(),
+        handle_property_event,
+    );
+    SUBSCRIPTION_CALLBACK_IDS.lock().unwrap().insert(subscription_id, next_id);
+    subscription_id
+}
+
+fn handle_property_event(event: (PropertyEvent, String)) {
+    let callback = SUBSCRIPTION_CB_STRUCTS.lock().unwrap().get(&event.1).copied();
+    if let Some((callback, callback_id)) = callback {
+        callback(iOSPropertyEvent::new_c_object(&event.0), callback_id);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ios_cloudcore_unsubscribe(ptr_cloudcore: *mut CloudCore, subscription_id: u32) {
+    cloudcore_unsubscribe(ptr_cloudcore, subscription_id);
+    if let Some(callback_id) = SUBSCRIPTION_CALLBACK_IDS.lock().unwrap().remove(&subscription_id) {
+        SUBSCRIPTION_CB_STRUCTS.lock().unwrap().remove(&callback_id);
+    }
+}
+
+fn next_callback_id() -> String {
+    let mut next_id = NEXT_CALLBACK_ID.lock().unwrap();
+    *next_id += 1;
+    next_id.to_string()
+}
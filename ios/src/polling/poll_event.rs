@@ -0,0 +1,99 @@
+use std::os::raw::c_char;
+use ffi_utilities::{MantleList, MantleOptionString, MantleString, RustCBridge};
+use cloudcore::polling;
+use crate::properties::property::IoTProperty;
+use crate::properties::value::IoTPropertyValue;
+
+#[repr(C)]
+#[derive(Debug)]
+pub enum PollEventType {
+    Data,
+    Changes,
+    Error,
+    Stopped,
+}
+
+/// `properties` is only set for `Data` events, `changes` for `Changes` and `error` for `Error`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PollEvent {
+    event_type: PollEventType,
+    dsn: *const c_char,
+    properties: *const MantleList<IoTProperty>,
+    changes: *const MantleList<IoTPropertyChange>,
+    error: *const c_char,
+}
+
+impl RustCBridge<polling::PollEvent> for PollEvent {
+    fn new_c_object(rust_event: &polling::PollEvent) -> Self {
+        let (event_type, properties, changes, error) = match rust_event {
+            polling::PollEvent::Data { properties, .. } => (
+                PollEventType::Data,
+                MantleList::<IoTProperty>::boxed_list(properties.clone()),
+                std::ptr::null(),
+                None,
+            ),
+            polling::PollEvent::Changes { changes, .. } => (
+                PollEventType::Changes,
+                std::ptr::null(),
+                MantleList::<IoTPropertyChange>::boxed_list(changes.clone()),
+                None,
+            ),
+            polling::PollEvent::Error { error, .. } => (
+                PollEventType::Error,
+                std::ptr::null(),
+                std::ptr::null(),
+                Some(error.to_string()),
+            ),
+            polling::PollEvent::Stopped { .. } => (PollEventType::Stopped, std::ptr::null(), std::ptr::null(), None),
+        };
+        Self {
+            event_type,
+            dsn: MantleString(rust_event.dsn().to_owned()).to_ptr(),
+            properties,
+            changes,
+            error: MantleOptionString(error.as_ref()).to_ptr(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub enum IoTPropertyChangeType {
+    Added,
+    Changed,
+    Removed,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct IoTPropertyChange {
+    change_type: IoTPropertyChangeType,
+    name: *const c_char,
+    old_value: *const IoTPropertyValue,
+    new_value: *const IoTPropertyValue,
+}
+
+impl RustCBridge<polling::IoTPropertyChange> for IoTPropertyChange {
+    fn new_c_object(rust_change: &polling::IoTPropertyChange) -> Self {
+        let (change_type, old_value, new_value) = match rust_change {
+            polling::IoTPropertyChange::Added { value, .. } => (IoTPropertyChangeType::Added, None, value.as_ref()),
+            polling::IoTPropertyChange::Changed { old_value, new_value, .. } => {
+                (IoTPropertyChangeType::Changed, old_value.as_ref(), new_value.as_ref())
+            }
+            polling::IoTPropertyChange::Removed { old_value, .. } => (IoTPropertyChangeType::Removed, old_value.as_ref(), None),
+        };
+        Self {
+            change_type,
+            name: MantleString(rust_change.name().to_owned()).to_ptr(),
+            old_value: match old_value {
+                Some(val) => Box::into_raw(Box::new(IoTPropertyValue::new_c_object(val))),
+                None => std::ptr::null(),
+            },
+            new_value: match new_value {
+                Some(val) => Box::into_raw(Box::new(IoTPropertyValue::new_c_object(val))),
+                None => std::ptr::null(),
+            },
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::os::raw::c_char;
+use ffi_utilities::{MantleOptionString, MantleString, RustCBridge};
+use cloudcore::polling;
+use crate::polling::poll_event::IoTPropertyChange;
+
+#[repr(C)]
+#[derive(Debug)]
+pub enum PropertyEventType {
+    Changed,
+    Error,
+}
+
+/// `change` is only set for `Changed` events and `error` for `Error`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct PropertyEvent {
+    event_type: PropertyEventType,
+    dsn: *const c_char,
+    change: *const IoTPropertyChange,
+    error: *const c_char,
+}
+
+impl RustCBridge<polling::PropertyEvent> for PropertyEvent {
+    fn new_c_object(rust_event: &polling::PropertyEvent) -> Self {
+        let (event_type, change, error) = match rust_event {
+            polling::PropertyEvent::Changed { change, .. } => (
+                PropertyEventType::Changed,
+                Box::into_raw(Box::new(IoTPropertyChange::new_c_object(change))) as *const IoTPropertyChange,
+                None,
+            ),
+            polling::PropertyEvent::Error { error, .. } => (PropertyEventType::Error, std::ptr::null(), Some(error.to_string())),
+        };
+        Self {
+            event_type,
+            dsn: MantleString(rust_event.dsn().to_owned()).to_ptr(),
+            change,
+            error: MantleOptionString(error.as_ref()).to_ptr(),
+        }
+    }
+}
@@ -1,5 +1,5 @@
-mod property;
-mod value;
+pub(crate) mod property;
+pub(crate) mod value;
 mod datapoint;
 
 use std::os::raw::{c_char, c_int};
@@ -9,10 +9,10 @@ use ios_utilities::{CallbackStruct, CallbackStructMap, ListCallbackStruct, ListC
 use lazy_static::lazy_static;
 use std::sync::Mutex;
 use mantle_utilities::MantleError;
-use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointFile, IoTDatapointMessage};
+use cloudcore::properties::datapoint::{IoTDatapoint, IoTDatapointAck, IoTDatapointFile, IoTDatapointMessage, IoTPropertyWriteResult};
 use cloudcore::properties::property::IoTProperty;
 use cloudcore::properties::value::IoTPropertyValue;
-use crate::properties::datapoint::{IoTDatapoint as iOSIoTDatapoint, IoTDatapointFile as iOSIoTDatapointFile, IoTDatapointMessage as iOSIoTDatapointMessage};
+use crate::properties::datapoint::{IoTDatapoint as iOSIoTDatapoint, IoTDatapointAck as iOSIoTDatapointAck, IoTDatapointFile as iOSIoTDatapointFile, IoTDatapointMessage as iOSIoTDatapointMessage, IoTPropertyWriteResult as iOSIoTPropertyWriteResult};
 use crate::properties::property::IoTProperty as iOSIoTProperty;
 use crate::properties::value::IoTPropertyValue as iOSIoTPropertyValue;
 
@@ -28,6 +28,8 @@ lazy_static! {
     static ref MULTI_MSG_CB_STRUCT: Mutex<ListCallbackStructMap<iOSIoTDatapointMessage>> = Mutex::new(ListCallbackStructMap::new());
     static ref MSG_DATAPOINT_CB_STRUCT: Mutex<CallbackStructMap<iOSIoTDatapointMessage>> = Mutex::new(CallbackStructMap::new());
     static ref SET_PROP_CB_STRUCT: Mutex<CallbackStructMap<()>> = Mutex::new(CallbackStructMap::new());
+    static ref SET_PROP_ACK_CB_STRUCT: Mutex<CallbackStructMap<iOSIoTDatapointAck>> = Mutex::new(CallbackStructMap::new());
+    static ref SET_PROPS_CB_STRUCT: Mutex<ListCallbackStructMap<iOSIoTPropertyWriteResult>> = Mutex::new(ListCallbackStructMap::new());
     static ref FILES_CB_STRUCT: Mutex<ListCallbackStructMap<iOSIoTDatapointFile>> = Mutex::new(ListCallbackStructMap::new());
     static ref MSGS_CB_STRUCT: Mutex<ListCallbackStructMap<iOSIoTDatapointMessage>> = Mutex::new(ListCallbackStructMap::new());
     static ref SAVE_FILE_CB_STRUCT: Mutex<CallbackStructMap<()>> = Mutex::new(CallbackStructMap::new());
@@ -59,6 +61,15 @@ extern "C" {
         callback_id: *const c_char,
         callback: fn(result: (Result<Vec<IoTDatapoint>, Box<MantleError>>, String)),
     );
+    fn cloudcore_get_all_data_points(
+        ptr_cloudcore: *mut CloudCore,
+        dsn: *const c_char,
+        prop_name: *const c_char,
+        from: *const c_char,
+        to: *const c_char,
+        callback_id: *const c_char,
+        callback: fn(result: (Result<Vec<IoTDatapoint>, Box<MantleError>>, String)),
+    );
     fn cloudcore_get_file_property(
         ptr_cloudcore: *mut CloudCore,
         dsn: *const c_char,
@@ -111,6 +122,22 @@ extern "C" {
         callback_id: *const c_char,
         callback: fn(result: (Result<(), Box<MantleError>>, String)),
     );
+    fn cloudcore_set_property_value_and_wait_ack(
+        ptr_cloudcore: *mut CloudCore,
+        dsn: *const c_char,
+        name: *const c_char,
+        value: *mut IoTPropertyValue,
+        timeout_millis: u64,
+        callback_id: *const c_char,
+        callback: fn(result: (Result<IoTDatapointAck, Box<MantleError>>, String)),
+    );
+    fn cloudcore_set_property_values(
+        ptr_cloudcore: *mut CloudCore,
+        dsn: *const c_char,
+        values: *mut Vec<(String, IoTPropertyValue)>,
+        callback_id: *const c_char,
+        callback: fn(result: (Result<Vec<IoTPropertyWriteResult>, Box<MantleError>>, String)),
+    );
     fn cloudcore_get_file_property_as_files(
         ptr_cloudcore: *mut CloudCore,
         dsn: *const c_char,
@@ -214,6 +241,26 @@ fn handle_data_points(result: (Result<Vec<IoTDatapoint>, Box<MantleError>>, Stri
     DATA_POINTS_CB_STRUCT.lock().unwrap().run(result);
 }
 
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn ios_cloudcore_get_all_data_points(
+    ptr_cloudcore: *mut CloudCore,
+    dsn: *const c_char,
+    prop_name: *const c_char,
+    from: *const c_char,
+    to: *const c_char,
+    callback: fn(result: MantleResult<MantleList<iOSIoTDatapoint>>, callback_id: u64),
+    callback_id: u64,
+) {
+    let mut cb_struct = DATA_POINTS_CB_STRUCT.lock().unwrap();
+    let next_id = cb_struct.get_next_id_as_string();
+    let struct_callback_id = MantleString(next_id.clone()).to_ptr();
+    let mut struct_callback: ListCallbackStruct<iOSIoTDatapoint> = ListCallbackStruct::new();
+    struct_callback.update(callback, callback_id);
+    cb_struct.update(struct_callback, next_id.clone());
+    cloudcore_get_all_data_points(ptr_cloudcore, dsn, prop_name, from, to, struct_callback_id, handle_data_points);
+}
+
 #[no_mangle]
 #[allow(improper_ctypes_definitions)]
 pub unsafe extern "C" fn ios_cloudcore_get_file_property(
@@ -379,6 +426,59 @@ fn handle_set_prop(result: (Result<(), Box<MantleError>>, String)) {
     SET_PROP_CB_STRUCT.lock().unwrap().run(result);
 }
 
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn ios_cloudcore_set_property_value_and_wait_ack(
+    ptr_cloudcore: *mut CloudCore,
+    dsn: *const c_char,
+    name: *const c_char,
+    ptr_value: *const iOSIoTPropertyValue,
+    timeout_millis: u64,
+    callback: fn(result: MantleResult<iOSIoTDatapointAck>, callback_id: u64),
+    callback_id: u64,
+) {
+    let value = iOSIoTPropertyValue::new_rust_object(ptr_value).unwrap();
+    let boxed_value = Box::into_raw(Box::new(value));
+    let mut cb_struct = SET_PROP_ACK_CB_STRUCT.lock().unwrap();
+    let next_id = cb_struct.get_next_id_as_string();
+    let struct_callback_id = MantleString(next_id.clone()).to_ptr();
+    let mut struct_callback: CallbackStruct<iOSIoTDatapointAck> = CallbackStruct::new();
+    struct_callback.update(callback, callback_id);
+    cb_struct.update(struct_callback, next_id.clone());
+    cloudcore_set_property_value_and_wait_ack(ptr_cloudcore, dsn, name, boxed_value, timeout_millis, struct_callback_id, handle_set_prop_ack);
+}
+
+fn handle_set_prop_ack(result: (Result<IoTDatapointAck, Box<MantleError>>, String)) {
+    SET_PROP_ACK_CB_STRUCT.lock().unwrap().run(result);
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub unsafe extern "C" fn ios_cloudcore_set_property_values(
+    ptr_cloudcore: *mut CloudCore,
+    dsn: *const c_char,
+    ptr_names: *const MantleList<*const c_char>,
+    ptr_values: *const MantleList<*const iOSIoTPropertyValue>,
+    callback: fn(result: MantleResult<MantleList<iOSIoTPropertyWriteResult>>, callback_id: u64),
+    callback_id: u64,
+) {
+    // Names and values are parallel lists
+    let names = (&*ptr_names).map_list(|c_name|{ MantleStringPointer(c_name).to_string() });
+    let values = (&*ptr_values).map_list(|c_value|{ iOSIoTPropertyValue::new_rust_object(c_value).unwrap() });
+    let boxed_values = Box::into_raw(Box::new(names.into_iter().zip(values).collect::<Vec<_>>()));
+    let mut cb_struct = SET_PROPS_CB_STRUCT.lock().unwrap();
+    let next_id = cb_struct.get_next_id_as_string();
+    let struct_callback_id = MantleString(next_id.clone()).to_ptr();
+    let mut struct_callback: ListCallbackStruct<iOSIoTPropertyWriteResult> = ListCallbackStruct::new();
+    struct_callback.update(callback, callback_id);
+    cb_struct.update(struct_callback, next_id.clone());
+    cloudcore_set_property_values(ptr_cloudcore, dsn, boxed_values, struct_callback_id, handle_set_props);
+}
+
+fn handle_set_props(result: (Result<Vec<IoTPropertyWriteResult>, Box<MantleError>>, String)) {
+    SET_PROPS_CB_STRUCT.lock().unwrap().run(result);
+}
+
 #[no_mangle]
 #[allow(improper_ctypes_definitions)]
 pub unsafe extern "C" fn ios_cloudcore_get_file_property_as_files(
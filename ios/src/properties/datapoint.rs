@@ -18,8 +18,8 @@ impl RustCBridge<datapoint::IoTDatapoint> for IoTDatapoint {
         Self {
             value: Box::into_raw(Box::new(IoTPropertyValue::new_c_object(rust_datapoint.value()))),
             user_uuid: MantleOptionString(rust_datapoint.metadata().user_uuid()).to_ptr(),
-            updated_at: MantleOptionString(rust_datapoint.updated_at().to_owned()).to_ptr(),
-            created_at: MantleOptionString(rust_datapoint.created_at().to_owned()).to_ptr(),
+            updated_at: MantleOptionString(rust_datapoint.updated_at().map(|datetime| datetime.to_rfc3339()).as_ref()).to_ptr(),
+            created_at: MantleOptionString(rust_datapoint.created_at().map(|datetime| datetime.to_rfc3339()).as_ref()).to_ptr(),
             echo: match rust_datapoint.echo() {
                 Some(echo) => Box::into_raw(Box::new(echo)),
                 None => std::ptr::null()
@@ -74,8 +74,8 @@ impl RustCBridge<datapoint::IoTDatapointMessage> for IoTDatapointMessage {
     fn new_c_object(rust_datapoint: &datapoint::IoTDatapointMessage) -> Self {
         Self {
             user_uuid: MantleOptionString(rust_datapoint.metadata.user_uuid()).to_ptr(),
-            updated_at: MantleOptionString(rust_datapoint.updated_at.as_ref().to_owned()).to_ptr(),
-            created_at: MantleOptionString(rust_datapoint.created_at.as_ref().to_owned()).to_ptr(),
+            updated_at: MantleOptionString(rust_datapoint.updated_at.map(|datetime| datetime.to_rfc3339()).as_ref()).to_ptr(),
+            created_at: MantleOptionString(rust_datapoint.created_at.map(|datetime| datetime.to_rfc3339()).as_ref()).to_ptr(),
             echo: match rust_datapoint.echo {
                 Some(echo) => Box::into_raw(Box::new(echo)),
                 None => std::ptr::null()
@@ -83,4 +83,38 @@ impl RustCBridge<datapoint::IoTDatapointMessage> for IoTDatapointMessage {
             local_file: MantleString(rust_datapoint.local_file.to_owned()).to_ptr(),
         }
     }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct IoTDatapointAck {
+    ack_status: *const c_char,
+    ack_message: *const c_char,
+    acked_at: *const c_char,
+}
+
+impl RustCBridge<datapoint::IoTDatapointAck> for IoTDatapointAck {
+    fn new_c_object(rust_ack: &datapoint::IoTDatapointAck) -> Self {
+        Self {
+            ack_status: MantleOptionString(rust_ack.ack_status()).to_ptr(),
+            ack_message: MantleOptionString(rust_ack.ack_message()).to_ptr(),
+            acked_at: MantleOptionString(rust_ack.acked_at().map(|datetime| datetime.to_rfc3339()).as_ref()).to_ptr(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct IoTPropertyWriteResult {
+    name: *const c_char,
+    error: *const c_char,
+}
+
+impl RustCBridge<datapoint::IoTPropertyWriteResult> for IoTPropertyWriteResult {
+    fn new_c_object(rust_result: &datapoint::IoTPropertyWriteResult) -> Self {
+        Self {
+            name: MantleString(rust_result.name().to_owned()).to_ptr(),
+            error: MantleOptionString(rust_result.error()).to_ptr(),
+        }
+    }
 }
\ No newline at end of file
@@ -39,7 +39,7 @@ impl RustCBridge<property::IoTProperty> for IoTProperty {
             read_only: rust_property.read_only().to_owned(),
             direction: MantleString(rust_property.direction().to_owned()).to_ptr(),
             scope: MantleString(rust_property.scope().to_owned()).to_ptr(),
-            data_updated_at: MantleOptionString(rust_property.data_updated_at().to_owned()).to_ptr(),
+            data_updated_at: MantleOptionString(rust_property.data_updated_at().map(|datetime| datetime.to_rfc3339()).as_ref()).to_ptr(),
             key: match rust_property.key() {
                 Some(key) => Box::into_raw(Box::new(key)),
                 None => std::ptr::null()
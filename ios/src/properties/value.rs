@@ -8,6 +8,9 @@ pub enum IoTPropertyValue {
     Int(i32),
     Str(*const c_char),
     Bool(bool),
+    Int64(i64),
+    Decimal(f64),
+    Json(*const c_char),
 }
 
 impl RustCBridge<value::IoTPropertyValue> for IoTPropertyValue {
@@ -15,7 +18,10 @@ impl RustCBridge<value::IoTPropertyValue> for IoTPropertyValue {
         match rust_value {
             value::IoTPropertyValue::Int(value) => IoTPropertyValue::Int(*value),
             value::IoTPropertyValue::Str(value) => IoTPropertyValue::Str(MantleString(value.to_string()).to_ptr()),
-            value::IoTPropertyValue::Bool(value) => IoTPropertyValue::Bool(*value)
+            value::IoTPropertyValue::Bool(value) => IoTPropertyValue::Bool(*value),
+            value::IoTPropertyValue::Int64(value) => IoTPropertyValue::Int64(*value),
+            value::IoTPropertyValue::Decimal(value) => IoTPropertyValue::Decimal(*value),
+            value::IoTPropertyValue::Json(value) => IoTPropertyValue::Json(MantleString(value.to_string()).to_ptr())
         }
     }
 }
@@ -31,7 +37,16 @@ impl CRustBridge<value::IoTPropertyValue> for IoTPropertyValue {
                     match obj_ref {
                         IoTPropertyValue::Int(value) => value::IoTPropertyValue::Int(*value),
                         IoTPropertyValue::Str(value) => value::IoTPropertyValue::Str(MantleStringPointer(*value).to_string()),
-                        IoTPropertyValue::Bool(value) => value::IoTPropertyValue::Bool(*value)
+                        IoTPropertyValue::Bool(value) => value::IoTPropertyValue::Bool(*value),
+                        IoTPropertyValue::Int64(value) => value::IoTPropertyValue::Int64(*value),
+                        IoTPropertyValue::Decimal(value) => value::IoTPropertyValue::Decimal(*value),
+                        IoTPropertyValue::Json(value) => {
+                            let json = MantleStringPointer(*value).to_string();
+                            match serde_json::from_str(&json) {
+                                Ok(json) => value::IoTPropertyValue::Json(json),
+                                Err(_) => return Option::None,
+                            }
+                        }
                     }
                 ),
             }